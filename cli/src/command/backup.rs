@@ -0,0 +1,305 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Standard Exonum CLI command used to back up the node database.
+
+use anyhow::{anyhow, bail, Error};
+use exonum::merkledb::{list_backups, purge_old_backups, DbOptions, RocksDB};
+use serde_derive::{Deserialize, Serialize};
+use structopt::StructOpt;
+use tempfile::TempDir;
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    command::{compact_db::is_locked, ExonumCommand, StandardResult},
+    config::NodeConfig,
+    io::load_config_file,
+};
+
+/// Back up the node database. The backup is created from a consistent snapshot
+/// of the database, so the command can be used while the node is running.
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Backup {
+    /// Path to a node configuration file.
+    #[structopt(long, short = "c")]
+    pub node_config: PathBuf,
+
+    /// Path to a database directory.
+    #[structopt(long, short = "d")]
+    pub db_path: PathBuf,
+
+    /// Directory to write the backup to.
+    #[structopt(long)]
+    pub backup_dir: PathBuf,
+
+    /// Backup mode: `checkpoint` or `incremental`.
+    #[structopt(long, default_value = "checkpoint")]
+    pub mode: BackupMode,
+
+    /// Number of latest incremental backups to keep; older backups are removed after
+    /// the new backup is created. Only valid in the `incremental` mode.
+    #[structopt(long)]
+    pub keep: Option<usize>,
+}
+
+/// Mode of the `backup` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum BackupMode {
+    /// Checkpoint of the database, which can be opened as a separate database.
+    /// The backup directory must be empty or absent.
+    Checkpoint,
+    /// Incremental backup managed by the `RocksDB` backup engine. Only the files changed
+    /// since the previous backup in the same directory are copied.
+    Incremental,
+}
+
+impl BackupMode {
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (Self::Checkpoint, "checkpoint"),
+        (Self::Incremental, "incremental"),
+    ];
+}
+
+impl fmt::Display for BackupMode {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (_, name) = Self::NAMES
+            .iter()
+            .find(|(mode, _)| mode == self)
+            .expect("Mode is not named");
+        formatter.write_str(name)
+    }
+}
+
+impl FromStr for BackupMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lowercase = s.to_lowercase();
+        Self::NAMES
+            .iter()
+            .find(|(_, name)| *name == lowercase)
+            .map(|(mode, _)| *mode)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::NAMES.iter().map(|(_, name)| *name).collect();
+                anyhow!(
+                    "Unknown backup mode: {}. Available modes: {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Outcome of the `backup` command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct BackupReport {
+    /// Directory with the backup.
+    pub backup_dir: PathBuf,
+    /// Backup mode.
+    pub mode: BackupMode,
+    /// Identifier of the incremental backup; `None` for checkpoints.
+    pub backup_id: Option<u32>,
+    /// Size of the backup in bytes. For incremental backups, the size includes files
+    /// shared with other backups.
+    pub size: u64,
+    /// Time taken to create the backup.
+    pub duration: Duration,
+}
+
+pub(super) fn is_empty_dir(path: &Path) -> bool {
+    fs::read_dir(path)
+        .map(|mut entries| entries.next().is_none())
+        .unwrap_or(false)
+}
+
+/// Returns the total size of files in the directory (recursively).
+pub(super) fn dir_size(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Creates a temporary directory next to `path`, so that it is located on the same device.
+pub(super) fn temp_dir_near(path: &Path) -> Result<TempDir, Error> {
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(parent)?;
+    Ok(tempfile::Builder::new()
+        .prefix(".exonum-backup.")
+        .tempdir_in(parent)?)
+}
+
+/// Returns options for opening a copy of the node database. The WAL and info LOG
+/// directories from the node config may be used by the node database, so the copy keeps
+/// its log files in its own directory.
+pub(super) fn copy_options(options: &DbOptions) -> DbOptions {
+    let mut options = options.clone();
+    options.wal_dir = None;
+    options.db_log_dir = None;
+    options
+}
+
+/// Creates a database at `target` with the contents of a consistent snapshot of `db`.
+/// Unlike checkpoints, this does not require that the database files are not deleted
+/// concurrently, so it works for secondary instances following a running node.
+///
+/// `options` must not refer to the directories used by the node database;
+/// see `copy_options`.
+fn copy_database(db: &RocksDB, options: &DbOptions, target: &Path) -> Result<(), Error> {
+    let export_dir = temp_dir_near(target)?;
+    let sst_dir = export_dir.path().join("sst");
+    db.export_sst(&sst_dir)?;
+    let target_db = RocksDB::open(target, options)?;
+    target_db.ingest_sst(&sst_dir)?;
+    target_db.close()?;
+    Ok(())
+}
+
+impl Backup {
+    fn check_args(&self) -> Result<(), Error> {
+        if self.keep.is_some() && self.mode != BackupMode::Incremental {
+            bail!("`--keep` is only valid with `--mode incremental`");
+        }
+        if self.mode == BackupMode::Checkpoint
+            && self.backup_dir.exists()
+            && !is_empty_dir(&self.backup_dir)
+        {
+            bail!(
+                "Cannot create checkpoint at {}: the path exists and is not an empty directory",
+                self.backup_dir.display()
+            );
+        }
+        if !self.db_path.join("CURRENT").is_file() {
+            bail!(
+                "Cannot back up database at {}: the directory does not contain a database",
+                self.db_path.display()
+            );
+        }
+        Ok(())
+    }
+
+    fn backup_stopped(&self, db: &RocksDB) -> Result<Option<u32>, Error> {
+        match self.mode {
+            BackupMode::Checkpoint => {
+                db.create_checkpoint(&self.backup_dir)?;
+                Ok(None)
+            }
+            BackupMode::Incremental => Ok(Some(db.backup(&self.backup_dir)?.id)),
+        }
+    }
+
+    /// Backs up the database opened by a running node. The database cannot be opened
+    /// for writing, and files of a read-only instance may be deleted by the node, so
+    /// a secondary instance is used to obtain a consistent snapshot.
+    fn backup_running(&self, options: &DbOptions) -> Result<Option<u32>, Error> {
+        log::info!(
+            "Database at {} is used by a running node; backing up its current state",
+            self.db_path.display()
+        );
+        let secondary_dir = temp_dir_near(&self.backup_dir)?;
+        let db = RocksDB::open_secondary(&self.db_path, secondary_dir.path(), options)?;
+        db.try_catch_up_with_primary()?;
+
+        let copy_options = copy_options(options);
+        match self.mode {
+            BackupMode::Checkpoint => {
+                copy_database(&db, &copy_options, &self.backup_dir)?;
+                Ok(None)
+            }
+            BackupMode::Incremental => {
+                log::warn!(
+                    "Files of the backup are not shared with the previous backups, \
+                     since the node is running"
+                );
+                let copy_dir = temp_dir_near(&self.backup_dir)?;
+                let copy_path = copy_dir.path().join("db");
+                copy_database(&db, &copy_options, &copy_path)?;
+                let copy = RocksDB::open(&copy_path, &copy_options)?;
+                Ok(Some(copy.backup(&self.backup_dir)?.id))
+            }
+        }
+    }
+}
+
+impl ExonumCommand for Backup {
+    fn execute(self) -> Result<StandardResult, Error> {
+        self.check_args()?;
+        let node_config: NodeConfig = load_config_file(&self.node_config)?;
+        let options = &node_config.private_config.database;
+
+        let start = Instant::now();
+        let backup_id = match RocksDB::open(&self.db_path, options) {
+            Ok(db) => self.backup_stopped(&db)?,
+            Err(e) if is_locked(&e) => self.backup_running(options)?,
+            Err(e) => return Err(e.into()),
+        };
+        let duration = start.elapsed();
+
+        let size = match backup_id {
+            Some(id) => {
+                let info = list_backups(&self.backup_dir)?
+                    .into_iter()
+                    .find(|info| info.id == id);
+                info.map_or(0, |info| info.size)
+            }
+            None => dir_size(&self.backup_dir),
+        };
+        if let Some(keep) = self.keep {
+            purge_old_backups(&self.backup_dir, keep)?;
+        }
+
+        match backup_id {
+            Some(id) => log::info!(
+                "Created backup #{} in {}: {} bytes in {:.1} s",
+                id,
+                self.backup_dir.display(),
+                size,
+                duration.as_secs_f64()
+            ),
+            None => log::info!(
+                "Created checkpoint at {}: {} bytes in {:.1} s",
+                self.backup_dir.display(),
+                size,
+                duration.as_secs_f64()
+            ),
+        }
+
+        Ok(StandardResult::Backup(BackupReport {
+            backup_dir: self.backup_dir,
+            mode: self.mode,
+            backup_id,
+            size,
+            duration,
+        }))
+    }
+}
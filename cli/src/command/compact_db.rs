@@ -0,0 +1,234 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Standard Exonum CLI command used to compact the node database.
+
+use anyhow::{bail, format_err, Error};
+use exonum::merkledb::{self, DbOptions, ErrorKind, RocksDB};
+use serde_derive::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{
+    command::{ExonumCommand, StandardResult},
+    config::NodeConfig,
+    io::load_config_file,
+};
+
+/// Interval between progress reports for long compactions.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+/// Write-ahead log modified more recently than this is considered a sign of a running node.
+const ACTIVITY_THRESHOLD: Duration = Duration::from_secs(30);
+
+/// Compact the node database to reclaim disk space, e.g., after pruning or heavy deletes.
+/// The node must be stopped.
+///
+/// The command always waits for the compaction to finish. A manual compaction runs
+/// in the process executing the command and is cancelled when the process exits, so
+/// the command has no mode returning right after the compaction is started. To avoid
+/// blocking the shell, run the command in the background instead.
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct CompactDb {
+    /// Path to a node configuration file.
+    #[structopt(long, short = "c")]
+    pub node_config: PathBuf,
+
+    /// Path to a database directory.
+    #[structopt(long, short = "d")]
+    pub db_path: PathBuf,
+
+    /// Compact only the specified column family instead of the whole database.
+    #[structopt(long)]
+    pub column_family: Option<String>,
+
+    /// Compact the database even if the node appears to be running. The check is based
+    /// on the recent activity in the database directory, and may produce false positives
+    /// right after the node is stopped.
+    #[structopt(long)]
+    pub force: bool,
+}
+
+/// Outcome of the `compact-db` command.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct CompactionReport {
+    /// Compacted column families.
+    pub column_families: Vec<String>,
+    /// Total size of SST files of the compacted column families before the compaction
+    /// in bytes.
+    pub size_before: u64,
+    /// Total size of SST files of the compacted column families after the compaction
+    /// in bytes.
+    pub size_after: u64,
+}
+
+/// Events sent by the compaction thread.
+enum CompactionEvent {
+    Started { cf_index: usize },
+    Finished(Result<(), exonum::merkledb::Error>),
+}
+
+/// Returns the total size of SST files of the specified column families.
+fn sst_files_size(db: &RocksDB, cf_names: &[String]) -> u64 {
+    cf_names
+        .iter()
+        .filter_map(|name| db.cf_int_property(name, "rocksdb.total-sst-files-size"))
+        .sum()
+}
+
+/// Returns `true` if the database cannot be opened because it is locked, e.g.,
+/// by a running node.
+pub(super) fn is_locked(error: &merkledb::Error) -> bool {
+    error.kind() == ErrorKind::Locked
+}
+
+/// Returns `true` if the write-ahead log of the database has been modified recently,
+/// which indicates that the database is used by a running node.
+pub(super) fn has_recent_activity(db_path: &Path, options: &DbOptions) -> bool {
+    let wal_dir = options.wal_dir.as_deref().unwrap_or(db_path);
+    let now = SystemTime::now();
+    fs::read_dir(wal_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().map_or(false, |ext| ext == "log"))
+        .filter_map(|entry| entry.metadata().ok()?.modified().ok())
+        .any(|modified| {
+            now.duration_since(modified)
+                .map_or(true, |elapsed| elapsed < ACTIVITY_THRESHOLD)
+        })
+}
+
+impl CompactDb {
+    fn open_database(&self, options: &DbOptions) -> Result<RocksDB, Error> {
+        if !self.db_path.join("CURRENT").is_file() {
+            bail!(
+                "Cannot compact database at {}: the directory does not contain a database",
+                self.db_path.display()
+            );
+        }
+        if !self.force && has_recent_activity(&self.db_path, options) {
+            bail!(
+                "Cannot compact database at {}: the database appears to be used by a running \
+                 node. Stop the node, or use `--force` if it is already stopped",
+                self.db_path.display()
+            );
+        }
+
+        RocksDB::open(&self.db_path, options).map_err(|e| {
+            if is_locked(&e) {
+                format_err!(
+                    "Cannot compact database at {}: it appears to be opened by a running \
+                     node ({})",
+                    self.db_path.display(),
+                    e
+                )
+            } else {
+                e.into()
+            }
+        })
+    }
+
+    /// Waits for the compaction to finish, periodically reporting its progress.
+    fn wait_for_compaction(
+        receiver: &mpsc::Receiver<CompactionEvent>,
+        cf_names: &[String],
+    ) -> Result<(), Error> {
+        let start = Instant::now();
+        let mut cf_index = 0;
+        loop {
+            match receiver.recv_timeout(PROGRESS_INTERVAL) {
+                Ok(CompactionEvent::Started { cf_index: index }) => cf_index = index,
+                Ok(CompactionEvent::Finished(result)) => return result.map_err(Error::from),
+                Err(mpsc::RecvTimeoutError::Timeout) => log::info!(
+                    "Compacting column family `{}` ({} of {}), {} s elapsed",
+                    cf_names[cf_index],
+                    cf_index + 1,
+                    cf_names.len(),
+                    start.elapsed().as_secs()
+                ),
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    bail!("Compaction thread has stopped unexpectedly")
+                }
+            }
+        }
+    }
+}
+
+impl ExonumCommand for CompactDb {
+    fn execute(self) -> Result<StandardResult, Error> {
+        let node_config: NodeConfig = load_config_file(&self.node_config)?;
+        let db = Arc::new(self.open_database(&node_config.private_config.database)?);
+
+        let cf_names = match self.column_family {
+            Some(ref name) => {
+                if !db.column_families()?.contains(name) {
+                    bail!("Column family `{}` does not exist", name);
+                }
+                vec![name.clone()]
+            }
+            None => db.column_families()?,
+        };
+        let size_before = sst_files_size(&db, &cf_names);
+        log::info!(
+            "Compacting {} column family(-ies) of database at {}, SST files size: {} bytes",
+            cf_names.len(),
+            self.db_path.display(),
+            size_before
+        );
+
+        let (sender, receiver) = mpsc::channel();
+        let thread_db = Arc::clone(&db);
+        let thread_cf_names = cf_names.clone();
+        thread::spawn(move || {
+            let mut result = Ok(());
+            for (cf_index, name) in thread_cf_names.iter().enumerate() {
+                sender.send(CompactionEvent::Started { cf_index }).ok();
+                result = thread_db.compact_range(Some(name.as_str()), None, None);
+                if result.is_err() {
+                    break;
+                }
+            }
+            sender.send(CompactionEvent::Finished(result)).ok();
+        });
+
+        // The compaction is stopped when the process exits, so the command always waits
+        // for it to finish.
+        Self::wait_for_compaction(&receiver, &cf_names)?;
+        let size_after = sst_files_size(&db, &cf_names);
+        log::info!(
+            "Compaction finished, SST files size: {} bytes (was {} bytes)",
+            size_after,
+            size_before
+        );
+
+        Ok(StandardResult::CompactDb {
+            node_config_path: self.node_config,
+            db_path: self.db_path,
+            report: CompactionReport {
+                column_families: cf_names,
+                size_before,
+                size_after,
+            },
+        })
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Standard Exonum CLI command used to generate shell completion scripts.
+
+use anyhow::{anyhow, Context, Error};
+use serde_derive::{Deserialize, Serialize};
+use structopt::{clap::Shell, StructOpt};
+
+use std::{env, fs, path::PathBuf};
+
+use crate::command::{Command, ExonumCommand, OutputFormat, StandardResult};
+
+/// Shells supported by the `completions` command.
+pub const COMPLETION_SHELLS: &[&str] = &["bash", "zsh", "fish", "powershell"];
+
+/// Generate a shell completion script for all commands of the node.
+///
+/// The script is generated from the command definitions, so it covers all commands
+/// and their arguments available in the current version of the node.
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Completions {
+    /// Shell to generate the completion script for.
+    #[structopt(possible_values = COMPLETION_SHELLS, case_insensitive = true)]
+    pub shell: String,
+
+    /// Name of the node executable to complete. Defaults to the name of the current
+    /// executable.
+    #[structopt(long)]
+    pub bin_name: Option<String>,
+
+    /// Directory to write the completion script to. If not specified, the script
+    /// is printed to stdout.
+    #[structopt(long)]
+    pub out_dir: Option<PathBuf>,
+}
+
+/// Returns the conventional name of the completion script file for the shell.
+fn script_file_name(shell: Shell, bin_name: &str) -> String {
+    match shell {
+        Shell::Zsh => format!("_{}", bin_name),
+        Shell::PowerShell => format!("_{}.ps1", bin_name),
+        Shell::Bash => format!("{}.bash", bin_name),
+        Shell::Fish => format!("{}.fish", bin_name),
+        Shell::Elvish => format!("{}.elv", bin_name),
+    }
+}
+
+fn default_bin_name() -> String {
+    env::current_exe()
+        .ok()
+        .and_then(|path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "node".to_owned())
+}
+
+impl ExonumCommand for Completions {
+    fn execute(self) -> Result<StandardResult, Error> {
+        let shell: Shell = self
+            .shell
+            .to_lowercase()
+            .parse()
+            .map_err(|e| anyhow!("{}", e))?;
+        let bin_name = self.bin_name.unwrap_or_else(default_bin_name);
+
+        let mut script = vec![];
+        Command::clap().gen_completions_to(&bin_name, shell, &mut script);
+        let script = String::from_utf8(script)?;
+
+        let (output_file, content) = if let Some(out_dir) = self.out_dir {
+            fs::create_dir_all(&out_dir)
+                .with_context(|| format!("creating directory {}", out_dir.display()))?;
+            let path = out_dir.join(script_file_name(shell, &bin_name));
+            fs::write(&path, &script)
+                .with_context(|| format!("writing completion script to {}", path.display()))?;
+            (Some(path), None)
+        } else if OutputFormat::is_text() {
+            print!("{}", script);
+            (None, None)
+        } else {
+            (None, Some(script))
+        };
+
+        Ok(StandardResult::Completions {
+            shell: self.shell,
+            output_file,
+            content,
+        })
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Standard Exonum CLI command used to compare node configurations.
+
+use anyhow::Error;
+use serde_derive::{Deserialize, Serialize};
+use structopt::StructOpt;
+use toml::Value;
+
+use std::{collections::BTreeSet, fmt, path::PathBuf};
+
+use crate::{
+    command::{ConfigFieldChange, ExonumCommand, OutputFormat, StandardResult},
+    io::load_config_file,
+};
+
+/// Fields which legitimately differ among the nodes of the same network.
+pub const DEFAULT_IGNORED_FIELDS: &[&str] = &[
+    "private_config.consensus_public_key",
+    "private_config.listen_address",
+    "private_config.external_address",
+    "private_config.master_key_path",
+    "private_config.previous_keys",
+    "private_config.api.public_api_address",
+    "private_config.api.private_api_address",
+    "private_config.connect_list",
+    "public_config.validator_keys",
+    "public_config.address",
+];
+
+/// Compare two node configurations field by field.
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ConfigDiff {
+    /// Path to the first configuration file.
+    pub first_config: PathBuf,
+
+    /// Path to the second configuration file.
+    pub second_config: PathBuf,
+
+    /// Dot-separated path to a field which is not compared, e.g.,
+    /// `private_config.listen_address`. Nested fields of the ignored field are
+    /// not compared either. May be repeated.
+    #[structopt(long, number_of_values = 1)]
+    pub ignore: Vec<String>,
+
+    /// Compare the fields which legitimately differ among the nodes (keys, addresses,
+    /// the connect list), which are ignored by default.
+    #[structopt(long)]
+    pub no_default_ignores: bool,
+
+    /// Print the differences in the JSON format.
+    #[structopt(long)]
+    pub json: bool,
+}
+
+/// Error returned by the `config-diff` command if the configurations differ.
+#[derive(Debug, Clone)]
+pub struct ConfigsDifferError {
+    /// Differing fields. The old value of each change corresponds to the first
+    /// configuration, and the new value to the second one.
+    pub changes: Vec<ConfigFieldChange>,
+}
+
+impl fmt::Display for ConfigsDifferError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            formatter,
+            "Configurations differ in {} field(s)",
+            self.changes.len()
+        )
+    }
+}
+
+impl std::error::Error for ConfigsDifferError {}
+
+impl ConfigDiff {
+    fn is_ignored(&self, field: &str) -> bool {
+        let default_ignores = if self.no_default_ignores {
+            &[][..]
+        } else {
+            DEFAULT_IGNORED_FIELDS
+        };
+        let ignores = default_ignores
+            .iter()
+            .copied()
+            .chain(self.ignore.iter().map(String::as_str));
+        for ignored in ignores {
+            if field == ignored
+                || (field.starts_with(ignored) && field[ignored.len()..].starts_with('.'))
+            {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Compares the values recursively, collecting differing fields into `changes`.
+    fn diff(
+        &self,
+        field: &str,
+        first: Option<&Value>,
+        second: Option<&Value>,
+        changes: &mut Vec<ConfigFieldChange>,
+    ) {
+        if self.is_ignored(field) {
+            return;
+        }
+        let join = |key: &str| {
+            if field.is_empty() {
+                key.to_owned()
+            } else {
+                format!("{}.{}", field, key)
+            }
+        };
+
+        match (first, second) {
+            (Some(Value::Table(first)), Some(Value::Table(second))) => {
+                let keys: BTreeSet<_> = first.keys().chain(second.keys()).collect();
+                for key in keys {
+                    self.diff(&join(key), first.get(key), second.get(key), changes);
+                }
+            }
+            (Some(Value::Array(first)), Some(Value::Array(second)))
+                if first.len() == second.len() =>
+            {
+                for (i, (first, second)) in first.iter().zip(second).enumerate() {
+                    self.diff(&join(&i.to_string()), Some(first), Some(second), changes);
+                }
+            }
+            _ if first != second => changes.push(ConfigFieldChange {
+                field: field.to_owned(),
+                old_value: first.map(ToString::to_string),
+                new_value: second.map(ToString::to_string),
+            }),
+            _ => {}
+        }
+    }
+}
+
+impl ExonumCommand for ConfigDiff {
+    fn execute(self) -> Result<StandardResult, Error> {
+        let first: Value = load_config_file(&self.first_config)?;
+        let second: Value = load_config_file(&self.second_config)?;
+        let mut changes = vec![];
+        self.diff("", Some(&first), Some(&second), &mut changes);
+
+        if OutputFormat::is_text() {
+            if self.json {
+                println!("{}", serde_json::to_string_pretty(&changes)?);
+            } else {
+                for change in &changes {
+                    println!("{}", change);
+                }
+            }
+        }
+
+        if changes.is_empty() {
+            Ok(StandardResult::ConfigDiff {
+                first_config_path: self.first_config,
+                second_config_path: self.second_config,
+            })
+        } else {
+            Err(ConfigsDifferError { changes }.into())
+        }
+    }
+}
@@ -0,0 +1,391 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Standard Exonum CLI command used to print the contents summary of the node database.
+
+use anyhow::{bail, Context, Error};
+use exonum::{
+    merkledb::{Database, IndexInfo, IndexType, RocksDB, Snapshot, SystemSchema},
+    runtime::SnapshotExt,
+};
+use serde_derive::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use std::{
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+};
+
+use crate::{
+    command::{ExonumCommand, OutputFormat, StandardResult},
+    config::NodeConfig,
+    io::load_config_file,
+};
+
+/// Names and expected types of the persisted core and runtime indexes read by the command.
+const BLOCK_HASHES_BY_HEIGHT: (&str, IndexType) = ("core.block_hashes_by_height", IndexType::List);
+const ARTIFACTS: (&str, IndexType) = ("dispatcher_artifacts", IndexType::ProofMap);
+const INSTANCES: (&str, IndexType) = ("dispatcher_instances", IndexType::ProofMap);
+
+/// Print the layout version, indexes, deployed artifacts, service instances and
+/// the latest block height of the node database. The database is opened read-only,
+/// so the command can be used while the node is running.
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DbInfo {
+    /// Path to a node configuration file.
+    #[structopt(long, short = "c")]
+    pub node_config: PathBuf,
+
+    /// Path to a database directory.
+    #[structopt(long, short = "d")]
+    pub db_path: PathBuf,
+
+    /// Print the information in the JSON format.
+    #[structopt(long)]
+    pub json: bool,
+}
+
+/// Index or a group of indexes in the database.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct IndexSummary {
+    /// Name of the index or of the index group.
+    pub name: String,
+    /// Type of the index or of the indexes in the group.
+    pub index_type: IndexType,
+    /// Number of indexes in the group; `None` if the index is not a part of a group.
+    pub group_size: Option<u64>,
+    /// Is the index created within a migration?
+    pub in_migration: bool,
+    /// Estimated number of keys in the column family storing the index.
+    pub estimated_num_keys: Option<u64>,
+    /// Total size of SST files of the column family storing the index in bytes.
+    pub estimated_size: Option<u64>,
+}
+
+/// Service artifact recorded in the dispatcher schema.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ArtifactSummary {
+    /// Artifact identifier, e.g., `0:exonum-supervisor:1.0.0`.
+    pub artifact: String,
+    /// Deployment status of the artifact.
+    pub status: String,
+}
+
+/// Service instance recorded in the dispatcher schema.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct InstanceSummary {
+    /// Numeric identifier of the instance.
+    pub id: u32,
+    /// Name of the instance.
+    pub name: String,
+    /// Artifact the instance is created from.
+    pub artifact: String,
+    /// Status of the instance; `None` if the instance is not yet committed.
+    pub status: Option<String>,
+    /// Version of the service data if it differs from the artifact version.
+    pub data_version: Option<String>,
+}
+
+/// Summary of the node database contents collected by the `db-info` command.
+///
+/// Parts of the summary which cannot be read, e.g., because the database is not
+/// initialized by a node, are left empty, and the reasons are listed in `problems`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DatabaseInfo {
+    /// Path to the database directory.
+    pub db_path: PathBuf,
+    /// `MerkleDB` data layout version recorded in the database.
+    pub db_version: Option<u8>,
+    /// Length of the key prefix used by prefix bloom filters recorded in the database.
+    pub prefix_extractor_len: Option<u64>,
+    /// Indexes in the database ordered by name.
+    pub indexes: Vec<IndexSummary>,
+    /// Service artifacts recorded in the dispatcher schema ordered by identifier.
+    pub artifacts: Vec<ArtifactSummary>,
+    /// Service instances recorded in the dispatcher schema ordered by numeric identifier.
+    pub instances: Vec<InstanceSummary>,
+    /// Height of the latest committed block; `None` if there are no blocks.
+    pub latest_height: Option<u64>,
+    /// Problems encountered while reading the database.
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for DatabaseInfo {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn optional<T: ToString>(value: Option<T>) -> String {
+            value.map_or_else(|| "(not available)".to_owned(), |value| value.to_string())
+        }
+
+        writeln!(formatter, "{:<28}{}", "Database:", self.db_path.display())?;
+        writeln!(
+            formatter,
+            "{:<28}{}",
+            "Layout version:",
+            optional(self.db_version)
+        )?;
+        writeln!(
+            formatter,
+            "{:<28}{}",
+            "Prefix extractor length:",
+            optional(self.prefix_extractor_len)
+        )?;
+        writeln!(
+            formatter,
+            "{:<28}{}",
+            "Latest block height:",
+            optional(self.latest_height)
+        )?;
+
+        writeln!(formatter, "\nIndexes ({}):", self.indexes.len())?;
+        for index in &self.indexes {
+            let mut kind = format!("{:?}", index.index_type);
+            if let Some(group_size) = index.group_size {
+                kind = format!("{} group of {}", kind, group_size);
+            }
+            if index.in_migration {
+                kind += ", migration";
+            }
+            writeln!(
+                formatter,
+                "  {:<48}{:<28}keys: {}, size: {}",
+                index.name,
+                kind,
+                optional(index.estimated_num_keys),
+                optional(index.estimated_size)
+            )?;
+        }
+
+        writeln!(formatter, "\nArtifacts ({}):", self.artifacts.len())?;
+        for artifact in &self.artifacts {
+            writeln!(formatter, "  {} ({})", artifact.artifact, artifact.status)?;
+        }
+
+        write!(formatter, "\nInstances ({}):", self.instances.len())?;
+        for instance in &self.instances {
+            write!(
+                formatter,
+                "\n  {} {} ({}, {})",
+                instance.id,
+                instance.name,
+                instance.artifact,
+                instance.status.as_deref().unwrap_or("pending")
+            )?;
+            if let Some(ref data_version) = instance.data_version {
+                write!(formatter, ", data version {}", data_version)?;
+            }
+        }
+
+        if !self.problems.is_empty() {
+            write!(formatter, "\n\nProblems:")?;
+            for problem in &self.problems {
+                write!(formatter, "\n  {}", problem)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs `read` catching panics, which may occur if the database contents are corrupted.
+fn read_guarded<T>(what: &str, read: impl FnOnce() -> T) -> Result<T, String> {
+    panic::catch_unwind(AssertUnwindSafe(read)).map_err(|e| {
+        let message = e
+            .downcast_ref::<&str>()
+            .map(|s| (*s).to_owned())
+            .or_else(|| e.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        format!("Cannot read {}: {}", what, message)
+    })
+}
+
+/// Merges indexes in the same group into a single summary.
+fn summarize_indexes(indexes: Vec<IndexInfo>, db: &RocksDB) -> Vec<IndexSummary> {
+    let mut summaries: Vec<IndexSummary> = vec![];
+    for index in indexes {
+        if index.index_type == IndexType::Tombstone {
+            continue;
+        }
+        if let Some(last) = summaries.last_mut() {
+            if index.is_in_group
+                && last.name == index.name
+                && last.in_migration == index.is_in_migration
+            {
+                last.group_size = last.group_size.map(|size| size + 1);
+                continue;
+            }
+        }
+        summaries.push(IndexSummary {
+            estimated_num_keys: db.cf_int_property(&index.name, "rocksdb.estimate-num-keys"),
+            estimated_size: db.cf_int_property(&index.name, "rocksdb.total-sst-files-size"),
+            name: index.name,
+            index_type: index.index_type,
+            group_size: if index.is_in_group { Some(1) } else { None },
+            in_migration: index.is_in_migration,
+        });
+    }
+    summaries.sort_by(|a, b| {
+        a.name
+            .cmp(&b.name)
+            .then(a.in_migration.cmp(&b.in_migration))
+    });
+    summaries
+}
+
+impl DatabaseInfo {
+    /// Checks that the index exists and has the expected type before it is accessed,
+    /// since accessing an index with a wrong type panics.
+    fn check_index(&mut self, (name, index_type): (&str, IndexType)) -> bool {
+        let actual_type = self
+            .indexes
+            .iter()
+            .find(|index| index.name == name && !index.in_migration)
+            .map(|index| index.index_type);
+        match actual_type {
+            Some(actual_type) if actual_type == index_type => true,
+            Some(actual_type) => {
+                self.problems.push(format!(
+                    "Index `{}` has type {:?} instead of {:?}",
+                    name, actual_type, index_type
+                ));
+                false
+            }
+            None => false,
+        }
+    }
+
+    fn read_runtime_data(&mut self, snapshot: &dyn Snapshot) {
+        if self.check_index(BLOCK_HASHES_BY_HEIGHT) {
+            let len = read_guarded("block hashes", || {
+                snapshot.for_core().block_hashes_by_height().len()
+            });
+            match len {
+                Ok(len) => self.latest_height = len.checked_sub(1),
+                Err(problem) => self.problems.push(problem),
+            }
+        } else {
+            self.problems
+                .push("Core schema is not initialized: there are no blocks".to_owned());
+        }
+
+        if self.check_index(ARTIFACTS) {
+            let artifacts = read_guarded("service artifacts", || {
+                let schema = snapshot.for_dispatcher();
+                let artifacts = schema.service_artifacts();
+                artifacts
+                    .iter()
+                    .map(|(artifact, state)| ArtifactSummary {
+                        artifact: artifact.to_string(),
+                        status: state.status.to_string(),
+                    })
+                    .collect()
+            });
+            match artifacts {
+                Ok(mut artifacts) => {
+                    // Proof maps are ordered by key hashes, so entries are sorted explicitly.
+                    artifacts.sort_by(|a: &ArtifactSummary, b| a.artifact.cmp(&b.artifact));
+                    self.artifacts = artifacts;
+                }
+                Err(problem) => self.problems.push(problem),
+            }
+        }
+
+        if self.check_index(INSTANCES) {
+            let instances = read_guarded("service instances", || {
+                let schema = snapshot.for_dispatcher();
+                let instances = schema.service_instances();
+                instances
+                    .values()
+                    .map(|state| InstanceSummary {
+                        id: state.spec.id,
+                        name: state.spec.name.clone(),
+                        artifact: state.spec.artifact.to_string(),
+                        status: state.status.as_ref().map(ToString::to_string),
+                        data_version: state.data_version.as_ref().map(ToString::to_string),
+                    })
+                    .collect()
+            });
+            match instances {
+                Ok(mut instances) => {
+                    instances.sort_by_key(|instance: &InstanceSummary| instance.id);
+                    self.instances = instances;
+                }
+                Err(problem) => self.problems.push(problem),
+            }
+        }
+    }
+}
+
+impl DbInfo {
+    fn collect(&self) -> Result<DatabaseInfo, Error> {
+        if !self.db_path.join("CURRENT").is_file() {
+            bail!(
+                "Cannot read database at {}: the directory does not contain a database",
+                self.db_path.display()
+            );
+        }
+
+        let node_config: NodeConfig = load_config_file(&self.node_config)?;
+        let options = &node_config.private_config.database;
+        let db = RocksDB::open_read_only(&self.db_path, options, false)
+            .with_context(|| format!("opening database at {}", self.db_path.display()))?;
+        let snapshot = db.snapshot();
+        let system_schema = SystemSchema::new(snapshot.as_ref());
+
+        let mut info = DatabaseInfo {
+            db_path: self.db_path.clone(),
+            ..DatabaseInfo::default()
+        };
+        match read_guarded("layout version", || system_schema.db_version()) {
+            Ok(Some(db_version)) => info.db_version = Some(db_version),
+            Ok(None) => info
+                .problems
+                .push("Layout version is not recorded in the database".to_owned()),
+            Err(problem) => info.problems.push(problem),
+        }
+        match read_guarded("prefix extractor length", || {
+            system_schema.prefix_extractor_len()
+        }) {
+            Ok(len) => info.prefix_extractor_len = len,
+            Err(problem) => info.problems.push(problem),
+        }
+        match read_guarded("index metadata", || system_schema.indexes()) {
+            Ok(indexes) => {
+                info.indexes = summarize_indexes(indexes, &db);
+                info.read_runtime_data(snapshot.as_ref());
+            }
+            Err(problem) => info.problems.push(problem),
+        }
+        Ok(info)
+    }
+}
+
+impl ExonumCommand for DbInfo {
+    fn execute(self) -> Result<StandardResult, Error> {
+        let info = self.collect()?;
+        // In the JSON output format, the result is printed by the command dispatcher.
+        if OutputFormat::is_text() {
+            if self.json {
+                println!("{}", serde_json::to_string_pretty(&info)?);
+            } else {
+                println!("{}", info);
+            }
+        }
+        Ok(StandardResult::DbInfo(info))
+    }
+}
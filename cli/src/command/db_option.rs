@@ -0,0 +1,246 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ad-hoc overrides of the database options from the command line.
+
+use anyhow::{anyhow, bail, Error};
+use exonum::merkledb::DbOptions;
+use serde_derive::{Deserialize, Serialize};
+use toml::{value::Table, Value};
+
+use std::{fmt, str::FromStr};
+
+/// Override of a single database option in the `name=value` format, e.g.,
+/// `max_open_files=512`.
+///
+/// The name is a key of the `private_config.database` section of the node configuration;
+/// options of nested tables are addressed with dotted keys, e.g.,
+/// `column_family_overrides.default.compression_type=lz4`. The value is parsed as a TOML
+/// value; values which are not valid TOML, such as `lz4` or `/mnt/wal`, are treated
+/// as strings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DbOptionOverride {
+    /// Name of the option.
+    pub name: String,
+    /// Value of the option.
+    pub value: String,
+}
+
+impl DbOptionOverride {
+    /// Applies the override to the database options.
+    ///
+    /// The options are serialized, the overridden value is merged into the serialized
+    /// options, and the result is deserialized back, so that the override is checked
+    /// in the same way as the options in the configuration file.
+    pub fn apply(&self, options: &mut DbOptions) -> Result<(), Error> {
+        let mut serialized = Value::try_from(&*options)?;
+        set_value(&mut serialized, &self.name, self.parse_value()?)?;
+        *options = serialized
+            .try_into()
+            .map_err(|e| anyhow!("Invalid database option `{}`: {}", self, e))?;
+        Ok(())
+    }
+
+    fn parse_value(&self) -> Result<Value, Error> {
+        if self.value.is_empty() {
+            bail!("Invalid database option `{}`: value is empty", self);
+        }
+        let value = format!("value = {}", self.value)
+            .parse::<Value>()
+            .ok()
+            .and_then(|mut document| document.as_table_mut()?.remove("value"))
+            .unwrap_or_else(|| Value::String(self.value.clone()));
+        Ok(value)
+    }
+}
+
+/// Sets the value at the dotted `path` in the TOML table, creating intermediate tables
+/// if necessary.
+fn set_value(target: &mut Value, path: &str, value: Value) -> Result<(), Error> {
+    let keys: Vec<_> = path.split('.').collect();
+    if keys.iter().any(|key| key.is_empty()) {
+        bail!("Invalid name of database option: `{}`", path);
+    }
+
+    let (last_key, parent_keys) = keys.split_last().expect("`split` returns a non-empty list");
+    let mut table = as_table(target, path)?;
+    for &key in parent_keys {
+        if !table.contains_key(key) {
+            table.insert(key.to_owned(), Value::Table(Table::new()));
+        }
+        table = as_table(table.get_mut(key).unwrap(), path)?;
+    }
+    table.insert((*last_key).to_owned(), value);
+    Ok(())
+}
+
+fn as_table<'a>(value: &'a mut Value, path: &str) -> Result<&'a mut Table, Error> {
+    value
+        .as_table_mut()
+        .ok_or_else(|| anyhow!("Invalid name of database option `{}`: not a table", path))
+}
+
+impl fmt::Display for DbOptionOverride {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{}={}", self.name, self.value)
+    }
+}
+
+impl FromStr for DbOptionOverride {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pos = s
+            .find('=')
+            .ok_or_else(|| anyhow!("Database option must have `name=value` format: {}", s))?;
+        let option = Self {
+            name: s[..pos].trim().to_owned(),
+            value: s[pos + 1..].trim().to_owned(),
+        };
+        // Check the name and the value, so that errors are reported during argument parsing.
+        option.apply(&mut DbOptions::default())?;
+        Ok(option)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use exonum::merkledb::{
+        CompactionStyle, CompressionType, LogVerbosity, OpenRetries, OptionsError,
+    };
+
+    use std::{path::PathBuf, time::Duration};
+
+    use super::*;
+
+    #[test]
+    fn db_option_parsing() {
+        let option: DbOptionOverride = "max_open_files=512".parse().unwrap();
+        assert_eq!(option.name, "max_open_files");
+        assert_eq!(option.value, "512");
+        assert_eq!(option.to_string(), "max_open_files=512");
+
+        let mut options = DbOptions::default();
+        option.apply(&mut options).unwrap();
+        assert_eq!(options.max_open_files, Some(512));
+
+        let options_to_apply = [
+            "create_if_missing=false",
+            "compression_type = zstd",
+            "log_verbosity=\"debug\"",
+            "compaction_style=universal",
+            "wal_dir=/tmp/wal",
+            "compression_per_level = [\"none\", \"none\", \"lz4\"]",
+            "open_retries = { attempts = 3, backoff_millis = 100 }",
+            "column_family_overrides.default.compression_type=lz4",
+        ];
+        for option in &options_to_apply {
+            let option: DbOptionOverride = option.parse().unwrap();
+            option.apply(&mut options).unwrap();
+        }
+        assert!(!options.create_if_missing);
+        assert_eq!(options.compression_type, CompressionType::Zstd);
+        assert_eq!(options.log_verbosity, Some(LogVerbosity::Debug));
+        assert_eq!(options.compaction_style, Some(CompactionStyle::Universal));
+        assert_eq!(options.wal_dir, Some(PathBuf::from("/tmp/wal")));
+        assert_eq!(
+            options.compression_per_level,
+            Some(vec![
+                CompressionType::None,
+                CompressionType::None,
+                CompressionType::Lz4
+            ])
+        );
+        assert_eq!(
+            options.open_retries,
+            Some(OpenRetries::new(3, Duration::from_millis(100)))
+        );
+        assert_eq!(
+            options.column_family_overrides["default"].compression_type,
+            Some(CompressionType::Lz4)
+        );
+        // Other options are not changed.
+        assert_eq!(options.max_open_files, Some(512));
+    }
+
+    #[test]
+    fn preset_override() {
+        let mut options = DbOptions::default();
+        let option: DbOptionOverride = "max_open_files=1024".parse().unwrap();
+        option.apply(&mut options).unwrap();
+        let option: DbOptionOverride = "preset=small-node".parse().unwrap();
+        option.apply(&mut options).unwrap();
+
+        // Options set explicitly take precedence over the preset.
+        let mut expected = DbOptions::small_node();
+        expected.max_open_files = Some(1024);
+        expected.compression_type = CompressionType::None;
+        assert_eq!(options, expected);
+    }
+
+    #[test]
+    fn invalid_db_options() {
+        let err = "max_open_files".parse::<DbOptionOverride>().unwrap_err();
+        assert!(err.to_string().contains("`name=value` format"), "{}", err);
+
+        let err = "max_open_files=".parse::<DbOptionOverride>().unwrap_err();
+        assert!(err.to_string().contains("value is empty"), "{}", err);
+
+        let err = "max_open_files=many"
+            .parse::<DbOptionOverride>()
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("Invalid database option `max_open_files=many`"),
+            "{}",
+            err
+        );
+        let err = "atomic_flush=1".parse::<DbOptionOverride>().unwrap_err();
+        assert!(err.to_string().contains("`atomic_flush=1`"), "{}", err);
+        let err = "wal_sync_mode=fast"
+            .parse::<DbOptionOverride>()
+            .unwrap_err();
+        assert!(err.to_string().contains("`wal_sync_mode=fast`"), "{}", err);
+        let err = "max_open_files.limit=1"
+            .parse::<DbOptionOverride>()
+            .unwrap_err();
+        assert!(err.to_string().contains("not a table"), "{}", err);
+
+        let err = "max-open-files=512"
+            .parse::<DbOptionOverride>()
+            .unwrap_err();
+        assert!(
+            err.to_string().contains(
+                "unknown database option `max-open-files`; did you mean `max_open_files`?"
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn overrides_are_not_validated() {
+        // Options are validated after all overrides are applied.
+        let mut options = DbOptions::default();
+        let option: DbOptionOverride = "write_buffer_size=67108864".parse().unwrap();
+        option.apply(&mut options).unwrap();
+        let option: DbOptionOverride = "max_total_wal_size=1048576".parse().unwrap();
+        option.apply(&mut options).unwrap();
+        assert!(matches!(
+            options.validate().unwrap_err(),
+            OptionsError::WalSizeBelowWriteBuffer { .. }
+        ));
+    }
+}
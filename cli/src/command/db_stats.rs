@@ -0,0 +1,175 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Standard Exonum CLI command used to print statistics of the node database.
+
+use anyhow::{bail, Error};
+use exonum::merkledb::{Database, RocksDB};
+use serde_derive::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    command::{ExonumCommand, OutputFormat, StandardResult},
+    config::NodeConfig,
+    io::load_config_file,
+};
+
+/// Print statistics of the node database. The database is opened read-only,
+/// so the command can be used while the node is running.
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DbStats {
+    /// Path to a node configuration file.
+    #[structopt(long, short = "c")]
+    pub node_config: PathBuf,
+
+    /// Path to a database directory.
+    #[structopt(long, short = "d")]
+    pub db_path: PathBuf,
+
+    /// Print statistics in the JSON format instead of a table.
+    #[structopt(long)]
+    pub json: bool,
+}
+
+/// Statistics of the node database collected by the `db-stats` command.
+///
+/// Estimated values are `None` if the corresponding database property is not available.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DatabaseStats {
+    /// Path to the database directory.
+    pub db_path: PathBuf,
+    /// Total size of the files in the database directory in bytes.
+    pub size_on_disk: u64,
+    /// Total size of the write-ahead log files in bytes.
+    pub wal_size: u64,
+    /// Number of column families in the database.
+    pub column_families: usize,
+    /// Estimated number of keys in all column families.
+    pub estimated_num_keys: Option<u64>,
+    /// Estimated size of the live data in bytes.
+    pub estimated_live_data_size: Option<u64>,
+    /// Estimated number of bytes which pending compactions need to rewrite.
+    pub pending_compaction_bytes: Option<u64>,
+    /// Number of currently running compactions.
+    pub running_compactions: Option<u64>,
+}
+
+impl fmt::Display for DatabaseStats {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn estimate(value: Option<u64>) -> String {
+            value.map_or_else(|| "(not available)".to_owned(), |value| value.to_string())
+        }
+
+        let rows = [
+            ("Database", self.db_path.display().to_string()),
+            ("Size on disk (bytes)", self.size_on_disk.to_string()),
+            ("WAL size (bytes)", self.wal_size.to_string()),
+            ("Column families", self.column_families.to_string()),
+            ("Estimated keys", estimate(self.estimated_num_keys)),
+            (
+                "Live data size (bytes)",
+                estimate(self.estimated_live_data_size),
+            ),
+            (
+                "Compaction backlog (bytes)",
+                estimate(self.pending_compaction_bytes),
+            ),
+            ("Running compactions", estimate(self.running_compactions)),
+        ];
+        for (i, (name, value)) in rows.iter().enumerate() {
+            if i > 0 {
+                writeln!(formatter)?;
+            }
+            write!(formatter, "{:<28}{}", format!("{}:", name), value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the total size of files in the directory (recursively), for which `filter` returns
+/// `true`. Files which cannot be accessed, e.g., because they were removed concurrently
+/// by the running node, are ignored.
+fn dir_size(dir: &Path, filter: &dyn Fn(&Path) -> bool) -> u64 {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| {
+            let path = entry.path();
+            match entry.metadata() {
+                Ok(metadata) if metadata.is_dir() => dir_size(&path, filter),
+                Ok(metadata) if filter(&path) => metadata.len(),
+                _ => 0,
+            }
+        })
+        .sum()
+}
+
+fn is_wal_file(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |extension| extension == "log")
+}
+
+impl DbStats {
+    fn collect(&self) -> Result<DatabaseStats, Error> {
+        if !self.db_path.join("CURRENT").is_file() {
+            bail!(
+                "Cannot collect statistics of database at {}: the directory does not \
+                 contain a database",
+                self.db_path.display()
+            );
+        }
+
+        let node_config: NodeConfig = load_config_file(&self.node_config)?;
+        let options = &node_config.private_config.database;
+        let db = RocksDB::open_read_only(&self.db_path, options, false)?;
+        let db_stats = db.stats();
+        let wal_dir = options.wal_dir.as_deref().unwrap_or(self.db_path.as_path());
+
+        Ok(DatabaseStats {
+            db_path: self.db_path.clone(),
+            size_on_disk: dir_size(&self.db_path, &|_| true),
+            wal_size: dir_size(wal_dir, &is_wal_file),
+            column_families: db.column_families()?.len(),
+            estimated_num_keys: db_stats.estimated_num_keys,
+            estimated_live_data_size: db_stats.estimated_live_data_size,
+            pending_compaction_bytes: db_stats.pending_compaction_bytes,
+            running_compactions: db_stats.running_compactions,
+        })
+    }
+}
+
+impl ExonumCommand for DbStats {
+    fn execute(self) -> Result<StandardResult, Error> {
+        let stats = self.collect()?;
+        // In the JSON output format, the result is printed by the command dispatcher.
+        if OutputFormat::is_text() {
+            if self.json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+            } else {
+                println!("{}", stats);
+            }
+        }
+        Ok(StandardResult::DbStats(stats))
+    }
+}
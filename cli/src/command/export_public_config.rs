@@ -0,0 +1,174 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Standard Exonum CLI command used to export the public part of the node configuration.
+
+use anyhow::{anyhow, bail, Context, Error};
+use exonum::crypto::{hash, Hash};
+use serde_derive::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use std::{fs, io::Write, path::PathBuf};
+
+use crate::{
+    command::{ExonumCommand, OutputFormat, StandardResult},
+    config::{NodeConfig, NodePublicConfig},
+    io::{load_config_file, write_file_atomically},
+};
+
+/// Prefix of the header comment containing the hex-encoded SHA-256 hash of the exported
+/// content, i.e., of everything after the header line.
+pub const CONTENT_HASH_PREFIX: &str = "# content-sha256: ";
+
+/// Export the public part of the node configuration shared by all nodes of the network:
+/// the consensus configuration and the general parameters. The node keys, addresses,
+/// local paths and database tuning are not exported.
+///
+/// The exported file has the same format as the template produced by `generate-template`,
+/// so it can be used to bootstrap additional nodes with `generate-config`.
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ExportPublicConfig {
+    /// Path to node configuration file (node.toml).
+    pub node_config_file: PathBuf,
+
+    /// Where to write the exported configuration.
+    #[structopt(required_unless = "stdout")]
+    pub output_file: Option<PathBuf>,
+
+    /// Print the exported configuration to stdout instead of writing it to a file.
+    #[structopt(long, conflicts_with = "output-file")]
+    pub stdout: bool,
+}
+
+/// Serializes the public config, prepending the header with the content hash.
+fn export(config: &NodePublicConfig) -> Result<(String, Hash), Error> {
+    let content = toml::Value::try_from(config)?.to_string();
+    let content_hash = hash(content.as_bytes());
+    Ok((
+        format!("{}{}\n{}", CONTENT_HASH_PREFIX, content_hash, content),
+        content_hash,
+    ))
+}
+
+/// Checks the content hash in the header of the exported config and parses the config.
+fn verify(exported: &str) -> Result<NodePublicConfig, Error> {
+    let pos = exported
+        .find('\n')
+        .ok_or_else(|| anyhow!("Exported config has no content"))?;
+    let (header, content) = (&exported[..pos], &exported[pos + 1..]);
+    if !header.starts_with(CONTENT_HASH_PREFIX) {
+        bail!("Exported config has no content hash header");
+    }
+    let expected_hash: Hash = header[CONTENT_HASH_PREFIX.len()..]
+        .parse()
+        .context("Invalid content hash in the header")?;
+    if hash(content.as_bytes()) != expected_hash {
+        bail!("Content hash in the header does not match the exported config");
+    }
+    Ok(toml::from_str(exported)?)
+}
+
+impl ExonumCommand for ExportPublicConfig {
+    fn execute(self) -> Result<StandardResult, Error> {
+        let config: NodeConfig = load_config_file(&self.node_config_file)?;
+        let public_config = NodePublicConfig {
+            validator_keys: None,
+            address: None,
+            ..config.public_config
+        };
+        let (exported, content_hash) = export(&public_config)?;
+
+        let (exported, output_file) = if self.stdout {
+            (exported, None)
+        } else {
+            let path = self
+                .output_file
+                .ok_or_else(|| anyhow!("Output file is not specified"))?;
+            write_file_atomically(&path, |tmp_file| {
+                tmp_file.write_all(exported.as_bytes())?;
+                Ok(())
+            })
+            .with_context(|| format!("writing exported config to {}", path.display()))?;
+            let exported = fs::read_to_string(&path)?;
+            (exported, Some(path))
+        };
+
+        // Check that the exported config round-trips.
+        if verify(&exported)? != public_config {
+            bail!("Exported config differs from the public part of the node configuration");
+        }
+
+        let content = if self.stdout {
+            if OutputFormat::is_text() {
+                print!("{}", exported);
+                None
+            } else {
+                Some(exported)
+            }
+        } else {
+            None
+        };
+        Ok(StandardResult::ExportPublicConfig {
+            output_file,
+            content_hash,
+            content,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use exonum::blockchain::ConsensusConfig;
+    use exonum_supervisor::mode::Mode as SupervisorMode;
+
+    use super::*;
+    use crate::config::GeneralConfig;
+
+    fn public_config() -> NodePublicConfig {
+        NodePublicConfig {
+            consensus: ConsensusConfig::default(),
+            general: GeneralConfig {
+                validators_count: 4,
+                supervisor_mode: SupervisorMode::Simple,
+            },
+            validator_keys: None,
+            address: None,
+        }
+    }
+
+    #[test]
+    fn exported_config_round_trips() {
+        let config = public_config();
+        let (exported, content_hash) = export(&config).unwrap();
+        assert!(exported.starts_with(&format!("{}{}\n", CONTENT_HASH_PREFIX, content_hash)));
+        assert_eq!(verify(&exported).unwrap(), config);
+    }
+
+    #[test]
+    fn modified_exported_config_is_rejected() {
+        let (exported, _) = export(&public_config()).unwrap();
+        let modified = exported.replace("validators_count = 4", "validators_count = 5");
+        assert_ne!(modified, exported);
+        let err = verify(&modified).unwrap_err();
+        assert!(err.to_string().contains("does not match"), "{}", err);
+
+        let err = verify(&exported.replacen("# content", "# hash", 1)).unwrap_err();
+        assert!(
+            err.to_string().contains("no content hash header"),
+            "{}",
+            err
+        );
+    }
+}
@@ -175,6 +175,7 @@ impl ExonumCommand for GenerateConfig {
             thread_pool_size: None,
             connect_list: ConnectListConfig::default(),
             consensus_public_key: keys.consensus_pk(),
+            previous_keys: vec![],
         };
 
         save_config_file(&private_config, &private_config_path)?;
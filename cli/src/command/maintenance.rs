@@ -14,8 +14,8 @@
 
 //! Standard Exonum CLI command used to perform different maintenance actions.
 
-use anyhow::Error;
-use exonum::merkledb::{migration::rollback_migration, Database, RocksDB};
+use anyhow::{bail, format_err, Error};
+use exonum::merkledb::{migration::rollback_migration, repair, Database, DbOptions, RocksDB};
 use exonum::runtime::remove_local_migration_result;
 use exonum_node::helpers::clear_consensus_messages_cache;
 use serde_derive::{Deserialize, Serialize};
@@ -23,7 +23,10 @@ use std::path::{Path, PathBuf};
 use structopt::StructOpt;
 
 use crate::{
-    command::{ExonumCommand, StandardResult},
+    command::{
+        compact_db::{has_recent_activity, is_locked},
+        ExonumCommand, OutputFormat, StandardResult,
+    },
     config::NodeConfig,
     io::load_config_file,
 };
@@ -40,6 +43,12 @@ pub struct Maintenance {
     #[structopt(long, short = "d")]
     pub db_path: PathBuf,
 
+    /// Perform the action even if the node appears to be running. The check is based
+    /// on the recent activity in the database directory, and may produce false positives
+    /// right after the node is stopped.
+    #[structopt(long)]
+    pub force: bool,
+
     /// Action to be performed.
     #[structopt(subcommand)]
     pub action: MaintenanceAction,
@@ -49,7 +58,7 @@ pub struct Maintenance {
 #[derive(StructOpt, Debug, Clone, Serialize, Deserialize)]
 #[non_exhaustive]
 pub enum MaintenanceAction {
-    /// Clear consensus messages cache.
+    /// Clear consensus messages cache. The blockchain state is not affected.
     #[structopt(name = "clear-cache")]
     ClearCache,
 
@@ -59,31 +68,61 @@ pub enum MaintenanceAction {
         /// Name of the service for migration restart, e.g. "explorer" or "my-service".
         service_name: String,
     },
+
+    /// Repair a corrupted database. The node must be stopped. Files which cannot be
+    /// recovered are moved to the `lost` subdirectory of the database directory.
+    #[structopt(name = "repair-db")]
+    RepairDb,
+}
+
+/// Fails if the database appears to be used by a running node, unless `force` is set.
+fn check_node_stopped(db_path: &Path, options: &DbOptions, force: bool) -> Result<(), Error> {
+    if !force && has_recent_activity(db_path, options) {
+        bail!(
+            "Database at {} appears to be used by a running node. Stop the node, \
+             or use `--force` if it is already stopped",
+            db_path.display()
+        );
+    }
+    Ok(())
 }
 
 impl MaintenanceAction {
-    fn clear_cache(node_config: &Path, db_path: &Path) -> Result<(), Error> {
+    /// Opens the node database, refusing to do so if the node appears to be running.
+    fn open_database(node_config: &Path, db_path: &Path, force: bool) -> Result<RocksDB, Error> {
         let node_config: NodeConfig = load_config_file(node_config)?;
-        let db: Box<dyn Database> = Box::new(RocksDB::open(
-            db_path,
-            &node_config.private_config.database,
-        )?);
+        let options = &node_config.private_config.database;
+        check_node_stopped(db_path, options, force)?;
+
+        RocksDB::open(db_path, options).map_err(|e| {
+            if is_locked(&e) {
+                format_err!(
+                    "Cannot open database at {}: it appears to be opened by a running node ({})",
+                    db_path.display(),
+                    e
+                )
+            } else {
+                e.into()
+            }
+        })
+    }
+
+    /// Returns the number of removed cache entries.
+    fn clear_cache(node_config: &Path, db_path: &Path, force: bool) -> Result<u64, Error> {
+        let db = Self::open_database(node_config, db_path, force)?;
         let fork = db.fork();
-        clear_consensus_messages_cache(&fork);
+        let removed_entries = clear_consensus_messages_cache(&fork);
         db.merge_sync(fork.into_patch())?;
-        Ok(())
+        Ok(removed_entries)
     }
 
     fn restart_migration(
         node_config: &Path,
         db_path: &Path,
         service_name: &str,
+        force: bool,
     ) -> Result<(), Error> {
-        let node_config: NodeConfig = load_config_file(node_config)?;
-        let db: Box<dyn Database> = Box::new(RocksDB::open(
-            db_path,
-            &node_config.private_config.database,
-        )?);
+        let db = Self::open_database(node_config, db_path, force)?;
         let mut fork = db.fork();
         rollback_migration(&mut fork, service_name);
         remove_local_migration_result(&fork, service_name);
@@ -91,27 +130,57 @@ impl MaintenanceAction {
 
         Ok(())
     }
+
+    fn repair_db(node_config: &Path, db_path: &Path, force: bool) -> Result<(), Error> {
+        let node_config: NodeConfig = load_config_file(node_config)?;
+        check_node_stopped(db_path, &node_config.private_config.database, force)?;
+        let report = repair(db_path, &node_config.private_config.database)?;
+        log::info!(
+            "Repaired database at {}: {} column families, {} SST files",
+            db_path.display(),
+            report.column_families.len(),
+            report.sst_files
+        );
+        for lost_file in &report.lost_files {
+            log::warn!(
+                "File {} could not be recovered and was moved aside",
+                lost_file.display()
+            );
+        }
+        Ok(())
+    }
 }
 
 impl ExonumCommand for Maintenance {
     fn execute(self) -> Result<StandardResult, Error> {
+        let mut removed_entries = None;
         match self.action {
             MaintenanceAction::ClearCache => {
-                MaintenanceAction::clear_cache(&self.node_config, &self.db_path)?
+                let removed =
+                    MaintenanceAction::clear_cache(&self.node_config, &self.db_path, self.force)?;
+                if OutputFormat::is_text() {
+                    println!("Removed {} consensus messages from the cache", removed);
+                }
+                removed_entries = Some(removed);
             }
             MaintenanceAction::RestartMigration { ref service_name } => {
                 MaintenanceAction::restart_migration(
                     &self.node_config,
                     &self.db_path,
                     service_name,
+                    self.force,
                 )?
             }
+            MaintenanceAction::RepairDb => {
+                MaintenanceAction::repair_db(&self.node_config, &self.db_path, self.force)?
+            }
         }
 
         Ok(StandardResult::Maintenance {
             node_config_path: self.node_config,
             db_path: self.db_path,
             performed_action: self.action,
+            removed_entries,
         })
     }
 }
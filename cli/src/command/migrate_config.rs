@@ -0,0 +1,466 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Standard Exonum CLI command used to upgrade node configurations written
+//! by earlier releases.
+
+use anyhow::{anyhow, bail, Error};
+use exonum::blockchain::ConsensusConfig;
+use exonum_node::{ConnectListConfig, MemoryPoolConfig, NetworkConfiguration, NodeApiConfig};
+use serde_derive::{Deserialize, Serialize};
+use structopt::StructOpt;
+use toml::{value::Table, Value};
+
+use std::{fmt, path::PathBuf};
+
+use crate::{
+    command::{ExonumCommand, OutputFormat, StandardResult},
+    config::NodeConfig,
+    io::{load_config_file, save_config_file_atomically},
+};
+
+/// Keys renamed in the node configuration, as `(legacy, current)` pairs of
+/// dot-separated paths.
+pub const LEGACY_KEYS: &[(&str, &str)] = &[
+    (
+        "private_config.listen_addr",
+        "private_config.listen_address",
+    ),
+    (
+        "private_config.external_addr",
+        "private_config.external_address",
+    ),
+    ("public_config.addr", "public_config.address"),
+    (
+        "public_config.consensus.round_timeout",
+        "public_config.consensus.first_round_timeout",
+    ),
+];
+
+/// Upgrade the node configuration written by an earlier release: rename legacy keys
+/// and fill in the missing sections and fields with the default values.
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct MigrateConfig {
+    /// Path to node configuration file (node.toml).
+    pub node_config_file: PathBuf,
+
+    /// Where to store the migrated node configuration.
+    ///
+    /// Default: overwrite the input file.
+    #[structopt(long, short = "o")]
+    pub output_file: Option<PathBuf>,
+
+    /// Print the transformations which would be performed without writing any files.
+    #[structopt(long)]
+    pub check: bool,
+}
+
+/// Transformation performed by the `migrate-config` command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ConfigMigration {
+    /// Legacy key was renamed.
+    RenamedKey {
+        /// Legacy dot-separated path to the field.
+        from: String,
+        /// Current dot-separated path to the field.
+        to: String,
+    },
+    /// Missing field or section was added with the default value.
+    AddedDefault {
+        /// Dot-separated path to the field.
+        field: String,
+        /// Added value in the TOML format.
+        value: String,
+    },
+}
+
+impl fmt::Display for ConfigMigration {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RenamedKey { from, to } => write!(formatter, "renamed `{}` to `{}`", from, to),
+            Self::AddedDefault { field, value } => {
+                write!(formatter, "added `{}` = {}", field, value)
+            }
+        }
+    }
+}
+
+/// Splits a dot-separated path into the path to the parent table and the key.
+fn split_path(path: &str) -> (Vec<&str>, &str) {
+    let mut parts: Vec<_> = path.split('.').collect();
+    let key = parts.pop().expect("Empty path");
+    (parts, key)
+}
+
+/// Returns the table at the dot-separated path, or `None` if the path does not exist
+/// or does not point to a table.
+fn table_mut<'a>(root: &'a mut Table, path: &[&str]) -> Option<&'a mut Table> {
+    path.iter()
+        .try_fold(root, |table, key| table.get_mut(*key)?.as_table_mut())
+}
+
+fn rename_legacy_keys(
+    root: &mut Table,
+    migrations: &mut Vec<ConfigMigration>,
+) -> Result<(), Error> {
+    for &(legacy, current) in LEGACY_KEYS {
+        let (legacy_parent, legacy_key) = split_path(legacy);
+        let (current_parent, current_key) = split_path(current);
+        debug_assert_eq!(legacy_parent, current_parent);
+
+        let table = match table_mut(root, &legacy_parent) {
+            Some(table) => table,
+            None => continue,
+        };
+        if let Some(value) = table.remove(legacy_key) {
+            if table.contains_key(current_key) {
+                bail!(
+                    "Both legacy `{}` and current `{}` keys are specified",
+                    legacy,
+                    current
+                );
+            }
+            table.insert(current_key.to_owned(), value);
+            migrations.push(ConfigMigration::RenamedKey {
+                from: legacy.to_owned(),
+                to: current.to_owned(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Returns the default values of the sections which may be missing from the configs
+/// written by earlier releases. The database section is not included, since the missing
+/// database options are filled in during deserialization.
+fn default_sections() -> Result<Vec<(&'static str, Value)>, Error> {
+    Ok(vec![
+        (
+            "private_config.api",
+            Value::try_from(NodeApiConfig::default())?,
+        ),
+        (
+            "private_config.network",
+            Value::try_from(NetworkConfiguration::default())?,
+        ),
+        (
+            "private_config.mempool",
+            Value::try_from(MemoryPoolConfig::default())?,
+        ),
+        (
+            "private_config.connect_list",
+            Value::try_from(ConnectListConfig::default())?,
+        ),
+        (
+            "public_config.consensus",
+            Value::try_from(ConsensusConfig::default())?,
+        ),
+    ])
+}
+
+/// Formats the value in the TOML format on a single line.
+fn inline(value: &Value) -> String {
+    match value {
+        Value::Table(table) => {
+            let fields: Vec<_> = table
+                .iter()
+                .map(|(key, value)| format!("{} = {}", key, inline(value)))
+                .collect();
+            format!("{{ {} }}", fields.join(", "))
+        }
+        Value::Array(array) => {
+            let items: Vec<_> = array.iter().map(inline).collect();
+            format!("[{}]", items.join(", "))
+        }
+        _ => value.to_string(),
+    }
+}
+
+/// Adds the fields missing from `table`, recursing into the nested tables. Tables with
+/// the `type` key represent enum variants, so they are added or kept as a whole.
+fn fill_defaults(
+    table: &mut Table,
+    defaults: &Table,
+    prefix: &str,
+    migrations: &mut Vec<ConfigMigration>,
+) {
+    for (key, default) in defaults {
+        let field = format!("{}.{}", prefix, key);
+        let nested_defaults = default
+            .as_table()
+            .filter(|nested_defaults| !nested_defaults.contains_key("type"));
+        if nested_defaults.is_some() && !table.contains_key(key) {
+            table.insert(key.to_owned(), Value::Table(Table::new()));
+        }
+
+        match (table.get_mut(key), nested_defaults) {
+            (None, _) => {
+                migrations.push(ConfigMigration::AddedDefault {
+                    field,
+                    value: inline(default),
+                });
+                table.insert(key.to_owned(), default.clone());
+            }
+            (Some(Value::Table(nested)), Some(nested_defaults)) => {
+                fill_defaults(nested, nested_defaults, &field, migrations);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Migrates the raw node configuration in place, returning the performed transformations.
+fn migrate(config: &mut Value) -> Result<Vec<ConfigMigration>, Error> {
+    let root = config
+        .as_table_mut()
+        .ok_or_else(|| anyhow!("Node configuration is not a table"))?;
+    let mut migrations = vec![];
+    rename_legacy_keys(root, &mut migrations)?;
+
+    for (path, default) in default_sections()? {
+        let (parent_path, key) = split_path(path);
+        // The parent sections are required, so the deserialization fails if they are missing.
+        if let Some(parent) = table_mut(root, &parent_path) {
+            let mut defaults = Table::new();
+            defaults.insert(key.to_owned(), default);
+            fill_defaults(parent, &defaults, &parent_path.join("."), &mut migrations);
+        }
+    }
+    Ok(migrations)
+}
+
+impl ExonumCommand for MigrateConfig {
+    fn execute(self) -> Result<StandardResult, Error> {
+        let mut config: Value = load_config_file(&self.node_config_file)?;
+        let migrations = migrate(&mut config)?;
+        // Check that the migrated configuration is valid for the current release.
+        config
+            .clone()
+            .try_into::<NodeConfig>()
+            .map_err(|e| anyhow!("Node configuration cannot be migrated automatically: {}", e))?;
+
+        if OutputFormat::is_text() {
+            for migration in &migrations {
+                println!("{}", migration);
+            }
+        }
+        if migrations.is_empty() {
+            log::info!(
+                "Node configuration {} is up to date",
+                self.node_config_file.display()
+            );
+        }
+
+        let node_config_path = if self.check {
+            self.node_config_file
+        } else {
+            let out_file = self.output_file.unwrap_or(self.node_config_file);
+            // Since this may overwrite the input file, the config is saved atomically.
+            save_config_file_atomically(&config, &out_file)?;
+            out_file
+        };
+
+        Ok(StandardResult::MigrateConfig {
+            node_config_path,
+            migrations,
+            written: !self.check,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Configuration written by a release before the keys were renamed, which lacks
+    /// the network, mempool and connect list sections.
+    const LEGACY_CONFIG: &str = r#"
+        [private_config]
+        listen_addr = "0.0.0.0:6333"
+        external_addr = "127.0.0.1:6333"
+        master_key_path = "master.key.toml"
+        consensus_public_key = "11e1b1902690aa22c8c976cb74a27f167b3899141c1c6ce8e19bafef32715d40"
+
+        [private_config.api]
+        state_update_timeout = 10000
+        public_api_address = "0.0.0.0:8200"
+
+        [public_config]
+        addr = "127.0.0.1:6333"
+
+        [public_config.general]
+        validators_count = 1
+        supervisor_mode = "simple"
+
+        [public_config.consensus]
+        round_timeout = 3000
+        status_timeout = 5000
+        peers_timeout = 10000
+        txs_block_limit = 1000
+        max_message_len = 1048576
+        min_propose_timeout = 10
+        max_propose_timeout = 200
+        propose_timeout_threshold = 500
+    "#;
+
+    fn migrate_str(config: &str) -> Result<(NodeConfig, Vec<ConfigMigration>), Error> {
+        let mut config: Value = toml::from_str(config)?;
+        let migrations = migrate(&mut config)?;
+        // Check that the migrated config survives a round trip through the TOML format.
+        let serialized = toml::to_string(&config)?;
+        Ok((toml::from_str(&serialized)?, migrations))
+    }
+
+    #[test]
+    fn legacy_config_migration() {
+        toml::from_str::<NodeConfig>(LEGACY_CONFIG).unwrap_err();
+
+        let (config, migrations) = migrate_str(LEGACY_CONFIG).unwrap();
+        let private_config = &config.private_config;
+        assert_eq!(
+            private_config.listen_address,
+            "0.0.0.0:6333".parse().unwrap()
+        );
+        assert_eq!(private_config.external_address, "127.0.0.1:6333");
+        assert_eq!(
+            private_config.api.public_api_address,
+            Some("0.0.0.0:8200".parse().unwrap())
+        );
+        assert_eq!(private_config.network, NetworkConfiguration::default());
+        assert_eq!(private_config.mempool, MemoryPoolConfig::default());
+        assert!(private_config.connect_list.peers.is_empty());
+        assert_eq!(
+            config.public_config.address.as_deref(),
+            Some("127.0.0.1:6333")
+        );
+        assert_eq!(config.public_config.consensus.first_round_timeout, 3_000);
+
+        let renamed: Vec<_> = migrations
+            .iter()
+            .filter_map(|migration| match migration {
+                ConfigMigration::RenamedKey { from, .. } => Some(from.as_str()),
+                _ => None,
+            })
+            .collect();
+        let all_legacy_keys: Vec<_> = LEGACY_KEYS.iter().map(|&(legacy, _)| legacy).collect();
+        assert_eq!(renamed, all_legacy_keys);
+
+        let added: Vec<_> = migrations
+            .iter()
+            .filter_map(|migration| match migration {
+                ConfigMigration::AddedDefault { field, .. } => Some(field.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert!(
+            added.contains(&"private_config.network.max_incoming_connections"),
+            "{:?}",
+            added
+        );
+        assert!(
+            added.contains(&"private_config.mempool.flush_pool_strategy"),
+            "{:?}",
+            added
+        );
+        assert!(
+            added.contains(&"private_config.connect_list.peers"),
+            "{:?}",
+            added
+        );
+        // Only the missing fields of the existing sections are added.
+        assert!(
+            added.contains(&"private_config.api.server_restart.max_retries"),
+            "{:?}",
+            added
+        );
+        assert!(!added.contains(&"private_config.api.public_api_address"));
+        assert!(!added.contains(&"private_config.api.state_update_timeout"));
+
+        let summary: Vec<_> = migrations.iter().map(ToString::to_string).collect();
+        assert!(
+            summary.contains(
+                &"renamed `private_config.listen_addr` to `private_config.listen_address`"
+                    .to_owned()
+            ),
+            "{:?}",
+            summary
+        );
+        assert!(
+            summary.contains(&"added `private_config.connect_list.peers` = []".to_owned()),
+            "{:?}",
+            summary
+        );
+    }
+
+    #[test]
+    fn migration_of_partial_sections() {
+        let config = LEGACY_CONFIG.replace(
+            "[public_config]",
+            r#"
+            [private_config.mempool.events_pool_capacity]
+            api_requests_capacity = 2048
+
+            [private_config.mempool.flush_pool_strategy]
+            type = "never"
+
+            [public_config]
+            "#,
+        );
+        let (config, migrations) = migrate_str(&config).unwrap();
+        let mempool = &config.private_config.mempool;
+        assert_eq!(mempool.events_pool_capacity.api_requests_capacity, 2_048);
+        let defaults = MemoryPoolConfig::default().events_pool_capacity;
+        assert_eq!(
+            mempool.events_pool_capacity.network_requests_capacity,
+            defaults.network_requests_capacity
+        );
+
+        assert!(migrations.contains(&ConfigMigration::AddedDefault {
+            field: "private_config.mempool.events_pool_capacity.network_requests_capacity"
+                .to_owned(),
+            value: defaults.network_requests_capacity.to_string(),
+        }));
+        // Enum variants are not merged with the defaults.
+        assert!(!migrations.iter().any(|migration| match migration {
+            ConfigMigration::AddedDefault { field, .. } => field.contains("flush_pool_strategy"),
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn current_config_is_not_changed() {
+        let (config, _) = migrate_str(LEGACY_CONFIG).unwrap();
+        let serialized = toml::to_string(&config).unwrap();
+        let (migrated, migrations) = migrate_str(&serialized).unwrap();
+        assert_eq!(migrated, config);
+        assert!(migrations.is_empty(), "{:?}", migrations);
+    }
+
+    #[test]
+    fn conflicting_legacy_keys() {
+        let config = LEGACY_CONFIG.replace(
+            "listen_addr = \"0.0.0.0:6333\"",
+            "listen_addr = \"0.0.0.0:6333\"\nlisten_address = \"0.0.0.0:6334\"",
+        );
+        let err = migrate_str(&config).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Both legacy `private_config.listen_addr` and current \
+             `private_config.listen_address` keys are specified"
+        );
+    }
+}
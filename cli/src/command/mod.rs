@@ -15,6 +15,14 @@
 //! Standard Exonum CLI node configuration commands.
 
 pub use self::{
+    backup::{Backup, BackupMode, BackupReport},
+    compact_db::{CompactDb, CompactionReport},
+    completions::{Completions, COMPLETION_SHELLS},
+    config_diff::{ConfigDiff, ConfigsDifferError, DEFAULT_IGNORED_FIELDS},
+    db_info::{ArtifactSummary, DatabaseInfo, DbInfo, IndexSummary, InstanceSummary},
+    db_option::DbOptionOverride,
+    db_stats::{DatabaseStats, DbStats},
+    export_public_config::{ExportPublicConfig, CONTENT_HASH_PREFIX},
     finalize::Finalize,
     generate_config::{
         GenerateConfig, DEFAULT_EXONUM_LISTEN_PORT, MASTER_KEY_FILE_NAME, PRIVATE_CONFIG_FILE_NAME,
@@ -22,26 +30,53 @@ pub use self::{
     },
     generate_template::GenerateTemplate,
     maintenance::{Maintenance, MaintenanceAction},
-    optimize_config::OptimizeConfig,
+    migrate_config::{ConfigMigration, MigrateConfig, LEGACY_KEYS},
+    optimize_config::{ConfigFieldChange, HardwareProfile, OptimizeConfig},
+    output::{ErrorOutput, OutputFormat, OUTPUT_FORMAT_ENV_VAR},
+    restore::{Restore, RestoreReport},
+    rotate_keys::RotateKeys,
     run::{NodeRunConfig, Run},
     run_dev::RunDev,
+    validate_config::{ConfigProblem, ConfigValidationError, Severity, ValidateConfig},
+    wizard::Wizard,
 };
 
+mod backup;
+mod compact_db;
+mod completions;
+mod config_diff;
+mod db_info;
+mod db_option;
+mod db_stats;
+mod export_public_config;
 mod finalize;
 mod generate_config;
 mod generate_template;
 mod maintenance;
+mod migrate_config;
 mod optimize_config;
+mod output;
+mod restore;
+mod rotate_keys;
 mod run;
 mod run_dev;
+mod validate_config;
+mod wizard;
 
 use anyhow::Error;
+use exonum::crypto::{Hash, PublicKey};
 use serde_derive::{Deserialize, Serialize};
 use structopt::StructOpt;
 
 use std::path::PathBuf;
 
 /// Interface of standard Exonum Core configuration command.
+///
+/// The output format of the commands is selected with the `EXONUM_OUTPUT` environment
+/// variable (see [`OutputFormat`](enum.OutputFormat.html)). Commands printing human-readable
+/// text should do so only in the text format; in the JSON format, the serialized
+/// [`StandardResult`](enum.StandardResult.html) is printed by the [`Command`](enum.Command.html)
+/// dispatcher instead.
 pub trait ExonumCommand {
     /// Returns the result of the command execution.
     fn execute(self) -> Result<StandardResult, Error>;
@@ -79,6 +114,54 @@ pub enum Command {
     /// Perform different maintenance actions.
     #[structopt(name = "maintenance")]
     Maintenance(Maintenance),
+
+    /// Print statistics of the node database.
+    #[structopt(name = "db-stats")]
+    DbStats(DbStats),
+
+    /// Compact the node database.
+    #[structopt(name = "compact-db")]
+    CompactDb(CompactDb),
+
+    /// Back up the node database.
+    #[structopt(name = "backup")]
+    Backup(Backup),
+
+    /// Restore the node database from a backup.
+    #[structopt(name = "restore")]
+    Restore(Restore),
+
+    /// Validate the node configuration.
+    #[structopt(name = "validate-config")]
+    ValidateConfig(ValidateConfig),
+
+    /// Compare two node configurations.
+    #[structopt(name = "config-diff")]
+    ConfigDiff(ConfigDiff),
+
+    /// Upgrade the node configuration written by an earlier release.
+    #[structopt(name = "migrate-config")]
+    MigrateConfig(MigrateConfig),
+
+    /// Configure the node interactively.
+    #[structopt(name = "wizard")]
+    Wizard(Wizard),
+
+    /// Rotate the keys of the node.
+    #[structopt(name = "rotate-keys")]
+    RotateKeys(RotateKeys),
+
+    /// Export the public part of the node configuration.
+    #[structopt(name = "export-public-config")]
+    ExportPublicConfig(ExportPublicConfig),
+
+    /// Print the summary of the node database contents.
+    #[structopt(name = "db-info")]
+    DbInfo(DbInfo),
+
+    /// Generate a shell completion script.
+    #[structopt(name = "completions")]
+    Completions(Completions),
 }
 
 impl Command {
@@ -90,6 +173,20 @@ impl Command {
 
 impl ExonumCommand for Command {
     fn execute(self) -> Result<StandardResult, Error> {
+        let format = OutputFormat::from_env()?;
+        let result = self.execute_inner();
+        if format == OutputFormat::Json {
+            match result {
+                Ok(ref result) => println!("{}", serde_json::to_string_pretty(result)?),
+                Err(ref e) => eprintln!("{}", serde_json::to_string_pretty(&ErrorOutput::from(e))?),
+            }
+        }
+        result
+    }
+}
+
+impl Command {
+    fn execute_inner(self) -> Result<StandardResult, Error> {
         match self {
             Self::GenerateTemplate(command) => command.execute(),
             Self::GenerateConfig(command) => command.execute(),
@@ -98,12 +195,29 @@ impl ExonumCommand for Command {
             Self::Run(command) => command.execute(),
             Self::RunDev(command) => command.execute(),
             Self::Maintenance(command) => command.execute(),
+            Self::DbStats(command) => command.execute(),
+            Self::CompactDb(command) => command.execute(),
+            Self::Backup(command) => command.execute(),
+            Self::Restore(command) => command.execute(),
+            Self::ValidateConfig(command) => command.execute(),
+            Self::ConfigDiff(command) => command.execute(),
+            Self::MigrateConfig(command) => command.execute(),
+            Self::Wizard(command) => command.execute(),
+            Self::RotateKeys(command) => command.execute(),
+            Self::ExportPublicConfig(command) => command.execute(),
+            Self::DbInfo(command) => command.execute(),
+            Self::Completions(command) => command.execute(),
         }
     }
 }
 
 /// Output of any of the standard Exonum Core configuration commands.
-#[derive(Debug)]
+///
+/// In the JSON output format, the result is serialized as an object with the `command`
+/// field containing the variant name in the kebab case (e.g., `generate-config`),
+/// and the variant fields.
+#[derive(Debug, Serialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
 #[non_exhaustive]
 pub enum StandardResult {
     /// `generate-template` command output.
@@ -134,6 +248,14 @@ pub enum StandardResult {
         node_config_path: PathBuf,
     },
 
+    /// `optimize-config` command output in the dry-run mode. No files are written.
+    OptimizeConfigDryRun {
+        /// Path to the node configuration which would be optimized.
+        node_config_path: PathBuf,
+        /// Changes to the database section of the configuration, including unchanged fields.
+        changes: Vec<ConfigFieldChange>,
+    },
+
     /// `run` command output.
     Run(Box<NodeRunConfig>),
 
@@ -145,5 +267,112 @@ pub enum StandardResult {
         db_path: PathBuf,
         /// Performed action.
         performed_action: MaintenanceAction,
+        /// Number of entries removed by the `clear-cache` action; `None` for other actions.
+        removed_entries: Option<u64>,
+    },
+
+    /// `db-stats` command output.
+    DbStats(DatabaseStats),
+
+    /// `compact-db` command output.
+    CompactDb {
+        /// Path to a node configuration file.
+        node_config_path: PathBuf,
+        /// Path to a database directory.
+        db_path: PathBuf,
+        /// Compacted column families and their sizes.
+        report: CompactionReport,
+    },
+
+    /// `backup` command output.
+    Backup(BackupReport),
+
+    /// `restore` command output.
+    Restore(RestoreReport),
+
+    /// `validate-config` command output. The configuration has no errors, but may
+    /// have warnings.
+    ValidateConfig {
+        /// Path to the validated node configuration.
+        node_config_path: PathBuf,
+        /// Warnings found in the configuration.
+        problems: Vec<ConfigProblem>,
+    },
+
+    /// `config-diff` command output. The configurations are equal, except for
+    /// the ignored fields.
+    ConfigDiff {
+        /// Path to the first configuration.
+        first_config_path: PathBuf,
+        /// Path to the second configuration.
+        second_config_path: PathBuf,
+    },
+
+    /// `migrate-config` command output.
+    MigrateConfig {
+        /// Path to the migrated node configuration. With `--check`, this is the path
+        /// to the input configuration.
+        node_config_path: PathBuf,
+        /// Performed transformations; empty if the configuration is up to date.
+        migrations: Vec<ConfigMigration>,
+        /// Whether the migrated configuration was written; `false` with `--check`.
+        written: bool,
+    },
+
+    /// `wizard` command output.
+    Wizard {
+        /// Path to the generated template config.
+        template_config_path: PathBuf,
+        /// Path to the generated public config of the node.
+        public_config_path: PathBuf,
+        /// Path to the generated private config of the node.
+        private_config_path: PathBuf,
+        /// Path to the finalized node configuration; `None` if there are several
+        /// validators, so the configuration should be finalized manually.
+        node_config_path: Option<PathBuf>,
+        /// Suggested path to the node database.
+        db_path: PathBuf,
+        /// Equivalent non-interactive commands executed by the wizard.
+        commands: Vec<String>,
+    },
+
+    /// `rotate-keys` command output.
+    RotateKeys {
+        /// Path to the updated config.
+        config_path: PathBuf,
+        /// Path to the new master key file.
+        master_key_path: PathBuf,
+        /// New consensus public key of the node.
+        consensus_public_key: PublicKey,
+        /// New service public key of the node.
+        service_public_key: PublicKey,
+        /// Path to the copy of the previous master key file; `None` if the previous key
+        /// is not kept.
+        previous_master_key_path: Option<PathBuf>,
+    },
+
+    /// `export-public-config` command output.
+    ExportPublicConfig {
+        /// Path to the exported configuration; `None` with `--stdout`.
+        output_file: Option<PathBuf>,
+        /// Hash of the exported content, which is also written in the header comment.
+        content_hash: Hash,
+        /// Exported configuration printed with `--stdout` in the JSON output format.
+        /// In the text format, the configuration is printed as is.
+        content: Option<String>,
+    },
+
+    /// `db-info` command output.
+    DbInfo(DatabaseInfo),
+
+    /// `completions` command output.
+    Completions {
+        /// Shell the completion script is generated for.
+        shell: String,
+        /// Path to the written completion script; `None` if the script is printed.
+        output_file: Option<PathBuf>,
+        /// Completion script printed in the JSON output format. In the text format,
+        /// the script is printed as is.
+        content: Option<String>,
     },
 }
@@ -1,12 +1,20 @@
 use crate::{
-    command::{ExonumCommand, StandardResult},
+    command::{ExonumCommand, OutputFormat, StandardResult},
     config::NodeConfig,
-    io::{load_config_file, save_config_file},
+    io::{load_config_file, save_config_file_atomically},
 };
 use anyhow::{anyhow, bail, Error};
-use exonum::merkledb::LogVerbosity;
+use exonum::merkledb::{
+    AccessPattern, CompactionStyle, CompressionType, DbOptions, DbOptionsBuilder, LogVerbosity,
+    WalSyncMode,
+};
 use serde_derive::{Deserialize, Serialize};
-use std::{fs, path::PathBuf};
+use std::{
+    collections::BTreeSet,
+    convert::TryFrom,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
 use structopt::StructOpt;
 
 /// Maximum number of files that RocksDb may keep open.
@@ -25,7 +33,7 @@ pub const MAX_LOG_FILE_SIZE: usize = 10 * (1 << 20);
 pub const KEEP_LOG_FILE_NUM: usize = 10;
 
 /// Options for optimizing RocksDb.
-#[derive(StructOpt, Debug, Serialize, Deserialize)]
+#[derive(StructOpt, Debug, Default, Serialize, Deserialize)]
 #[non_exhaustive]
 pub struct OptimizeConfig {
     /// Path to node configuration file (node.toml).
@@ -37,93 +45,1028 @@ pub struct OptimizeConfig {
     #[structopt(long, short = "o")]
     pub output_file: Option<PathBuf>,
 
+    /// Print the changes to the database section of the configuration without
+    /// writing any files.
+    #[structopt(long)]
+    pub dry_run: bool,
+
+    /// Set the database options which are not specified explicitly to the defaults
+    /// for a node: `--max-open-files`, `--max-total-wal-size`, `--log-level`,
+    /// `--max-log-file-size` and `--keep-log-file-num`. Log file recycling is reset
+    /// to the RocksDb default (disabled) unless `--recycle-log-files` is specified.
+    ///
+    /// Unlike older releases, in which these defaults were always applied, this flag
+    /// also enables `--atomic-flush` unless the write-ahead log is disabled.
+    ///
+    /// Without this flag, only explicitly specified options are changed.
+    #[structopt(long)]
+    pub apply_defaults: bool,
+
+    /// Hardware profile: `ssd`, `hdd`, `low-memory` or `archive`. The profile sets
+    /// a group of database options, which can be overridden with explicit flags.
+    #[structopt(long, parse(try_from_str = parse_profile))]
+    pub profile: Option<HardwareProfile>,
+
+    /// Reset a database option to the RocksDb default, e.g., `--reset max-total-wal-size`.
+    /// Options are named as the corresponding flags. May be repeated; takes precedence
+    /// over other flags.
+    #[structopt(long, number_of_values = 1, parse(try_from_str = parse_reset_field))]
+    pub reset: Vec<String>,
+
     /// Maximum number of files that RocksDb may keep open.
     ///
-    /// Defaults to 256.
+    /// Not changed if not specified; set to 256 with `--apply-defaults`.
     #[structopt(long)]
     pub max_open_files: Option<i32>,
 
     /// Maximum size of RocksDb's WAL journal in bytes.
     ///
-    /// Defaults to 1 MiB.
-    #[structopt(long)]
+    /// Not changed if not specified; set to 1 MiB with `--apply-defaults`.
+    #[structopt(long, parse(try_from_str = parse_size))]
     pub max_total_wal_size: Option<u64>,
 
-    /// Log level.
+    /// Log level: `debug`, `info`, `warn`, `error`, `fatal` or `header`.
     ///
-    /// Defaults to `Warn`.
-    #[structopt(long, parse(try_from_str = parse_log_level))]
+    /// Not changed if not specified; set to `warn` with `--apply-defaults`.
+    #[structopt(long)]
     pub log_level: Option<LogVerbosity>,
 
     /// Maximum size of log files.
     ///
-    /// Defaults to 10 MiB.
-    #[structopt(long)]
+    /// Not changed if not specified; set to 10 MiB with `--apply-defaults`.
+    #[structopt(long, parse(try_from_str = parse_size))]
     pub max_log_file_size: Option<usize>,
 
+    /// Compression of RocksDb data blocks: `none`, `snappy`, `lz4`, `lz4hc`, `zlib`, `bz2`
+    /// or `zstd`. Only newly written data is compressed with the new algorithm.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long)]
+    pub compression: Option<CompressionType>,
+
     /// Maximum number of log files to keep.
     ///
-    /// Defaults to 10.
+    /// Not changed if not specified; set to 10 with `--apply-defaults`.
     #[structopt(long)]
     pub keep_log_file_num: Option<usize>,
 
     /// Recycle existing log files.
     ///
-    /// Defaults to true.
+    /// Not changed if not specified; reset to the RocksDb default (disabled) with
+    /// `--apply-defaults`.
     #[structopt(long)]
     pub recycle_log_files: Option<bool>,
+
+    /// Size of RocksDb's block cache in bytes; 0 disables the cache.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long, parse(try_from_str = parse_size))]
+    pub block_cache_size: Option<u64>,
+
+    /// Size of a single RocksDb memtable in bytes.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long, parse(try_from_str = parse_size))]
+    pub write_buffer_size: Option<usize>,
+
+    /// Maximum number of RocksDb memtables.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long)]
+    pub max_write_buffer_number: Option<i32>,
+
+    /// RocksDb compaction style: `level`, `universal` or `fifo`.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long, parse(try_from_str = parse_compaction_style))]
+    pub compaction_style: Option<CompactionStyle>,
+
+    /// Maximum total size of SST files in bytes for the `fifo` compaction style.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long, parse(try_from_str = parse_size))]
+    pub fifo_max_table_files_size: Option<u64>,
+
+    /// Maximum number of concurrent RocksDb background jobs.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long)]
+    pub max_background_jobs: Option<i32>,
+
+    /// Total number of RocksDb background threads.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long)]
+    pub increase_parallelism: Option<i32>,
+
+    /// Derive background parallelism from the number of CPUs.
+    ///
+    /// Explicitly specified `--max-background-jobs` and `--increase-parallelism`
+    /// take precedence over the derived values.
+    #[structopt(long)]
+    pub auto_parallelism: bool,
+
+    /// Use direct I/O for RocksDb reads, bypassing the OS page cache.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long)]
+    pub use_direct_reads: Option<bool>,
+
+    /// Use direct I/O for RocksDb flushes and compactions.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long)]
+    pub use_direct_io_for_flush_and_compaction: Option<bool>,
+
+    /// Limit on the write rate of RocksDb flushes and compactions in bytes per second,
+    /// e.g., `64MiB`. Zero disables the limiter.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long, parse(try_from_str = parse_size))]
+    pub rate_limit: Option<i64>,
+
+    /// Durability mode of the RocksDb write-ahead log: `sync`, `async` or `disabled`.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long, parse(try_from_str = parse_wal_sync_mode))]
+    pub wal_sync: Option<WalSyncMode>,
+
+    /// Directory for RocksDb write-ahead log files, e.g., on a faster device.
+    /// A relative path is resolved against the database directory.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long)]
+    pub wal_dir: Option<PathBuf>,
+
+    /// Directory for RocksDb info LOG files. A relative path is resolved against
+    /// the database directory.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long)]
+    pub db_log_dir: Option<PathBuf>,
+
+    /// Preset for the prevailing access pattern to RocksDb: `mixed` or
+    /// `point-lookup:<cache size in MiB>`, e.g., `point-lookup:256`.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long, parse(try_from_str = parse_access_pattern))]
+    pub access_pattern: Option<AccessPattern>,
+
+    /// Interval after which RocksDb compacts SST files regardless of other triggers,
+    /// e.g., `7d` or `12h`.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long, parse(try_from_str = parse_duration))]
+    pub periodic_compaction: Option<u64>,
+
+    /// Size of the readahead for RocksDb compactions in bytes, e.g., `2MiB`.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long, parse(try_from_str = parse_size))]
+    pub compaction_readahead_size: Option<usize>,
+
+    /// Flush all RocksDb column families atomically.
+    ///
+    /// Not changed if not specified; set to true with `--apply-defaults` unless
+    /// the write-ahead log is disabled.
+    #[structopt(long)]
+    pub atomic_flush: Option<bool>,
+
+    /// Collect RocksDb statistics. Has a measurable performance overhead.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long)]
+    pub enable_statistics: Option<bool>,
+
+    /// Period of dumping RocksDb statistics to the info LOG, e.g., `10m`.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long, parse(try_from_str = parse_duration))]
+    pub stats_dump_period: Option<u64>,
+
+    /// Period of deleting obsolete RocksDb files, e.g., `6h`.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long, parse(try_from_str = parse_duration))]
+    pub delete_obsolete_files_period: Option<u64>,
+
+    /// Maximum size of the RocksDb MANIFEST file, e.g., `64MiB`. Must be at least 1 MiB.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long, parse(try_from_str = parse_size))]
+    pub max_manifest_file_size: Option<usize>,
+
+    /// Use memory-mapped I/O for RocksDb reads. Cannot be combined with direct reads.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long)]
+    pub allow_mmap_reads: Option<bool>,
+
+    /// Use memory-mapped I/O for RocksDb writes.
+    ///
+    /// Not changed if not specified.
+    #[structopt(long)]
+    pub allow_mmap_writes: Option<bool>,
+}
+
+/// Hardware profile, which maps to a set of database options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum HardwareProfile {
+    /// Node with an SSD:
+    ///
+    /// - `max_open_files`: 4096
+    /// - `max_total_wal_size`: 256 MiB
+    /// - `write_buffer_size`: 64 MiB
+    /// - `max_write_buffer_number`: 4
+    /// - `compression_type`: `Snappy`
+    /// - `max_log_file_size`: 10 MiB
+    /// - `keep_log_file_num`: 10
+    Ssd,
+    /// Node with a spinning disk. Larger memtables and compaction readahead reduce
+    /// the number of random disk accesses:
+    ///
+    /// - `max_open_files`: 1024
+    /// - `max_total_wal_size`: 512 MiB
+    /// - `write_buffer_size`: 128 MiB
+    /// - `max_write_buffer_number`: 4
+    /// - `compaction_readahead_size`: 2 MiB
+    /// - `compression_type`: `Snappy`
+    /// - `max_log_file_size`: 10 MiB
+    /// - `keep_log_file_num`: 10
+    Hdd,
+    /// Node with little memory, e.g., a small VPS:
+    ///
+    /// - `max_open_files`: 256
+    /// - `max_total_wal_size`: 64 MiB
+    /// - `write_buffer_size`: 16 MiB
+    /// - `max_write_buffer_number`: 2
+    /// - `block_cache_size`: 64 MiB
+    /// - `compression_type`: `Snappy`
+    /// - `max_log_file_size`: 10 MiB
+    /// - `keep_log_file_num`: 5
+    LowMemory,
+    /// Archive node storing a lot of data:
+    ///
+    /// - `max_open_files`: 2048
+    /// - `max_total_wal_size`: 1 GiB
+    /// - `write_buffer_size`: 128 MiB
+    /// - `max_write_buffer_number`: 4
+    /// - `block_cache_size`: 1 GiB
+    /// - `compression_type`: `Snappy`
+    /// - `bottommost_compression`: `Snappy`
+    /// - `max_log_file_size`: 64 MiB
+    /// - `keep_log_file_num`: 10
+    Archive,
+}
+
+impl HardwareProfile {
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (Self::Ssd, "ssd"),
+        (Self::Hdd, "hdd"),
+        (Self::LowMemory, "low-memory"),
+        (Self::Archive, "archive"),
+    ];
+
+    /// Sets the database options of the profile.
+    pub fn apply(self, builder: DbOptionsBuilder) -> DbOptionsBuilder {
+        match self {
+            Self::Ssd => builder
+                .max_open_files(4096)
+                .wal_size_limit(256 << 20)
+                .write_buffer_size(64 << 20)
+                .max_write_buffer_number(4)
+                .compression(CompressionType::Snappy)
+                .max_log_file_size(10 << 20)
+                .keep_log_file_num(10),
+            Self::Hdd => builder
+                .max_open_files(1024)
+                .wal_size_limit(512 << 20)
+                .write_buffer_size(128 << 20)
+                .max_write_buffer_number(4)
+                .compaction_readahead_size(2 << 20)
+                .compression(CompressionType::Snappy)
+                .max_log_file_size(10 << 20)
+                .keep_log_file_num(10),
+            Self::LowMemory => builder
+                .max_open_files(256)
+                .wal_size_limit(64 << 20)
+                .write_buffer_size(16 << 20)
+                .max_write_buffer_number(2)
+                .block_cache_size(64 << 20)
+                .compression(CompressionType::Snappy)
+                .max_log_file_size(10 << 20)
+                .keep_log_file_num(5),
+            Self::Archive => builder
+                .max_open_files(2048)
+                .wal_size_limit(1 << 30)
+                .write_buffer_size(128 << 20)
+                .max_write_buffer_number(4)
+                .block_cache_size(1 << 30)
+                .compression(CompressionType::Snappy)
+                .bottommost_compression(CompressionType::Snappy)
+                .max_log_file_size(64 << 20)
+                .keep_log_file_num(10),
+        }
+    }
+}
+
+pub(super) fn parse_profile(src: &str) -> Result<HardwareProfile, Error> {
+    let lowercase = src.to_lowercase();
+    HardwareProfile::NAMES
+        .iter()
+        .find(|(_, name)| *name == lowercase)
+        .map(|(profile, _)| *profile)
+        .ok_or_else(|| {
+            let names: Vec<_> = HardwareProfile::NAMES
+                .iter()
+                .map(|(_, name)| *name)
+                .collect();
+            anyhow!(
+                "Unknown profile: {}. Available profiles: {}",
+                src,
+                names.join(", ")
+            )
+        })
+}
+
+fn parse_compaction_style(src: &str) -> Result<CompactionStyle, Error> {
+    match src.to_lowercase().as_ref() {
+        "level" => Ok(CompactionStyle::Level),
+        "universal" => Ok(CompactionStyle::Universal),
+        "fifo" => Ok(CompactionStyle::Fifo),
+        _ => Err(anyhow!("Unknown compaction style: {}", src)),
+    }
 }
 
-fn parse_log_level(src: &str) -> Result<LogVerbosity, Error> {
+fn parse_wal_sync_mode(src: &str) -> Result<WalSyncMode, Error> {
     match src.to_lowercase().as_ref() {
-        "debug" => Ok(LogVerbosity::Debug),
-        "info" => Ok(LogVerbosity::Info),
-        "warn" => Ok(LogVerbosity::Warn),
-        "error" => Ok(LogVerbosity::Error),
-        "fatal" => Ok(LogVerbosity::Fatal),
-        "header" => Ok(LogVerbosity::Header),
-        _ => Err(anyhow!("Unknown log level: {}", src)),
+        "sync" => Ok(WalSyncMode::Sync),
+        "async" => Ok(WalSyncMode::Async),
+        "disabled" => Ok(WalSyncMode::Disabled),
+        _ => Err(anyhow!("Unknown WAL sync mode: {}", src)),
+    }
+}
+
+fn parse_access_pattern(src: &str) -> Result<AccessPattern, Error> {
+    let lowercase = src.to_lowercase();
+    let mut parts = lowercase.splitn(2, ':');
+    match (parts.next(), parts.next()) {
+        (Some("mixed"), None) => Ok(AccessPattern::Mixed),
+        (Some("point-lookup"), Some(cache_mb)) => {
+            let cache_mb = cache_mb
+                .parse()
+                .map_err(|_| anyhow!("Invalid cache size in access pattern: {}", src))?;
+            Ok(AccessPattern::PointLookup { cache_mb })
+        }
+        (Some("point-lookup"), None) => Err(anyhow!(
+            "Cache size in MiB is required for access pattern: {}, e.g., point-lookup:256",
+            src
+        )),
+        _ => Err(anyhow!("Unknown access pattern: {}", src)),
+    }
+}
+
+/// Change of a single field in the database section of the node configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ConfigFieldChange {
+    /// Name of the field.
+    pub field: String,
+    /// Old value of the field in the TOML format, or `None` if the field is not set.
+    pub old_value: Option<String>,
+    /// New value of the field in the TOML format, or `None` if the field is not set.
+    pub new_value: Option<String>,
+}
+
+impl ConfigFieldChange {
+    /// Returns `true` if the value of the field is changed.
+    pub fn is_changed(&self) -> bool {
+        self.old_value != self.new_value
+    }
+}
+
+impl fmt::Display for ConfigFieldChange {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let old_value = self.old_value.as_deref().unwrap_or("(not set)");
+        if self.is_changed() {
+            let new_value = self.new_value.as_deref().unwrap_or("(not set)");
+            write!(formatter, "{}: {} -> {}", self.field, old_value, new_value)
+        } else {
+            write!(formatter, "{}: {} (unchanged)", self.field, old_value)
+        }
+    }
+}
+
+/// Computes field-by-field changes between two sets of database options. Fields which
+/// are not set in both sets are omitted.
+fn database_diff(old: &DbOptions, new: &DbOptions) -> Result<Vec<ConfigFieldChange>, Error> {
+    let old = toml::Value::try_from(old)?;
+    let new = toml::Value::try_from(new)?;
+    let (old, new) = match (old.as_table(), new.as_table()) {
+        (Some(old), Some(new)) => (old, new),
+        _ => bail!("Database options are not serialized as a table"),
+    };
+
+    let fields: BTreeSet<_> = old.keys().chain(new.keys()).collect();
+    Ok(fields
+        .into_iter()
+        .map(|field| ConfigFieldChange {
+            field: field.to_owned(),
+            old_value: old.get(field).map(ToString::to_string),
+            new_value: new.get(field).map(ToString::to_string),
+        })
+        .collect())
+}
+
+/// Resets a database option named as the corresponding flag of `optimize-config`
+/// to `None`.
+fn reset_field(database: &mut DbOptions, field: &str) -> Result<(), Error> {
+    match field {
+        "max-open-files" => database.max_open_files = None,
+        "max-total-wal-size" => database.max_total_wal_size = None,
+        "log-level" => database.log_verbosity = None,
+        "max-log-file-size" => database.max_log_file_size = None,
+        "keep-log-file-num" => database.keep_log_file_num = None,
+        "recycle-log-files" => database.recycle_log_file_num = None,
+        "block-cache-size" => database.block_cache_size = None,
+        "write-buffer-size" => database.write_buffer_size = None,
+        "max-write-buffer-number" => database.max_write_buffer_number = None,
+        "compaction-style" => database.compaction_style = None,
+        "fifo-max-table-files-size" => database.fifo_max_table_files_size = None,
+        "max-background-jobs" => database.max_background_jobs = None,
+        "increase-parallelism" => database.increase_parallelism = None,
+        "use-direct-reads" => database.use_direct_reads = None,
+        "use-direct-io-for-flush-and-compaction" => {
+            database.use_direct_io_for_flush_and_compaction = None
+        }
+        "rate-limit" => database.rate_limiter_bytes_per_sec = None,
+        "wal-sync" => database.wal_sync_mode = None,
+        "wal-dir" => database.wal_dir = None,
+        "db-log-dir" => database.db_log_dir = None,
+        "access-pattern" => database.access_pattern = None,
+        "periodic-compaction" => database.periodic_compaction_seconds = None,
+        "compaction-readahead-size" => database.compaction_readahead_size = None,
+        "atomic-flush" => database.atomic_flush = None,
+        "enable-statistics" => database.enable_statistics = None,
+        "stats-dump-period" => database.stats_dump_period_sec = None,
+        "delete-obsolete-files-period" => database.delete_obsolete_files_period_micros = None,
+        "max-manifest-file-size" => database.max_manifest_file_size = None,
+        "allow-mmap-reads" => database.allow_mmap_reads = None,
+        "allow-mmap-writes" => database.allow_mmap_writes = None,
+        _ => bail!("Unknown database option: {}", field),
+    }
+    Ok(())
+}
+
+fn parse_reset_field(src: &str) -> Result<String, Error> {
+    reset_field(&mut DbOptions::default(), src)?;
+    Ok(src.to_owned())
+}
+
+/// Returns a warning if the compression of the database is changed, since the data already
+/// stored in the database is not recompressed automatically.
+fn compression_warning(old: &DbOptions, new: &DbOptions) -> Option<String> {
+    if old.compression_type == new.compression_type {
+        return None;
     }
+    Some(format!(
+        "Compression is changed from `{}` to `{}`. Data already stored in an existing \
+         database is not recompressed; only newly written data uses the new compression. \
+         To rewrite the existing data, recompress the database with \
+         `exonum_merkledb::recompress` while the node is stopped.",
+        old.compression_type, new.compression_type
+    ))
+}
+
+/// Checks that an absolute directory exists, creating it if `create` is set. Relative
+/// directories are resolved against the database directory, which is only known
+/// when the node is run, so they are not checked.
+fn prepare_dir(dir: &Path, create: bool) -> Result<(), Error> {
+    if dir.is_relative() || dir.is_dir() {
+        return Ok(());
+    }
+    if create {
+        fs::create_dir_all(dir)?;
+        Ok(())
+    } else {
+        bail!("Directory does not exist: {}", dir.display())
+    }
+}
+
+/// Parses a size in bytes with an optional suffix, e.g., `512`, `64MiB` or `1 GB`.
+///
+/// Suffixes without `i` (`KB`, `MB`, ...) are decimal; suffixes with `i` (`KiB`, `MiB`, ...)
+/// and single-letter suffixes (`K`, `M`, ...) are binary.
+fn parse_size<T: TryFrom<u64>>(src: &str) -> Result<T, Error> {
+    let src = src.trim();
+    let digits_end = src
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or_else(|| src.len());
+    let (number, suffix) = src.split_at(digits_end);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| anyhow!("Invalid size: {}", src))?;
+    let multiplier: u64 = match suffix.trim().to_lowercase().as_ref() {
+        "" | "b" => 1,
+        "k" | "kib" => 1 << 10,
+        "m" | "mib" => 1 << 20,
+        "g" | "gib" => 1 << 30,
+        "t" | "tib" => 1 << 40,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "tb" => 1_000_000_000_000,
+        _ => bail!("Unknown size suffix: {}", src),
+    };
+    number
+        .checked_mul(multiplier)
+        .and_then(|size| T::try_from(size).ok())
+        .ok_or_else(|| anyhow!("Size is too large: {}", src))
+}
+
+/// Parses a duration in seconds with an optional unit suffix: `s`, `m`, `h`, `d` or `w`,
+/// e.g., `90`, `30m` or `7d`.
+fn parse_duration(src: &str) -> Result<u64, Error> {
+    let src = src.trim();
+    let digits_end = src
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or_else(|| src.len());
+    let (number, suffix) = src.split_at(digits_end);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| anyhow!("Invalid duration: {}", src))?;
+    let multiplier: u64 = match suffix.trim().to_lowercase().as_ref() {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => bail!("Unknown duration unit: {}", src),
+    };
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| anyhow!("Duration is too large: {}", src))
+}
+
+/// Returns `(max_background_jobs, increase_parallelism)` for the given number of CPUs.
+fn auto_parallelism(cpus: usize) -> (i32, i32) {
+    let cpus = i32::try_from(cpus).unwrap_or(i32::MAX).max(1);
+    // RocksDb needs at least one flush and one compaction job to avoid write stalls.
+    (cpus.max(2), cpus)
 }
 
 impl ExonumCommand for OptimizeConfig {
     fn execute(self) -> Result<StandardResult, Error> {
         // tune the settings from the previous configuration step
         let mut node_config: NodeConfig = load_config_file(&self.node_config_file)?;
-        node_config.private_config.database.max_open_files =
-            self.max_open_files.or(Some(MAX_OPEN_FILES));
-        node_config.private_config.database.max_total_wal_size =
-            self.max_total_wal_size.or(Some(MAX_TOTAL_WAL_SIZE));
-        node_config.private_config.database.log_verbosity =
-            self.log_level.or(Some(DEFAULT_LOG_LEVEL));
-        node_config.private_config.database.max_log_file_size =
-            self.max_log_file_size.or(Some(MAX_LOG_FILE_SIZE));
-        node_config.private_config.database.keep_log_file_num =
-            self.keep_log_file_num.or(Some(KEEP_LOG_FILE_NUM));
-        node_config.private_config.database.recycle_log_file_num =
-            self.recycle_log_files.map(|value| {
-                if value {
-                    1
-                } else {
-                    0
+        let old_database = node_config.private_config.database.clone();
+        let create_if_missing = node_config.private_config.database.create_if_missing;
+        // Directories are not created in the dry-run mode.
+        let prepare = |dir: &Path| {
+            if self.dry_run && create_if_missing {
+                Ok(())
+            } else {
+                prepare_dir(dir, create_if_missing)
+            }
+        };
+        // Atomic flush is incompatible with the disabled WAL, so it is not enabled by default
+        // in this case.
+        let wal_sync_mode = self
+            .wal_sync
+            .or(node_config.private_config.database.wal_sync_mode);
+        let atomic_flush = match self.atomic_flush {
+            None if self.apply_defaults => Some(wal_sync_mode != Some(WalSyncMode::Disabled)),
+            atomic_flush => atomic_flush,
+        };
+        if self.apply_defaults && self.recycle_log_files.is_none() {
+            node_config.private_config.database.recycle_log_file_num = None;
+        }
+        let mut database = DbOptionsBuilder::from(node_config.private_config.database);
+        if self.apply_defaults {
+            database = database
+                .max_open_files(MAX_OPEN_FILES)
+                .wal_size_limit(MAX_TOTAL_WAL_SIZE)
+                .log_verbosity(DEFAULT_LOG_LEVEL)
+                .max_log_file_size(MAX_LOG_FILE_SIZE)
+                .keep_log_file_num(KEEP_LOG_FILE_NUM);
+        }
+        if let Some(profile) = self.profile {
+            database = profile.apply(database);
+        }
+        if let Some(max_open_files) = self.max_open_files {
+            database = database.max_open_files(max_open_files);
+        }
+        if let Some(max_total_wal_size) = self.max_total_wal_size {
+            database = database.wal_size_limit(max_total_wal_size);
+        }
+        if let Some(log_level) = self.log_level {
+            database = database.log_verbosity(log_level);
+        }
+        if let Some(max_log_file_size) = self.max_log_file_size {
+            database = database.max_log_file_size(max_log_file_size);
+        }
+        if let Some(keep_log_file_num) = self.keep_log_file_num {
+            database = database.keep_log_file_num(keep_log_file_num);
+        }
+        if let Some(atomic_flush) = atomic_flush {
+            database = database.atomic_flush(atomic_flush);
+        }
+        if let Some(compression) = self.compression {
+            database = database.compression(compression);
+        }
+        if let Some(recycle_log_files) = self.recycle_log_files {
+            database = database.recycle_log_file_num(usize::from(recycle_log_files));
+        }
+        if let Some(block_cache_size) = self.block_cache_size {
+            database = database.block_cache_size(block_cache_size);
+        }
+        if let Some(write_buffer_size) = self.write_buffer_size {
+            database = database.write_buffer_size(write_buffer_size);
+        }
+        if let Some(max_write_buffer_number) = self.max_write_buffer_number {
+            database = database.max_write_buffer_number(max_write_buffer_number);
+        }
+        if let Some(compaction_style) = self.compaction_style {
+            database = database.compaction_style(compaction_style);
+        }
+        if let Some(max_table_files_size) = self.fifo_max_table_files_size {
+            database = database.fifo_max_table_files_size(max_table_files_size);
+        }
+        if self.auto_parallelism {
+            let cpus = num_cpus::get();
+            let (max_background_jobs, increase_parallelism) = auto_parallelism(cpus);
+            database = database
+                .max_background_jobs(max_background_jobs)
+                .increase_parallelism(increase_parallelism);
+        }
+        if let Some(max_background_jobs) = self.max_background_jobs {
+            database = database.max_background_jobs(max_background_jobs);
+        }
+        if let Some(increase_parallelism) = self.increase_parallelism {
+            database = database.increase_parallelism(increase_parallelism);
+        }
+        if let Some(direct_reads) = self.use_direct_reads {
+            database = database.use_direct_reads(direct_reads);
+        }
+        if let Some(direct_io) = self.use_direct_io_for_flush_and_compaction {
+            database = database.use_direct_io_for_flush_and_compaction(direct_io);
+        }
+        if let Some(rate_limit) = self.rate_limit {
+            database = database.rate_limiter_bytes_per_sec(rate_limit);
+        }
+        if let Some(wal_sync_mode) = self.wal_sync {
+            database = database.wal_sync_mode(wal_sync_mode);
+        }
+        if let Some(ref wal_dir) = self.wal_dir {
+            prepare(wal_dir)?;
+            database = database.wal_dir(wal_dir);
+        }
+        if let Some(ref db_log_dir) = self.db_log_dir {
+            prepare(db_log_dir)?;
+            database = database.db_log_dir(db_log_dir);
+        }
+        if let Some(access_pattern) = self.access_pattern {
+            database = database.access_pattern(access_pattern);
+        }
+        if let Some(seconds) = self.periodic_compaction {
+            database = database.periodic_compaction_seconds(seconds);
+        }
+        if let Some(readahead_size) = self.compaction_readahead_size {
+            database = database.compaction_readahead_size(readahead_size);
+        }
+        if let Some(enable_statistics) = self.enable_statistics {
+            database = database.enable_statistics(enable_statistics);
+        }
+        if let Some(period) = self.stats_dump_period {
+            let period = u32::try_from(period)
+                .map_err(|_| anyhow!("Statistics dump period is too large: {} s", period))?;
+            database = database.stats_dump_period_sec(period);
+        }
+        if let Some(period) = self.delete_obsolete_files_period {
+            let period_micros = period
+                .checked_mul(1_000_000)
+                .ok_or_else(|| anyhow!("Obsolete files deletion period is too large"))?;
+            database = database.delete_obsolete_files_period_micros(period_micros);
+        }
+        if let Some(size) = self.max_manifest_file_size {
+            database = database.max_manifest_file_size(size);
+        }
+        if let Some(mmap_reads) = self.allow_mmap_reads {
+            database = database.allow_mmap_reads(mmap_reads);
+        }
+        if let Some(mmap_writes) = self.allow_mmap_writes {
+            database = database.allow_mmap_writes(mmap_writes);
+        }
+        let mut database = database.build()?;
+        if !self.reset.is_empty() {
+            for field in &self.reset {
+                reset_field(&mut database, field)?;
+            }
+            database.validate()?;
+        }
+        if let Some(warning) = compression_warning(&old_database, &database) {
+            log::warn!("{}", warning);
+        }
+        node_config.private_config.database = database;
+
+        if self.dry_run {
+            let changes = database_diff(&old_database, &node_config.private_config.database)?;
+            if OutputFormat::is_text() {
+                for change in &changes {
+                    println!("{}", change);
                 }
-            } as usize);
-
-        // Since this may overwrite the input file, we aim for consistency
-        // by first writing to a temporary file, then moving atomically.
-        let out_file = self.output_file.unwrap_or(self.node_config_file.clone());
-        let tmp_file = out_file.with_extension(".tmp");
-        if tmp_file.exists() {
-            bail!(
-                "Failed to write to temporary output file. File exists: {:?}",
-                tmp_file
-            )
+            }
+            return Ok(StandardResult::OptimizeConfigDryRun {
+                node_config_path: self.node_config_file,
+                changes,
+            });
         }
-        save_config_file(&node_config, &tmp_file)?;
-        fs::rename(tmp_file, &out_file)?;
+
+        // Since this may overwrite the input file, the config is saved atomically.
+        let out_file = self.output_file.unwrap_or(self.node_config_file);
+        save_config_file_atomically(&node_config, &out_file)?;
 
         Ok(StandardResult::OptimizeConfig {
             node_config_path: out_file,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compaction_style_parsing() {
+        assert_eq!(
+            parse_compaction_style("level").unwrap(),
+            CompactionStyle::Level
+        );
+        assert_eq!(
+            parse_compaction_style("Universal").unwrap(),
+            CompactionStyle::Universal
+        );
+        assert_eq!(
+            parse_compaction_style("FIFO").unwrap(),
+            CompactionStyle::Fifo
+        );
+
+        let err = parse_compaction_style("tiered").unwrap_err();
+        assert_eq!(err.to_string(), "Unknown compaction style: tiered");
+    }
+
+    #[test]
+    fn auto_parallelism_from_cpu_count() {
+        assert_eq!(auto_parallelism(0), (2, 1));
+        assert_eq!(auto_parallelism(1), (2, 1));
+        assert_eq!(auto_parallelism(2), (2, 2));
+        assert_eq!(auto_parallelism(16), (16, 16));
+        assert_eq!(auto_parallelism(usize::MAX), (i32::MAX, i32::MAX));
+    }
+
+    #[test]
+    fn size_parsing() {
+        assert_eq!(parse_size::<u64>("0").unwrap(), 0);
+        assert_eq!(parse_size::<u64>("512").unwrap(), 512);
+        assert_eq!(parse_size::<u64>("512B").unwrap(), 512);
+        assert_eq!(parse_size::<u64>("4K").unwrap(), 4 << 10);
+        assert_eq!(parse_size::<u64>("4KiB").unwrap(), 4 << 10);
+        assert_eq!(parse_size::<u64>("4kb").unwrap(), 4_000);
+        assert_eq!(parse_size::<u64>("64MiB").unwrap(), 64 << 20);
+        assert_eq!(parse_size::<u64>("64 MB").unwrap(), 64_000_000);
+        assert_eq!(parse_size::<u64>("2g").unwrap(), 2 << 30);
+        assert_eq!(parse_size::<u64>("2GB").unwrap(), 2_000_000_000);
+        assert_eq!(parse_size::<u64>("1TiB").unwrap(), 1 << 40);
+        assert_eq!(parse_size::<i64>("1TB").unwrap(), 1_000_000_000_000);
+        assert_eq!(parse_size::<usize>(" 16M ").unwrap(), 16 << 20);
+    }
+
+    #[test]
+    fn size_parsing_errors() {
+        let err = parse_size::<u64>("").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid size: ");
+        let err = parse_size::<u64>("MiB").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid size: MiB");
+        let err = parse_size::<u64>("-1").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid size: -1");
+        let err = parse_size::<u64>("1.5GiB").unwrap_err();
+        assert_eq!(err.to_string(), "Unknown size suffix: 1.5GiB");
+        let err = parse_size::<u64>("64PiB").unwrap_err();
+        assert_eq!(err.to_string(), "Unknown size suffix: 64PiB");
+        let err = parse_size::<u64>("100000000TiB").unwrap_err();
+        assert_eq!(err.to_string(), "Size is too large: 100000000TiB");
+        let err = parse_size::<i32>("4GiB").unwrap_err();
+        assert_eq!(err.to_string(), "Size is too large: 4GiB");
+    }
+
+    #[test]
+    fn wal_sync_mode_parsing() {
+        assert_eq!(parse_wal_sync_mode("sync").unwrap(), WalSyncMode::Sync);
+        assert_eq!(parse_wal_sync_mode("Async").unwrap(), WalSyncMode::Async);
+        assert_eq!(
+            parse_wal_sync_mode("DISABLED").unwrap(),
+            WalSyncMode::Disabled
+        );
+
+        let err = parse_wal_sync_mode("fsync").unwrap_err();
+        assert_eq!(err.to_string(), "Unknown WAL sync mode: fsync");
+    }
+
+    #[test]
+    fn profile_parsing() {
+        assert_eq!(parse_profile("ssd").unwrap(), HardwareProfile::Ssd);
+        assert_eq!(parse_profile("HDD").unwrap(), HardwareProfile::Hdd);
+        assert_eq!(
+            parse_profile("low-memory").unwrap(),
+            HardwareProfile::LowMemory
+        );
+        assert_eq!(parse_profile("archive").unwrap(), HardwareProfile::Archive);
+
+        let err = parse_profile("nvme").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown profile: nvme. Available profiles: ssd, hdd, low-memory, archive"
+        );
+    }
+
+    fn profile_options(profile: HardwareProfile) -> DbOptions {
+        profile.apply(DbOptions::builder()).build().unwrap()
+    }
+
+    #[test]
+    fn ssd_profile() {
+        let options = profile_options(HardwareProfile::Ssd);
+        assert_eq!(options.max_open_files, Some(4096));
+        assert_eq!(options.max_total_wal_size, Some(256 << 20));
+        assert_eq!(options.write_buffer_size, Some(64 << 20));
+        assert_eq!(options.max_write_buffer_number, Some(4));
+        assert_eq!(options.compression_type, CompressionType::Snappy);
+        assert_eq!(options.max_log_file_size, Some(10 << 20));
+        assert_eq!(options.keep_log_file_num, Some(10));
+    }
+
+    #[test]
+    fn hdd_profile() {
+        let options = profile_options(HardwareProfile::Hdd);
+        assert_eq!(options.max_open_files, Some(1024));
+        assert_eq!(options.max_total_wal_size, Some(512 << 20));
+        assert_eq!(options.write_buffer_size, Some(128 << 20));
+        assert_eq!(options.max_write_buffer_number, Some(4));
+        assert_eq!(options.compaction_readahead_size, Some(2 << 20));
+        assert_eq!(options.compression_type, CompressionType::Snappy);
+        assert_eq!(options.max_log_file_size, Some(10 << 20));
+        assert_eq!(options.keep_log_file_num, Some(10));
+    }
+
+    #[test]
+    fn low_memory_profile() {
+        let options = profile_options(HardwareProfile::LowMemory);
+        assert_eq!(options.max_open_files, Some(256));
+        assert_eq!(options.max_total_wal_size, Some(64 << 20));
+        assert_eq!(options.write_buffer_size, Some(16 << 20));
+        assert_eq!(options.max_write_buffer_number, Some(2));
+        assert_eq!(options.block_cache_size, Some(64 << 20));
+        assert_eq!(options.compression_type, CompressionType::Snappy);
+        assert_eq!(options.max_log_file_size, Some(10 << 20));
+        assert_eq!(options.keep_log_file_num, Some(5));
+    }
+
+    #[test]
+    fn archive_profile() {
+        let options = profile_options(HardwareProfile::Archive);
+        assert_eq!(options.max_open_files, Some(2048));
+        assert_eq!(options.max_total_wal_size, Some(1 << 30));
+        assert_eq!(options.write_buffer_size, Some(128 << 20));
+        assert_eq!(options.max_write_buffer_number, Some(4));
+        assert_eq!(options.block_cache_size, Some(1 << 30));
+        assert_eq!(options.compression_type, CompressionType::Snappy);
+        assert_eq!(
+            options.bottommost_compression,
+            Some(CompressionType::Snappy)
+        );
+        assert_eq!(options.max_log_file_size, Some(64 << 20));
+        assert_eq!(options.keep_log_file_num, Some(10));
+    }
+
+    #[test]
+    fn compression_parsing() {
+        let args = ["optimize-config", "node.toml", "--compression", "zstd"];
+        let command = OptimizeConfig::from_iter_safe(&args).unwrap();
+        assert_eq!(command.compression, Some(CompressionType::Zstd));
+        let args = ["optimize-config", "node.toml", "--compression", "LZ4hc"];
+        let command = OptimizeConfig::from_iter_safe(&args).unwrap();
+        assert_eq!(command.compression, Some(CompressionType::Lz4hc));
+        let command = OptimizeConfig::from_iter_safe(&["optimize-config", "node.toml"]).unwrap();
+        assert_eq!(command.compression, None);
+
+        let args = ["optimize-config", "node.toml", "--compression", "gzip"];
+        let err = OptimizeConfig::from_iter_safe(&args).unwrap_err();
+        assert!(
+            err.message
+                .contains("expected one of: bz2, lz4, lz4hc, snappy, zlib, zstd, none"),
+            "{}",
+            err.message
+        );
+    }
+
+    #[test]
+    fn compression_change_warning() {
+        let old = DbOptions::default();
+        assert!(compression_warning(&old, &old).is_none());
+
+        let new = DbOptions::builder()
+            .compression(CompressionType::Zstd)
+            .build()
+            .unwrap();
+        let warning = compression_warning(&old, &new).unwrap();
+        assert!(warning.contains("to `zstd`"), "{}", warning);
+        assert!(warning.contains("recompress"), "{}", warning);
+    }
+
+    #[test]
+    fn reset_field_parsing() {
+        assert_eq!(
+            parse_reset_field("max-total-wal-size").unwrap(),
+            "max-total-wal-size"
+        );
+        let err = parse_reset_field("max_total_wal_size").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown database option: max_total_wal_size"
+        );
+
+        let mut database = DbOptions::default();
+        database.max_open_files = Some(512);
+        database.atomic_flush = Some(true);
+        reset_field(&mut database, "max-open-files").unwrap();
+        assert_eq!(database.max_open_files, None);
+        assert_eq!(database.atomic_flush, Some(true));
+    }
+
+    #[test]
+    fn directories_preparation() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let missing_dir = temp_dir.path().join("wal");
+
+        let err = prepare_dir(&missing_dir, false).unwrap_err();
+        assert!(err.to_string().starts_with("Directory does not exist"));
+        prepare_dir(&missing_dir, true).unwrap();
+        assert!(missing_dir.is_dir());
+        prepare_dir(&missing_dir, false).unwrap();
+
+        prepare_dir(Path::new("relative/wal"), false).unwrap();
+    }
+
+    #[test]
+    fn access_pattern_parsing() {
+        assert_eq!(parse_access_pattern("mixed").unwrap(), AccessPattern::Mixed);
+        assert_eq!(
+            parse_access_pattern("point-lookup:256").unwrap(),
+            AccessPattern::PointLookup { cache_mb: 256 }
+        );
+        assert_eq!(
+            parse_access_pattern("Point-Lookup:64").unwrap(),
+            AccessPattern::PointLookup { cache_mb: 64 }
+        );
+
+        let err = parse_access_pattern("point-lookup").unwrap_err();
+        assert!(err.to_string().starts_with("Cache size in MiB is required"));
+        let err = parse_access_pattern("point-lookup:lots").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid cache size in access pattern: point-lookup:lots"
+        );
+        let err = parse_access_pattern("mixed:1").unwrap_err();
+        assert_eq!(err.to_string(), "Unknown access pattern: mixed:1");
+        let err = parse_access_pattern("scan").unwrap_err();
+        assert_eq!(err.to_string(), "Unknown access pattern: scan");
+    }
+
+    #[test]
+    fn duration_parsing() {
+        assert_eq!(parse_duration("0").unwrap(), 0);
+        assert_eq!(parse_duration("90").unwrap(), 90);
+        assert_eq!(parse_duration("90s").unwrap(), 90);
+        assert_eq!(parse_duration("30m").unwrap(), 1_800);
+        assert_eq!(parse_duration("12h").unwrap(), 43_200);
+        assert_eq!(parse_duration("7d").unwrap(), 604_800);
+        assert_eq!(parse_duration("7D").unwrap(), 604_800);
+        assert_eq!(parse_duration("2w").unwrap(), 1_209_600);
+        assert_eq!(parse_duration(" 1 h ").unwrap(), 3_600);
+
+        let err = parse_duration("").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid duration: ");
+        let err = parse_duration("d").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid duration: d");
+        let err = parse_duration("1y").unwrap_err();
+        assert_eq!(err.to_string(), "Unknown duration unit: 1y");
+        let err = parse_duration("1.5h").unwrap_err();
+        assert_eq!(err.to_string(), "Unknown duration unit: 1.5h");
+        let err = parse_duration("99999999999999999999w").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid duration: 99999999999999999999w");
+        let err = parse_duration("99999999999999999w").unwrap_err();
+        assert_eq!(err.to_string(), "Duration is too large: 99999999999999999w");
+    }
+}
@@ -0,0 +1,131 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Output formats of the standard Exonum CLI commands.
+
+use anyhow::{anyhow, Error};
+use serde_derive::{Deserialize, Serialize};
+
+use std::{env, fmt, str::FromStr};
+
+use crate::command::{ConfigFieldChange, ConfigProblem, ConfigValidationError, ConfigsDifferError};
+
+/// Environment variable used to select the output format of the commands,
+/// e.g., `EXONUM_OUTPUT=json`.
+pub const OUTPUT_FORMAT_ENV_VAR: &str = "EXONUM_OUTPUT";
+
+/// Output format of the standard Exonum CLI commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum OutputFormat {
+    /// Human-readable text. Commands print free-form text to stdout and report
+    /// errors as text.
+    Text,
+    /// Each command prints a single JSON document describing its
+    /// [`StandardResult`](enum.StandardResult.html) to stdout. Errors are printed to stderr
+    /// as a JSON document with the [`ErrorOutput`](struct.ErrorOutput.html) schema.
+    Json,
+}
+
+impl OutputFormat {
+    const NAMES: &'static [(Self, &'static str)] = &[(Self::Text, "text"), (Self::Json, "json")];
+
+    /// Returns the output format specified with the `EXONUM_OUTPUT` environment variable,
+    /// or `Text` if the variable is not set.
+    pub fn from_env() -> Result<Self, Error> {
+        match env::var(OUTPUT_FORMAT_ENV_VAR) {
+            Ok(value) => value
+                .parse()
+                .map_err(|e| anyhow!("Invalid `{}` value: {}", OUTPUT_FORMAT_ENV_VAR, e)),
+            Err(env::VarError::NotPresent) => Ok(Self::Text),
+            Err(e) => Err(anyhow!("Invalid `{}` value: {}", OUTPUT_FORMAT_ENV_VAR, e)),
+        }
+    }
+
+    /// Returns `true` if the commands should print human-readable text. An invalid
+    /// environment variable is reported by the command dispatcher, so it is treated
+    /// as text here.
+    pub(crate) fn is_text() -> bool {
+        Self::from_env().map_or(true, |format| format == Self::Text)
+    }
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self {
+        Self::Text
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (_, name) = Self::NAMES
+            .iter()
+            .find(|(format, _)| format == self)
+            .expect("Format is not named");
+        formatter.write_str(name)
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lowercase = s.to_lowercase();
+        Self::NAMES
+            .iter()
+            .find(|(_, name)| *name == lowercase)
+            .map(|(format, _)| *format)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::NAMES.iter().map(|(_, name)| *name).collect();
+                anyhow!(
+                    "Unknown output format: {}. Available formats: {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Error of a command in the JSON output format.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ErrorOutput {
+    /// Error message.
+    pub error: String,
+    /// Messages of the underlying errors, from the outermost to the innermost one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub causes: Vec<String>,
+    /// Problems found by the `validate-config` command.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub problems: Option<Vec<ConfigProblem>>,
+    /// Differing fields found by the `config-diff` command.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changes: Option<Vec<ConfigFieldChange>>,
+}
+
+impl From<&Error> for ErrorOutput {
+    fn from(e: &Error) -> Self {
+        Self {
+            error: e.to_string(),
+            causes: e.chain().skip(1).map(ToString::to_string).collect(),
+            problems: e
+                .downcast_ref::<ConfigValidationError>()
+                .map(|e| e.problems.clone()),
+            changes: e
+                .downcast_ref::<ConfigsDifferError>()
+                .map(|e| e.changes.clone()),
+        }
+    }
+}
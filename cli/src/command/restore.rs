@@ -0,0 +1,313 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Standard Exonum CLI command used to restore the node database from a backup.
+
+use anyhow::{bail, format_err, Error};
+use exonum::merkledb::{list_backups, restore_from_backup, RocksDB};
+use serde_derive::{Deserialize, Serialize};
+use structopt::StructOpt;
+use tempfile::TempDir;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    command::{
+        backup::{copy_options, dir_size, is_empty_dir, temp_dir_near},
+        compact_db::is_locked,
+        ExonumCommand, StandardResult,
+    },
+    config::NodeConfig,
+    io::load_config_file,
+};
+
+/// Restore the node database from a backup created with the `backup` command.
+/// The node must be stopped.
+///
+/// If the node keeps its WAL in a separate directory (`wal_dir` in the database options),
+/// the WAL files of the restored database are placed there, and the WAL files
+/// of the replaced database are removed together with it.
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Restore {
+    /// Path to a node configuration file.
+    #[structopt(long, short = "c")]
+    pub node_config: PathBuf,
+
+    /// Path to a database directory to restore the database to.
+    #[structopt(long, short = "d")]
+    pub db_path: PathBuf,
+
+    /// Directory with a checkpoint or incremental backups.
+    #[structopt(long)]
+    pub backup_dir: PathBuf,
+
+    /// Identifier of the incremental backup to restore. The latest backup is restored
+    /// if not specified. Not valid for checkpoints.
+    #[structopt(long)]
+    pub backup_id: Option<u32>,
+
+    /// Replace the existing database at the database path.
+    #[structopt(long)]
+    pub force: bool,
+}
+
+/// Outcome of the `restore` command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct RestoreReport {
+    /// Path to the restored database.
+    pub db_path: PathBuf,
+    /// Identifier of the restored incremental backup; `None` for checkpoints.
+    pub backup_id: Option<u32>,
+    /// Time of the backup creation in seconds since the UNIX epoch; `None` for checkpoints.
+    pub timestamp: Option<i64>,
+    /// Size of the restored database in bytes.
+    pub size: u64,
+}
+
+/// Recursively copies the contents of the `from` directory to the `to` directory.
+fn copy_dir(from: &Path, to: &Path) -> Result<(), Error> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns paths to the WAL files in the directory, or an empty list if the directory
+/// does not exist.
+fn wal_files(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().map_or(false, |ext| ext == "log"))
+        .collect()
+}
+
+/// Moves WAL files from the `from` directory to the `to` directory. The directories
+/// may be located on different devices, in which case the files are copied.
+fn move_wal_files(from: &Path, to: &Path) -> Result<(), Error> {
+    fs::create_dir_all(to)?;
+    for path in wal_files(from) {
+        let target = to.join(path.file_name().expect("WAL file has a name"));
+        if fs::rename(&path, &target).is_err() {
+            fs::copy(&path, &target)?;
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Temporary directory for the WAL files, if the node keeps them outside of the database
+/// directory.
+struct WalDirs {
+    /// WAL directory of the node.
+    wal_dir: PathBuf,
+    /// Temporary directory next to `wal_dir`.
+    temp_dir: TempDir,
+}
+
+impl WalDirs {
+    fn new(wal_dir: &Path) -> Result<Self, Error> {
+        Ok(Self {
+            wal_dir: wal_dir.to_owned(),
+            temp_dir: temp_dir_near(wal_dir)?,
+        })
+    }
+
+    /// Directory with the WAL files of the restored database.
+    fn restored(&self) -> PathBuf {
+        self.temp_dir.path().join("restored")
+    }
+
+    /// Directory, to which the WAL files of the replaced database are moved.
+    fn replaced(&self) -> PathBuf {
+        self.temp_dir.path().join("replaced")
+    }
+}
+
+impl Restore {
+    fn check_target(&self, node_config: &NodeConfig) -> Result<(), Error> {
+        if !self.db_path.exists() || is_empty_dir(&self.db_path) {
+            return Ok(());
+        }
+        if !self.force {
+            bail!(
+                "Cannot restore database to {}: the path exists and is not an empty \
+                 directory. Use `--force` to replace the existing database",
+                self.db_path.display()
+            );
+        }
+        if self.db_path.join("CURRENT").is_file() {
+            // Check that the existing database is not used by a running node.
+            let options = &node_config.private_config.database;
+            if let Err(e) = RocksDB::open(&self.db_path, options) {
+                if is_locked(&e) {
+                    bail!(
+                        "Cannot restore database to {}: the existing database appears \
+                         to be opened by a running node ({})",
+                        self.db_path.display(),
+                        e
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the restored database into place. The existing database and its WAL files,
+    /// if any, are moved aside to `replaced_path` and `WalDirs::replaced()` respectively.
+    /// If moving the restored database fails, the existing database is moved back.
+    fn replace_database(
+        &self,
+        restored_path: &Path,
+        replaced_path: &Path,
+        wal_dirs: Option<&WalDirs>,
+    ) -> Result<(), Error> {
+        let replaces_existing = self.db_path.exists();
+        if replaces_existing {
+            fs::rename(&self.db_path, replaced_path)?;
+        }
+        // The WAL files of the replaced database must not be replayed into the restored one.
+        if let Some(wal_dirs) = wal_dirs {
+            move_wal_files(&wal_dirs.wal_dir, &wal_dirs.replaced())?;
+        }
+
+        let moved = fs::rename(restored_path, &self.db_path)
+            .map_err(Error::from)
+            .and_then(|()| match wal_dirs {
+                Some(wal_dirs) => move_wal_files(&wal_dirs.restored(), &wal_dirs.wal_dir),
+                None => Ok(()),
+            });
+        if let Err(e) = moved {
+            if self.db_path.exists() {
+                fs::rename(&self.db_path, restored_path).ok();
+            }
+            if replaces_existing {
+                fs::rename(replaced_path, &self.db_path).ok();
+            }
+            if let Some(wal_dirs) = wal_dirs {
+                move_wal_files(&wal_dirs.wal_dir, &wal_dirs.restored()).ok();
+                move_wal_files(&wal_dirs.replaced(), &wal_dirs.wal_dir).ok();
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Restores the backup to `target`, returning the identifier and the timestamp
+    /// of the restored incremental backup.
+    fn restore_to(&self, target: &Path) -> Result<(Option<u32>, Option<i64>), Error> {
+        if self.backup_dir.join("CURRENT").is_file() {
+            if self.backup_id.is_some() {
+                bail!(
+                    "`--backup-id` is not valid for the checkpoint at {}",
+                    self.backup_dir.display()
+                );
+            }
+            copy_dir(&self.backup_dir, target)?;
+            return Ok((None, None));
+        }
+
+        let backups = list_backups(&self.backup_dir)?;
+        let info = match self.backup_id {
+            Some(id) => backups.into_iter().find(|info| info.id == id),
+            None => backups.into_iter().last(),
+        };
+        let info = info.ok_or_else(|| match self.backup_id {
+            Some(id) => format_err!(
+                "Backup #{} does not exist in {}",
+                id,
+                self.backup_dir.display()
+            ),
+            None => format_err!("No backups in {}", self.backup_dir.display()),
+        })?;
+        restore_from_backup(&self.backup_dir, target, info.id)?;
+        Ok((Some(info.id), Some(info.timestamp)))
+    }
+}
+
+impl ExonumCommand for Restore {
+    fn execute(self) -> Result<StandardResult, Error> {
+        let node_config: NodeConfig = load_config_file(&self.node_config)?;
+        self.check_target(&node_config)?;
+
+        // The backup is restored to a temporary directory and is moved into place
+        // only after it is validated, so that the existing database is never overwritten
+        // partially.
+        let temp_dir = temp_dir_near(&self.db_path)?;
+        let restored_path = temp_dir.path().join("db");
+        let (backup_id, timestamp) = self.restore_to(&restored_path)?;
+
+        // If the node keeps the WAL outside of the database directory, the WAL files
+        // of the restored database are moved to a temporary directory next to the WAL
+        // directory, so that the restored database is validated with the same layout
+        // which the node uses. Info logs are still kept in the database directory.
+        let database_options = &node_config.private_config.database;
+        let wal_dirs = match database_options.wal_dir {
+            Some(ref wal_dir) if *wal_dir != self.db_path => Some(WalDirs::new(wal_dir)?),
+            _ => None,
+        };
+        let mut options = copy_options(database_options);
+        if let Some(ref wal_dirs) = wal_dirs {
+            move_wal_files(&restored_path, &wal_dirs.restored())?;
+            options.wal_dir = Some(wal_dirs.restored());
+        }
+        RocksDB::open(&restored_path, &options)
+            .and_then(RocksDB::close)
+            .map_err(|e| format_err!("Restored database cannot be opened: {}", e))?;
+        let size = dir_size(&restored_path)
+            + wal_dirs
+                .as_ref()
+                .map_or(0, |wal_dirs| dir_size(&wal_dirs.restored()));
+
+        let replaced_path = temp_dir.path().join("replaced");
+        self.replace_database(&restored_path, &replaced_path, wal_dirs.as_ref())?;
+
+        match backup_id {
+            Some(id) => log::info!(
+                "Restored backup #{} (created at {} s since the UNIX epoch, {} bytes) to {}",
+                id,
+                timestamp.unwrap_or_default(),
+                size,
+                self.db_path.display()
+            ),
+            None => log::info!(
+                "Restored checkpoint {} ({} bytes) to {}",
+                self.backup_dir.display(),
+                size,
+                self.db_path.display()
+            ),
+        }
+
+        Ok(StandardResult::Restore(RestoreReport {
+            db_path: self.db_path,
+            backup_id,
+            timestamp,
+            size,
+        }))
+    }
+}
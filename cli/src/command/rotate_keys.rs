@@ -0,0 +1,271 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Standard Exonum CLI command used to rotate the keys of the node.
+
+use anyhow::{bail, Context, Error};
+use exonum::{
+    crypto::SEED_LENGTH,
+    keys::{generate_keys, generate_keys_from_seed, read_keys_from_file, Keys},
+};
+use serde_derive::{Deserialize, Serialize};
+use structopt::StructOpt;
+use toml::Value;
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    command::{ExonumCommand, OutputFormat, StandardResult},
+    config::{NodeConfig, NodePrivateConfig, PreviousKeys},
+    io::{load_config_file, save_config_file_atomically, write_file_atomically},
+    password::{PassInputMethod, PassphraseUsage},
+};
+
+/// Rotate the keys of the node. The command replaces the master key file with a new
+/// master key encrypted with the same passphrase and updates the consensus public key
+/// in the private config.
+///
+/// The new keys are used after the node is restarted. The printed public keys should be
+/// communicated to other validators, so that the consensus configuration of the network
+/// is updated.
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct RotateKeys {
+    /// Path to the node configuration file (node.toml) or to the private config
+    /// produced by `generate-config` (sec.toml).
+    pub config_path: PathBuf,
+
+    /// Passphrase entry method for master key.
+    ///
+    /// Possible values are: `stdin`, `env{:ENV_VAR_NAME}`, `pass:PASSWORD`.
+    /// Default Value is `stdin`.
+    /// If `ENV_VAR_NAME` is not specified `$EXONUM_MASTER_PASS` is used
+    /// by default.
+    #[structopt(long)]
+    pub master_key_pass: Option<PassInputMethod>,
+
+    /// Path to the file with the hex-encoded 32-byte seed of the new master key.
+    ///
+    /// If not specified, the seed is generated randomly.
+    #[structopt(long)]
+    pub seed_file: Option<PathBuf>,
+
+    /// Keep the previous master key in the `previous_keys` section of the private config
+    /// for the specified number of seconds. Expired previous keys are removed by
+    /// the subsequent rotations.
+    #[structopt(long)]
+    pub grace_period: Option<u64>,
+}
+
+/// Private config of the node, either standalone or as a part of the node configuration.
+enum PrivateConfigFile {
+    Node(Box<NodeConfig>),
+    Private(NodePrivateConfig),
+}
+
+impl PrivateConfigFile {
+    fn load(path: &Path) -> Result<Self, Error> {
+        let raw: Value = load_config_file(path)?;
+        let config = if raw.get("private_config").is_some() {
+            Self::Node(Box::new(raw.try_into()?))
+        } else {
+            Self::Private(raw.try_into()?)
+        };
+        Ok(config)
+    }
+
+    fn private_config_mut(&mut self) -> &mut NodePrivateConfig {
+        match self {
+            Self::Node(config) => &mut config.private_config,
+            Self::Private(config) => config,
+        }
+    }
+
+    fn save(&self, path: &Path) -> Result<(), Error> {
+        match self {
+            Self::Node(config) => save_config_file_atomically(config, path),
+            Self::Private(config) => save_config_file_atomically(config, path),
+        }
+    }
+}
+
+/// Checks that the file with secrets is not readable by other users.
+#[cfg(unix)]
+fn check_permissions(path: &Path) -> Result<(), Error> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = fs::metadata(path)
+        .with_context(|| format!("reading metadata of {}", path.display()))?
+        .permissions()
+        .mode();
+    if mode & 0o004 != 0 {
+        bail!(
+            "{} is readable by other users; restrict its permissions, e.g., with `chmod 600 {}`",
+            path.display(),
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_permissions(_path: &Path) -> Result<(), Error> {
+    Ok(())
+}
+
+fn read_seed(path: &Path) -> Result<Vec<u8>, Error> {
+    check_permissions(path)?;
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("reading seed from {}", path.display()))?;
+    let seed = hex::decode(contents.trim())
+        .with_context(|| format!("seed in {} is not hex-encoded", path.display()))?;
+    if seed.len() != SEED_LENGTH {
+        bail!(
+            "Seed in {} has {} bytes, while {} bytes are expected",
+            path.display(),
+            seed.len(),
+            SEED_LENGTH
+        );
+    }
+    Ok(seed)
+}
+
+/// Returns the path to the previous master key file, e.g., `master.key.1600000000.toml`
+/// for `master.key.toml`.
+fn previous_key_path(master_key_path: &Path, timestamp: u64) -> PathBuf {
+    let stem = master_key_path
+        .file_stem()
+        .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+    let file_name = match master_key_path.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, timestamp, extension.to_string_lossy()),
+        None => format!("{}.{}", stem, timestamp),
+    };
+    master_key_path.with_file_name(file_name)
+}
+
+/// Writes a new master key to `path`, replacing the existing file atomically.
+fn write_master_key(path: &Path, passphrase: &[u8], seed: Option<&[u8]>) -> Result<Keys, Error> {
+    write_file_atomically(path, |tmp_file| match seed {
+        Some(seed) => {
+            let (keys, encrypted_key) = generate_keys_from_seed(passphrase, seed)?;
+            tmp_file.write_all(toml::to_string_pretty(&encrypted_key)?.as_bytes())?;
+            Ok(keys)
+        }
+        None => generate_keys(tmp_file.path(), passphrase),
+    })
+}
+
+impl ExonumCommand for RotateKeys {
+    fn execute(self) -> Result<StandardResult, Error> {
+        check_permissions(&self.config_path)?;
+        let mut config = PrivateConfigFile::load(&self.config_path)?;
+        let private_config = config.private_config_mut();
+        // Relative paths are resolved relative to the directory of the config file.
+        let config_dir = self.config_path.parent().unwrap_or_else(|| Path::new(""));
+        let master_key_path = config_dir.join(&private_config.master_key_path);
+        check_permissions(&master_key_path)?;
+        let seed = self.seed_file.as_deref().map(read_seed).transpose()?;
+
+        let passphrase = self
+            .master_key_pass
+            .unwrap_or_default()
+            .get_passphrase(PassphraseUsage::Using)?;
+        let old_keys = read_keys_from_file(&master_key_path, passphrase.as_bytes())
+            .with_context(|| format!("reading master key from {}", master_key_path.display()))?;
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let (expired_keys, previous_keys): (Vec<_>, Vec<_>) = private_config
+            .previous_keys
+            .drain(..)
+            .partition(|keys| keys.expires_at <= now);
+        private_config.previous_keys = previous_keys;
+
+        let mut previous_master_key_path = None;
+        if let Some(grace_period) = self.grace_period {
+            let path = previous_key_path(&private_config.master_key_path, now);
+            let full_path = config_dir.join(&path);
+            if full_path.exists() {
+                bail!("Previous master key file {} exists", full_path.display());
+            }
+            fs::copy(&master_key_path, &full_path).with_context(|| {
+                format!("copying previous master key to {}", full_path.display())
+            })?;
+            private_config.previous_keys.push(PreviousKeys {
+                master_key_path: path,
+                consensus_public_key: old_keys.consensus_pk(),
+                service_public_key: old_keys.service_pk(),
+                expires_at: now.saturating_add(grace_period),
+            });
+            previous_master_key_path = Some(full_path);
+        }
+
+        let keys = write_master_key(&master_key_path, passphrase.as_bytes(), seed.as_deref())?;
+        private_config.consensus_public_key = keys.consensus_pk();
+        config.save(&self.config_path)?;
+        log::info!(
+            "Rotated master key {}; new consensus public key: {}",
+            master_key_path.display(),
+            keys.consensus_pk()
+        );
+
+        // Expired keys are removed only after the config no longer refers to them.
+        for expired in expired_keys {
+            let path = config_dir.join(&expired.master_key_path);
+            match fs::remove_file(&path) {
+                Ok(()) => log::info!("Removed expired master key {}", path.display()),
+                Err(e) => log::warn!("Cannot remove expired master key {}: {}", path.display(), e),
+            }
+        }
+
+        if OutputFormat::is_text() {
+            println!("Consensus public key: {}", keys.consensus_pk());
+            println!("Service public key: {}", keys.service_pk());
+            println!(
+                "Share the new public keys with other validators. The node uses the new keys \
+                 after a restart; until the consensus configuration of the network is updated, \
+                 the node is not recognized as a validator."
+            );
+        }
+
+        Ok(StandardResult::RotateKeys {
+            config_path: self.config_path,
+            master_key_path,
+            consensus_public_key: keys.consensus_pk(),
+            service_public_key: keys.service_pk(),
+            previous_master_key_path,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn previous_key_paths() {
+        assert_eq!(
+            previous_key_path(Path::new("master.key.toml"), 1_600_000_000),
+            PathBuf::from("master.key.1600000000.toml")
+        );
+        assert_eq!(
+            previous_key_path(Path::new("/etc/exonum/master"), 5),
+            PathBuf::from("/etc/exonum/master.5")
+        );
+    }
+}
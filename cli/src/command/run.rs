@@ -25,19 +25,20 @@ use std::{
 use structopt::StructOpt;
 
 use crate::{
-    command::{ExonumCommand, StandardResult},
+    command::{DbOptionOverride, ExonumCommand, StandardResult},
     config::NodeConfig,
     io::load_config_file,
     password::{PassInputMethod, PassphraseUsage},
 };
 
 /// Container for node configuration parameters produced by `Run` command.
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 #[non_exhaustive]
 pub struct NodeRunConfig {
     /// Final node configuration parameters.
     pub node_config: NodeConfig,
-    /// Node keys.
+    /// Node keys. Not serialized, since the keys include secret keys.
+    #[serde(skip)]
     pub node_keys: Keys,
     /// Path to a directory containing database files, provided by user.
     pub db_path: PathBuf,
@@ -73,6 +74,14 @@ pub struct Run {
     /// by default.
     #[structopt(long)]
     pub master_key_pass: Option<PassInputMethod>,
+    /// Override a database option for this run, e.g., `--db-option max_open_files=512`.
+    ///
+    /// Options are named as the keys of the `private_config.database` section of
+    /// the node configuration, and values are specified in the TOML format; strings
+    /// may be unquoted. May be repeated; the overrides are applied in order
+    /// and are not saved to the configuration file.
+    #[structopt(long, number_of_values = 1)]
+    pub db_option: Vec<DbOptionOverride>,
 }
 
 impl ExonumCommand for Run {
@@ -91,6 +100,16 @@ impl ExonumCommand for Run {
             config.private_config.api.private_api_address = Some(private_api_address);
         }
 
+        // Override database options
+        if !self.db_option.is_empty() {
+            let database = &mut config.private_config.database;
+            for option in &self.db_option {
+                option.apply(database)?;
+                log::info!("Overriding database option: {}", option);
+            }
+            database.validate()?;
+        }
+
         let master_passphrase = self
             .master_key_pass
             .unwrap_or_default()
@@ -113,16 +113,9 @@ impl ExonumCommand for RunDev {
 
             let optimize = OptimizeConfig {
                 node_config_file: node_config_path.clone(),
-                // by default, modify the node_config_file in-place
-                output_file: None,
-                // use default
-                max_open_files: None,
-                // use default
-                max_total_wal_size: None,
-                log_level: None,
-                max_log_file_size: None,
-                keep_log_file_num: None,
-                recycle_log_files: None,
+                // by default, modify the node_config_file in-place and use default settings
+                apply_defaults: true,
+                ..OptimizeConfig::default()
             };
             optimize.execute()?;
         }
@@ -133,6 +126,7 @@ impl ExonumCommand for RunDev {
             public_api_address: None,
             private_api_address: None,
             master_key_pass: Some(FromStr::from_str("pass:").unwrap()),
+            db_option: vec![],
         };
         run.execute()
     }
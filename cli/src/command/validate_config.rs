@@ -0,0 +1,399 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Standard Exonum CLI command used to validate the node configuration.
+
+use anyhow::Error;
+use serde_derive::{Deserialize, Serialize};
+use structopt::StructOpt;
+use toml::Value;
+
+use std::{
+    fmt, fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    command::{ExonumCommand, OutputFormat, StandardResult},
+    config::NodeConfig,
+};
+
+/// Ports below this value usually require elevated privileges.
+const MIN_UNPRIVILEGED_PORT: u16 = 1024;
+
+/// Validate the node configuration without starting the node.
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ValidateConfig {
+    /// Path to node configuration file (node.toml).
+    pub node_config_file: PathBuf,
+
+    /// Print the found problems in the JSON format.
+    #[structopt(long)]
+    pub json: bool,
+}
+
+/// Severity of a problem found in the node configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum Severity {
+    /// The node cannot start or will malfunction with the configuration.
+    Error,
+    /// The configuration is valid, but is likely to be a mistake.
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+        })
+    }
+}
+
+/// Problem found in the node configuration by the `validate-config` command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ConfigProblem {
+    /// Severity of the problem.
+    pub severity: Severity,
+    /// Dot-separated path to the problematic field, e.g., `private_config.listen_address`.
+    /// Empty if the problem relates to the whole file.
+    pub field: String,
+    /// Description of the problem.
+    pub message: String,
+}
+
+impl ConfigProblem {
+    pub(super) fn error(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+
+    fn warning(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.field.is_empty() {
+            write!(formatter, "{}: {}", self.severity, self.message)
+        } else {
+            write!(
+                formatter,
+                "{}: {}: {}",
+                self.severity, self.field, self.message
+            )
+        }
+    }
+}
+
+/// Error returned by the `validate-config` command if the configuration has errors.
+/// The error contains all the found problems, including warnings.
+#[derive(Debug, Clone)]
+pub struct ConfigValidationError {
+    /// Path to the validated configuration file.
+    pub node_config_path: PathBuf,
+    /// Problems found in the configuration.
+    pub problems: Vec<ConfigProblem>,
+}
+
+impl fmt::Display for ConfigValidationError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors = self
+            .problems
+            .iter()
+            .filter(|problem| problem.severity == Severity::Error)
+            .count();
+        write!(
+            formatter,
+            "Node configuration {} has {} error(s)",
+            self.node_config_path.display(),
+            errors
+        )
+    }
+}
+
+impl std::error::Error for ConfigValidationError {}
+
+/// Collects keys present in `raw`, but missing from `known`, i.e., the keys which
+/// are ignored during deserialization. Empty arrays and tables are not reported,
+/// since they may be omitted during serialization.
+fn collect_unknown_keys(raw: &Value, known: &Value, prefix: &str, unknown: &mut Vec<String>) {
+    let (raw, known) = match (raw, known) {
+        (Value::Table(raw), Value::Table(known)) => (raw, known),
+        _ => return,
+    };
+    for (key, raw_value) in raw {
+        let field = if prefix.is_empty() {
+            key.to_owned()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match known.get(key) {
+            Some(known_value) => collect_unknown_keys(raw_value, known_value, &field, unknown),
+            None => {
+                let is_empty = match raw_value {
+                    Value::Array(array) => array.is_empty(),
+                    Value::Table(table) => table.is_empty(),
+                    _ => false,
+                };
+                if !is_empty {
+                    unknown.push(field);
+                }
+            }
+        }
+    }
+}
+
+/// Checks that the directory exists or can be created.
+pub(super) fn check_dir(field: &str, path: &Path, problems: &mut Vec<ConfigProblem>) {
+    if path.exists() {
+        if !path.is_dir() {
+            problems.push(ConfigProblem::error(
+                field,
+                format!("{} is not a directory", path.display()),
+            ));
+        }
+        return;
+    }
+
+    // The directory can be created if its closest existing ancestor is a writable directory.
+    let ancestor = path
+        .ancestors()
+        .skip(1)
+        .map(|ancestor| {
+            if ancestor.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                ancestor
+            }
+        })
+        .find(|ancestor| ancestor.exists());
+    let creatable = ancestor.map_or(false, |ancestor| {
+        ancestor.is_dir()
+            && fs::metadata(ancestor).map_or(false, |metadata| !metadata.permissions().readonly())
+    });
+    if !creatable {
+        problems.push(ConfigProblem::error(
+            field,
+            format!(
+                "directory {} does not exist and cannot be created",
+                path.display()
+            ),
+        ));
+    }
+}
+
+/// Checks the `host:port` address of a peer or the node itself.
+pub(super) fn check_address(field: &str, address: &str, problems: &mut Vec<ConfigProblem>) {
+    let port = address
+        .rfind(':')
+        .and_then(|pos| address[pos + 1..].parse::<u16>().ok());
+    match port {
+        None => problems.push(ConfigProblem::error(
+            field,
+            format!("`{}` does not contain a valid port (1 to 65535)", address),
+        )),
+        Some(0) => problems.push(ConfigProblem::error(
+            field,
+            format!("port in `{}` must not be zero", address),
+        )),
+        Some(_) => {}
+    }
+}
+
+/// Checks the port of an address, which the node listens to.
+pub(super) fn check_listen_address(
+    field: &str,
+    address: SocketAddr,
+    problems: &mut Vec<ConfigProblem>,
+) {
+    let port = address.port();
+    if port == 0 {
+        problems.push(ConfigProblem::error(
+            field,
+            "port must not be zero, since peers and clients need a fixed port",
+        ));
+    } else if port < MIN_UNPRIVILEGED_PORT {
+        problems.push(ConfigProblem::warning(
+            field,
+            format!("port {} usually requires elevated privileges", port),
+        ));
+    }
+}
+
+/// Checks that the file is readable.
+fn check_key_file(field: &str, path: &Path, problems: &mut Vec<ConfigProblem>) {
+    if let Err(e) = fs::File::open(path) {
+        problems.push(ConfigProblem::error(
+            field,
+            format!("cannot read {}: {}", path.display(), e),
+        ));
+        return;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        if let Ok(metadata) = fs::metadata(path) {
+            if metadata.permissions().mode() & 0o077 != 0 {
+                problems.push(ConfigProblem::warning(
+                    field,
+                    format!("{} is accessible by other users", path.display()),
+                ));
+            }
+        }
+    }
+}
+
+impl ValidateConfig {
+    /// Checks the node configuration, returning the found problems.
+    fn check(&self) -> Vec<ConfigProblem> {
+        let contents = match fs::read_to_string(&self.node_config_file) {
+            Ok(contents) => contents,
+            Err(e) => return vec![ConfigProblem::error("", format!("cannot read file: {}", e))],
+        };
+        let raw: Value = match toml::from_str(&contents) {
+            Ok(raw) => raw,
+            Err(e) => return vec![ConfigProblem::error("", format!("invalid TOML: {}", e))],
+        };
+        let config: NodeConfig = match raw.clone().try_into() {
+            Ok(config) => config,
+            Err(e) => return vec![ConfigProblem::error("", e.to_string())],
+        };
+
+        let mut problems = vec![];
+        let mut unknown = vec![];
+        if let Ok(known) = Value::try_from(&config) {
+            collect_unknown_keys(&raw, &known, "", &mut unknown);
+        }
+        // Database options reject unknown keys during deserialization, and may omit
+        // keys with default values during serialization, so they are not checked here.
+        let unknown = unknown
+            .into_iter()
+            .filter(|field| !field.starts_with("private_config.database"));
+        for field in unknown {
+            problems.push(ConfigProblem::error(field, "unknown field"));
+        }
+
+        let private = &config.private_config;
+        let database = &private.database;
+        if let Err(e) = database.validate() {
+            problems.push(ConfigProblem::error(
+                "private_config.database",
+                e.to_string(),
+            ));
+        }
+        if let Some(ref wal_dir) = database.wal_dir {
+            check_dir("private_config.database.wal_dir", wal_dir, &mut problems);
+        }
+        if let Some(ref db_log_dir) = database.db_log_dir {
+            check_dir(
+                "private_config.database.db_log_dir",
+                db_log_dir,
+                &mut problems,
+            );
+        }
+
+        check_listen_address(
+            "private_config.listen_address",
+            private.listen_address,
+            &mut problems,
+        );
+        check_address(
+            "private_config.external_address",
+            &private.external_address,
+            &mut problems,
+        );
+        let api_addresses = [
+            ("public_api_address", private.api.public_api_address),
+            ("private_api_address", private.api.private_api_address),
+        ];
+        for &(name, address) in &api_addresses {
+            if let Some(address) = address {
+                let field = format!("private_config.api.{}", name);
+                check_listen_address(&field, address, &mut problems);
+                if address.port() != 0 && address.port() == private.listen_address.port() {
+                    problems.push(ConfigProblem::error(
+                        field,
+                        "port is already used by `private_config.listen_address`",
+                    ));
+                }
+            }
+        }
+        for (i, peer) in private.connect_list.peers.iter().enumerate() {
+            let field = format!("private_config.connect_list.peers.{}.address", i);
+            check_address(&field, &peer.address, &mut problems);
+        }
+
+        // Relative paths are resolved relative to the directory of the config file.
+        let config_dir = self
+            .node_config_file
+            .parent()
+            .unwrap_or_else(|| Path::new(""));
+        let master_key_path = config_dir.join(&private.master_key_path);
+        check_key_file(
+            "private_config.master_key_path",
+            &master_key_path,
+            &mut problems,
+        );
+
+        problems
+    }
+}
+
+impl ExonumCommand for ValidateConfig {
+    fn execute(self) -> Result<StandardResult, Error> {
+        let problems = self.check();
+        if OutputFormat::is_text() {
+            if self.json {
+                println!("{}", serde_json::to_string_pretty(&problems)?);
+            } else if problems.is_empty() {
+                println!("OK");
+            } else {
+                for problem in &problems {
+                    println!("{}", problem);
+                }
+            }
+        }
+
+        if problems
+            .iter()
+            .any(|problem| problem.severity == Severity::Error)
+        {
+            return Err(ConfigValidationError {
+                node_config_path: self.node_config_file,
+                problems,
+            }
+            .into());
+        }
+        Ok(StandardResult::ValidateConfig {
+            node_config_path: self.node_config_file,
+            problems,
+        })
+    }
+}
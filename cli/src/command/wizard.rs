@@ -0,0 +1,438 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Standard Exonum CLI command used to configure the node interactively.
+
+use anyhow::{bail, Error};
+use exonum::merkledb::DbOptions;
+use exonum_supervisor::mode::Mode as SupervisorMode;
+use serde_derive::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use std::{
+    env,
+    io::{self, BufRead, Write},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+use crate::command::{
+    optimize_config::parse_profile,
+    validate_config::{check_address, check_dir, check_listen_address},
+    ConfigProblem, ExonumCommand, Finalize, GenerateConfig, GenerateTemplate, HardwareProfile,
+    OptimizeConfig, Severity, StandardResult, PRIVATE_CONFIG_FILE_NAME, PUBLIC_CONFIG_FILE_NAME,
+};
+
+/// Name of the template file created by the wizard.
+const TEMPLATE_FILE_NAME: &str = "template.toml";
+/// Name of the node configuration file created by the wizard.
+const NODE_CONFIG_FILE_NAME: &str = "node.toml";
+
+/// Configure the node interactively. The wizard prompts for the essential settings
+/// and runs the `generate-template`, `generate-config` and (for a single validator)
+/// `finalize` and `optimize-config` commands, printing them for reproducibility.
+#[derive(StructOpt, Debug, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Wizard {}
+
+/// Settings entered by the user.
+struct Answers {
+    data_dir: PathBuf,
+    validators_count: u32,
+    addresses: Addresses,
+    profile: HardwareProfile,
+    profile_name: String,
+    use_password: bool,
+}
+
+/// Network addresses entered by the user.
+struct Addresses {
+    peer_address: String,
+    listen_address: SocketAddr,
+    public_api_address: SocketAddr,
+    private_api_address: SocketAddr,
+}
+
+/// Prompts for answers, repeating the question until a valid answer is entered.
+struct Prompt<R, W> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Prompt<R, W> {
+    /// Asks the question, using `default` if the answer is empty. `check` parses
+    /// the answer and reports the found problems; the answer is accepted if it is parsed
+    /// and has no problems with the `Error` severity. Warnings are printed.
+    fn ask<T>(
+        &mut self,
+        question: &str,
+        default: &str,
+        mut check: impl FnMut(&str, &mut Vec<ConfigProblem>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        loop {
+            write!(self.output, "{} [{}]: ", question, default)?;
+            self.output.flush()?;
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                bail!("Input has ended before the configuration was completed");
+            }
+            let answer = match line.trim() {
+                "" => default,
+                answer => answer,
+            };
+
+            let mut problems = vec![];
+            let value = match check(answer, &mut problems) {
+                Ok(value) => value,
+                Err(e) => {
+                    writeln!(self.output, "error: {}", e)?;
+                    continue;
+                }
+            };
+            for problem in &problems {
+                writeln!(self.output, "{}", problem)?;
+            }
+            if problems
+                .iter()
+                .all(|problem| problem.severity != Severity::Error)
+            {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn ask_answers(&mut self) -> Result<Answers, Error> {
+        let data_dir = self.ask("Data directory", "exonum", |answer, problems| {
+            let data_dir = PathBuf::from(answer);
+            check_dir("data directory", &data_dir, problems);
+            let config_dir = data_dir.join("config");
+            if config_dir.join(PRIVATE_CONFIG_FILE_NAME).exists() {
+                bail!(
+                    "{} already contains a node configuration",
+                    config_dir.display()
+                );
+            }
+            Ok(data_dir)
+        })?;
+
+        let validators_count = self.ask(
+            "Number of validators in the network",
+            "1",
+            |answer, _| match answer.parse::<u32>() {
+                Ok(0) => bail!("there must be at least one validator"),
+                Ok(count) => Ok(count),
+                Err(e) => bail!("{}", e),
+            },
+        )?;
+
+        let addresses = self.ask_addresses()?;
+
+        let (profile, profile_name) = self.ask(
+            "Database profile (ssd, hdd, low-memory, archive)",
+            "ssd",
+            |answer, _| {
+                let profile = parse_profile(answer)?;
+                let options: DbOptions = profile.apply(DbOptions::builder()).build()?;
+                options.validate()?;
+                Ok((profile, answer.to_lowercase()))
+            },
+        )?;
+
+        let use_password = self.ask(
+            "Protect the master key with a passphrase? (yes/no)",
+            "yes",
+            |answer, _| match answer.to_lowercase().as_str() {
+                "y" | "yes" => Ok(true),
+                "n" | "no" => Ok(false),
+                _ => bail!("answer `yes` or `no`"),
+            },
+        )?;
+
+        Ok(Answers {
+            data_dir,
+            validators_count,
+            addresses,
+            profile,
+            profile_name,
+            use_password,
+        })
+    }
+
+    fn ask_addresses(&mut self) -> Result<Addresses, Error> {
+        let peer_address = self.ask(
+            "Address advertised to other nodes (host:port)",
+            "127.0.0.1:6333",
+            |answer, problems| {
+                check_address("private_config.external_address", answer, problems);
+                Ok(answer.to_owned())
+            },
+        )?;
+        let peer_port = peer_address[peer_address.rfind(':').map_or(0, |pos| pos + 1)..]
+            .parse::<u16>()
+            .unwrap_or(0);
+
+        let listen_address = self.ask(
+            "Address to listen for connections from other nodes",
+            &format!("0.0.0.0:{}", peer_port),
+            |answer, problems| {
+                let address = answer.parse()?;
+                check_listen_address("private_config.listen_address", address, problems);
+                Ok(address)
+            },
+        )?;
+
+        let public_api_address = self.ask(
+            "Public API listen address",
+            "0.0.0.0:8080",
+            |answer, problems| {
+                let address = answer.parse()?;
+                check_api_address("public_api_address", address, &[listen_address], problems);
+                Ok(address)
+            },
+        )?;
+        let private_api_address = self.ask(
+            "Private API listen address",
+            "127.0.0.1:8081",
+            |answer, problems| {
+                let address = answer.parse()?;
+                let used = [listen_address, public_api_address];
+                check_api_address("private_api_address", address, &used, problems);
+                Ok(address)
+            },
+        )?;
+
+        Ok(Addresses {
+            peer_address,
+            listen_address,
+            public_api_address,
+            private_api_address,
+        })
+    }
+}
+
+/// Checks the API listen address, which must not use the ports of `used` addresses.
+fn check_api_address(
+    name: &str,
+    address: SocketAddr,
+    used: &[SocketAddr],
+    problems: &mut Vec<ConfigProblem>,
+) {
+    let field = format!("private_config.api.{}", name);
+    check_listen_address(&field, address, problems);
+    if address.port() != 0 && used.iter().any(|used| used.port() == address.port()) {
+        problems.push(ConfigProblem::error(
+            field,
+            format!("port {} is already used", address.port()),
+        ));
+    }
+}
+
+/// Returns the name of the current executable to be used in the printed commands.
+fn program_name() -> String {
+    env::args_os()
+        .next()
+        .and_then(|arg| Some(Path::new(&arg).file_name()?.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "exonum-node".to_owned())
+}
+
+/// Quotes the command argument for a POSIX shell if necessary.
+fn quote(arg: impl AsRef<str>) -> String {
+    let arg = arg.as_ref();
+    let is_safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@[]".contains(c));
+    if is_safe {
+        arg.to_owned()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+fn path_arg(path: &Path) -> String {
+    quote(path.to_string_lossy())
+}
+
+fn print_commands(output: &mut impl Write, commands: &[String]) -> io::Result<()> {
+    for command in commands {
+        writeln!(output, "    {}", command)?;
+    }
+    Ok(())
+}
+
+impl Answers {
+    /// Writes the configuration files, printing the summary to `output`.
+    fn write_configs(&self, output: &mut impl Write) -> Result<StandardResult, Error> {
+        let program = program_name();
+        let addresses = &self.addresses;
+        let config_dir = self.data_dir.join("config");
+        let db_path = self.data_dir.join("db");
+        let template_config_path = config_dir.join(TEMPLATE_FILE_NAME);
+        let public_config_path = config_dir.join(PUBLIC_CONFIG_FILE_NAME);
+        let private_config_path = config_dir.join(PRIVATE_CONFIG_FILE_NAME);
+        let node_config_path = config_dir.join(NODE_CONFIG_FILE_NAME);
+        let mut commands = vec![];
+
+        GenerateTemplate {
+            common_config: template_config_path.clone(),
+            validators_count: self.validators_count,
+            supervisor_mode: SupervisorMode::Simple,
+        }
+        .execute()?;
+        commands.push(format!(
+            "{} generate-template {} --validators-count {}",
+            program,
+            path_arg(&template_config_path),
+            self.validators_count
+        ));
+
+        GenerateConfig {
+            common_config: template_config_path.clone(),
+            output_dir: config_dir.clone(),
+            peer_address: addresses.peer_address.clone(),
+            listen_address: Some(addresses.listen_address),
+            no_password: !self.use_password,
+            master_key_pass: None,
+            master_key_path: None,
+        }
+        .execute()?;
+        commands.push(format!(
+            "{} generate-config {} {} --peer-address {} --listen-address {}{}",
+            program,
+            path_arg(&template_config_path),
+            path_arg(&config_dir),
+            quote(&addresses.peer_address),
+            addresses.listen_address,
+            if self.use_password {
+                ""
+            } else {
+                " --no-password"
+            }
+        ));
+
+        let finalize_command = format!(
+            "{} finalize {} {} --public-configs {}{} --public-api-address {} \
+             --private-api-address {}",
+            program,
+            path_arg(&private_config_path),
+            path_arg(&node_config_path),
+            path_arg(&public_config_path),
+            if self.validators_count > 1 {
+                " <PUBLIC_CONFIGS_OF_OTHER_VALIDATORS>..."
+            } else {
+                ""
+            },
+            addresses.public_api_address,
+            addresses.private_api_address
+        );
+        let optimize_command = format!(
+            "{} optimize-config {} --apply-defaults --profile {}",
+            program,
+            path_arg(&node_config_path),
+            self.profile_name
+        );
+        let run_command = format!(
+            "{} run --node-config {} --db-path {}",
+            program,
+            path_arg(&node_config_path),
+            path_arg(&db_path)
+        );
+
+        // Other validators' public configs are required to finalize the configuration.
+        let is_finalized = self.validators_count == 1;
+        if is_finalized {
+            Finalize {
+                private_config_path: private_config_path.clone(),
+                output_config_path: node_config_path.clone(),
+                public_configs: vec![public_config_path.clone()],
+                public_api_address: Some(addresses.public_api_address),
+                private_api_address: Some(addresses.private_api_address),
+                public_allow_origin: None,
+                private_allow_origin: None,
+            }
+            .execute()?;
+            commands.push(finalize_command.clone());
+
+            OptimizeConfig {
+                node_config_file: node_config_path.clone(),
+                apply_defaults: true,
+                profile: Some(self.profile),
+                ..OptimizeConfig::default()
+            }
+            .execute()?;
+            commands.push(optimize_command.clone());
+        }
+
+        writeln!(
+            output,
+            "\nConfiguration files are written to {}. Equivalent commands:",
+            config_dir.display()
+        )?;
+        print_commands(output, &commands)?;
+        if is_finalized {
+            writeln!(output, "\nRun the node with:")?;
+            print_commands(output, &[run_command])?;
+        } else {
+            writeln!(
+                output,
+                "\nShare {} with other validators and collect their public configs. \
+                 Then finalize the configuration and run the node with:",
+                public_config_path.display()
+            )?;
+            print_commands(output, &[finalize_command, optimize_command, run_command])?;
+        }
+
+        Ok(StandardResult::Wizard {
+            template_config_path,
+            public_config_path,
+            private_config_path,
+            node_config_path: if is_finalized {
+                Some(node_config_path)
+            } else {
+                None
+            },
+            db_path,
+            commands,
+        })
+    }
+}
+
+impl Wizard {
+    /// Executes the wizard, reading answers from `input` and writing prompts and
+    /// the summary to `output`. Unlike `execute`, this method does not require
+    /// an interactive terminal.
+    pub fn execute_with<R: BufRead, W: Write>(
+        self,
+        input: R,
+        output: W,
+    ) -> Result<StandardResult, Error> {
+        let mut prompt = Prompt { input, output };
+        let answers = prompt.ask_answers()?;
+        answers.write_configs(&mut prompt.output)
+    }
+}
+
+impl ExonumCommand for Wizard {
+    fn execute(self) -> Result<StandardResult, Error> {
+        if !atty::is(atty::Stream::Stdin) || !atty::is(atty::Stream::Stdout) {
+            bail!(
+                "The wizard requires an interactive terminal. To configure the node \
+                 non-interactively, use the `generate-template`, `generate-config`, \
+                 `finalize` and `optimize-config` commands"
+            );
+        }
+        let stdin = io::stdin();
+        self.execute_with(stdin.lock(), io::stdout())
+    }
+}
@@ -78,6 +78,24 @@ pub struct NodePrivateConfig {
     pub connect_list: ConnectListConfig,
     /// Consensus public key of the node.
     pub consensus_public_key: PublicKey,
+    /// Master keys replaced by the `rotate-keys` command, which are kept for a grace period.
+    /// The keys are not used by the node.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub previous_keys: Vec<PreviousKeys>,
+}
+
+/// Master key replaced by the `rotate-keys` command.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PreviousKeys {
+    /// Path to the previous master key file.
+    pub master_key_path: PathBuf,
+    /// Consensus public key derived from the previous master key.
+    pub consensus_public_key: PublicKey,
+    /// Service public key derived from the previous master key.
+    pub service_public_key: PublicKey,
+    /// Unix timestamp (in seconds) after which the previous master key is removed
+    /// by the next key rotation.
+    pub expires_at: u64,
 }
 
 /// Configuration for the `Node`.
@@ -103,3 +121,36 @@ impl Into<CoreNodeConfig> for NodeConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use exonum::merkledb::DbOptions;
+    use serde_derive::Deserialize;
+
+    use crate::io::load_config_file;
+
+    #[test]
+    fn unknown_database_options_are_reported_when_loading_config() {
+        #[derive(Debug, Deserialize)]
+        struct Config {
+            database: DbOptions,
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("node.toml");
+        std::fs::write(&path, "[database]\nmax_opne_files = 64\n").unwrap();
+        let err = load_config_file::<_, Config>(&path).unwrap_err();
+        let message = format!("{:#}", err);
+        assert!(
+            message.contains(
+                "unknown database option `max_opne_files`; did you mean `max_open_files`?"
+            ),
+            "{}",
+            message
+        );
+
+        std::fs::write(&path, "[database]\nmax_open_files = 64\n").unwrap();
+        let config: Config = load_config_file(&path).unwrap();
+        assert_eq!(config.database.max_open_files, Some(64));
+    }
+}
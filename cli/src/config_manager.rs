@@ -106,6 +106,7 @@ mod tests {
                 thread_pool_size: None,
                 connect_list: ConnectListConfig::default(),
                 consensus_public_key: KeyPair::random().public_key(),
+                previous_keys: vec![],
             },
             public_config: NodePublicConfig {
                 consensus: ConsensusConfig::default(),
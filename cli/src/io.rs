@@ -16,9 +16,11 @@
 
 use anyhow::{Context, Error};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tempfile::{Builder as TempFileBuilder, NamedTempFile};
+
 use std::{
     fs::{self, File},
-    io::{Read, Write},
+    io::{self, Read, Write},
     path::Path,
 };
 
@@ -46,6 +48,24 @@ where
     Ok(())
 }
 
+/// Saves the config file atomically, so that the file at `path` either keeps its previous
+/// contents or has the new ones even if the process crashes. The config is written to a temporary
+/// file with a unique name in the same directory, which is then renamed to `path`.
+///
+/// If the file already exists, its permissions are preserved. Otherwise, the saved file
+/// is only accessible by its owner on Unix (has `0600` mode), since configs may contain
+/// secrets.
+pub fn save_config_file_atomically<P, T>(value: &T, path: P) -> Result<(), Error>
+where
+    T: Serialize,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    do_save_atomically(value, path, |_| Ok(()))
+        .with_context(|| format!("saving config to {}", path.display()))?;
+    Ok(())
+}
+
 fn do_load<T: DeserializeOwned>(path: &Path) -> Result<T, Error> {
     let mut file = File::open(path)?;
     let mut toml = String::new();
@@ -62,3 +82,168 @@ fn do_save<T: Serialize>(value: &T, path: &Path) -> Result<(), Error> {
     file.write_all(value_toml.to_string().as_bytes())?;
     Ok(())
 }
+
+/// Implementation of `save_config_file_atomically` with a hook, which is called with the path
+/// to the temporary file immediately before the rename.
+fn do_save_atomically<T, F>(value: &T, path: &Path, before_rename: F) -> Result<(), Error>
+where
+    T: Serialize,
+    F: FnOnce(&Path) -> Result<(), Error>,
+{
+    write_file_atomically(path, |tmp_file| {
+        let value_toml = toml::Value::try_from(value)?;
+        tmp_file.write_all(value_toml.to_string().as_bytes())?;
+        before_rename(tmp_file.path())
+    })
+}
+
+/// Writes the file atomically in the same way as `save_config_file_atomically`. `write` fills
+/// the temporary file, which is then renamed to `path`.
+pub(crate) fn write_file_atomically<T, F>(path: &Path, write: F) -> Result<T, Error>
+where
+    F: FnOnce(&mut NamedTempFile) -> Result<T, Error>,
+{
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(dir)?;
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("path does not contain a file name"))?;
+
+    // The temporary file is removed when dropped, i.e., on all error paths.
+    let mut tmp_file = TempFileBuilder::new()
+        .prefix(&format!(".{}.", file_name.to_string_lossy()))
+        .suffix(".tmp")
+        .tempfile_in(dir)?;
+    let output = write(&mut tmp_file)?;
+    match fs::metadata(path) {
+        Ok(metadata) => fs::set_permissions(tmp_file.path(), metadata.permissions())?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    tmp_file.as_file().sync_all()?;
+    sync_dir(dir)?;
+
+    tmp_file.persist(path).map_err(|e| e.error)?;
+    sync_dir(dir)?;
+    Ok(output)
+}
+
+/// Flushes changes to the directory entries, such as created or renamed files.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> Result<(), Error> {
+    File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+/// Directories cannot be opened as files on this platform, so changes to them are not flushed
+/// explicitly.
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> Result<(), Error> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::anyhow;
+    use tempfile::TempDir;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Config {
+        value: u32,
+    }
+
+    fn files_in(dir: &Path) -> Vec<String> {
+        let mut files: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn rename_makes_new_contents_visible() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("node.toml");
+        save_config_file(&Config { value: 1 }, &path).unwrap();
+
+        do_save_atomically(&Config { value: 2 }, &path, |tmp_path| {
+            assert_eq!(tmp_path.parent().unwrap(), dir.path());
+            let tmp_config: Config = load_config_file(tmp_path).unwrap();
+            assert_eq!(tmp_config, Config { value: 2 });
+            // The old contents are still visible.
+            let config: Config = load_config_file(&path).unwrap();
+            assert_eq!(config, Config { value: 1 });
+            Ok(())
+        })
+        .unwrap();
+
+        let config: Config = load_config_file(&path).unwrap();
+        assert_eq!(config, Config { value: 2 });
+        assert_eq!(files_in(dir.path()), vec!["node.toml"]);
+    }
+
+    #[test]
+    fn temporary_file_is_removed_on_error() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("node.toml");
+        save_config_file(&Config { value: 1 }, &path).unwrap();
+
+        let err =
+            do_save_atomically(&Config { value: 2 }, &path, |_| Err(anyhow!("crash"))).unwrap_err();
+        assert_eq!(err.to_string(), "crash");
+        let config: Config = load_config_file(&path).unwrap();
+        assert_eq!(config, Config { value: 1 });
+        assert_eq!(files_in(dir.path()), vec!["node.toml"]);
+    }
+
+    #[test]
+    fn leftover_temporary_files_are_ignored() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("node.toml");
+        // Temporary files left by a crashed process.
+        fs::write(dir.path().join("node..tmp"), "garbage").unwrap();
+        fs::write(dir.path().join(".node.toml.leftover.tmp"), "garbage").unwrap();
+
+        save_config_file_atomically(&Config { value: 1 }, &path).unwrap();
+        let config: Config = load_config_file(&path).unwrap();
+        assert_eq!(config, Config { value: 1 });
+        assert_eq!(
+            files_in(dir.path()),
+            vec![".node.toml.leftover.tmp", "node..tmp", "node.toml"]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn permissions_of_existing_file_are_preserved() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let file_mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("node.toml");
+        save_config_file_atomically(&Config { value: 1 }, &path).unwrap();
+        assert_eq!(file_mode(&path), 0o600);
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        save_config_file_atomically(&Config { value: 2 }, &path).unwrap();
+        assert_eq!(file_mode(&path), 0o644);
+        let config: Config = load_config_file(&path).unwrap();
+        assert_eq!(config, Config { value: 2 });
+    }
+
+    #[test]
+    fn saving_to_other_directory() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("configs").join("node.backup.toml");
+        save_config_file_atomically(&Config { value: 1 }, &path).unwrap();
+        let config: Config = load_config_file(&path).unwrap();
+        assert_eq!(config, Config { value: 1 });
+        assert_eq!(files_in(path.parent().unwrap()), vec!["node.backup.toml"]);
+    }
+}
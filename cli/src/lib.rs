@@ -45,8 +45,9 @@
 //!
 //! - `run-dev` command automatically generates network configuration with a single node and runs
 //!   it. This command can be useful for fast testing of the services during development process.
-//! - `maintenance` command allows to clear node's consensus messages with `clear-cache`, and
-//!   restart node's service migration script with `restart-migration`.
+//! - `maintenance` command allows to clear node's consensus messages with `clear-cache`,
+//!   restart node's service migration script with `restart-migration`, and repair
+//!   a corrupted database with `repair-db`.
 //!
 //! ## How to Extend Parameters
 //!
@@ -132,7 +133,7 @@
 
 pub use crate::{
     config_manager::DefaultConfigManager,
-    io::{load_config_file, save_config_file},
+    io::{load_config_file, save_config_file, save_config_file_atomically},
 };
 pub use exonum_rust_runtime::spec::Spec;
 pub use structopt;
@@ -14,9 +14,18 @@
 
 // This is a regression test for exonum configuration.
 
-use exonum::{blockchain::ValidatorKeys, crypto::KeyPair};
+use exonum::{
+    blockchain::ValidatorKeys,
+    crypto::{hash, KeyPair},
+    keys::{generate_keys_from_seed, read_keys_from_file},
+    merkledb::{
+        access::CopyAccessExt, list_backups, restore_from_backup, CompressionType, Database,
+        IndexType, LogVerbosity, RocksDB,
+    },
+};
 use exonum_supervisor::mode::Mode as SupervisorMode;
 use pretty_assertions::assert_eq;
+use serde_json::json;
 use structopt::StructOpt;
 use tempfile::TempDir;
 
@@ -26,14 +35,19 @@ use std::{
     env,
     ffi::OsString,
     fs::{self, OpenOptions},
+    io,
     path::{Path, PathBuf},
 };
 
 use exonum_cli::{
     command::{
-        Command, ExonumCommand, Finalize, GenerateConfig, GenerateTemplate, Run, StandardResult,
+        BackupMode, BackupReport, Command, ConfigFieldChange, ConfigMigration, ConfigProblem,
+        ConfigValidationError, ConfigsDifferError, DatabaseInfo, ErrorOutput, ExonumCommand,
+        Finalize, GenerateConfig, GenerateTemplate, RestoreReport, Run, Severity, StandardResult,
+        Wizard, CONTENT_HASH_PREFIX, DEFAULT_IGNORED_FIELDS, MASTER_KEY_FILE_NAME,
+        PRIVATE_CONFIG_FILE_NAME, PUBLIC_CONFIG_FILE_NAME,
     },
-    config::{GeneralConfig, NodePrivateConfig, NodePublicConfig},
+    config::{GeneralConfig, NodeConfig, NodePrivateConfig, NodePublicConfig},
     load_config_file,
     password::DEFAULT_MASTER_PASS_ENV_VAR,
     save_config_file,
@@ -471,6 +485,75 @@ fn test_finalize_run_with_pass() {
     assert!(is_run_node_config(feedback.unwrap()));
 }
 
+#[test]
+fn test_run_with_db_options() {
+    let env = ConfigSpec::new_without_pass();
+    let node_config_path = copy_node_config_with_keys(&env);
+    let mut node_config: NodeConfig = load_config_file(&node_config_path).unwrap();
+    node_config.private_config.database.max_open_files = Some(256);
+    save_config_file(&node_config, &node_config_path).unwrap();
+    let contents = fs::read_to_string(&node_config_path).unwrap();
+
+    let result = env
+        .command("run")
+        .with_named_arg("-c", &node_config_path)
+        .with_named_arg("-d", env.output_dir().join("db"))
+        .with_named_arg("--master-key-pass", "pass:")
+        .with_named_arg("--db-option", "max_open_files=1024")
+        .with_named_arg("--db-option", "compression_type=lz4")
+        .with_named_arg("--db-option", "atomic_flush=true")
+        // Later overrides take precedence.
+        .with_named_arg("--db-option", "max_open_files=2048")
+        .run()
+        .unwrap();
+    let database = match result {
+        StandardResult::Run(run_config) => run_config.node_config.private_config.database,
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(database.max_open_files, Some(2_048));
+    assert_eq!(database.compression_type, CompressionType::Lz4);
+    assert_eq!(database.atomic_flush, Some(true));
+    // Options which are not overridden are taken from the file.
+    assert!(database.create_if_missing);
+
+    // Overrides are not saved to the configuration file.
+    assert_eq!(fs::read_to_string(&node_config_path).unwrap(), contents);
+}
+
+#[test]
+fn test_run_with_invalid_db_options() {
+    let env = ConfigSpec::new_without_pass();
+    let args = vec![
+        OsString::from("exonum-config-test"),
+        "run".into(),
+        "-c".into(),
+        env.expected_node_config_file(0).into(),
+        "-d".into(),
+        env.output_dir().join("db").into(),
+        "--db-option".into(),
+        "max-open-files=512".into(),
+    ];
+    let err = <Command as StructOpt>::from_iter_safe(args).unwrap_err();
+    assert!(
+        err.message
+            .contains("unknown database option `max-open-files`; did you mean `max_open_files`?"),
+        "{}",
+        err.message
+    );
+
+    let node_config_path = copy_node_config_with_keys(&env);
+    let err = env
+        .command("run")
+        .with_named_arg("-c", &node_config_path)
+        .with_named_arg("-d", env.output_dir().join("db"))
+        .with_named_arg("--master-key-pass", "pass:")
+        .with_named_arg("--db-option", "write_buffer_size=67108864")
+        .with_named_arg("--db-option", "max_total_wal_size=1048576")
+        .run()
+        .unwrap_err();
+    assert!(err.to_string().contains("`max_total_wal_size`"), "{}", err);
+}
+
 #[test]
 #[should_panic(
     expected = "The number of validators (3) does not match the number of validators keys (4)."
@@ -579,17 +662,1713 @@ fn test_run_dev_with_cleanup() {
 
 #[test]
 fn test_clear_cache() {
+    const CONSENSUS_MESSAGES_CACHE: &str = "core.consensus_messages_cache";
+
+    let env = ConfigSpec::new_without_pass();
+    let db_path = env.output_dir().join("db0");
+    create_database(&env, &db_path);
+
+    let node_config: NodeConfig = load_config_file(env.expected_node_config_file(0)).unwrap();
+    let options = &node_config.private_config.database;
+    {
+        let db = RocksDB::open(&db_path, options).unwrap();
+        let fork = db.fork();
+        fork.get_list::<_, Vec<u8>>(CONSENSUS_MESSAGES_CACHE)
+            .extend(vec![vec![1], vec![2, 3]]);
+        fork.get_list::<_, Vec<u8>>("cache_test.list").push(vec![4]);
+        fork.get_entry("cache_test.entry").set(42_u64);
+        db.merge_sync(fork.into_patch()).unwrap();
+    }
+
+    let clear_cache = |force: bool| {
+        let mut command = env
+            .command("maintenance")
+            .with_named_arg("--node-config", &env.expected_node_config_file(0))
+            .with_named_arg("--db-path", &db_path);
+        if force {
+            command = command.with_arg("--force");
+        }
+        command.with_arg("clear-cache").run()
+    };
+
+    // The database has just been written to, as if the node was running.
+    let err = clear_cache(false).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("appears to be used by a running node"),
+        "{}",
+        err
+    );
+
+    match clear_cache(true).unwrap() {
+        StandardResult::Maintenance {
+            removed_entries, ..
+        } => assert_eq!(removed_entries, Some(2)),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+
+    // Only the cache is cleared.
+    {
+        let db = RocksDB::open(&db_path, options).unwrap();
+        let snapshot = db.snapshot();
+        assert!(snapshot
+            .get_list::<_, Vec<u8>>(CONSENSUS_MESSAGES_CACHE)
+            .is_empty());
+        assert_eq!(snapshot.get_list::<_, Vec<u8>>("cache_test.list").len(), 1);
+        assert_eq!(
+            snapshot.get_entry::<_, u64>("cache_test.entry").get(),
+            Some(42)
+        );
+    }
+
+    match clear_cache(true).unwrap() {
+        StandardResult::Maintenance {
+            removed_entries, ..
+        } => assert_eq!(removed_entries, Some(0)),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn test_repair_db() {
     let env = ConfigSpec::new_without_pass();
     let db_path = env.output_dir().join("db0");
+    create_database(&env, &db_path);
 
+    // The database has just been created, so the activity check is skipped.
     env.command("maintenance")
         .with_named_arg("--node-config", &env.expected_node_config_file(0))
         .with_named_arg("--db-path", &db_path)
+        .with_arg("--force")
+        .with_arg("repair-db")
+        .run()
+        .unwrap();
+}
+
+#[test]
+fn test_repair_db_fails_without_database() {
+    let env = ConfigSpec::new_without_pass();
+    let db_path = env.output_dir().join("db0");
+
+    let err = env
+        .command("maintenance")
+        .with_named_arg("--node-config", &env.expected_node_config_file(0))
+        .with_named_arg("--db-path", &db_path)
+        .with_arg("repair-db")
+        .run()
+        .unwrap_err();
+    assert!(err.to_string().contains("does not contain a database"));
+}
+
+fn create_database(env: &ConfigSpec, db_path: &Path) {
+    env.command("maintenance")
+        .with_named_arg("--node-config", &env.expected_node_config_file(0))
+        .with_named_arg("--db-path", db_path)
         .with_arg("clear-cache")
         .run()
         .unwrap();
 }
 
+#[test]
+fn test_db_stats() {
+    let env = ConfigSpec::new_without_pass();
+    let db_path = env.output_dir().join("db0");
+    create_database(&env, &db_path);
+    // The database is kept open for writing, as if the node was running.
+    let node_config: NodeConfig = load_config_file(env.expected_node_config_file(0)).unwrap();
+    let db = RocksDB::open(&db_path, &node_config.private_config.database).unwrap();
+
+    for json in &[false, true] {
+        let mut command = env
+            .command("db-stats")
+            .with_named_arg("--node-config", &env.expected_node_config_file(0))
+            .with_named_arg("--db-path", &db_path);
+        if *json {
+            command = command.with_arg("--json");
+        }
+        let stats = match command.run().unwrap() {
+            StandardResult::DbStats(stats) => stats,
+            other => panic!("Unexpected command result: {:?}", other),
+        };
+        assert_eq!(stats.db_path, db_path);
+        assert!(stats.size_on_disk > 0);
+        assert!(stats.column_families > 0);
+        assert!(stats.estimated_num_keys.is_some());
+        assert!(stats.pending_compaction_bytes.is_some());
+    }
+    drop(db);
+}
+
+#[test]
+fn test_db_stats_fails_without_database() {
+    let env = ConfigSpec::new_without_pass();
+    let db_path = env.output_dir().join("db0");
+
+    let err = env
+        .command("db-stats")
+        .with_named_arg("--node-config", &env.expected_node_config_file(0))
+        .with_named_arg("--db-path", &db_path)
+        .run()
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("does not contain a database"),
+        "{}",
+        err
+    );
+    assert!(!db_path.exists());
+}
+
+fn db_info(env: &ConfigSpec, db_path: &Path, json: bool) -> DatabaseInfo {
+    let mut command = env
+        .command("db-info")
+        .with_named_arg("--node-config", &env.expected_node_config_file(0))
+        .with_named_arg("--db-path", db_path);
+    if json {
+        command = command.with_arg("--json");
+    }
+    match command.run().unwrap() {
+        StandardResult::DbInfo(info) => info,
+        other => panic!("Unexpected command result: {:?}", other),
+    }
+}
+
+#[test]
+fn test_db_info() {
+    let env = ConfigSpec::new_without_pass();
+    let db_path = env.output_dir().join("db0");
+    create_database(&env, &db_path);
+
+    let info = db_info(&env, &db_path, false);
+    assert_eq!(info.db_path, db_path);
+    assert_eq!(info.db_version, Some(0));
+    assert_eq!(info.latest_height, None);
+    assert!(info.artifacts.is_empty());
+    assert!(info.instances.is_empty());
+    assert!(
+        info.problems
+            .iter()
+            .any(|problem| problem.contains("no blocks")),
+        "{:?}",
+        info.problems
+    );
+
+    // Add blocks and a group of indexes, keeping the database open as if the node was running.
+    let node_config: NodeConfig = load_config_file(env.expected_node_config_file(0)).unwrap();
+    let db = RocksDB::open(&db_path, &node_config.private_config.database).unwrap();
+    let fork = db.fork();
+    fork.get_list("core.block_hashes_by_height")
+        .extend(vec![hash(b"0"), hash(b"1"), hash(b"2")]);
+    fork.get_map(("test.group", &1_u64)).put(&1_u64, 2_u64);
+    fork.get_map(("test.group", &2_u64)).put(&1_u64, 2_u64);
+    db.merge_sync(fork.into_patch()).unwrap();
+
+    for &json in &[false, true] {
+        let info = db_info(&env, &db_path, json);
+        assert_eq!(info.latest_height, Some(2));
+        assert!(info.problems.is_empty(), "{:?}", info.problems);
+        let blocks = info
+            .indexes
+            .iter()
+            .find(|index| index.name == "core.block_hashes_by_height")
+            .unwrap();
+        assert_eq!(blocks.index_type, IndexType::List);
+        assert_eq!(blocks.group_size, None);
+        let group = info
+            .indexes
+            .iter()
+            .find(|index| index.name == "test.group")
+            .unwrap();
+        assert_eq!(group.index_type, IndexType::Map);
+        assert_eq!(group.group_size, Some(2));
+    }
+    drop(db);
+}
+
+#[test]
+fn test_db_info_with_empty_directory() {
+    let env = ConfigSpec::new_without_pass();
+    let db_path = env.output_dir().join("db0");
+    fs::create_dir_all(&db_path).unwrap();
+
+    let err = env
+        .command("db-info")
+        .with_named_arg("--node-config", &env.expected_node_config_file(0))
+        .with_named_arg("--db-path", &db_path)
+        .run()
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("does not contain a database"),
+        "{}",
+        err
+    );
+    assert_eq!(fs::read_dir(&db_path).unwrap().count(), 0);
+}
+
+#[test]
+fn test_compact_db() {
+    let env = ConfigSpec::new_without_pass();
+    let db_path = env.output_dir().join("db0");
+    create_database(&env, &db_path);
+
+    let result = env
+        .command("compact-db")
+        .with_named_arg("--node-config", &env.expected_node_config_file(0))
+        .with_named_arg("--db-path", &db_path)
+        .with_arg("--force")
+        .run()
+        .unwrap();
+    let report = match result {
+        StandardResult::CompactDb { report, .. } => report,
+        other => panic!("Unexpected command result: {:?}", other),
+    };
+    assert!(report.column_families.len() > 1);
+
+    // Compact a single column family.
+    let result = env
+        .command("compact-db")
+        .with_named_arg("--node-config", &env.expected_node_config_file(0))
+        .with_named_arg("--db-path", &db_path)
+        .with_named_arg("--column-family", "default")
+        .with_arg("--force")
+        .run()
+        .unwrap();
+    let report = match result {
+        StandardResult::CompactDb { report, .. } => report,
+        other => panic!("Unexpected command result: {:?}", other),
+    };
+    assert_eq!(report.column_families, vec!["default".to_owned()]);
+}
+
+#[test]
+fn test_compact_db_refuses_to_compact_active_database() {
+    let env = ConfigSpec::new_without_pass();
+    let db_path = env.output_dir().join("db0");
+    // The write-ahead log of the freshly created database is modified recently.
+    create_database(&env, &db_path);
+
+    let err = env
+        .command("compact-db")
+        .with_named_arg("--node-config", &env.expected_node_config_file(0))
+        .with_named_arg("--db-path", &db_path)
+        .run()
+        .unwrap_err();
+    assert!(err.to_string().contains("--force"), "{}", err);
+
+    // The database is kept open for writing, as if the node was running.
+    let node_config: NodeConfig = load_config_file(env.expected_node_config_file(0)).unwrap();
+    let _db = RocksDB::open(&db_path, &node_config.private_config.database).unwrap();
+    let err = env
+        .command("compact-db")
+        .with_named_arg("--node-config", &env.expected_node_config_file(0))
+        .with_named_arg("--db-path", &db_path)
+        .with_arg("--force")
+        .run()
+        .unwrap_err();
+    assert!(err.to_string().contains("running node"), "{}", err);
+}
+
+#[test]
+fn test_compact_db_with_unknown_column_family() {
+    let env = ConfigSpec::new_without_pass();
+    let db_path = env.output_dir().join("db0");
+    create_database(&env, &db_path);
+
+    let err = env
+        .command("compact-db")
+        .with_named_arg("--node-config", &env.expected_node_config_file(0))
+        .with_named_arg("--db-path", &db_path)
+        .with_named_arg("--column-family", "no_such_cf")
+        .with_arg("--force")
+        .run()
+        .unwrap_err();
+    assert!(err.to_string().contains("does not exist"), "{}", err);
+}
+
+fn backup(env: &ConfigSpec, db_path: &Path, args: &[&str]) -> anyhow::Result<BackupReport> {
+    let result = env
+        .command("backup")
+        .with_named_arg("--node-config", &env.expected_node_config_file(0))
+        .with_named_arg("--db-path", db_path)
+        .with_args(args)
+        .run()?;
+    match result {
+        StandardResult::Backup(report) => Ok(report),
+        other => panic!("Unexpected command result: {:?}", other),
+    }
+}
+
+#[test]
+fn test_backup_checkpoint() {
+    let env = ConfigSpec::new_without_pass();
+    let db_path = env.output_dir().join("db0");
+    let backup_dir = env.output_dir().join("backup");
+    create_database(&env, &db_path);
+
+    let backup_dir_arg = backup_dir.to_str().unwrap();
+    let report = backup(&env, &db_path, &["--backup-dir", backup_dir_arg]).unwrap();
+    assert_eq!(report.mode, BackupMode::Checkpoint);
+    assert_eq!(report.backup_id, None);
+    assert!(report.size > 0);
+
+    let node_config: NodeConfig = load_config_file(env.expected_node_config_file(0)).unwrap();
+    let options = node_config.private_config.database;
+    let db = RocksDB::open(&db_path, &options).unwrap();
+    let checkpoint = RocksDB::open(&backup_dir, &options).unwrap();
+    assert_eq!(
+        checkpoint.column_families().unwrap(),
+        db.column_families().unwrap()
+    );
+    drop((db, checkpoint));
+
+    let err = backup(&env, &db_path, &["--backup-dir", backup_dir_arg]).unwrap_err();
+    assert!(
+        err.to_string().contains("not an empty directory"),
+        "{}",
+        err
+    );
+    let err = backup(
+        &env,
+        &db_path,
+        &["--backup-dir", backup_dir_arg, "--keep", "1"],
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("--keep"), "{}", err);
+}
+
+#[test]
+fn test_backup_incremental() {
+    let env = ConfigSpec::new_without_pass();
+    let db_path = env.output_dir().join("db0");
+    let backup_dir = env.output_dir().join("backup");
+    create_database(&env, &db_path);
+
+    let args = [
+        "--backup-dir",
+        backup_dir.to_str().unwrap(),
+        "--mode",
+        "incremental",
+        "--keep",
+        "1",
+    ];
+    let report = backup(&env, &db_path, &args).unwrap();
+    assert_eq!(report.mode, BackupMode::Incremental);
+    assert_eq!(report.backup_id, Some(1));
+    assert!(report.size > 0);
+    let report = backup(&env, &db_path, &args).unwrap();
+    assert_eq!(report.backup_id, Some(2));
+
+    let backups = list_backups(&backup_dir).unwrap();
+    assert_eq!(backups.len(), 1);
+    assert_eq!(backups[0].id, 2);
+
+    let restored_path = env.output_dir().join("restored");
+    restore_from_backup(&backup_dir, &restored_path, 2).unwrap();
+    let node_config: NodeConfig = load_config_file(env.expected_node_config_file(0)).unwrap();
+    let options = node_config.private_config.database;
+    let db = RocksDB::open(&db_path, &options).unwrap();
+    let restored = RocksDB::open(&restored_path, &options).unwrap();
+    assert_eq!(
+        restored.column_families().unwrap(),
+        db.column_families().unwrap()
+    );
+}
+
+#[test]
+fn test_backup_of_running_node() {
+    let env = ConfigSpec::new_without_pass();
+    let db_path = env.output_dir().join("db0");
+    let backup_dir = env.output_dir().join("backup");
+    create_database(&env, &db_path);
+
+    // The database is kept open for writing, as if the node was running.
+    let node_config: NodeConfig = load_config_file(env.expected_node_config_file(0)).unwrap();
+    let db = RocksDB::open(&db_path, &node_config.private_config.database).unwrap();
+    let fork = db.fork();
+    fork.get_entry("backup_test").set(42_u64);
+    db.merge_sync(fork.into_patch()).unwrap();
+
+    let backup_dir_arg = backup_dir.to_str().unwrap();
+    let report = backup(&env, &db_path, &["--backup-dir", backup_dir_arg]).unwrap();
+    assert!(report.size > 0);
+    // The backup directory does not contain leftover temporary files.
+    assert_eq!(
+        fs::read_dir(env.output_dir())
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_str().unwrap().starts_with(".exonum-backup.")
+            })
+            .count(),
+        0
+    );
+
+    let checkpoint = RocksDB::open(&backup_dir, &node_config.private_config.database).unwrap();
+    let snapshot = checkpoint.snapshot();
+    assert_eq!(snapshot.get_entry::<_, u64>("backup_test").get(), Some(42));
+}
+
+fn wal_files(wal_dir: &Path) -> Vec<OsString> {
+    let mut files: Vec<_> = fs::read_dir(wal_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn test_backup_of_running_node_with_wal_dir() {
+    let env = ConfigSpec::new_without_pass();
+    let node_config_path = copy_node_config_with_keys(&env);
+    let mut config: NodeConfig = load_config_file(&node_config_path).unwrap();
+    let wal_dir = env.output_dir().join("wal");
+    config.private_config.database.wal_dir = Some(wal_dir.clone());
+    save_config_file(&config, &node_config_path).unwrap();
+    let db_path = env.output_dir().join("db0");
+    let backup_dir = env.output_dir().join("backup");
+
+    // The database is kept open for writing, as if the node was running.
+    let db = RocksDB::open(&db_path, &config.private_config.database).unwrap();
+    let fork = db.fork();
+    fork.get_entry("backup_test").set(42_u64);
+    db.merge_sync(fork.into_patch()).unwrap();
+    let node_wal_files = wal_files(&wal_dir);
+    assert!(!node_wal_files.is_empty());
+
+    let result = env
+        .command("backup")
+        .with_named_arg("--node-config", &node_config_path)
+        .with_named_arg("--db-path", &db_path)
+        .with_named_arg("--backup-dir", &backup_dir)
+        .run()
+        .unwrap();
+    assert!(matches!(result, StandardResult::Backup(_)));
+    // The backup does not write to or purge files in the WAL directory of the node.
+    assert_eq!(wal_files(&wal_dir), node_wal_files);
+    let snapshot = db.snapshot();
+    assert_eq!(snapshot.get_entry::<_, u64>("backup_test").get(), Some(42));
+
+    let mut options = config.private_config.database;
+    options.wal_dir = None;
+    let checkpoint = RocksDB::open(&backup_dir, &options).unwrap();
+    let snapshot = checkpoint.snapshot();
+    assert_eq!(snapshot.get_entry::<_, u64>("backup_test").get(), Some(42));
+}
+
+fn restore(env: &ConfigSpec, db_path: &Path, args: &[&str]) -> anyhow::Result<RestoreReport> {
+    let result = env
+        .command("restore")
+        .with_named_arg("--node-config", &env.expected_node_config_file(0))
+        .with_named_arg("--db-path", db_path)
+        .with_args(args)
+        .run()?;
+    match result {
+        StandardResult::Restore(report) => Ok(report),
+        other => panic!("Unexpected command result: {:?}", other),
+    }
+}
+
+fn read_test_entry(env: &ConfigSpec, db_path: &Path) -> Option<u64> {
+    let node_config: NodeConfig = load_config_file(env.expected_node_config_file(0)).unwrap();
+    let db = RocksDB::open(db_path, &node_config.private_config.database).unwrap();
+    db.snapshot().get_entry::<_, u64>("restore_test").get()
+}
+
+fn write_test_entry(env: &ConfigSpec, db_path: &Path, value: u64) {
+    let node_config: NodeConfig = load_config_file(env.expected_node_config_file(0)).unwrap();
+    let db = RocksDB::open(db_path, &node_config.private_config.database).unwrap();
+    let fork = db.fork();
+    fork.get_entry("restore_test").set(value);
+    db.merge_sync(fork.into_patch()).unwrap();
+}
+
+#[test]
+fn test_backup_restore_incremental() {
+    let env = ConfigSpec::new_without_pass();
+    let db_path = env.output_dir().join("db0");
+    let backup_dir = env.output_dir().join("backup");
+    let backup_dir_arg = backup_dir.to_str().unwrap();
+    create_database(&env, &db_path);
+
+    let backup_args = ["--backup-dir", backup_dir_arg, "--mode", "incremental"];
+    write_test_entry(&env, &db_path, 1);
+    backup(&env, &db_path, &backup_args).unwrap();
+    write_test_entry(&env, &db_path, 2);
+    let latest = backup(&env, &db_path, &backup_args).unwrap();
+
+    // Restore the latest backup into a new directory.
+    let restored_path = env.output_dir().join("restored");
+    let report = restore(&env, &restored_path, &["--backup-dir", backup_dir_arg]).unwrap();
+    assert_eq!(report.backup_id, latest.backup_id);
+    assert!(report.timestamp.is_some());
+    assert!(report.size > 0);
+    assert_eq!(read_test_entry(&env, &restored_path), Some(2));
+
+    // The existing database is not replaced without `--force`.
+    let args = ["--backup-dir", backup_dir_arg, "--backup-id", "1"];
+    let err = restore(&env, &restored_path, &args).unwrap_err();
+    assert!(err.to_string().contains("--force"), "{}", err);
+    assert_eq!(read_test_entry(&env, &restored_path), Some(2));
+
+    let args = [
+        "--backup-dir",
+        backup_dir_arg,
+        "--backup-id",
+        "1",
+        "--force",
+    ];
+    let report = restore(&env, &restored_path, &args).unwrap();
+    assert_eq!(report.backup_id, Some(1));
+    assert_eq!(read_test_entry(&env, &restored_path), Some(1));
+    // No temporary files are left.
+    assert_eq!(
+        fs::read_dir(env.output_dir())
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_str().unwrap().starts_with('.')
+            })
+            .count(),
+        0
+    );
+
+    let args = [
+        "--backup-dir",
+        backup_dir_arg,
+        "--backup-id",
+        "10",
+        "--force",
+    ];
+    let err = restore(&env, &restored_path, &args).unwrap_err();
+    assert!(err.to_string().contains("does not exist"), "{}", err);
+    assert_eq!(read_test_entry(&env, &restored_path), Some(1));
+}
+
+#[test]
+fn test_backup_restore_checkpoint() {
+    let env = ConfigSpec::new_without_pass();
+    let db_path = env.output_dir().join("db0");
+    let backup_dir = env.output_dir().join("backup");
+    let backup_dir_arg = backup_dir.to_str().unwrap();
+    create_database(&env, &db_path);
+    write_test_entry(&env, &db_path, 1);
+    backup(&env, &db_path, &["--backup-dir", backup_dir_arg]).unwrap();
+
+    let restored_path = env.output_dir().join("restored");
+    let report = restore(&env, &restored_path, &["--backup-dir", backup_dir_arg]).unwrap();
+    assert_eq!(report.backup_id, None);
+    assert!(report.size > 0);
+    assert_eq!(read_test_entry(&env, &restored_path), Some(1));
+    // The checkpoint is left intact.
+    assert_eq!(read_test_entry(&env, &backup_dir), Some(1));
+}
+
+#[test]
+fn test_backup_restore_with_wal_dir() {
+    let env = ConfigSpec::new_without_pass();
+    let node_config_path = copy_node_config_with_keys(&env);
+    let mut config: NodeConfig = load_config_file(&node_config_path).unwrap();
+    let wal_dir = env.output_dir().join("wal");
+    config.private_config.database.wal_dir = Some(wal_dir.clone());
+    save_config_file(&config, &node_config_path).unwrap();
+    let options = config.private_config.database;
+    let db_path = env.output_dir().join("db0");
+    let backup_dir = env.output_dir().join("backup");
+
+    let write_entry = |value: u64| {
+        let db = RocksDB::open(&db_path, &options).unwrap();
+        let fork = db.fork();
+        fork.get_entry("restore_test").set(value);
+        db.merge_sync(fork.into_patch()).unwrap();
+    };
+    let read_entry = || {
+        let db = RocksDB::open(&db_path, &options).unwrap();
+        db.snapshot().get_entry::<_, u64>("restore_test").get()
+    };
+
+    write_entry(1);
+    env.command("backup")
+        .with_named_arg("--node-config", &node_config_path)
+        .with_named_arg("--db-path", &db_path)
+        .with_named_arg("--backup-dir", &backup_dir)
+        .with_named_arg("--mode", "incremental")
+        .run()
+        .unwrap();
+    // This change is only recorded in the WAL of the existing database.
+    write_entry(2);
+    assert!(!wal_files(&wal_dir).is_empty());
+
+    let result = env
+        .command("restore")
+        .with_named_arg("--node-config", &node_config_path)
+        .with_named_arg("--db-path", &db_path)
+        .with_named_arg("--backup-dir", &backup_dir)
+        .with_arg("--force")
+        .run()
+        .unwrap();
+    assert!(matches!(result, StandardResult::Restore(_)));
+
+    // The restored database keeps its WAL in the configured directory, and the WAL files
+    // of the replaced database are not replayed.
+    let db_files: Vec<_> = fs::read_dir(&db_path)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert!(
+        db_files
+            .iter()
+            .all(|path| path.extension().map_or(true, |ext| ext != "log")),
+        "{:?}",
+        db_files
+    );
+    assert_eq!(read_entry(), Some(1));
+    // No temporary files are left.
+    let temp_files: Vec<_> = fs::read_dir(env.output_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .filter(|name| name.to_str().unwrap().starts_with('.'))
+        .collect();
+    assert!(temp_files.is_empty(), "{:?}", temp_files);
+}
+
+/// Copies the node config and the master key to the output directory, and returns the path
+/// to the copied node config.
+fn copy_node_config_with_keys(env: &ConfigSpec) -> PathBuf {
+    env.copy_node_config_to_output(0);
+    let node_config_path = env.output_node_config(0);
+    fs::copy(env.expected_node_config_file(0), &node_config_path).unwrap();
+    node_config_path
+}
+
+fn validate_config(env: &ConfigSpec, path: &Path) -> anyhow::Result<Vec<ConfigProblem>> {
+    let result = env
+        .command("validate-config")
+        .with_arg(path)
+        .with_arg("--json")
+        .run()?;
+    match result {
+        StandardResult::ValidateConfig { problems, .. } => Ok(problems),
+        other => panic!("Unexpected command result: {:?}", other),
+    }
+}
+
+fn validation_problems(err: anyhow::Error) -> Vec<ConfigProblem> {
+    err.downcast::<ConfigValidationError>().unwrap().problems
+}
+
+#[test]
+fn test_validate_config() {
+    let env = ConfigSpec::new_without_pass();
+    let node_config_path = copy_node_config_with_keys(&env);
+    assert_eq!(validate_config(&env, &node_config_path).unwrap(), vec![]);
+
+    // Master key accessible by other users is reported as a warning.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let key_path = env.output_node_config_dir(0).join("master.key.toml");
+        fs::set_permissions(&key_path, fs::Permissions::from_mode(0o644)).unwrap();
+        let problems = validate_config(&env, &node_config_path).unwrap();
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].severity, Severity::Warning);
+        assert_eq!(problems[0].field, "private_config.master_key_path");
+    }
+}
+
+#[test]
+fn test_validate_config_with_invalid_database_options() {
+    let env = ConfigSpec::new_without_pass();
+    let node_config_path = copy_node_config_with_keys(&env);
+    let mut config: NodeConfig = load_config_file(&node_config_path).unwrap();
+    config.private_config.database.keep_log_file_num = Some(0);
+    save_config_file(&config, &node_config_path).unwrap();
+
+    let err = validate_config(&env, &node_config_path).unwrap_err();
+    assert!(err.to_string().contains("1 error(s)"), "{}", err);
+    let problems = validation_problems(err);
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].severity, Severity::Error);
+    assert_eq!(problems[0].field, "private_config.database");
+}
+
+#[test]
+fn test_validate_config_with_missing_paths() {
+    let env = ConfigSpec::new_without_pass();
+    let node_config_path = copy_node_config_with_keys(&env);
+    let mut config: NodeConfig = load_config_file(&node_config_path).unwrap();
+    config.private_config.master_key_path = "missing.key.toml".into();
+    // The WAL directory cannot be created, since its parent is a file.
+    config.private_config.database.wal_dir = Some(node_config_path.join("wal"));
+    // The directory for the info log can be created.
+    config.private_config.database.db_log_dir = Some(env.output_dir().join("logs/db"));
+    save_config_file(&config, &node_config_path).unwrap();
+
+    let problems = validation_problems(validate_config(&env, &node_config_path).unwrap_err());
+    let fields: Vec<_> = problems
+        .iter()
+        .map(|problem| problem.field.as_str())
+        .collect();
+    assert_eq!(
+        fields,
+        vec![
+            "private_config.database.wal_dir",
+            "private_config.master_key_path"
+        ]
+    );
+}
+
+#[test]
+fn test_validate_config_with_unknown_fields_and_invalid_addresses() {
+    let env = ConfigSpec::new_without_pass();
+    let node_config_path = copy_node_config_with_keys(&env);
+    let mut config: toml::Value = load_config_file(&node_config_path).unwrap();
+    let private_config = config["private_config"].as_table_mut().unwrap();
+    private_config.insert("listen_adress".into(), "0.0.0.0:80".into());
+    private_config.insert("external_address".into(), "127.0.0.1".into());
+    save_config_file(&config, &node_config_path).unwrap();
+
+    let problems = validation_problems(validate_config(&env, &node_config_path).unwrap_err());
+    let fields: Vec<_> = problems
+        .iter()
+        .map(|problem| (problem.severity, problem.field.as_str()))
+        .collect();
+    assert_eq!(
+        fields,
+        vec![
+            (Severity::Error, "private_config.listen_adress"),
+            (Severity::Error, "private_config.external_address"),
+        ]
+    );
+}
+
+fn config_diff_changes(result: anyhow::Result<StandardResult>) -> Vec<ConfigFieldChange> {
+    match result {
+        Ok(StandardResult::ConfigDiff { .. }) => vec![],
+        Ok(other) => panic!("Unexpected command result: {:?}", other),
+        Err(err) => err.downcast::<ConfigsDifferError>().unwrap().changes,
+    }
+}
+
+#[test]
+fn test_config_diff() {
+    let env = ConfigSpec::new_without_pass();
+    let first = env.expected_node_config_file(0);
+    let second = env.output_node_config(0);
+    fs::create_dir_all(env.output_node_config_dir(0)).unwrap();
+    // The config is modified as a TOML tree, so that other fields are kept intact.
+    let mut config: toml::Value = load_config_file(&first).unwrap();
+    let private_config = config["private_config"].as_table_mut().unwrap();
+    private_config.insert("listen_address".into(), "0.0.0.0:6400".into());
+    let database = private_config["database"].as_table_mut().unwrap();
+    database.insert("max_open_files".into(), 1_024.into());
+    save_config_file(&config, &second).unwrap();
+
+    let result = env
+        .command("config-diff")
+        .with_arg(&first)
+        .with_arg(&second)
+        .with_arg("--json")
+        .run();
+    let changes = config_diff_changes(result);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].field, "private_config.database.max_open_files");
+    assert_eq!(changes[0].old_value, None);
+    assert_eq!(changes[0].new_value, Some("1024".to_owned()));
+
+    let result = env
+        .command("config-diff")
+        .with_arg(&first)
+        .with_arg(&second)
+        .with_named_arg("--ignore", "private_config.database")
+        .run();
+    assert_eq!(config_diff_changes(result), vec![]);
+
+    let result = env
+        .command("config-diff")
+        .with_arg(&first)
+        .with_arg(&second)
+        .with_named_arg("--ignore", "private_config.database.max_open_files")
+        .with_arg("--no-default-ignores")
+        .run();
+    let changes = config_diff_changes(result);
+    let fields: Vec<_> = changes.iter().map(|change| change.field.as_str()).collect();
+    assert_eq!(fields, vec!["private_config.listen_address"]);
+}
+
+#[test]
+fn test_config_diff_of_different_nodes() {
+    let env = ConfigSpec::new_without_pass();
+    let first = env.expected_node_config_file(0);
+    let second = env.expected_node_config_file(1);
+
+    let result = env
+        .command("config-diff")
+        .with_arg(&first)
+        .with_arg(&second)
+        .run();
+    assert_eq!(config_diff_changes(result), vec![]);
+
+    let result = env
+        .command("config-diff")
+        .with_arg(&first)
+        .with_arg(&second)
+        .with_arg("--no-default-ignores")
+        .run();
+    let changes = config_diff_changes(result);
+    assert!(changes
+        .iter()
+        .any(|change| change.field == "private_config.consensus_public_key"));
+    assert!(changes.iter().all(|change| DEFAULT_IGNORED_FIELDS
+        .iter()
+        .any(|field| change.field.starts_with(field))));
+}
+
+#[test]
+fn test_optimize_config() {
+    let env = ConfigSpec::new_without_pass();
+    let output = env.output_node_config(0);
+    fs::create_dir_all(env.output_node_config_dir(0)).unwrap();
+
+    env.command("optimize-config")
+        .with_arg(&env.expected_node_config_file(0))
+        .with_named_arg("-o", &output)
+        .with_arg("--apply-defaults")
+        .with_named_arg("--write-buffer-size", "512KiB")
+        .run()
+        .unwrap();
+    let config: NodeConfig = load_config_file(&output).unwrap();
+    let database = config.private_config.database;
+    assert_eq!(database.max_open_files, Some(256));
+    assert_eq!(database.write_buffer_size, Some(512 << 10));
+    assert_eq!(database.atomic_flush, Some(true));
+}
+
+#[test]
+fn test_optimize_config_with_legacy_database_options() {
+    let env = ConfigSpec::new_without_pass();
+    let output = env.output_node_config(0);
+    fs::create_dir_all(env.output_node_config_dir(0)).unwrap();
+
+    // The fixture omits the boolean options, as configs of older releases do.
+    let legacy_path = env.expected_node_config_file(0);
+    let legacy_config = fs::read_to_string(&legacy_path).unwrap();
+    assert!(!legacy_config.contains("auto_open_files"));
+    assert!(!legacy_config.contains("block_based_bloom"));
+
+    env.command("optimize-config")
+        .with_arg(&legacy_path)
+        .with_named_arg("-o", &output)
+        .with_arg("--apply-defaults")
+        .run()
+        .unwrap();
+    let config: NodeConfig = load_config_file(&output).unwrap();
+    let database = config.private_config.database;
+    assert_eq!(database.max_open_files, Some(256));
+    assert!(!database.auto_open_files);
+    assert!(!database.block_based_bloom);
+}
+
+#[test]
+fn test_optimize_config_without_wal() {
+    let env = ConfigSpec::new_without_pass();
+    let output = env.output_node_config(0);
+    fs::create_dir_all(env.output_node_config_dir(0)).unwrap();
+
+    env.command("optimize-config")
+        .with_arg(&env.expected_node_config_file(0))
+        .with_named_arg("-o", &output)
+        .with_arg("--apply-defaults")
+        .with_named_arg("--wal-sync", "disabled")
+        .run()
+        .unwrap();
+    let config: NodeConfig = load_config_file(&output).unwrap();
+    assert_eq!(config.private_config.database.atomic_flush, Some(false));
+}
+
+#[test]
+fn test_optimize_config_changes_only_specified_options() {
+    let env = ConfigSpec::new_without_pass();
+    let tuned = env.output_node_config_dir(0).join("tuned.toml");
+    let output = env.output_node_config(0);
+    fs::create_dir_all(env.output_node_config_dir(0)).unwrap();
+    env.command("optimize-config")
+        .with_arg(&env.expected_node_config_file(0))
+        .with_named_arg("-o", &tuned)
+        .with_named_arg("--max-total-wal-size", "64MiB")
+        .with_named_arg("--log-level", "info")
+        .with_named_arg("--keep-log-file-num", "3")
+        .run()
+        .unwrap();
+    let tuned_database = load_config_file::<NodeConfig, _>(&tuned)
+        .unwrap()
+        .private_config
+        .database;
+    assert_eq!(tuned_database.max_open_files, None);
+    assert_eq!(tuned_database.atomic_flush, None);
+
+    env.command("optimize-config")
+        .with_arg(&tuned)
+        .with_named_arg("-o", &output)
+        .with_named_arg("--max-open-files", "512")
+        .run()
+        .unwrap();
+    let database = load_config_file::<NodeConfig, _>(&output)
+        .unwrap()
+        .private_config
+        .database;
+    let mut expected = tuned_database;
+    expected.max_open_files = Some(512);
+    assert_eq!(database, expected);
+}
+
+#[test]
+fn test_optimize_config_applies_defaults() {
+    let env = ConfigSpec::new_without_pass();
+    let output = env.output_node_config(0);
+    fs::create_dir_all(env.output_node_config_dir(0)).unwrap();
+    let mut config: NodeConfig = load_config_file(env.expected_node_config_file(0)).unwrap();
+    config.private_config.database.recycle_log_file_num = Some(1);
+    save_config_file(&config, &output).unwrap();
+
+    env.command("optimize-config")
+        .with_arg(&output)
+        .with_arg("--apply-defaults")
+        .with_named_arg("--log-level", "info")
+        .run()
+        .unwrap();
+
+    let database = load_config_file::<NodeConfig, _>(&output)
+        .unwrap()
+        .private_config
+        .database;
+    assert_eq!(database.max_open_files, Some(256));
+    assert_eq!(database.max_total_wal_size, Some(1 << 20));
+    assert_eq!(database.log_verbosity, Some(LogVerbosity::Info));
+    assert_eq!(database.max_log_file_size, Some(10 << 20));
+    assert_eq!(database.keep_log_file_num, Some(10));
+    assert_eq!(database.recycle_log_file_num, None);
+    assert_eq!(database.atomic_flush, Some(true));
+}
+
+#[test]
+fn test_optimize_config_resets_options() {
+    let env = ConfigSpec::new_without_pass();
+    let output = env.output_node_config(0);
+    fs::create_dir_all(env.output_node_config_dir(0)).unwrap();
+    env.command("optimize-config")
+        .with_arg(&env.expected_node_config_file(0))
+        .with_named_arg("-o", &output)
+        .with_arg("--apply-defaults")
+        .run()
+        .unwrap();
+
+    env.command("optimize-config")
+        .with_arg(&output)
+        .with_named_arg("--reset", "max-total-wal-size")
+        .run()
+        .unwrap();
+    let database = load_config_file::<NodeConfig, _>(&output)
+        .unwrap()
+        .private_config
+        .database;
+    assert_eq!(database.max_total_wal_size, None);
+    assert_eq!(database.max_open_files, Some(256));
+    assert_eq!(database.keep_log_file_num, Some(10));
+}
+
+#[test]
+fn test_optimize_config_with_leftover_temporary_file() {
+    let env = ConfigSpec::new_without_pass();
+    let config_dir = env.output_node_config_dir(0);
+    let input = config_dir.join("node.toml");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::copy(env.expected_node_config_file(0), &input).unwrap();
+    // A temporary file left by a crashed run.
+    fs::write(config_dir.join("node..tmp"), "").unwrap();
+
+    env.command("optimize-config")
+        .with_arg(&input)
+        .with_named_arg("--max-open-files", "512")
+        .run()
+        .unwrap();
+    let config: NodeConfig = load_config_file(&input).unwrap();
+    assert_eq!(config.private_config.database.max_open_files, Some(512));
+
+    // Output to another directory.
+    let output = env.output_dir().join("optimized").join("node.toml");
+    env.command("optimize-config")
+        .with_arg(&input)
+        .with_named_arg("-o", &output)
+        .with_named_arg("--max-open-files", "1024")
+        .run()
+        .unwrap();
+    let config: NodeConfig = load_config_file(&output).unwrap();
+    assert_eq!(config.private_config.database.max_open_files, Some(1024));
+    assert_eq!(fs::read_dir(output.parent().unwrap()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_optimize_config_with_profile() {
+    let env = ConfigSpec::new_without_pass();
+    let output = env.output_node_config(0);
+    fs::create_dir_all(env.output_node_config_dir(0)).unwrap();
+
+    env.command("optimize-config")
+        .with_arg(&env.expected_node_config_file(0))
+        .with_named_arg("-o", &output)
+        .with_named_arg("--profile", "low-memory")
+        .with_named_arg("--max-open-files", "128")
+        .run()
+        .unwrap();
+    let database = load_config_file::<NodeConfig, _>(&output)
+        .unwrap()
+        .private_config
+        .database;
+    // The explicit flag overrides the profile.
+    assert_eq!(database.max_open_files, Some(128));
+    assert_eq!(database.max_total_wal_size, Some(64 << 20));
+    assert_eq!(database.write_buffer_size, Some(16 << 20));
+    assert_eq!(database.keep_log_file_num, Some(5));
+}
+
+#[test]
+fn test_optimize_config_with_unknown_profile() {
+    let env = ConfigSpec::new_without_pass();
+    let args = vec![
+        OsString::from("exonum-config-test"),
+        "optimize-config".into(),
+        env.expected_node_config_file(0).into(),
+        "--profile".into(),
+        "nvme".into(),
+    ];
+    let err = <Command as StructOpt>::from_iter_safe(args).unwrap_err();
+    assert!(
+        err.message
+            .contains("Available profiles: ssd, hdd, low-memory, archive"),
+        "{}",
+        err.message
+    );
+}
+
+#[test]
+fn test_optimize_config_with_compression() {
+    let env = ConfigSpec::new_without_pass();
+    let output = env.output_node_config(0);
+    fs::create_dir_all(env.output_node_config_dir(0)).unwrap();
+
+    env.command("optimize-config")
+        .with_arg(&env.expected_node_config_file(0))
+        .with_named_arg("-o", &output)
+        .with_named_arg("--compression", "zstd")
+        .run()
+        .unwrap();
+    let config: NodeConfig = load_config_file(&output).unwrap();
+    assert_eq!(
+        config.private_config.database.compression_type,
+        CompressionType::Zstd
+    );
+
+    // Compression is not changed if not specified.
+    env.command("optimize-config")
+        .with_arg(&output)
+        .with_named_arg("--max-open-files", "512")
+        .run()
+        .unwrap();
+    let config: NodeConfig = load_config_file(&output).unwrap();
+    assert_eq!(
+        config.private_config.database.compression_type,
+        CompressionType::Zstd
+    );
+}
+
+#[test]
+fn test_optimize_config_dry_run() {
+    let env = ConfigSpec::new_without_pass();
+    let config_dir = env.output_node_config_dir(0);
+    let output = env.output_node_config(0);
+    fs::create_dir_all(&config_dir).unwrap();
+    env.command("optimize-config")
+        .with_arg(&env.expected_node_config_file(0))
+        .with_named_arg("-o", &output)
+        .with_arg("--apply-defaults")
+        .run()
+        .unwrap();
+    let contents = fs::read_to_string(&output).unwrap();
+
+    let result = env
+        .command("optimize-config")
+        .with_arg(&output)
+        .with_arg("--dry-run")
+        .with_named_arg("--max-open-files", "512")
+        .with_named_arg("--write-buffer-size", "512KiB")
+        .with_named_arg("--keep-log-file-num", "10")
+        .run()
+        .unwrap();
+    let changes = match result {
+        StandardResult::OptimizeConfigDryRun {
+            node_config_path,
+            changes,
+        } => {
+            assert_eq!(node_config_path, output);
+            changes
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    };
+
+    let diff: Vec<_> = changes.iter().map(ToString::to_string).collect();
+    assert!(
+        diff.contains(&"max_open_files: 256 -> 512".to_owned()),
+        "{:?}",
+        diff
+    );
+    assert!(
+        diff.contains(&"write_buffer_size: (not set) -> 524288".to_owned()),
+        "{:?}",
+        diff
+    );
+    assert!(
+        diff.contains(&"keep_log_file_num: 10 (unchanged)".to_owned()),
+        "{:?}",
+        diff
+    );
+    assert_eq!(
+        changes.iter().filter(|change| change.is_changed()).count(),
+        2
+    );
+
+    // No files are written.
+    assert_eq!(fs::read_to_string(&output).unwrap(), contents);
+    assert_eq!(fs::read_dir(&config_dir).unwrap().count(), 1);
+}
+
+#[test]
+fn test_optimize_config_rejects_invalid_options() {
+    let env = ConfigSpec::new_without_pass();
+    let output = env.output_node_config(0);
+    fs::create_dir_all(env.output_node_config_dir(0)).unwrap();
+
+    let err = env
+        .command("optimize-config")
+        .with_arg(&env.expected_node_config_file(0))
+        .with_named_arg("-o", &output)
+        .with_named_arg("--write-buffer-size", "64MiB")
+        .run()
+        .unwrap_err();
+    assert!(err.to_string().contains("`max_total_wal_size`"), "{}", err);
+    assert!(!output.exists());
+}
+
+/// Serializes the command result as in the JSON output format, checking that
+/// the output round-trips through `serde_json::Value`.
+fn result_to_json(result: &StandardResult) -> serde_json::Value {
+    let output = serde_json::to_string_pretty(result).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(serde_json::to_value(result).unwrap(), value);
+    value
+}
+
+#[test]
+fn test_optimize_config_json_output() {
+    let env = ConfigSpec::new_without_pass();
+    let output = env.output_node_config(0);
+    fs::create_dir_all(env.output_node_config_dir(0)).unwrap();
+
+    let result = env
+        .command("optimize-config")
+        .with_arg(&env.expected_node_config_file(0))
+        .with_named_arg("-o", &output)
+        .with_arg("--apply-defaults")
+        .run()
+        .unwrap();
+    assert_eq!(
+        result_to_json(&result),
+        json!({
+            "command": "optimize-config",
+            "node_config_path": output,
+        })
+    );
+
+    let result = env
+        .command("optimize-config")
+        .with_arg(&output)
+        .with_arg("--dry-run")
+        .with_named_arg("--max-open-files", "512")
+        .run()
+        .unwrap();
+    let value = result_to_json(&result);
+    assert_eq!(value["command"], "optimize-config-dry-run");
+    assert_eq!(value["node_config_path"], json!(output));
+    let changes = value["changes"].as_array().unwrap();
+    assert!(
+        changes.contains(&json!({
+            "field": "max_open_files",
+            "old_value": "256",
+            "new_value": "512",
+        })),
+        "{:?}",
+        changes
+    );
+    assert!(changes.iter().all(|change| change["field"].is_string()));
+}
+
+#[test]
+fn test_generate_config_json_output() {
+    let env = ConfigSpec::new_without_pass();
+    let result = env
+        .command("generate-config")
+        .with_arg(&env.expected_template_file(SupervisorMode::Simple))
+        .with_arg(&env.output_node_config_dir(0))
+        .with_named_arg("-a", "0.0.0.0:8000")
+        .with_arg("--no-password")
+        .run()
+        .unwrap();
+
+    let output_dir = env.output_node_config_dir(0);
+    assert_eq!(
+        result_to_json(&result),
+        json!({
+            "command": "generate-config",
+            "public_config_path": output_dir.join(PUBLIC_CONFIG_FILE_NAME),
+            "private_config_path": output_dir.join(PRIVATE_CONFIG_FILE_NAME),
+            "master_key_path": MASTER_KEY_FILE_NAME,
+        })
+    );
+}
+
+#[test]
+fn test_error_json_output() {
+    let env = ConfigSpec::new_without_pass();
+    let config_path = copy_node_config_with_keys(&env);
+    let mut config: NodeConfig = load_config_file(&config_path).unwrap();
+    config.private_config.listen_address = "0.0.0.0:0".parse().unwrap();
+    save_config_file(&config, &config_path).unwrap();
+
+    let err = validate_config(&env, &config_path).unwrap_err();
+    let output = serde_json::to_value(ErrorOutput::from(&err)).unwrap();
+    assert_eq!(output["error"], err.to_string());
+    assert!(output.get("changes").is_none());
+    let problems = output["problems"].as_array().unwrap();
+    assert!(
+        problems.contains(&json!({
+            "severity": "error",
+            "field": "private_config.listen_address",
+            "message": "port must not be zero, since peers and clients need a fixed port",
+        })),
+        "{:?}",
+        problems
+    );
+}
+
+#[test]
+fn test_migrate_config() {
+    let env = ConfigSpec::new_without_pass();
+    fs::create_dir_all(env.output_node_config_dir(0)).unwrap();
+    let legacy_path = env.output_node_config_dir(0).join("legacy.toml");
+    let output = env.output_node_config(0);
+
+    // Emulate the config written by an earlier release.
+    let mut config: toml::Value = load_config_file(env.expected_node_config_file(0)).unwrap();
+    let private_config = config["private_config"].as_table_mut().unwrap();
+    let listen_address = private_config.remove("listen_address").unwrap();
+    private_config.insert("listen_addr".into(), listen_address);
+    private_config.remove("network").unwrap();
+    save_config_file(&config, &legacy_path).unwrap();
+    let contents = fs::read_to_string(&legacy_path).unwrap();
+    load_config_file::<_, NodeConfig>(&legacy_path).unwrap_err();
+
+    let result = env
+        .command("migrate-config")
+        .with_arg(&legacy_path)
+        .with_arg("--check")
+        .run()
+        .unwrap();
+    let migrations = match result {
+        StandardResult::MigrateConfig {
+            migrations,
+            written: false,
+            ..
+        } => migrations,
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert!(migrations.contains(&ConfigMigration::RenamedKey {
+        from: "private_config.listen_addr".to_owned(),
+        to: "private_config.listen_address".to_owned(),
+    }));
+    assert!(migrations.contains(&ConfigMigration::AddedDefault {
+        field: "private_config.network.max_incoming_connections".to_owned(),
+        value: "128".to_owned(),
+    }));
+    // No files are written in the check mode.
+    assert_eq!(fs::read_to_string(&legacy_path).unwrap(), contents);
+    assert!(!output.exists());
+
+    env.command("migrate-config")
+        .with_arg(&legacy_path)
+        .with_named_arg("-o", &output)
+        .run()
+        .unwrap();
+    let migrated: NodeConfig = load_config_file(&output).unwrap();
+    let expected: NodeConfig = load_config_file(env.expected_node_config_file(0)).unwrap();
+    assert_eq!(migrated, expected);
+
+    // The migrated config is up to date.
+    let result = env
+        .command("migrate-config")
+        .with_arg(&output)
+        .run()
+        .unwrap();
+    match result {
+        StandardResult::MigrateConfig { migrations, .. } => assert!(migrations.is_empty()),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+fn run_wizard(answers: &[&str]) -> (anyhow::Result<StandardResult>, String) {
+    let mut input = answers.join("\n");
+    input.push('\n');
+    let mut output = vec![];
+    let result = Wizard::default().execute_with(io::Cursor::new(input), &mut output);
+    (result, String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn test_wizard() {
+    let env = ConfigSpec::new_without_pass();
+    let data_dir = env.output_dir().join("wizard");
+    let (result, output) = run_wizard(&[
+        data_dir.to_str().unwrap(),
+        "0",
+        "1",
+        "127.0.0.1:7000",
+        "",
+        // The port is used by the peer connections.
+        "0.0.0.0:7000",
+        "",
+        "",
+        "hdd",
+        "no",
+    ]);
+
+    let (node_config_path, commands) = match result.unwrap() {
+        StandardResult::Wizard {
+            node_config_path: Some(node_config_path),
+            commands,
+            ..
+        } => (node_config_path, commands),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert!(output.contains("error: there must be at least one validator"));
+    assert!(output.contains("port 7000 is already used"));
+    assert_eq!(commands.len(), 4);
+    assert!(commands[1].contains(" generate-config "));
+    assert!(commands[1].ends_with(" --no-password"));
+    for command in &commands {
+        assert!(output.contains(command.as_str()), "{}", command);
+    }
+
+    let config: NodeConfig = load_config_file(&node_config_path).unwrap();
+    let private_config = &config.private_config;
+    assert_eq!(private_config.external_address, "127.0.0.1:7000");
+    assert_eq!(
+        private_config.listen_address,
+        "0.0.0.0:7000".parse().unwrap()
+    );
+    assert_eq!(
+        private_config.api.public_api_address,
+        Some("0.0.0.0:8080".parse().unwrap())
+    );
+    assert_eq!(
+        private_config.api.private_api_address,
+        Some("127.0.0.1:8081".parse().unwrap())
+    );
+    assert_eq!(private_config.database.max_open_files, Some(1024));
+    assert!(data_dir.join("config").join(MASTER_KEY_FILE_NAME).exists());
+
+    // The existing configuration is not overwritten.
+    let (result, output) = run_wizard(&[data_dir.to_str().unwrap()]);
+    assert!(output.contains("already contains a node configuration"));
+    let err = result.unwrap_err();
+    assert!(err.to_string().contains("Input has ended"), "{}", err);
+}
+
+#[test]
+fn test_wizard_with_several_validators() {
+    let env = ConfigSpec::new_without_pass();
+    let data_dir = env.output_dir().join("wizard");
+    let (result, output) = run_wizard(&[data_dir.to_str().unwrap(), "4", "", "", "", "", "", "no"]);
+
+    match result.unwrap() {
+        StandardResult::Wizard {
+            public_config_path,
+            node_config_path: None,
+            commands,
+            ..
+        } => {
+            assert!(public_config_path.exists());
+            assert_eq!(commands.len(), 2);
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    }
+    assert!(output.contains("Share "));
+    assert!(output.contains("<PUBLIC_CONFIGS_OF_OTHER_VALIDATORS>"));
+    assert!(!data_dir.join("config").join("node.toml").exists());
+}
+
+#[cfg(unix)]
+fn set_file_mode(path: &Path, mode: u32) {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+}
+
+#[cfg(unix)]
+fn file_mode(path: &Path) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).unwrap().permissions().mode() & 0o777
+}
+
+#[test]
+#[cfg(unix)]
+fn test_rotate_keys() {
+    let env = ConfigSpec::new_without_pass();
+    let node_config_path = copy_node_config_with_keys(&env);
+    let config_dir = env.output_node_config_dir(0);
+    let master_key_path = config_dir.join(MASTER_KEY_FILE_NAME);
+    let old_keys = read_keys_from_file(&master_key_path, "").unwrap();
+
+    set_file_mode(&node_config_path, 0o644);
+    let err = env
+        .command("rotate-keys")
+        .with_arg(&node_config_path)
+        .with_named_arg("--master-key-pass", "pass:")
+        .run()
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("is readable by other users"),
+        "{}",
+        err
+    );
+    assert_eq!(read_keys_from_file(&master_key_path, "").unwrap(), old_keys);
+
+    set_file_mode(&node_config_path, 0o600);
+    let result = env
+        .command("rotate-keys")
+        .with_arg(&node_config_path)
+        .with_named_arg("--master-key-pass", "pass:")
+        .with_named_arg("--grace-period", "3600")
+        .run()
+        .unwrap();
+    let (consensus_public_key, service_public_key, previous_key_path) = match result {
+        StandardResult::RotateKeys {
+            consensus_public_key,
+            service_public_key,
+            previous_master_key_path: Some(path),
+            ..
+        } => (consensus_public_key, service_public_key, path),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+
+    // The returned public keys match the stored master key.
+    let keys = read_keys_from_file(&master_key_path, "").unwrap();
+    assert_ne!(keys, old_keys);
+    assert_eq!(keys.consensus_pk(), consensus_public_key);
+    assert_eq!(keys.service_pk(), service_public_key);
+    let config: NodeConfig = load_config_file(&node_config_path).unwrap();
+    assert_eq!(
+        config.private_config.consensus_public_key,
+        consensus_public_key
+    );
+
+    // The previous key is kept for the grace period.
+    let previous_keys = &config.private_config.previous_keys;
+    assert_eq!(previous_keys.len(), 1);
+    assert_eq!(
+        previous_keys[0].consensus_public_key,
+        old_keys.consensus_pk()
+    );
+    assert_eq!(previous_keys[0].service_public_key, old_keys.service_pk());
+    assert_eq!(
+        config_dir.join(&previous_keys[0].master_key_path),
+        previous_key_path
+    );
+    assert_eq!(
+        read_keys_from_file(&previous_key_path, "").unwrap(),
+        old_keys
+    );
+
+    // Files are replaced atomically, so no temporary files are left, and are accessible
+    // only by the owner.
+    for path in &[&node_config_path, &master_key_path, &previous_key_path] {
+        assert_eq!(file_mode(path), 0o600, "{}", path.display());
+    }
+    let tmp_files: Vec<_> = fs::read_dir(&config_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .filter(|name| name.to_string_lossy().ends_with(".tmp"))
+        .collect();
+    assert!(tmp_files.is_empty(), "{:?}", tmp_files);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_rotate_keys_with_seed() {
+    let env = ConfigSpec::new_without_pass();
+    env.copy_node_config_to_output(0);
+    let config_dir = env.output_node_config_dir(0);
+    let private_config_path = config_dir.join(PRIVATE_CONFIG_FILE_NAME);
+    let seed_path = config_dir.join("seed.txt");
+    fs::write(&seed_path, format!("{}\n", "01".repeat(32))).unwrap();
+
+    set_file_mode(&seed_path, 0o644);
+    let err = env
+        .command("rotate-keys")
+        .with_arg(&private_config_path)
+        .with_named_arg("--master-key-pass", "pass:")
+        .with_named_arg("--seed-file", &seed_path)
+        .run()
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("is readable by other users"),
+        "{}",
+        err
+    );
+
+    set_file_mode(&seed_path, 0o600);
+    let result = env
+        .command("rotate-keys")
+        .with_arg(&private_config_path)
+        .with_named_arg("--master-key-pass", "pass:")
+        .with_named_arg("--seed-file", &seed_path)
+        .run()
+        .unwrap();
+    let (expected_keys, _) = generate_keys_from_seed(b"", &[1; 32]).unwrap();
+    match result {
+        StandardResult::RotateKeys {
+            consensus_public_key,
+            service_public_key,
+            previous_master_key_path: None,
+            ..
+        } => {
+            assert_eq!(consensus_public_key, expected_keys.consensus_pk());
+            assert_eq!(service_public_key, expected_keys.service_pk());
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    }
+
+    let master_key_path = config_dir.join(MASTER_KEY_FILE_NAME);
+    let keys = read_keys_from_file(&master_key_path, "").unwrap();
+    assert_eq!(keys, expected_keys);
+    let private_config: NodePrivateConfig = load_config_file(&private_config_path).unwrap();
+    assert_eq!(
+        private_config.consensus_public_key,
+        expected_keys.consensus_pk()
+    );
+    assert!(private_config.previous_keys.is_empty());
+}
+
+#[test]
+fn test_export_public_config() {
+    let env = ConfigSpec::new_without_pass();
+    let node_config_path = env.expected_node_config_file(0);
+    let exported_path = env.output_dir().join("exported.toml");
+
+    let result = env
+        .command("export-public-config")
+        .with_arg(&node_config_path)
+        .with_arg(&exported_path)
+        .run()
+        .unwrap();
+    let content_hash = match result {
+        StandardResult::ExportPublicConfig {
+            output_file: Some(path),
+            content_hash,
+            content: None,
+        } => {
+            assert_eq!(path, exported_path);
+            content_hash
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    };
+
+    let contents = fs::read_to_string(&exported_path).unwrap();
+    let mut lines = contents.splitn(2, '\n');
+    let header = lines.next().unwrap();
+    assert_eq!(header, format!("{}{}", CONTENT_HASH_PREFIX, content_hash));
+    assert_eq!(hash(lines.next().unwrap().as_bytes()), content_hash);
+    for private_field in &[
+        "master_key_path",
+        "listen_address",
+        "external_address",
+        "database",
+    ] {
+        assert!(!contents.contains(private_field), "{}", private_field);
+    }
+
+    let exported: NodePublicConfig = load_config_file(&exported_path).unwrap();
+    let source: NodeConfig = load_config_file(&node_config_path).unwrap();
+    assert_eq!(exported.consensus, source.public_config.consensus);
+    assert_eq!(exported.general, source.public_config.general);
+    assert_eq!(exported.validator_keys, None);
+    assert_eq!(exported.address, None);
+
+    // The exported config can be used to bootstrap a new node.
+    env.command("generate-config")
+        .with_arg(&exported_path)
+        .with_arg(env.output_dir().join("new-node"))
+        .with_named_arg("-a", "127.0.0.1:6400")
+        .with_arg("--no-password")
+        .run()
+        .unwrap();
+
+    // Export is deterministic.
+    let result = env
+        .command("export-public-config")
+        .with_arg(&node_config_path)
+        .with_arg("--stdout")
+        .run()
+        .unwrap();
+    match result {
+        StandardResult::ExportPublicConfig {
+            output_file: None,
+            content_hash: stdout_hash,
+            ..
+        } => assert_eq!(stdout_hash, content_hash),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn test_completions() {
+    let env = ConfigSpec::new_without_pass();
+    let out_dir = env.output_dir().join("completions");
+    let result = env
+        .command("completions")
+        .with_arg("bash")
+        .with_named_arg("--bin-name", "exonum-node")
+        .with_named_arg("--out-dir", &out_dir)
+        .run()
+        .unwrap();
+    let script_path = out_dir.join("exonum-node.bash");
+    match result {
+        StandardResult::Completions {
+            shell,
+            output_file,
+            content,
+        } => {
+            assert_eq!(shell, "bash");
+            assert_eq!(output_file, Some(script_path.clone()));
+            assert_eq!(content, None);
+        }
+        other => panic!("Unexpected command result: {:?}", other),
+    }
+
+    let script = fs::read_to_string(&script_path).unwrap();
+    assert!(script.contains("exonum-node"));
+    assert!(script.contains("optimize-config"));
+    assert!(script.contains("--dry-run"));
+    assert!(script.contains("--apply-defaults"));
+}
+
 #[test]
 fn test_restart_migration() {
     let env = ConfigSpec::new_without_pass();
@@ -628,6 +2407,7 @@ fn different_supervisor_modes_in_public_configs() -> anyhow::Result<()> {
         thread_pool_size: None,
         connect_list: Default::default(),
         consensus_public_key: KeyPair::random().public_key(),
+        previous_keys: vec![],
     };
 
     let testnet_dir = tempfile::tempdir()?;
@@ -0,0 +1,317 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Background merging of patches.
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+};
+
+use crate::{Error, Patch, Result};
+
+/// Outcome of a background merge sent to the corresponding handle.
+#[derive(Debug)]
+struct MergeOutcome {
+    result: Result<()>,
+    /// Was the database poisoned by this merge?
+    poisoned_by_merge: bool,
+}
+
+/// Handle to a patch merge performed in the background by [`Database::merge_async`].
+///
+/// Dropping the handle without calling `wait()` blocks until the merge is finished.
+/// If the merge has failed, the error is logged and acknowledged, so that the database
+/// accepts new merges again.
+///
+/// [`Database::merge_async`]: trait.Database.html#method.merge_async
+pub struct MergeHandle {
+    outcome: Option<MergeOutcome>,
+    receiver: Option<mpsc::Receiver<MergeOutcome>>,
+    poisoned: Option<Arc<AtomicBool>>,
+}
+
+impl fmt::Debug for MergeHandle {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("MergeHandle")
+            .field("outcome", &self.outcome)
+            .finish()
+    }
+}
+
+impl MergeHandle {
+    /// Creates a handle for a merge which has already been performed.
+    pub(crate) fn ready(result: Result<()>) -> Self {
+        Self {
+            outcome: Some(MergeOutcome {
+                result,
+                poisoned_by_merge: false,
+            }),
+            receiver: None,
+            poisoned: None,
+        }
+    }
+
+    /// Returns `true` if the merge has finished (successfully or not). The changes
+    /// of a finished merge may be relied upon only after `wait()` returns.
+    pub fn is_done(&mut self) -> bool {
+        if self.outcome.is_none() {
+            let receiver = self
+                .receiver
+                .as_ref()
+                .expect("No receiver for merge outcome");
+            match receiver.try_recv() {
+                Ok(outcome) => self.outcome = Some(outcome),
+                Err(mpsc::TryRecvError::Empty) => return false,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.outcome = Some(MergeOutcome {
+                        result: Err(writer_stopped()),
+                        poisoned_by_merge: false,
+                    })
+                }
+            }
+        }
+        true
+    }
+
+    /// Blocks until the merge is finished and returns its result. If the merge has failed,
+    /// returning the error acknowledges it, so that the database accepts new merges again.
+    pub fn wait(mut self) -> Result<()> {
+        let outcome = self.take_outcome().expect("Merge outcome is already taken");
+        self.acknowledge(&outcome);
+        outcome.result
+    }
+
+    /// Takes the outcome of the merge, blocking until the merge is finished. Returns `None`
+    /// if the outcome is already taken.
+    fn take_outcome(&mut self) -> Option<MergeOutcome> {
+        if let Some(outcome) = self.outcome.take() {
+            return Some(outcome);
+        }
+        let outcome = self
+            .receiver
+            .take()?
+            .recv()
+            .unwrap_or_else(|_| MergeOutcome {
+                result: Err(writer_stopped()),
+                poisoned_by_merge: false,
+            });
+        Some(outcome)
+    }
+
+    /// Clears the poison flag of the database if it was set by this merge.
+    fn acknowledge(&mut self, outcome: &MergeOutcome) {
+        if outcome.poisoned_by_merge {
+            if let Some(poisoned) = self.poisoned.take() {
+                poisoned.store(false, Ordering::SeqCst);
+            }
+        }
+    }
+}
+
+impl Drop for MergeHandle {
+    fn drop(&mut self) {
+        if let Some(outcome) = self.take_outcome() {
+            if outcome.poisoned_by_merge {
+                log::warn!("Error of a background merge is acknowledged by dropping its handle");
+            }
+            self.acknowledge(&outcome);
+        }
+    }
+}
+
+fn writer_stopped() -> Error {
+    Error::new("Background merge was not performed: the writer thread has stopped")
+}
+
+/// Returns an error if the database is poisoned by a failed background merge, which
+/// has not been acknowledged yet.
+pub(super) fn check_not_poisoned(poisoned: &AtomicBool) -> Result<()> {
+    if poisoned.load(Ordering::SeqCst) {
+        Err(poisoned_error())
+    } else {
+        Ok(())
+    }
+}
+
+fn poisoned_error() -> Error {
+    Error::new(
+        "Cannot merge a patch: a previous background merge has failed, and its error \
+         has not been acknowledged with `MergeHandle::wait()` or by dropping the handle",
+    )
+}
+
+struct MergeJob {
+    patch: Patch,
+    sender: mpsc::Sender<MergeOutcome>,
+}
+
+/// Single writer thread applying patches in the order of their submission.
+/// Dropping the writer waits until all submitted patches are applied.
+pub(super) struct MergeWriter {
+    sender: Option<mpsc::Sender<MergeJob>>,
+    thread: Option<thread::JoinHandle<()>>,
+    poisoned: Arc<AtomicBool>,
+}
+
+impl fmt::Debug for MergeWriter {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("MergeWriter")
+            .field("poisoned", &self.poisoned)
+            .finish()
+    }
+}
+
+impl MergeWriter {
+    /// Spawns a writer thread applying patches with `merge`. The database is poisoned
+    /// with the `poisoned` flag if `merge` fails.
+    pub fn spawn<F>(poisoned: Arc<AtomicBool>, merge: F) -> Self
+    where
+        F: Fn(Patch) -> Result<()> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel::<MergeJob>();
+        let thread_poisoned = Arc::clone(&poisoned);
+        let thread = thread::Builder::new()
+            .name("merkledb-merge".to_owned())
+            .spawn(move || {
+                for job in receiver {
+                    // Patches submitted after a failed one are not applied, since they
+                    // may depend on its changes.
+                    let was_poisoned = thread_poisoned.load(Ordering::SeqCst);
+                    let result = if was_poisoned {
+                        Err(poisoned_error())
+                    } else {
+                        merge(job.patch)
+                    };
+                    let poisoned_by_merge = !was_poisoned && result.is_err();
+                    if let Err(ref e) = result {
+                        if poisoned_by_merge {
+                            log::error!("Background merge of a patch has failed: {}", e);
+                            thread_poisoned.store(true, Ordering::SeqCst);
+                        }
+                    }
+                    // The handle may be dropped, which is fine.
+                    job.sender
+                        .send(MergeOutcome {
+                            result,
+                            poisoned_by_merge,
+                        })
+                        .ok();
+                }
+            })
+            .expect("Cannot spawn background merge thread");
+
+        Self {
+            sender: Some(sender),
+            thread: Some(thread),
+            poisoned,
+        }
+    }
+
+    /// Submits a patch for merging.
+    pub fn submit(&self, patch: Patch) -> MergeHandle {
+        let (sender, receiver) = mpsc::channel();
+        let job = MergeJob { patch, sender };
+        if self.sender.as_ref().unwrap().send(job).is_err() {
+            return MergeHandle::ready(Err(writer_stopped()));
+        }
+        MergeHandle {
+            outcome: None,
+            receiver: Some(receiver),
+            poisoned: Some(Arc::clone(&self.poisoned)),
+        }
+    }
+}
+
+impl Drop for MergeWriter {
+    fn drop(&mut self) {
+        // Dropping the sender stops the thread after all submitted patches are processed.
+        self.sender = None;
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                log::error!("Background merge thread has panicked");
+            }
+        }
+    }
+}
+
+#[test]
+fn failed_merge_poisons_writer_until_acknowledged() {
+    use std::sync::atomic::AtomicUsize;
+
+    use crate::{Database, TemporaryDB};
+
+    let db = TemporaryDB::new();
+    let poisoned = Arc::new(AtomicBool::new(false));
+    // The first merge fails, and all subsequent ones succeed.
+    let merges = AtomicUsize::new(0);
+    let writer = MergeWriter::spawn(Arc::clone(&poisoned), move |_| {
+        if merges.fetch_add(1, Ordering::SeqCst) == 0 {
+            Err(Error::new("Write failure"))
+        } else {
+            Ok(())
+        }
+    });
+
+    let failed = writer.submit(db.fork().into_patch());
+    let skipped = writer.submit(db.fork().into_patch());
+    assert!(skipped.wait().is_err());
+    assert!(check_not_poisoned(&poisoned).is_err());
+    assert!(writer.submit(db.fork().into_patch()).wait().is_err());
+
+    let err = failed.wait().unwrap_err();
+    assert_eq!(err.to_string(), "Write failure");
+    assert!(check_not_poisoned(&poisoned).is_ok());
+    writer.submit(db.fork().into_patch()).wait().unwrap();
+}
+
+#[test]
+fn dropping_handle_acknowledges_failed_merge() {
+    use std::sync::atomic::AtomicUsize;
+
+    use crate::{Database, TemporaryDB};
+
+    let db = TemporaryDB::new();
+    let poisoned = Arc::new(AtomicBool::new(false));
+    // Every other merge fails.
+    let merges = AtomicUsize::new(0);
+    let writer = MergeWriter::spawn(Arc::clone(&poisoned), move |_| {
+        if merges.fetch_add(1, Ordering::SeqCst) % 2 == 0 {
+            Err(Error::new("Write failure"))
+        } else {
+            Ok(())
+        }
+    });
+
+    // The handle is dropped before the merge is finished.
+    drop(writer.submit(db.fork().into_patch()));
+    assert!(check_not_poisoned(&poisoned).is_ok());
+    writer.submit(db.fork().into_patch()).wait().unwrap();
+
+    // The handle is only polled before being dropped.
+    let mut failed = writer.submit(db.fork().into_patch());
+    while !failed.is_done() {
+        thread::yield_now();
+    }
+    assert!(check_not_poisoned(&poisoned).is_err());
+    drop(failed);
+    assert!(check_not_poisoned(&poisoned).is_ok());
+    writer.submit(db.fork().into_patch()).wait().unwrap();
+}
@@ -0,0 +1,224 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Incremental backups of `RocksDB` databases.
+//!
+//! Backups are created with [`Database::backup`] and are stored in a backup directory
+//! managed by the `RocksDB` [backup engine]. Files shared by several backups are stored
+//! only once, so each backup after the first one only copies the files changed since
+//! the previous backup.
+//!
+//! [`Database::backup`]: ../trait.Database.html#method.backup
+//! [backup engine]: https://github.com/facebook/rocksdb/wiki/How-to-backup-RocksDB
+
+use rocksdb::backup::{BackupEngine, BackupEngineInfo, BackupEngineOptions, RestoreOptions};
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{Error, Result};
+
+/// Name of the subdirectory of the backup directory, in which sequence numbers of backups
+/// are recorded.
+const SEQUENCE_NUMBERS_DIR: &str = "exonum_sequence_numbers";
+
+/// Information about a backup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BackupInfo {
+    /// Identifier of the backup. Identifiers are increasing numbers starting from 1.
+    pub id: u32,
+    /// Time of the backup creation in seconds since the UNIX epoch.
+    pub timestamp: i64,
+    /// Total size of the files in the backup in bytes, including files shared
+    /// with other backups.
+    pub size: u64,
+    /// Number of files in the backup.
+    pub num_files: u32,
+    /// Sequence number of the latest write in the database when the backup was started.
+    /// Writes concurrent with the backup may be included into it as well.
+    ///
+    /// `None` if the sequence number was not recorded, e.g., for backups created by tools
+    /// other than `MerkleDB`.
+    pub sequence_number: Option<u64>,
+}
+
+impl BackupInfo {
+    fn new(info: &BackupEngineInfo, backup_dir: &Path) -> Self {
+        Self {
+            id: info.backup_id,
+            timestamp: info.timestamp,
+            size: info.size,
+            num_files: info.num_files,
+            sequence_number: read_sequence_number(backup_dir, info.backup_id),
+        }
+    }
+}
+
+fn sequence_number_path(backup_dir: &Path, backup_id: u32) -> PathBuf {
+    backup_dir
+        .join(SEQUENCE_NUMBERS_DIR)
+        .join(backup_id.to_string())
+}
+
+fn read_sequence_number(backup_dir: &Path, backup_id: u32) -> Option<u64> {
+    fs::read_to_string(sequence_number_path(backup_dir, backup_id))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn backup_error(backup_dir: &Path, message: impl fmt::Display) -> Error {
+    Error::new(format!(
+        "Backup directory {} is invalid or corrupted: {}",
+        backup_dir.display(),
+        message
+    ))
+}
+
+/// Opens the backup engine for an existing backup directory.
+fn open_existing(backup_dir: &Path) -> Result<BackupEngine> {
+    if !backup_dir.is_dir() {
+        return Err(Error::new(format!(
+            "Backup directory {} does not exist",
+            backup_dir.display()
+        )));
+    }
+    open_engine(backup_dir)
+}
+
+fn open_engine(backup_dir: &Path) -> Result<BackupEngine> {
+    BackupEngine::open(&BackupEngineOptions::default(), backup_dir)
+        .map_err(|e| backup_error(backup_dir, e))
+}
+
+/// Creates a new incremental backup of the database in the specified directory. If `flush`
+/// is set, memtables are flushed before the backup.
+pub(crate) fn create_backup(
+    db: &rocksdb::DB,
+    backup_dir: &Path,
+    flush: bool,
+) -> Result<BackupInfo> {
+    let mut engine = open_engine(backup_dir)?;
+    let sequence_number = db.latest_sequence_number();
+    engine
+        .create_new_backup_flush(db, flush)
+        .map_err(|e| Error::new(format!("Cannot create backup: {}", e)))?;
+
+    let info = engine
+        .get_backup_info()
+        .into_iter()
+        .max_by_key(|info| info.backup_id)
+        .ok_or_else(|| backup_error(backup_dir, "created backup is missing"))?;
+    let path = sequence_number_path(backup_dir, info.backup_id);
+    fs::create_dir_all(backup_dir.join(SEQUENCE_NUMBERS_DIR))
+        .and_then(|()| fs::write(&path, sequence_number.to_string()))
+        .map_err(|e| {
+            Error::new(format!(
+                "Cannot record sequence number of backup to {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+    Ok(BackupInfo::new(&info, backup_dir))
+}
+
+/// Lists backups in the specified directory, ordered by their identifiers.
+///
+/// # Errors
+///
+/// Returns an error if the directory does not exist or is not a valid backup directory.
+pub fn list_backups<P: AsRef<Path>>(backup_dir: P) -> Result<Vec<BackupInfo>> {
+    let backup_dir = backup_dir.as_ref();
+    let engine = open_existing(backup_dir)?;
+    let mut backups: Vec<_> = engine
+        .get_backup_info()
+        .iter()
+        .map(|info| BackupInfo::new(info, backup_dir))
+        .collect();
+    backups.sort_by_key(|info| info.id);
+    Ok(backups)
+}
+
+/// Removes all backups in the specified directory except for `keep` latest ones.
+///
+/// # Errors
+///
+/// Returns an error if the directory does not exist or is not a valid backup directory.
+pub fn purge_old_backups<P: AsRef<Path>>(backup_dir: P, keep: usize) -> Result<()> {
+    let backup_dir = backup_dir.as_ref();
+    let mut engine = open_existing(backup_dir)?;
+    engine
+        .purge_old_backups(keep)
+        .map_err(|e| backup_error(backup_dir, e))?;
+
+    // Remove sequence numbers of purged backups; failures are harmless.
+    let remaining: Vec<_> = engine
+        .get_backup_info()
+        .iter()
+        .map(|info| info.backup_id.to_string())
+        .collect();
+    if let Ok(entries) = fs::read_dir(backup_dir.join(SEQUENCE_NUMBERS_DIR)) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let name = entry.file_name();
+            if !remaining.iter().any(|id| name == id.as_str()) {
+                fs::remove_file(entry.path()).ok();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Restores the database from the backup with the specified identifier. This function
+/// does not require an open database; the database at `db_dir` must not be open
+/// while it is being restored. The existing contents of `db_dir` are overwritten.
+///
+/// # Errors
+///
+/// Returns an error if the backup directory does not exist or is corrupted, if there is
+/// no backup with the specified identifier, or if restoring fails.
+pub fn restore_from_backup<P, Q>(backup_dir: P, db_dir: Q, backup_id: u32) -> Result<()>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let backup_dir = backup_dir.as_ref();
+    let db_dir = db_dir.as_ref();
+    let mut engine = open_existing(backup_dir)?;
+    if !engine
+        .get_backup_info()
+        .iter()
+        .any(|info| info.backup_id == backup_id)
+    {
+        return Err(Error::new(format!(
+            "No backup with ID {} in {}",
+            backup_id,
+            backup_dir.display()
+        )));
+    }
+
+    engine
+        .restore_from_backup(db_dir, db_dir, &RestoreOptions::default(), backup_id)
+        .map_err(|e| {
+            Error::new(format!(
+                "Cannot restore backup {} from {}: {}",
+                backup_id,
+                backup_dir.display(),
+                e
+            ))
+        })
+}
@@ -0,0 +1,189 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Verification of the physical integrity of databases.
+
+use rocksdb::{LiveFile, ReadOptions};
+use serde_derive::{Deserialize, Serialize};
+
+use std::collections::HashSet;
+
+use crate::{Result, RocksDB};
+
+/// Report on the integrity verification of a database performed by
+/// [`Database::verify_integrity`].
+///
+/// [`Database::verify_integrity`]: trait.Database.html#method.verify_integrity
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct IntegrityReport {
+    /// Statistics of the verified column families.
+    pub column_families: Vec<ColumnFamilyIntegrity>,
+    /// Errors encountered during the verification.
+    pub errors: Vec<IntegrityError>,
+}
+
+impl IntegrityReport {
+    /// Returns `true` if no errors were encountered during the verification.
+    pub fn is_healthy(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Returns the total number of keys read during the verification.
+    pub fn total_keys(&self) -> u64 {
+        self.column_families.iter().map(|cf| cf.keys).sum()
+    }
+
+    /// Returns the total size of the keys and values read during the verification in bytes.
+    pub fn total_bytes(&self) -> u64 {
+        self.column_families.iter().map(|cf| cf.bytes).sum()
+    }
+}
+
+/// Statistics of a verified column family.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ColumnFamilyIntegrity {
+    /// Name of the column family.
+    pub name: String,
+    /// Number of keys successfully read from the column family.
+    pub keys: u64,
+    /// Total size of the keys and values successfully read from the column family in bytes.
+    pub bytes: u64,
+}
+
+/// Error encountered during the integrity verification. The error relates to a range of keys
+/// in a column family, which could not be read; keys are hex-encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct IntegrityError {
+    /// Name of the column family.
+    pub column_family: String,
+    /// Last key read successfully before the error, or `None` if the error occurred
+    /// at the start of the column family.
+    pub after_key: Option<String>,
+    /// Key, from which the verification was resumed (inclusive), or `None` if the rest
+    /// of the column family was skipped.
+    pub resumed_from: Option<String>,
+    /// Error message.
+    pub message: String,
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Returns the key lexicographically following all keys starting with `key`.
+fn key_after(key: &[u8]) -> Vec<u8> {
+    let mut next = key.to_vec();
+    next.push(0);
+    next
+}
+
+impl RocksDB {
+    /// Reads all data in the database with checksum verification forced on. Errors
+    /// are collected into the returned report instead of aborting the verification.
+    /// After an error, the verification resumes after the key range of the SST file,
+    /// which may contain the unreadable data.
+    ///
+    /// The verification reads the whole database, so it may take a long time for large
+    /// databases. It does not pollute the block cache.
+    pub fn verify_integrity(&self) -> Result<IntegrityReport> {
+        let db = self.get_lock_guard();
+        let live_files = db.live_files()?;
+        let mut report = IntegrityReport::default();
+
+        for name in self.column_families()? {
+            let cf = match db.cf_handle(&name) {
+                Some(cf) => cf,
+                None => continue,
+            };
+            let files: Vec<_> = live_files
+                .iter()
+                .filter(|file| file.column_family_name == name)
+                .collect();
+            let mut cf_report = ColumnFamilyIntegrity {
+                name: name.clone(),
+                ..ColumnFamilyIntegrity::default()
+            };
+
+            let mut read_opts = ReadOptions::default();
+            read_opts.set_verify_checksums(true);
+            read_opts.fill_cache(false);
+            let mut iter = db.raw_iterator_cf_opt(cf, read_opts);
+            let mut skipped_files = HashSet::new();
+            let mut last_key: Option<Vec<u8>> = None;
+            iter.seek_to_first();
+
+            loop {
+                while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+                    cf_report.keys += 1;
+                    cf_report.bytes += (key.len() + value.len()) as u64;
+                    let last_key = last_key.get_or_insert_with(Vec::new);
+                    last_key.clear();
+                    last_key.extend_from_slice(key);
+                    iter.next();
+                }
+
+                let err = match iter.status() {
+                    Ok(()) => break,
+                    Err(e) => e,
+                };
+                let resume_file = next_file(&files, last_key.as_deref(), &skipped_files);
+                let resume_key =
+                    resume_file.and_then(|file| file.end_key.as_deref().map(key_after));
+                report.errors.push(IntegrityError {
+                    column_family: name.clone(),
+                    after_key: last_key.as_deref().map(to_hex),
+                    resumed_from: resume_key.as_deref().map(to_hex),
+                    message: err.to_string(),
+                });
+                log::warn!(
+                    "Integrity error in column family `{}` after key {:?}: {}",
+                    name,
+                    last_key.as_deref().map(to_hex),
+                    err
+                );
+
+                match (resume_file, resume_key) {
+                    (Some(file), Some(resume_key)) => {
+                        skipped_files.insert(file.name.clone());
+                        iter.seek(&resume_key);
+                    }
+                    _ => break,
+                }
+            }
+            report.column_families.push(cf_report);
+        }
+        Ok(report)
+    }
+}
+
+/// Selects the SST file which is the most likely to contain the unreadable data following
+/// `last_key`, i.e., the file not skipped yet with the smallest end key after `last_key`.
+fn next_file<'a>(
+    files: &[&'a LiveFile],
+    last_key: Option<&[u8]>,
+    skipped_files: &HashSet<String>,
+) -> Option<&'a LiveFile> {
+    files
+        .iter()
+        .copied()
+        .filter(|file| !skipped_files.contains(&file.name))
+        .filter(|file| match (last_key, file.end_key.as_deref()) {
+            (Some(last_key), Some(end_key)) => end_key > last_key,
+            _ => true,
+        })
+        .min_by(|a, b| a.end_key.cmp(&b.end_key))
+}
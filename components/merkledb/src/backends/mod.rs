@@ -12,5 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod async_merge;
+pub mod backup;
+pub mod integrity;
+pub mod recompress;
 pub mod rocksdb;
+pub mod sst;
 pub mod temporarydb;
@@ -0,0 +1,268 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Recompression of existing `RocksDB` databases.
+//!
+//! Changing compression settings of a database only affects SST files written afterwards.
+//! [`recompress`] rewrites all SST files of the database with the new settings by performing
+//! a full compaction of each column family.
+//!
+//! [`recompress`]: ../fn.recompress.html
+
+use rocksdb::{BottommostLevelCompaction, CompactOptions};
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{options::NUM_LEVELS, DbOptions, Error, Result, RocksDB};
+
+/// Name of the file in the database directory, in which column families recompressed
+/// by an interrupted [`recompress`] call are recorded.
+///
+/// [`recompress`]: fn.recompress.html
+const PROGRESS_FILE: &str = "RECOMPRESS_PROGRESS";
+
+/// Progress of the database recompression reported by [`recompress`].
+///
+/// [`recompress`]: fn.recompress.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RecompressProgress {
+    /// Name of the column family being recompressed.
+    pub column_family: String,
+    /// Zero-based number of the column family among the recompressed ones.
+    pub index: usize,
+    /// Total number of the column families to recompress.
+    pub total: usize,
+    /// Approximate size of the live data in the column family in bytes before recompression.
+    pub size_before: u64,
+    /// Approximate size of the live data in the column family in bytes after recompression,
+    /// or `None` if the recompression of the column family has just started.
+    pub size_after: Option<u64>,
+}
+
+/// Report on the recompression performed by [`recompress`].
+///
+/// [`recompress`]: fn.recompress.html
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RecompressReport {
+    /// Column families recompressed by the call.
+    pub recompressed: Vec<String>,
+    /// Column families skipped since they were recompressed by a previous interrupted call.
+    pub resumed: Vec<String>,
+    /// Column families skipped since their compression settings have not changed.
+    pub unchanged: Vec<String>,
+    /// Approximate size of the live data in the recompressed column families in bytes
+    /// before recompression.
+    pub size_before: u64,
+    /// Approximate size of the live data in the recompressed column families in bytes
+    /// after recompression.
+    pub size_after: u64,
+}
+
+/// Loads names of the recompressed column families along with the fingerprints of their
+/// compression settings from the progress file.
+fn load_progress(path: &Path) -> Result<BTreeMap<String, String>> {
+    let contents = match fs::read_to_string(path.join(PROGRESS_FILE)) {
+        Ok(contents) => contents,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(progress_error(path, e)),
+    };
+    Ok(contents
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, '\t');
+            let name = parts.next()?;
+            let fingerprint = parts.next()?;
+            Some((name.to_owned(), fingerprint.to_owned()))
+        })
+        .collect())
+}
+
+/// Records that the column family is recompressed with the specified settings.
+fn save_progress(path: &Path, cf_name: &str, fingerprint: &str) -> Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path.join(PROGRESS_FILE))
+        .map_err(|e| progress_error(path, e))?;
+    writeln!(file, "{}\t{}", cf_name, fingerprint).map_err(|e| progress_error(path, e))?;
+    file.sync_all().map_err(|e| progress_error(path, e))
+}
+
+fn progress_error(path: &Path, err: io::Error) -> Error {
+    Error::new(format!(
+        "Cannot access recompression progress file in {}: {}",
+        path.display(),
+        err
+    ))
+}
+
+/// Rewrites all SST files of the database at `path` with the compression settings
+/// from `new_options`. `old_options` are the options with which the database was used
+/// so far; column families, for which compression settings in `old_options` and
+/// `new_options` coincide, are skipped.
+///
+/// The database is opened with `new_options`, and each column family is fully compacted
+/// to the bottommost level of the LSM tree. `progress` is called before and after
+/// the compaction of each column family. After each compaction, the function verifies that
+/// all data of the column family has been rewritten, i.e., that only the bottommost
+/// level of the column family contains SST files.
+///
+/// The database must not be used by other processes during the recompression. The process
+/// may be interrupted at any moment (e.g., by killing it); in this case, calling the function
+/// again with the same `new_options` resumes the recompression, skipping the column
+/// families recompressed before the interruption.
+///
+/// # Errors
+///
+/// Returns an error if the options are invalid, if the database does not exist or cannot
+/// be opened, or if the compaction of a column family fails.
+pub fn recompress<P, F>(
+    path: P,
+    old_options: &DbOptions,
+    new_options: &DbOptions,
+    progress: F,
+) -> Result<RecompressReport>
+where
+    P: AsRef<Path>,
+    F: Fn(RecompressProgress),
+{
+    let path = path.as_ref();
+    old_options.validate()?;
+    if !path.join("CURRENT").is_file() {
+        return Err(Error::new(format!(
+            "Cannot recompress database at {}: the directory does not contain a database",
+            path.display()
+        )));
+    }
+    let new_options = DbOptions {
+        create_if_missing: false,
+        read_only: None,
+        ..new_options.clone()
+    };
+    let db = RocksDB::open(path, &new_options)?;
+    let done = load_progress(path)?;
+
+    let mut report = RecompressReport::default();
+    let mut to_recompress = vec![];
+    for name in db.column_families()? {
+        let fingerprint = new_options
+            .for_column_family(&name)
+            .compression_fingerprint();
+        if done.get(&name) == Some(&fingerprint) {
+            report.resumed.push(name);
+        } else if old_options
+            .for_column_family(&name)
+            .compression_fingerprint()
+            == fingerprint
+        {
+            report.unchanged.push(name);
+        } else {
+            to_recompress.push((name, fingerprint));
+        }
+    }
+
+    let total = to_recompress.len();
+    for (index, (name, fingerprint)) in to_recompress.into_iter().enumerate() {
+        let size_before = db.cf_live_data_size(&name)?;
+        progress(RecompressProgress {
+            column_family: name.clone(),
+            index,
+            total,
+            size_before,
+            size_after: None,
+        });
+
+        db.recompress_column_family(&name)?;
+        let size_after = db.cf_live_data_size(&name)?;
+        save_progress(path, &name, &fingerprint)?;
+        log::info!(
+            "Recompressed column family `{}` ({} of {}): {} -> {} bytes",
+            name,
+            index + 1,
+            total,
+            size_before,
+            size_after
+        );
+        progress(RecompressProgress {
+            column_family: name.clone(),
+            index,
+            total,
+            size_before,
+            size_after: Some(size_after),
+        });
+
+        report.size_before += size_before;
+        report.size_after += size_after;
+        report.recompressed.push(name);
+    }
+
+    db.close()?;
+    if let Err(e) = fs::remove_file(path.join(PROGRESS_FILE)) {
+        if e.kind() != io::ErrorKind::NotFound {
+            return Err(progress_error(path, e));
+        }
+    }
+    Ok(report)
+}
+
+impl RocksDB {
+    /// Returns the estimated size of the live data in the column family.
+    fn cf_live_data_size(&self, cf_name: &str) -> Result<u64> {
+        let db = self.get_lock_guard();
+        let cf = db
+            .cf_handle(cf_name)
+            .ok_or_else(|| Error::new(format!("Column family `{}` does not exist", cf_name)))?;
+        Ok(db
+            .property_int_value_cf(cf, "rocksdb.estimate-live-data-size")?
+            .unwrap_or(0))
+    }
+
+    /// Compacts the column family to the bottommost level, rewriting all its SST files,
+    /// and checks that no SST files are left on other levels.
+    fn recompress_column_family(&self, cf_name: &str) -> Result<()> {
+        let db = self.get_lock_guard();
+        let cf = db
+            .cf_handle(cf_name)
+            .ok_or_else(|| Error::new(format!("Column family `{}` does not exist", cf_name)))?;
+
+        let mut compact_opts = CompactOptions::default();
+        compact_opts.set_exclusive_manual_compaction(true);
+        compact_opts.set_bottommost_level_compaction(BottommostLevelCompaction::Force);
+        db.compact_range_cf_opt(cf, None::<&[u8]>, None::<&[u8]>, &compact_opts);
+
+        let mut levels_with_files = (0..NUM_LEVELS).filter(|level| {
+            let property = format!("rocksdb.num-files-at-level{}", level);
+            db.property_int_value_cf(cf, &property)
+                .ok()
+                .flatten()
+                .map_or(false, |num_files| num_files > 0)
+        });
+        let first_level = levels_with_files.next();
+        if levels_with_files.next().is_some() {
+            return Err(Error::new(format!(
+                "Recompression of column family `{}` is incomplete: SST files are left \
+                 on several levels (starting from level {:?})",
+                cf_name, first_level
+            )));
+        }
+        Ok(())
+    }
+}
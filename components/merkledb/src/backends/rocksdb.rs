@@ -18,21 +18,63 @@ pub use rocksdb::{BlockBasedOptions as RocksBlockOptions, WriteOptions as RocksD
 
 use crossbeam::sync::{ShardedLock, ShardedLockReadGuard};
 use rocksdb::{
-    self, checkpoint::Checkpoint, ColumnFamily, DBIterator, Options as RocksDbOptions, WriteBatch,
+    self, checkpoint::Checkpoint, Cache, ColumnFamily, ColumnFamilyDescriptor, DBIterator,
+    FifoCompactOptions, FlushOptions, Options as RocksDbOptions, Range, ReadOptions,
+    SliceTransform, WriteBatch,
 };
 use smallvec::SmallVec;
-use std::{fmt, iter::Peekable, mem, path::Path, sync::Arc};
+use std::{
+    fmt, fs,
+    iter::Peekable,
+    mem,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
 
 use crate::{
-    db::{check_database, Change},
-    options::LogVerbosity,
-    Database, DbOptions, Iter, Iterator, Patch, ResolvedAddress, Snapshot,
+    backends::{
+        async_merge::{check_not_poisoned, MergeWriter},
+        backup::create_backup,
+    },
+    db::{
+        check_database, check_droppable_column_family, check_prefix_extractor_len,
+        unused_column_families, Change,
+    },
+    options::{AccessPattern, LogVerbosity, WalSyncMode},
+    BackupInfo, Database, DbOptions, DbStats, ErrorKind, IntegrityReport, Iter, Iterator,
+    MergeHandle, Patch, ResolvedAddress, Snapshot, SstStats,
 };
 
 /// Size of a byte representation of an index ID, which is used to prefix index keys
 /// in a column family.
 pub const ID_SIZE: usize = mem::size_of::<u64>();
 
+/// Refill period of the rate limiter for flushes and compactions (100 ms).
+const RATE_LIMITER_REFILL_PERIOD_US: i64 = 100_000;
+
+/// Fairness of the rate limiter, i.e., how often low-priority requests (compactions)
+/// are allowed to go before high-priority ones (flushes).
+const RATE_LIMITER_FAIRNESS: i32 = 10;
+
+/// Number of file descriptors reserved for sockets, log files, etc. when the maximum number
+/// of open files is derived from the limit of the process.
+const OPEN_FILES_RESERVE: u64 = 128;
+
+/// Minimum number of open files used by `RocksDB`.
+const MIN_OPEN_FILES: i32 = 20;
+
+/// Maximum number of open files used if the limit of the process cannot be determined.
+const FALLBACK_OPEN_FILES: i32 = 256;
+
+/// Block cache size (64 GiB) above which a warning is logged when opening the database.
+const LARGE_BLOCK_CACHE_SIZE: u64 = 64 << 30;
+
 /// Database implementation on top of [`RocksDB`](https://rocksdb.org)
 /// backend.
 ///
@@ -41,7 +83,13 @@ pub const ID_SIZE: usize = mem::size_of::<u64>();
 /// use different databases.
 pub struct RocksDB {
     db: Arc<ShardedLock<rocksdb::DB>>,
-    options: DbOptions,
+    pub(super) options: DbOptions,
+    verify_checksums: AtomicBool,
+    mode: OpenMode,
+    /// Is the database poisoned by a failed background merge?
+    merge_poisoned: Arc<AtomicBool>,
+    /// Writer applying patches submitted with `merge_async`, spawned on the first use.
+    merge_writer: Mutex<Option<MergeWriter>>,
 }
 
 impl From<DbOptions> for RocksDbOptions {
@@ -53,22 +101,482 @@ impl From<DbOptions> for RocksDbOptions {
 impl From<&DbOptions> for RocksDbOptions {
     fn from(opts: &DbOptions) -> Self {
         let mut defaults = Self::default();
+        // The preset is applied first, so that explicitly specified options override it.
+        if let Some(AccessPattern::PointLookup { cache_mb }) = opts.access_pattern {
+            defaults.optimize_for_point_lookup(cache_mb);
+        }
         defaults.create_if_missing(opts.create_if_missing);
         defaults.set_compression_type(opts.compression_type.into());
+        if let Some(bottommost_compression) = opts.bottommost_compression {
+            defaults.set_bottommost_compression_type(bottommost_compression.into());
+        }
+        if let Some(levels) = opts.level_compression_types() {
+            let levels = levels.into_iter().map(Into::into).collect::<Vec<_>>();
+            defaults.set_compression_per_level(&levels);
+        }
+        if let Some(compression_opts) = opts.compression_options {
+            if opts.uses_compression() {
+                defaults.set_compression_options(
+                    compression_opts.window_bits,
+                    compression_opts.level,
+                    0,
+                    compression_opts.max_dict_bytes,
+                );
+                defaults.set_zstd_max_train_bytes(compression_opts.zstd_max_train_bytes);
+            }
+        }
         defaults.set_max_open_files(opts.max_open_files.unwrap_or(-1));
         defaults.set_max_total_wal_size(opts.max_total_wal_size.unwrap_or(0));
         defaults.set_log_level(opts.log_verbosity.unwrap_or(LogVerbosity::Info).into());
         defaults.set_max_log_file_size(opts.max_log_file_size.unwrap_or(0));
         defaults.set_keep_log_file_num(opts.keep_log_file_num.unwrap_or(1000));
         defaults.set_recycle_log_file_num(opts.recycle_log_file_num.unwrap_or(0));
+        if let Some(write_buffer_size) = opts.write_buffer_size {
+            defaults.set_write_buffer_size(write_buffer_size);
+        }
+        if let Some(max_write_buffer_number) = opts.max_write_buffer_number {
+            defaults.set_max_write_buffer_number(max_write_buffer_number);
+        }
+        if let Some(min_to_merge) = opts.min_write_buffer_number_to_merge {
+            defaults.set_min_write_buffer_number_to_merge(min_to_merge);
+        }
+        if let Some(compaction_style) = opts.compaction_style {
+            defaults.set_compaction_style(compaction_style.into());
+        }
+        if let Some(max_table_files_size) = opts.fifo_max_table_files_size {
+            let mut fifo_opts = FifoCompactOptions::default();
+            fifo_opts.set_max_table_files_size(max_table_files_size);
+            defaults.set_fifo_compaction_options(&fifo_opts);
+        }
+        if opts.enable_statistics == Some(true) {
+            defaults.enable_statistics();
+        }
+        if let Some(period) = opts.stats_dump_period_sec {
+            defaults.set_stats_dump_period_sec(period);
+        }
+        if let Some(len) = opts.prefix_extractor_len {
+            defaults.set_prefix_extractor(SliceTransform::create_fixed_prefix(len));
+        }
+        if let Some(ratio) = opts.memtable_prefix_bloom_ratio {
+            defaults.set_memtable_prefix_bloom_ratio(ratio);
+        }
+        if let Some(period) = opts.delete_obsolete_files_period_micros {
+            defaults.set_delete_obsolete_files_period_micros(period);
+        }
+        if let Some(size) = opts.max_manifest_file_size {
+            defaults.set_max_manifest_file_size(size);
+        }
+        if let Some(mmap_reads) = opts.allow_mmap_reads {
+            defaults.set_allow_mmap_reads(mmap_reads);
+        }
+        if let Some(mmap_writes) = opts.allow_mmap_writes {
+            defaults.set_allow_mmap_writes(mmap_writes);
+        }
+        if let Some(pipelined_write) = opts.enable_pipelined_write {
+            defaults.set_enable_pipelined_write(pipelined_write);
+        }
+        if let Some(unordered_write) = opts.unordered_write {
+            defaults.set_unordered_write(unordered_write);
+        }
+        if let Some(atomic_flush) = opts.atomic_flush {
+            defaults.set_atomic_flush(atomic_flush);
+        }
+        if let Some(seconds) = opts.periodic_compaction_seconds {
+            defaults.set_periodic_compaction_seconds(seconds);
+        }
+        if let Some(readahead_size) = opts.compaction_readahead_size {
+            defaults.set_compaction_readahead_size(readahead_size);
+        }
+        if let Some(parallelism) = opts.increase_parallelism {
+            defaults.increase_parallelism(parallelism);
+        }
+        if let Some(max_background_jobs) = opts.max_background_jobs {
+            defaults.set_max_background_jobs(max_background_jobs);
+        }
+        if let Some(paranoid_checks) = opts.paranoid_checks {
+            defaults.set_paranoid_checks(paranoid_checks);
+        }
+        if let Some(direct_reads) = opts.use_direct_reads {
+            defaults.set_use_direct_reads(direct_reads);
+        }
+        if let Some(direct_io) = opts.use_direct_io_for_flush_and_compaction {
+            defaults.set_use_direct_io_for_flush_and_compaction(direct_io);
+        }
+        match opts.rate_limiter_bytes_per_sec {
+            Some(rate) if rate > 0 => {
+                defaults.set_ratelimiter(rate, RATE_LIMITER_REFILL_PERIOD_US, RATE_LIMITER_FAIRNESS)
+            }
+            _ => {}
+        }
+        if let Some(block_opts) = block_based_options(opts) {
+            defaults.set_block_based_table_factory(&block_opts);
+        }
         defaults
     }
 }
 
+/// Creates database-wide options, resolving relative directories in `opts` against
+/// the database `path`.
+fn db_options(path: &Path, opts: &DbOptions) -> RocksDbOptions {
+    let mut db_options = RocksDbOptions::from(opts);
+    if opts.max_open_files.is_none() && opts.auto_open_files {
+        let limit = open_files_limit();
+        let max_open_files = max_open_files_for_limit(limit);
+        log::info!(
+            "Using {} as the maximum number of open files for the database \
+             (limit of the process: {:?})",
+            max_open_files,
+            limit
+        );
+        db_options.set_max_open_files(max_open_files);
+    }
+    if let Some(ref wal_dir) = opts.wal_dir {
+        db_options.set_wal_dir(path.join(wal_dir));
+    }
+    if let Some(ref db_log_dir) = opts.db_log_dir {
+        db_options.set_db_log_dir(path.join(db_log_dir));
+    }
+    db_options
+}
+
+/// Returns the soft limit on the number of open files for the process, or `None` if it cannot
+/// be determined. An unlimited number of files is represented as `u64::MAX`.
+#[cfg(unix)]
+#[allow(unsafe_code)]
+fn open_files_limit() -> Option<u64> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: `getrlimit` only writes to the provided structure.
+    let res = unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) };
+    if res != 0 {
+        return None;
+    }
+    if limit.rlim_cur == libc::RLIM_INFINITY {
+        Some(u64::MAX)
+    } else {
+        #[allow(clippy::useless_conversion)] // `rlim_t` is not `u64` on all platforms.
+        Some(u64::from(limit.rlim_cur))
+    }
+}
+
+#[cfg(not(unix))]
+fn open_files_limit() -> Option<u64> {
+    None
+}
+
+/// Computes the maximum number of open files for the database from the limit of the process.
+fn max_open_files_for_limit(limit: Option<u64>) -> i32 {
+    match limit {
+        None => FALLBACK_OPEN_FILES,
+        Some(limit) if limit > i32::MAX as u64 => -1,
+        Some(limit) => {
+            let available = limit.saturating_sub(OPEN_FILES_RESERVE) as i32;
+            available.max(MIN_OPEN_FILES)
+        }
+    }
+}
+
+/// Creates options for the block-based SST table format, or `None` if `opts` do not
+/// override any of the `RocksDB` defaults for it.
+fn block_based_options(opts: &DbOptions) -> Option<RocksBlockOptions> {
+    if opts.block_cache_size.is_none() && opts.bloom_filter_bits_per_key.is_none() {
+        return None;
+    }
+
+    let mut block_opts = RocksBlockOptions::default();
+    match opts.block_cache_size {
+        Some(0) => block_opts.disable_cache(),
+        Some(block_cache_size) => match Cache::new_lru_cache(block_cache_size as usize) {
+            Ok(cache) => block_opts.set_block_cache(&cache),
+            Err(e) => log::warn!(
+                "Cannot create block cache of {} bytes, using the default one: {}",
+                block_cache_size,
+                e
+            ),
+        },
+        None => {}
+    }
+    if let Some(bits_per_key) = opts.bloom_filter_bits_per_key {
+        block_opts.set_bloom_filter(bits_per_key, opts.block_based_bloom);
+    }
+    Some(block_opts)
+}
+
+/// Checks whether a `RocksDB` error is caused by the database lock being held by another
+/// process or by another instance within the current process.
+fn is_lock_error(message: &str) -> bool {
+    message.contains("While lock file") || message.contains("lock hold by current process")
+}
+
+/// Returns the ID of the process holding a POSIX lock on the specified file, or `None`
+/// if it cannot be determined.
+#[cfg(unix)]
+#[allow(unsafe_code)]
+fn lock_holder_pid(lock_path: &Path) -> Option<u32> {
+    use std::{convert::TryFrom, os::unix::io::AsRawFd};
+
+    let file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(lock_path)
+        .ok()?;
+    // SAFETY: `flock` is a plain C structure, for which all zero bytes is a valid value.
+    let mut lock: libc::flock = unsafe { mem::zeroed() };
+    lock.l_type = libc::F_WRLCK as _;
+    lock.l_whence = libc::SEEK_SET as _;
+    // SAFETY: `fcntl` with `F_GETLK` only writes to the provided structure.
+    let res = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_GETLK, &mut lock) };
+    if res != 0 || lock.l_type == libc::F_UNLCK as _ {
+        return None;
+    }
+    u32::try_from(lock.l_pid).ok()
+}
+
+#[cfg(not(unix))]
+fn lock_holder_pid(_lock_path: &Path) -> Option<u32> {
+    None
+}
+
+/// Creates an error of the `Locked` kind with the information about the lock holder.
+fn lock_error(path: &Path, message: &str) -> crate::Error {
+    let lock_path = path.join("LOCK");
+    let holder_pid = if message.contains("lock hold by current process") {
+        Some(process::id())
+    } else {
+        lock_holder_pid(&lock_path)
+    };
+    let holder = holder_pid.map_or_else(
+        || "another process".to_owned(),
+        |pid| format!("process with PID {}", pid),
+    );
+    crate::Error::with_kind(
+        ErrorKind::Locked,
+        format!(
+            "Database at {} is locked by {} (lock file: {}). Make sure that no other node \
+             uses the database, or specify another database directory with `--db-path`. \
+             Original error: {}",
+            path.display(),
+            holder,
+            lock_path.display(),
+            message
+        ),
+    )
+}
+
+/// Opens the database using `open`. If the database is locked and `open_retries` are
+/// specified in the options, opening is retried with exponentially increasing delays.
+fn open_with_retries(
+    path: &Path,
+    options: &DbOptions,
+    open: impl Fn() -> Result<rocksdb::DB, rocksdb::Error>,
+) -> crate::Result<rocksdb::DB> {
+    let mut attempt = 0;
+    loop {
+        let err = match open() {
+            Ok(db) => return Ok(db),
+            Err(e) => open_error(e, path, options),
+        };
+        let retries = match options.open_retries {
+            Some(retries) if err.kind() == ErrorKind::Locked && attempt < retries.attempts => {
+                retries
+            }
+            _ => return Err(err),
+        };
+
+        let delay = retries.backoff() * 2_u32.saturating_pow(attempt.min(16));
+        attempt += 1;
+        log::warn!(
+            "{}. Retrying in {:?} (attempt {} of {})",
+            err,
+            delay,
+            attempt,
+            retries.attempts
+        );
+        thread::sleep(delay);
+    }
+}
+
+/// Converts an error occurred while opening the database, adding hints for the options
+/// which are known to be unsupported in some environments.
+fn open_error(err: rocksdb::Error, path: &Path, options: &DbOptions) -> crate::Error {
+    let message = err.to_string();
+    if is_lock_error(&message) {
+        lock_error(path, &message)
+    } else if options.uses_direct_io() {
+        crate::Error::new(format!(
+            "{} (direct I/O is enabled with `use_direct_reads` or \
+             `use_direct_io_for_flush_and_compaction`; it is not supported by some \
+             filesystems, such as tmpfs or overlayfs)",
+            err
+        ))
+    } else {
+        err.into()
+    }
+}
+
+/// Name of the subdirectory, into which `RocksDB` moves files it cannot recover
+/// during repair.
+const LOST_DIR: &str = "lost";
+
+/// Report on the repair of a database performed by [`repair`].
+///
+/// [`repair`]: fn.repair.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RepairReport {
+    /// Names of the column families in the repaired database.
+    pub column_families: Vec<String>,
+    /// Number of SST files in the repaired database.
+    pub sst_files: usize,
+    /// Files which could not be recovered and were moved to the `lost` subdirectory
+    /// of the database directory during the repair.
+    pub lost_files: Vec<PathBuf>,
+}
+
+impl RepairReport {
+    /// Returns `true` if no files were lost during the repair. Note that even in this case
+    /// some recent writes may be lost if the write-ahead log was corrupted.
+    pub fn is_complete(&self) -> bool {
+        self.lost_files.is_empty()
+    }
+}
+
+/// Lists files in the directory (non-recursively) with the specified extension, or all files
+/// if `extension` is `None`. Returns an empty list if the directory does not exist.
+fn list_files(dir: &Path, extension: Option<&str>) -> Vec<PathBuf> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            extension.map_or(true, |ext| {
+                path.extension().map_or(false, |path_ext| path_ext == ext)
+            })
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Tries to repair a database which cannot be opened because of corruption, salvaging
+/// as much data as possible. Files which cannot be recovered are moved to the `lost`
+/// subdirectory of the database directory.
+///
+/// The database must not be open while it is repaired. The repair may lose data, so it
+/// is advisable to back up the database directory before the repair. After a successful
+/// repair, the database can be opened with `RocksDB::open`.
+///
+/// # Errors
+///
+/// Returns an error if the database does not exist, if it is locked by a live process
+/// (such errors have the `Locked` kind, as when opening the database), or if the repair fails.
+pub fn repair<P: AsRef<Path>>(path: P, options: &DbOptions) -> crate::Result<RepairReport> {
+    let path = path.as_ref();
+    options.validate()?;
+    if !path.join("CURRENT").is_file() {
+        return Err(crate::Error::new(format!(
+            "Cannot repair database at {}: the directory does not contain a database",
+            path.display()
+        )));
+    }
+
+    let lost_dir = path.join(LOST_DIR);
+    let lost_before = list_files(&lost_dir, None);
+    rocksdb::DB::repair(&db_options(path, options), path).map_err(|e| {
+        let message = e.to_string();
+        if is_lock_error(&message) {
+            lock_error(path, &message)
+        } else {
+            crate::Error::new(format!(
+                "Cannot repair database at {}: {}",
+                path.display(),
+                message
+            ))
+        }
+    })?;
+
+    let lost_files = list_files(&lost_dir, None)
+        .into_iter()
+        .filter(|file| !lost_before.contains(file))
+        .collect();
+    let column_families = rocksdb::DB::list_cf(&RocksDbOptions::default(), path)?;
+    Ok(RepairReport {
+        column_families,
+        sst_files: list_files(path, Some("sst")).len(),
+        lost_files,
+    })
+}
+
+/// Returns names of all column families of the database.
+fn column_family_names(db: &rocksdb::DB) -> crate::Result<Vec<String>> {
+    rocksdb::DB::list_cf(&RocksDbOptions::default(), db.path()).map_err(Into::into)
+}
+
+/// Sums an integer property over all column families of the database.
+fn sum_int_property(db: &rocksdb::DB, name: &str) -> Option<u64> {
+    let names = column_family_names(db).ok()?;
+    names
+        .iter()
+        .filter_map(|cf_name| db.cf_handle(cf_name))
+        .map(|cf| db.property_int_value_cf(cf, name).ok().flatten())
+        .sum()
+}
+
+/// Mode in which the database is opened.
+#[derive(Debug, Clone, PartialEq)]
+enum OpenMode {
+    /// Ordinary read-write mode.
+    ReadWrite,
+    /// Read-write mode, in which entries expire after the specified time-to-live.
+    Ttl(Duration),
+    /// Read-only mode.
+    ReadOnly {
+        /// Whether opening fails if the write-ahead log is not empty.
+        fail_if_log_exists: bool,
+    },
+    /// Secondary instance following the primary database, which keeps its info LOG
+    /// in the specified directory.
+    Secondary(PathBuf),
+}
+
+impl OpenMode {
+    fn from_options(options: &DbOptions) -> Self {
+        if options.read_only == Some(true) {
+            return OpenMode::ReadOnly {
+                fail_if_log_exists: false,
+            };
+        }
+        match options.ttl_seconds {
+            Some(ttl) => OpenMode::Ttl(Duration::from_secs(ttl)),
+            None => OpenMode::ReadWrite,
+        }
+    }
+
+    fn is_read_only(&self) -> bool {
+        matches!(self, OpenMode::ReadOnly { .. } | OpenMode::Secondary(_))
+    }
+}
+
 /// A snapshot of a `RocksDB`.
 pub struct RocksDBSnapshot {
     snapshot: rocksdb::Snapshot<'static>,
     db: Arc<ShardedLock<rocksdb::DB>>,
+    verify_checksums: bool,
+    prefix_extractor_len: Option<usize>,
+}
+
+/// Bounds of the key space scanned by a `RocksDB` iterator, which allow `RocksDB` to skip
+/// the blocks past the end of the iterated index.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct IterBounds {
+    /// Exclusive upper bound of the keys in the column family.
+    upper_bound: Option<[u8; ID_SIZE]>,
+    /// Whether all keys in the iterated range have the same prefix according to
+    /// the prefix extractor of the database.
+    prefix_same_as_start: bool,
 }
 
 /// An iterator over the entries of a `RocksDB`.
@@ -86,45 +594,527 @@ impl RocksDB {
     /// If the database does not exist at the indicated path and the option
     /// `create_if_missing` is switched on in `DbOptions`, a new database will
     /// be created at the indicated path.
+    ///
+    /// Returns an error if the options are inconsistent; see [`DbOptions::validate`].
+    ///
+    /// [`DbOptions::validate`]: struct.DbOptions.html#method.validate
     pub fn open<P: AsRef<Path>>(path: P, options: &DbOptions) -> crate::Result<Self> {
-        let inner = {
+        Self::open_with_mode(path, options, OpenMode::from_options(options))
+    }
+
+    /// Opens a database stored at the specified path in the read-only mode. This is
+    /// equivalent to `open` with the `read_only` option switched on.
+    ///
+    /// The database may be opened read-only while it is opened for writing by another
+    /// process; the read-only handle sees the state of the database at the moment of opening.
+    /// Snapshots and iterators work normally, while merging patches returns an error
+    /// of [`ErrorKind::ReadOnly`] kind.
+    ///
+    /// If `fail_if_log_exists` is set, opening fails if the database has a non-empty
+    /// write-ahead log, i.e., if it may contain writes not flushed to SST files.
+    ///
+    /// [`ErrorKind::ReadOnly`]: enum.ErrorKind.html#variant.ReadOnly
+    pub fn open_read_only<P: AsRef<Path>>(
+        path: P,
+        options: &DbOptions,
+        fail_if_log_exists: bool,
+    ) -> crate::Result<Self> {
+        let options = DbOptions {
+            read_only: Some(true),
+            ..options.clone()
+        };
+        let mode = OpenMode::ReadOnly { fail_if_log_exists };
+        Self::open_with_mode(path, &options, mode)
+    }
+
+    /// Opens a secondary instance of the database stored at `primary_path`. The secondary
+    /// instance is read-only like the one returned by [`open_read_only`], but it can follow
+    /// the changes of the primary instance (e.g., a running node) by calling
+    /// [`try_catch_up_with_primary`]. The secondary instance keeps its info LOG
+    /// in `secondary_path`, which is created if necessary.
+    ///
+    /// The secondary instance keeps all SST files open, so the `max_open_files` option
+    /// is ignored. Column families created by the primary instance after the secondary one
+    /// is opened are not visible until the secondary instance is reopened.
+    ///
+    /// [`open_read_only`]: #method.open_read_only
+    /// [`try_catch_up_with_primary`]: #method.try_catch_up_with_primary
+    pub fn open_secondary<P, Q>(
+        primary_path: P,
+        secondary_path: Q,
+        options: &DbOptions,
+    ) -> crate::Result<Self>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let options = DbOptions {
+            read_only: Some(true),
+            ..options.clone()
+        };
+        let mode = OpenMode::Secondary(secondary_path.as_ref().to_owned());
+        Self::open_with_mode(primary_path, &options, mode)
+    }
+
+    /// Catches up with the changes made by the primary instance since the secondary instance
+    /// was opened or caught up last time. Snapshots created after this call reflect
+    /// the changes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is not a secondary instance, or if catching up
+    /// fails, e.g., because the primary instance has deleted files still needed by
+    /// the secondary instance. In the latter case, the secondary instance should be reopened.
+    pub fn try_catch_up_with_primary(&self) -> crate::Result<()> {
+        if let OpenMode::Secondary(_) = self.mode {
+            self.get_lock_guard()
+                .try_catch_up_with_primary()
+                .map_err(|e| crate::Error::new(format!("Cannot catch up with primary: {}", e)))
+        } else {
+            Err(crate::Error::new(
+                "Cannot catch up with primary: the database is not a secondary instance",
+            ))
+        }
+    }
+
+    fn open_with_mode<P: AsRef<Path>>(
+        path: P,
+        options: &DbOptions,
+        mode: OpenMode,
+    ) -> crate::Result<Self> {
+        options.validate()?;
+
+        if let Some(size) = options.effective_block_cache_size() {
+            if size > LARGE_BLOCK_CACHE_SIZE {
+                log::warn!(
+                    "Block cache size of {} bytes is unusually large; make sure \
+                     that the node has enough memory",
+                    size
+                );
+            }
+        }
+
+        if options.wal_sync_mode == Some(WalSyncMode::Disabled) {
+            log::warn!(
+                "Write-ahead log of the database is DISABLED; all writes not flushed \
+                 to disk will be lost on a crash. Never use this mode on production nodes"
+            );
+        }
+
+        if options.compression_options.is_some() && !options.uses_compression() {
+            log::warn!(
+                "Compression options are specified for the database, but compression \
+                 is disabled; the options are ignored"
+            );
+        }
+
+        let mut db_options = db_options(path.as_ref(), options);
+        if let OpenMode::Secondary(ref secondary_path) = mode {
+            // Secondary instances require all SST files to be open.
+            db_options.set_max_open_files(-1);
+            fs::create_dir_all(secondary_path).map_err(|e| {
+                crate::Error::new(format!(
+                    "Cannot create directory {} for secondary instance: {}",
+                    secondary_path.display(),
+                    e
+                ))
+            })?;
+        }
+        let path = path.as_ref();
+        let open_db = || {
             if let Ok(names) = rocksdb::DB::list_cf(&RocksDbOptions::default(), &path) {
-                let cf_names = names.iter().map(String::as_str).collect::<Vec<_>>();
-                rocksdb::DB::open_cf(&options.into(), path, cf_names)?
+                for cf_name in options.column_family_overrides.keys() {
+                    if !names.contains(cf_name) {
+                        log::warn!(
+                            "Column family `{}` does not exist in the database; its option \
+                             overrides are ignored until it is created",
+                            cf_name
+                        );
+                    }
+                }
+                let descriptors = names.iter().map(|name| {
+                    ColumnFamilyDescriptor::new(name, options.for_column_family(name).into())
+                });
+                match mode {
+                    OpenMode::ReadWrite => {
+                        rocksdb::DB::open_cf_descriptors(&db_options, path, descriptors)
+                    }
+                    OpenMode::Ttl(ttl) => rocksdb::DB::open_cf_descriptors_with_ttl(
+                        &db_options,
+                        path,
+                        descriptors,
+                        ttl,
+                    ),
+                    OpenMode::ReadOnly { fail_if_log_exists } => {
+                        rocksdb::DB::open_cf_descriptors_read_only(
+                            &db_options,
+                            path,
+                            descriptors,
+                            fail_if_log_exists,
+                        )
+                    }
+                    OpenMode::Secondary(ref secondary_path) => {
+                        rocksdb::DB::open_cf_as_secondary(&db_options, path, secondary_path, &names)
+                    }
+                }
             } else {
-                rocksdb::DB::open(&options.into(), path)?
+                match mode {
+                    OpenMode::ReadWrite => rocksdb::DB::open(&db_options, path),
+                    OpenMode::Ttl(ttl) => rocksdb::DB::open_with_ttl(&db_options, path, ttl),
+                    OpenMode::ReadOnly { fail_if_log_exists } => {
+                        rocksdb::DB::open_for_read_only(&db_options, path, fail_if_log_exists)
+                    }
+                    OpenMode::Secondary(ref secondary_path) => {
+                        rocksdb::DB::open_as_secondary(&db_options, path, secondary_path)
+                    }
+                }
             }
         };
+        let inner = open_with_retries(path, options, open_db)?;
         let mut db = Self {
             db: Arc::new(ShardedLock::new(inner)),
-            options: *options,
+            options: options.clone(),
+            verify_checksums: AtomicBool::new(options.verify_checksums_on_read.unwrap_or(true)),
+            mode,
+            merge_poisoned: Arc::default(),
+            merge_writer: Mutex::default(),
         };
         check_database(&mut db)?;
+        // Read-only instances cannot record the prefix length, and only check the recorded one.
+        let record_prefix_len = !db.mode.is_read_only();
+        check_prefix_extractor_len(&db, options.prefix_extractor_len, record_prefix_len)?;
         Ok(db)
     }
 
     /// Creates checkpoint of this database in the given directory. See [`RocksDB` docs] for
     /// details.
     ///
+    /// The checkpoint is a consistent view of the database, in which SST files are hard-linked
+    /// if possible, so it is cheap to create. It can be created while patches are being
+    /// merged into the database; the checkpoint then contains either all or none
+    /// of the changes of each patch.
+    ///
     /// Successfully created checkpoint can be opened using `RocksDB::open`.
     ///
+    /// # Errors
+    ///
+    /// Returns an error if `path` exists and is not an empty directory. An existing empty
+    /// directory is replaced with the checkpoint.
+    ///
     /// [`RocksDB` docs]: https://github.com/facebook/rocksdb/wiki/Checkpoints
     pub fn create_checkpoint<T: AsRef<Path>>(&self, path: T) -> crate::Result<()> {
+        let path = path.as_ref();
+        if path.exists() {
+            let is_empty_dir = path.is_dir()
+                && fs::read_dir(path)
+                    .map(|mut entries| entries.next().is_none())
+                    .unwrap_or(false);
+            if !is_empty_dir {
+                return Err(crate::Error::new(format!(
+                    "Cannot create checkpoint at {}: the path exists and is not an empty directory",
+                    path.display()
+                )));
+            }
+            // `RocksDB` requires that the checkpoint directory does not exist.
+            fs::remove_dir(path).map_err(|e| {
+                crate::Error::new(format!(
+                    "Cannot create checkpoint at {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+        }
+
         let db = self.get_lock_guard();
         let checkpoint = Checkpoint::new(&db)?;
         checkpoint.create_checkpoint(path)?;
         Ok(())
     }
 
-    fn cf_exists(&self, cf_name: &str) -> bool {
+    /// Creates a new incremental backup of this database in the given directory, which
+    /// is created if necessary. Only the files changed since the previous backup
+    /// in the same directory are copied.
+    ///
+    /// Backups can be managed with [`list_backups`] and [`purge_old_backups`] and restored
+    /// with [`restore_from_backup`].
+    ///
+    /// [`list_backups`]: fn.list_backups.html
+    /// [`purge_old_backups`]: fn.purge_old_backups.html
+    /// [`restore_from_backup`]: fn.restore_from_backup.html
+    pub fn backup<T: AsRef<Path>>(&self, backup_dir: T) -> crate::Result<BackupInfo> {
+        // Memtables cannot be flushed in the read-only mode.
+        let flush = !self.mode.is_read_only();
+        create_backup(&self.get_lock_guard(), backup_dir.as_ref(), flush)
+    }
+
+    /// Sets whether block checksums are verified on reads. The setting applies to snapshots
+    /// created after the call; it does not require reopening the database.
+    pub fn set_verify_checksums_on_read(&self, verify: bool) {
+        self.verify_checksums.store(verify, Ordering::SeqCst);
+    }
+
+    /// Returns whether block checksums are verified on reads.
+    pub fn verify_checksums_on_read(&self) -> bool {
+        self.verify_checksums.load(Ordering::SeqCst)
+    }
+
+    /// Returns the value of a database property, such as `rocksdb.stats` or
+    /// `rocksdb.levelstats`. Returns `None` if the property is unknown. See [`RocksDB` docs]
+    /// for the list of supported properties.
+    ///
+    /// [`RocksDB` docs]: https://github.com/facebook/rocksdb/blob/master/include/rocksdb/db.h
+    pub fn property(&self, name: &str) -> Option<String> {
+        self.get_lock_guard().property_value(name).ok().flatten()
+    }
+
+    /// Returns the value of an integer database property, such as
+    /// `rocksdb.estimate-num-keys`. Returns `None` if the property is unknown or
+    /// is not an integer one.
+    ///
+    /// For column family-specific properties, the returned value relates to the default
+    /// column family; use [`stats`] for values aggregated across all column families.
+    ///
+    /// [`stats`]: #method.stats
+    pub fn int_property(&self, name: &str) -> Option<u64> {
+        self.get_lock_guard()
+            .property_int_value(name)
+            .ok()
+            .flatten()
+    }
+
+    /// Returns the value of an integer property of the specified column family, such as
+    /// `rocksdb.total-sst-files-size`. Returns `None` if the column family does not exist,
+    /// or if the property is unknown or is not an integer one.
+    pub fn cf_int_property(&self, cf_name: &str, name: &str) -> Option<u64> {
+        let db = self.get_lock_guard();
+        let cf = db.cf_handle(cf_name)?;
+        db.property_int_value_cf(cf, name).ok().flatten()
+    }
+
+    /// Compacts all column families of the database. Compaction removes deleted and
+    /// overwritten entries, reclaiming disk space, e.g., after clearing large indexes.
+    ///
+    /// This call blocks until the compaction is finished, which may take a long time for
+    /// large databases; see [`spawn_compaction`] for a non-blocking alternative.
+    ///
+    /// [`spawn_compaction`]: #method.spawn_compaction
+    pub fn compact(&self) -> crate::Result<()> {
+        self.compact_range(None, None, None)
+    }
+
+    /// Compacts the range of keys from `from` (inclusive) to `to` (exclusive) in
+    /// the specified column family, or in all column families if `cf_name` is `None`.
+    /// Missing bounds mean that the range is unbounded on the corresponding side.
+    ///
+    /// This call blocks until the compaction is finished.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the column family does not exist or if the database is opened
+    /// in the read-only mode.
+    pub fn compact_range(
+        &self,
+        cf_name: Option<&str>,
+        from: Option<&[u8]>,
+        to: Option<&[u8]>,
+    ) -> crate::Result<()> {
+        self.check_writable("compact")?;
+        let db = self.get_lock_guard();
+        let cf_names = match cf_name {
+            Some(name) => vec![name.to_owned()],
+            None => column_family_names(&db)?,
+        };
+        for name in &cf_names {
+            let cf = db.cf_handle(name).ok_or_else(|| {
+                crate::Error::new(format!("Column family `{}` does not exist", name))
+            })?;
+            db.compact_range_cf(cf, from, to);
+        }
+        Ok(())
+    }
+
+    /// Compacts all column families of the database in a background thread. The returned
+    /// handle can be joined to wait for the compaction to finish and obtain its result.
+    pub fn spawn_compaction(&self) -> thread::JoinHandle<crate::Result<()>> {
+        let db = self.share();
+        thread::spawn(move || db.compact())
+    }
+
+    /// Applies a patch to the database in the background. Patches are applied by a single
+    /// writer thread strictly in the order of their submission. See [`Database::merge_async`]
+    /// for details.
+    ///
+    /// [`Database::merge_async`]: trait.Database.html#method.merge_async
+    pub fn merge_async(&self, patch: Patch) -> MergeHandle {
+        let check = self
+            .check_writable("merge a patch into")
+            .and_then(|()| check_not_poisoned(&self.merge_poisoned));
+        if let Err(e) = check {
+            return MergeHandle::ready(Err(e));
+        }
+
+        let mut writer = self
+            .merge_writer
+            .lock()
+            .expect("Couldn't get lock to merge writer");
+        let writer = writer.get_or_insert_with(|| {
+            let db = self.share();
+            MergeWriter::spawn(Arc::clone(&self.merge_poisoned), move |patch| {
+                Database::merge(&db, patch)
+            })
+        });
+        writer.submit(patch)
+    }
+
+    /// Creates another instance of the database sharing the underlying `RocksDB` handle
+    /// for use in background threads.
+    fn share(&self) -> Self {
+        Self {
+            db: Arc::clone(&self.db),
+            options: self.options.clone(),
+            verify_checksums: AtomicBool::new(self.verify_checksums_on_read()),
+            mode: self.mode.clone(),
+            merge_poisoned: Arc::clone(&self.merge_poisoned),
+            merge_writer: Mutex::default(),
+        }
+    }
+
+    /// Returns the approximate on-disk size in bytes of indexes with names starting with
+    /// `name_prefix`, e.g., `"my_service."` for all indexes of a service. The size is
+    /// estimated from SST files; data in memtables that has not been flushed yet
+    /// is not taken into account.
+    ///
+    /// Returns 0 if there are no indexes matching the prefix.
+    pub fn approximate_size(&self, name_prefix: &str) -> u64 {
+        let db = self.get_lock_guard();
+        let cf_names = column_family_names(&db).unwrap_or_default();
+        // Keys in column families are prefixed by index IDs, which are less than `u64::MAX`.
+        let range_end = [u8::max_value(); ID_SIZE];
+        cf_names
+            .iter()
+            .filter(|name| name.starts_with(name_prefix))
+            .filter_map(|name| db.cf_handle(name))
+            .flat_map(|cf| db.get_approximate_sizes_cf(cf, &[Range::new(&[], &range_end)]))
+            .sum()
+    }
+
+    /// Returns the approximate on-disk size of all indexes in bytes. See [`approximate_size`]
+    /// for details.
+    ///
+    /// [`approximate_size`]: #method.approximate_size
+    pub fn total_size(&self) -> u64 {
+        self.approximate_size("")
+    }
+
+    /// Flushes memtables of all column families to SST files, waiting for the flush
+    /// to complete. After the flush, the database directory can be copied with filesystem
+    /// tools without losing data, even if the write-ahead log is disabled.
+    ///
+    /// The flush may be performed concurrently with merging patches; the changes merged
+    /// during the flush may remain in memtables.
+    ///
+    /// Databases opened in the read-only mode have nothing to flush, so the call does nothing
+    /// for them.
+    pub fn flush(&self) -> crate::Result<()> {
+        if self.mode.is_read_only() {
+            return Ok(());
+        }
+        let db = self.get_lock_guard();
+        let mut flush_opts = FlushOptions::default();
+        flush_opts.set_wait(true);
+        for name in column_family_names(&db)? {
+            if let Some(cf) = db.cf_handle(&name) {
+                db.flush_cf_opt(cf, &flush_opts)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Gracefully closes the database: flushes memtables of all column families, syncs
+    /// the write-ahead log and waits for the background work (e.g., compactions) to stop.
+    /// Unlike dropping the database, this guarantees that all merged changes are persisted
+    /// to SST files once the method returns.
+    ///
+    /// Snapshots created before closing remain readable, but the database cannot be written
+    /// to via other handles sharing it (e.g., one used by [`spawn_compaction`]) after the call.
+    ///
+    /// [`spawn_compaction`]: #method.spawn_compaction
+    pub fn close(self) -> crate::Result<()> {
+        // Dropping the writer waits until all patches submitted with `merge_async` are applied.
+        let merge_writer = self
+            .merge_writer
+            .lock()
+            .expect("Couldn't get lock to merge writer")
+            .take();
+        drop(merge_writer);
+        self.flush()?;
+        let has_wal = self.options.wal_sync_mode != Some(WalSyncMode::Disabled);
+        if has_wal && !self.mode.is_read_only() {
+            self.get_lock_guard().flush_wal(true)?;
+        }
+        self.get_lock_guard().cancel_all_background_work(true);
+        Ok(())
+    }
+
+    /// Returns names of all column families in the database. Column families are created
+    /// lazily on the first write to an index stored in them.
+    pub fn column_families(&self) -> crate::Result<Vec<String>> {
+        column_family_names(&self.get_lock_guard())
+    }
+
+    /// Drops the column family with the specified name. See
+    /// [`Database::drop_column_family`] for details.
+    ///
+    /// [`Database::drop_column_family`]: trait.Database.html#method.drop_column_family
+    pub fn drop_column_family(&self, name: &str) -> crate::Result<()> {
+        self.check_writable("drop a column family in")?;
+        check_droppable_column_family(name, self.cf_exists(name))?;
+        self.db
+            .write()
+            .expect("Couldn't get write lock to DB")
+            .drop_cf(name)
+            .map_err(Into::into)
+    }
+
+    /// Drops all column families which are not used by the database itself and are not
+    /// listed in `keep`. See [`Database::drop_unused_column_families`] for details.
+    ///
+    /// [`Database::drop_unused_column_families`]: trait.Database.html#method.drop_unused_column_families
+    pub fn drop_unused_column_families(&self, keep: &[&str]) -> crate::Result<Vec<String>> {
+        self.check_writable("drop column families in")?;
+        let unused =
+            unused_column_families(&self.rocksdb_snapshot(), self.column_families()?, keep)?;
+        for name in &unused {
+            self.drop_column_family(name)?;
+            log::info!("Dropped unused column family `{}`", name);
+        }
+        Ok(unused)
+    }
+
+    /// Returns an error of `ReadOnly` kind if the database is opened in the read-only mode.
+    pub(super) fn check_writable(&self, action: &str) -> crate::Result<()> {
+        if self.mode.is_read_only() {
+            Err(crate::Error::with_kind(
+                ErrorKind::ReadOnly,
+                format!(
+                    "Cannot {} the database opened in the read-only mode",
+                    action
+                ),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub(super) fn cf_exists(&self, cf_name: &str) -> bool {
         self.get_lock_guard().cf_handle(cf_name).is_some()
     }
 
-    fn create_cf(&self, cf_name: &str) -> crate::Result<()> {
+    pub(super) fn create_cf(&self, cf_name: &str) -> crate::Result<()> {
         self.db
             .write()
             .expect("Couldn't get write lock to DB")
-            .create_cf(cf_name, &self.options.into())
+            .create_cf(cf_name, &self.options.for_column_family(cf_name).into())
             .map_err(Into::into)
     }
 
@@ -157,15 +1147,63 @@ impl RocksDB {
         }
     }
 
-    fn do_merge(&self, patch: Patch, w_opts: &RocksDBWriteOptions) -> crate::Result<()> {
+    /// Removes all data from all column families of the database.
+    pub(super) fn clear(&self) -> crate::Result<()> {
+        self.check_writable("clear")?;
+        let db = self.get_lock_guard();
+        let mut batch = WriteBatch::default();
+        for name in column_family_names(&db)? {
+            if let Some(cf) = db.cf_handle(&name) {
+                self.clear_column_family(&mut batch, cf);
+            }
+        }
+        db.write_opt(batch, &self.write_options(false))
+            .map_err(Into::into)
+    }
+
+    /// Creates write options according to the WAL sync mode of the database. `sync` requests
+    /// syncing the write regardless of the mode, unless the WAL is disabled.
+    fn write_options(&self, sync: bool) -> RocksDBWriteOptions {
+        let mut w_opts = RocksDBWriteOptions::default();
+        match self.options.wal_sync_mode {
+            Some(WalSyncMode::Disabled) => w_opts.disable_wal(true),
+            Some(WalSyncMode::Sync) => w_opts.set_sync(true),
+            Some(WalSyncMode::Async) | None => w_opts.set_sync(sync),
+        }
+        w_opts
+    }
+
+    /// Merges the patch into the database. If `max_batch_bytes` is set, the patch is split
+    /// into several write batches of approximately this size; all batches except the last one
+    /// are written without syncing the WAL.
+    fn do_merge(
+        &self,
+        patch: Patch,
+        w_opts: &RocksDBWriteOptions,
+        max_batch_bytes: Option<usize>,
+    ) -> crate::Result<()> {
+        self.check_writable("merge a patch into")?;
+        check_not_poisoned(&self.merge_poisoned)?;
+
+        let intermediate_w_opts = self.write_options(false);
         let mut batch = WriteBatch::default();
         for (resolved, changes) in patch.into_changes() {
             if !self.cf_exists(&resolved.name) {
+                // Column families are created lazily on the first write to them.
+                if changes.is_empty() {
+                    continue;
+                }
                 self.create_cf(&resolved.name)?;
             }
 
             let db_reader = self.get_lock_guard();
             let cf = db_reader.cf_handle(&resolved.name).unwrap();
+            let flush_if_full = |batch: &mut WriteBatch| -> crate::Result<()> {
+                if max_batch_bytes.map_or(false, |max| batch.size_in_bytes() >= max) {
+                    db_reader.write_opt(mem::take(batch), &intermediate_w_opts)?;
+                }
+                Ok(())
+            };
 
             if changes.is_cleared() {
                 self.clear_prefix(&mut batch, cf, &resolved);
@@ -187,6 +1225,7 @@ impl RocksDB {
                         Change::Put(ref value) => batch.put_cf(cf, &buffer, value),
                         Change::Delete => batch.delete_cf(cf, &buffer),
                     }
+                    flush_if_full(&mut batch)?;
                 }
             } else {
                 // Write changes to the column family as-is.
@@ -195,6 +1234,7 @@ impl RocksDB {
                         Change::Put(ref value) => batch.put_cf(cf, &key, value),
                         Change::Delete => batch.delete_cf(cf, &key),
                     }
+                    flush_if_full(&mut batch)?;
                 }
             }
         }
@@ -227,6 +1267,8 @@ impl RocksDB {
             // FIXME: Investigate changing `rocksdb::Snapshot` / `DB` to remove `unsafe` (ECR-4273).
             snapshot: unsafe { mem::transmute(self.get_lock_guard().snapshot()) },
             db: Arc::clone(&self.db),
+            verify_checksums: self.verify_checksums_on_read(),
+            prefix_extractor_len: self.options.prefix_extractor_len,
         }
     }
 }
@@ -236,15 +1278,58 @@ impl RocksDBSnapshot {
         self.db.read().expect("Couldn't get read lock to DB")
     }
 
-    fn rocksdb_iter(&self, name: &ResolvedAddress, from: &[u8]) -> RocksDBIterator<'_> {
+    fn read_options(&self) -> ReadOptions {
+        let mut read_opts = ReadOptions::default();
+        read_opts.set_verify_checksums(self.verify_checksums);
+        read_opts
+    }
+
+    fn iter_read_options(&self, bounds: &IterBounds) -> ReadOptions {
+        let mut read_opts = self.read_options();
+        if let Some(upper_bound) = bounds.upper_bound {
+            read_opts.set_iterate_upper_bound(upper_bound.to_vec());
+        }
+        read_opts.set_prefix_same_as_start(bounds.prefix_same_as_start);
+        // Without total order seek, iterators over column families with a prefix extractor
+        // do not guarantee the order or completeness of keys with different prefixes.
+        read_opts.set_total_order_seek(!bounds.prefix_same_as_start);
+        read_opts
+    }
+
+    /// Returns the bounds of the key space occupied by the index in its column family.
+    /// Indexes occupying a whole column family are not bounded.
+    fn index_bounds(&self, name: &ResolvedAddress) -> IterBounds {
+        match name.id_to_bytes() {
+            Some(id_bytes) => IterBounds {
+                upper_bound: Some(next_id_bytes(id_bytes)),
+                // All keys of the index start with its ID, so they have the same prefix
+                // if the prefix is not longer than the ID.
+                prefix_same_as_start: self
+                    .prefix_extractor_len
+                    .map_or(false, |len| len <= ID_SIZE),
+            },
+            None => IterBounds::default(),
+        }
+    }
+
+    fn rocksdb_iter(
+        &self,
+        name: &ResolvedAddress,
+        from: &[u8],
+        bounds: &IterBounds,
+    ) -> RocksDBIterator<'_> {
         use rocksdb::{Direction, IteratorMode};
 
         let from = name.keyed(from);
         let iter = match self.get_lock_guard().cf_handle(&name.name) {
-            Some(cf) => self
+            Some(cf) => self.snapshot.iterator_cf_opt(
+                cf,
+                self.iter_read_options(bounds),
+                IteratorMode::From(from.as_ref(), Direction::Forward),
+            ),
+            None => self
                 .snapshot
-                .iterator_cf(cf, IteratorMode::From(from.as_ref(), Direction::Forward)),
-            None => self.snapshot.iterator(IteratorMode::Start),
+                .iterator_opt(IteratorMode::Start, self.read_options()),
         };
         RocksDBIterator {
             iter: iter.peekable(),
@@ -262,21 +1347,93 @@ impl Database for RocksDB {
     }
 
     fn merge(&self, patch: Patch) -> crate::Result<()> {
-        let w_opts = RocksDBWriteOptions::default();
-        self.do_merge(patch, &w_opts)
+        let w_opts = self.write_options(false);
+        self.do_merge(patch, &w_opts, None)
     }
 
     fn merge_sync(&self, patch: Patch) -> crate::Result<()> {
-        let mut w_opts = RocksDBWriteOptions::default();
-        w_opts.set_sync(true);
-        self.do_merge(patch, &w_opts)
+        let w_opts = self.write_options(true);
+        self.do_merge(patch, &w_opts, None)
+    }
+
+    fn merge_async(&self, patch: Patch) -> MergeHandle {
+        Self::merge_async(self, patch)
+    }
+
+    fn merge_in_batches(&self, patch: Patch) -> crate::Result<()> {
+        let w_opts = self.write_options(true);
+        self.do_merge(patch, &w_opts, self.options.max_batch_bytes)
+    }
+
+    fn create_checkpoint(&self, path: &Path) -> crate::Result<()> {
+        Self::create_checkpoint(self, path)
+    }
+
+    fn backup(&self, backup_dir: &Path) -> crate::Result<BackupInfo> {
+        Self::backup(self, backup_dir)
+    }
+
+    fn try_catch_up_with_primary(&self) -> crate::Result<()> {
+        Self::try_catch_up_with_primary(self)
+    }
+
+    fn compact(&self) -> crate::Result<()> {
+        Self::compact(self)
+    }
+
+    fn approximate_size(&self, name_prefix: &str) -> u64 {
+        Self::approximate_size(self, name_prefix)
+    }
+
+    fn flush(&self) -> crate::Result<()> {
+        Self::flush(self)
+    }
+
+    fn export_sst(&self, dir: &Path) -> crate::Result<SstStats> {
+        Self::export_sst(self, dir)
+    }
+
+    fn ingest_sst(&self, dir: &Path) -> crate::Result<SstStats> {
+        Self::ingest_sst(self, dir)
+    }
+
+    fn verify_integrity(&self) -> crate::Result<IntegrityReport> {
+        Self::verify_integrity(self)
+    }
+
+    fn drop_column_family(&self, name: &str) -> crate::Result<()> {
+        Self::drop_column_family(self, name)
+    }
+
+    fn drop_unused_column_families(&self, keep: &[&str]) -> crate::Result<Vec<String>> {
+        Self::drop_unused_column_families(self, keep)
+    }
+
+    fn stats(&self) -> DbStats {
+        let db = self.get_lock_guard();
+        DbStats {
+            estimated_num_keys: sum_int_property(&db, "rocksdb.estimate-num-keys"),
+            memtables_size: sum_int_property(&db, "rocksdb.cur-size-all-mem-tables"),
+            estimated_live_data_size: sum_int_property(&db, "rocksdb.estimate-live-data-size"),
+            running_compactions: db
+                .property_int_value("rocksdb.num-running-compactions")
+                .ok()
+                .flatten(),
+            pending_compaction_bytes: sum_int_property(
+                &db,
+                "rocksdb.estimate-pending-compaction-bytes",
+            ),
+        }
     }
 }
 
 impl Snapshot for RocksDBSnapshot {
     fn get(&self, resolved_addr: &ResolvedAddress, key: &[u8]) -> Option<Vec<u8>> {
         if let Some(cf) = self.get_lock_guard().cf_handle(&resolved_addr.name) {
-            match self.snapshot.get_cf(cf, resolved_addr.keyed(key)) {
+            match self
+                .snapshot
+                .get_cf_opt(cf, resolved_addr.keyed(key), self.read_options())
+            {
                 Ok(value) => value.map(|v| v.to_vec()),
                 Err(e) => panic!("{}", e),
             }
@@ -285,8 +1442,25 @@ impl Snapshot for RocksDBSnapshot {
         }
     }
 
+    fn multi_get(&self, resolved_addr: &ResolvedAddress, keys: &[&[u8]]) -> Vec<Option<Vec<u8>>> {
+        let db = self.get_lock_guard();
+        let cf = match db.cf_handle(&resolved_addr.name) {
+            Some(cf) => cf,
+            None => return vec![None; keys.len()],
+        };
+        let keys = keys.iter().map(|key| (cf, resolved_addr.keyed(key)));
+        self.snapshot
+            .multi_get_cf_opt(keys, self.read_options())
+            .into_iter()
+            .map(|value| match value {
+                Ok(value) => value,
+                Err(e) => panic!("{}", e),
+            })
+            .collect()
+    }
+
     fn iter(&self, name: &ResolvedAddress, from: &[u8]) -> Iter<'_> {
-        Box::new(self.rocksdb_iter(name, from))
+        Box::new(self.rocksdb_iter(name, from, &self.index_bounds(name)))
     }
 }
 
@@ -389,3 +1563,95 @@ fn test_next_id_bytes() {
         [1, 2, 3, 4, 6, 0, 0, 0]
     );
 }
+
+#[test]
+fn test_max_open_files_for_limit() {
+    assert_eq!(max_open_files_for_limit(None), FALLBACK_OPEN_FILES);
+    assert_eq!(max_open_files_for_limit(Some(u64::MAX)), -1);
+    assert_eq!(max_open_files_for_limit(Some(1 << 40)), -1);
+    assert_eq!(max_open_files_for_limit(Some(1_024)), 896);
+    assert_eq!(max_open_files_for_limit(Some(65_536)), 65_408);
+    assert_eq!(max_open_files_for_limit(Some(148)), MIN_OPEN_FILES);
+    assert_eq!(max_open_files_for_limit(Some(100)), MIN_OPEN_FILES);
+    assert_eq!(max_open_files_for_limit(Some(0)), MIN_OPEN_FILES);
+}
+
+#[test]
+fn bounded_iteration_matches_unbounded_one() {
+    use crate::views::View;
+    use std::num::NonZeroU64;
+
+    fn collect(mut iter: impl Iterator) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let mut entries = vec![];
+        while let Some((peeked_key, _)) = iter.peek() {
+            let peeked_key = peeked_key.to_vec();
+            let (key, value) = iter.next().unwrap();
+            assert_eq!(key, &peeked_key[..]);
+            entries.push((key.to_vec(), value.to_vec()));
+        }
+        entries
+    }
+
+    fn check_iteration(db: &RocksDB, ids: &[u64], keys: &[Vec<u8>]) {
+        let snapshot = db.rocksdb_snapshot();
+        let addrs = ids
+            .iter()
+            .map(|&id| ResolvedAddress::new("idx", NonZeroU64::new(id)))
+            .chain(vec![
+                ResolvedAddress::new("idx", NonZeroU64::new(3)),
+                ResolvedAddress::new("whole_cf", None),
+            ]);
+        for addr in addrs {
+            let bounds = snapshot.index_bounds(&addr);
+            for from in keys.iter().chain(&[vec![255; 20]]) {
+                let bounded = collect(snapshot.rocksdb_iter(&addr, from, &bounds));
+                let unbounded = collect(snapshot.rocksdb_iter(&addr, from, &IterBounds::default()));
+                assert_eq!(bounded, unbounded);
+
+                let expected_len = keys.iter().filter(|&key| key >= from).count();
+                if addr.id.map_or(true, |id| ids.contains(&id.get())) {
+                    assert_eq!(bounded.len(), expected_len);
+                } else {
+                    assert!(bounded.is_empty());
+                }
+            }
+        }
+    }
+
+    let keys = vec![
+        vec![],
+        vec![0],
+        vec![1, 2, 3],
+        vec![255],
+        vec![255; 10],
+        vec![0, 0, 0, 0, 0, 0, 0, 1, 0],
+    ];
+    // IDs are chosen so that the byte representations of adjacent IDs share long prefixes.
+    let ids = [1_u64, 2, 255, 256, 257, 0x01ff_ffff_ffff, 0x0200_0000_0000];
+
+    for &prefix_extractor_len in &[None, Some(4), Some(ID_SIZE)] {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut options = DbOptions::default();
+        options.prefix_extractor_len = prefix_extractor_len;
+        let db = RocksDB::open(&dir, &options).unwrap();
+
+        let fork = db.fork();
+        for &id in &ids {
+            let mut view = View::new(&fork, ResolvedAddress::new("idx", NonZeroU64::new(id)));
+            for key in &keys {
+                view.put(key, id.to_be_bytes().to_vec());
+            }
+        }
+        let mut view = View::new(&fork, ResolvedAddress::new("whole_cf", None));
+        for key in &keys {
+            view.put(key, vec![1]);
+        }
+        drop(view);
+        db.merge(fork.into_patch()).unwrap();
+
+        // Check iteration over both memtables and SST files.
+        check_iteration(&db, &ids, &keys);
+        db.compact().unwrap();
+        check_iteration(&db, &ids, &keys);
+    }
+}
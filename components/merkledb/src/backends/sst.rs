@@ -0,0 +1,256 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Export of `RocksDB` databases to SST files and ingestion of such files.
+//!
+//! Exported files are named `{sequence}-{column family}.sst`, where `sequence` is
+//! a zero-padded number of the file within the export. Data of each column family may be
+//! split into several files with increasing sequence numbers; the files of a column family
+//! contain non-overlapping key ranges in the order of their sequence numbers.
+
+use rocksdb::{IngestExternalFileOptions, IteratorMode, Options as RocksDbOptions, SstFileWriter};
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{db::DB_METADATA, Error, Result, RocksDB};
+
+/// Actions used in error messages.
+const EXPORT: &str = "export SST files to";
+const INGEST: &str = "ingest SST files from";
+
+/// Approximate maximum size of an exported SST file (256 MiB).
+const MAX_SST_FILE_SIZE: u64 = 256 << 20;
+
+/// Statistics of exported or ingested SST files.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SstStats {
+    /// Number of exported or ingested column families.
+    pub column_families: usize,
+    /// Number of SST files.
+    pub files: usize,
+    /// Total size of the SST files in bytes.
+    pub size: u64,
+}
+
+/// Creates an error for `action` (e.g., "export SST files to") performed on the directory.
+fn sst_error(action: &str, dir: &Path, message: impl std::fmt::Display) -> Error {
+    Error::new(format!("Cannot {} {}: {}", action, dir.display(), message))
+}
+
+fn sst_file_name(sequence: usize, cf_name: &str) -> String {
+    format!("{:08}-{}.sst", sequence, cf_name)
+}
+
+/// Parses the name of an exported SST file into the sequence number and the name
+/// of the column family.
+fn parse_sst_file_name(file_name: &str) -> Option<(usize, &str)> {
+    let stem = file_name.strip_suffix(".sst")?;
+    let (sequence, cf_name) = stem.split_at(stem.find('-')?);
+    let cf_name = &cf_name[1..];
+    if cf_name.is_empty() {
+        return None;
+    }
+    Some((sequence.parse().ok()?, cf_name))
+}
+
+/// Lists exported SST files in the directory, grouped by column families. Files of each
+/// column family are ordered by their sequence numbers.
+fn list_sst_files(dir: &Path) -> Result<BTreeMap<String, Vec<PathBuf>>> {
+    let entries = fs::read_dir(dir).map_err(|e| sst_error(INGEST, dir, e))?;
+    let mut files = vec![];
+    for entry in entries {
+        let path = entry.map_err(|e| sst_error(INGEST, dir, e))?.path();
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("");
+        if !path.is_file() || !file_name.ends_with(".sst") {
+            continue;
+        }
+        let (sequence, cf_name) = parse_sst_file_name(file_name).ok_or_else(|| {
+            sst_error(INGEST, dir, format!("unexpected file name `{}`", file_name))
+        })?;
+        files.push((sequence, cf_name.to_owned(), path.clone()));
+    }
+    files.sort();
+
+    let mut files_by_cf: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for (_, cf_name, path) in files {
+        files_by_cf.entry(cf_name).or_default().push(path);
+    }
+    Ok(files_by_cf)
+}
+
+impl RocksDB {
+    /// Exports the data of all column families to SST files in the specified directory,
+    /// which is created if necessary. The data is read from a consistent snapshot of the database,
+    /// so the export may be performed while patches are being merged into the database.
+    ///
+    /// The database metadata (such as the data layout version) is not exported, since
+    /// it is initialized by the database into which the files are ingested.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `dir` exists and is not an empty directory, or if the files
+    /// cannot be written.
+    pub fn export_sst(&self, dir: &Path) -> Result<SstStats> {
+        if dir.exists() {
+            let is_empty_dir = dir.is_dir()
+                && fs::read_dir(dir)
+                    .map(|mut entries| entries.next().is_none())
+                    .unwrap_or(false);
+            if !is_empty_dir {
+                return Err(sst_error(
+                    EXPORT,
+                    dir,
+                    "the path exists and is not an empty directory",
+                ));
+            }
+        }
+        fs::create_dir_all(dir).map_err(|e| sst_error(EXPORT, dir, e))?;
+
+        let db = self.get_lock_guard();
+        let snapshot = db.snapshot();
+        let mut stats = SstStats::default();
+        for cf_name in self.column_families()? {
+            if cf_name == DB_METADATA {
+                continue;
+            }
+            let cf = match db.cf_handle(&cf_name) {
+                Some(cf) => cf,
+                None => continue,
+            };
+
+            let cf_options = RocksDbOptions::from(self.options.for_column_family(&cf_name));
+            let mut writer: Option<SstFileWriter<'_>> = None;
+            let mut cf_files = 0;
+            for (key, value) in snapshot.iterator_cf(cf, IteratorMode::Start) {
+                if writer.is_none() {
+                    let path = dir.join(sst_file_name(stats.files, &cf_name));
+                    let new_writer = SstFileWriter::create(&cf_options);
+                    new_writer.open(&path)?;
+                    writer = Some(new_writer);
+                    stats.files += 1;
+                    cf_files += 1;
+                }
+                let current_writer = writer.as_mut().unwrap();
+                current_writer.put(&key, &value)?;
+                if current_writer.file_size() >= MAX_SST_FILE_SIZE {
+                    current_writer.finish()?;
+                    writer = None;
+                }
+            }
+            if let Some(mut writer) = writer {
+                writer.finish()?;
+            }
+            if cf_files > 0 {
+                stats.column_families += 1;
+            }
+        }
+
+        stats.size = fs::read_dir(dir)
+            .map_err(|e| sst_error(EXPORT, dir, e))?
+            .filter_map(|entry| entry.ok()?.metadata().ok())
+            .map(|metadata| metadata.len())
+            .sum();
+        Ok(stats)
+    }
+
+    /// Ingests SST files exported with [`export_sst`] from the specified directory.
+    /// The files are copied into the database, so the directory can be reused afterwards.
+    ///
+    /// The database should be freshly created: all column families in which the files
+    /// are ingested must be empty. The emptiness is checked for all column families before
+    /// ingesting any files.
+    ///
+    /// [`export_sst`]: #method.export_sst
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is opened in the read-only mode, if any of the target
+    /// column families is not empty, if the directory contains SST files with unexpected names,
+    /// or if ingestion fails.
+    pub fn ingest_sst(&self, dir: &Path) -> Result<SstStats> {
+        self.check_writable("ingest SST files into")?;
+        let files_by_cf = list_sst_files(dir)?;
+        if let Some(cf_name) = files_by_cf.keys().find(|name| name.as_str() == DB_METADATA) {
+            return Err(sst_error(
+                INGEST,
+                dir,
+                format!("column family `{}` cannot be ingested", cf_name),
+            ));
+        }
+
+        {
+            let db = self.get_lock_guard();
+            for cf_name in files_by_cf.keys() {
+                let is_empty = db.cf_handle(cf_name).map_or(true, |cf| {
+                    db.iterator_cf(cf, IteratorMode::Start).next().is_none()
+                });
+                if !is_empty {
+                    return Err(sst_error(
+                        INGEST,
+                        dir,
+                        format!("column family `{}` in the database is not empty", cf_name),
+                    ));
+                }
+            }
+        }
+
+        let mut stats = SstStats::default();
+        let mut ingest_opts = IngestExternalFileOptions::default();
+        ingest_opts.set_move_files(false);
+        for (cf_name, files) in &files_by_cf {
+            if !self.cf_exists(cf_name) {
+                self.create_cf(cf_name)?;
+            }
+            let db = self.get_lock_guard();
+            let cf = db.cf_handle(cf_name).unwrap();
+            db.ingest_external_file_cf_opts(cf, &ingest_opts, files.clone())?;
+
+            stats.column_families += 1;
+            stats.files += files.len();
+            stats.size += files
+                .iter()
+                .filter_map(|path| fs::metadata(path).ok())
+                .map(|metadata| metadata.len())
+                .sum::<u64>();
+            log::info!(
+                "Ingested {} SST file(s) into column family `{}`",
+                files.len(),
+                cf_name
+            );
+        }
+        Ok(stats)
+    }
+}
+
+#[test]
+fn sst_file_names() {
+    let name = sst_file_name(12, "token.wallets-history");
+    assert_eq!(name, "00000012-token.wallets-history.sst");
+    assert_eq!(
+        parse_sst_file_name(&name),
+        Some((12, "token.wallets-history"))
+    );
+    assert_eq!(parse_sst_file_name("00000001-.sst"), None);
+    assert_eq!(parse_sst_file_name("00000001.sst"), None);
+    assert_eq!(parse_sst_file_name("abc-def.sst"), None);
+    assert_eq!(parse_sst_file_name("00000001-def.txt"), None);
+}
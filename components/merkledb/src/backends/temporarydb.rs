@@ -18,23 +18,53 @@ use crossbeam::sync::ShardedLock;
 use smallvec::SmallVec;
 use std::{
     collections::{btree_map::Range, BTreeMap, HashMap},
+    env,
     iter::{Iterator, Peekable},
+    path::Path,
     sync::Arc,
 };
+use tempfile::TempDir;
 
 use crate::{
     backends::rocksdb::{next_id_bytes, ID_SIZE},
-    db::{check_database, Change, Iterator as DbIterator},
-    Database, Iter, Patch, ResolvedAddress, Result, Snapshot,
+    db::{
+        check_database, check_droppable_column_family, unused_column_families, Change,
+        Iterator as DbIterator, DEFAULT_COLUMN_FAMILY,
+    },
+    BackupInfo, ColumnFamilyIntegrity, Database, DbOptions, DbPreset, DbStats, IntegrityReport,
+    Iter, MergeHandle, Patch, ResolvedAddress, Result, RocksDB, Snapshot, SstStats,
 };
 
 type MemoryDB = HashMap<ResolvedAddress, BTreeMap<Vec<u8>, Vec<u8>>>;
 
-/// This in-memory database is only used for testing and experimenting; is not designed to
+/// Prefix of the names of temporary directories created for `TemporaryDB`s backed
+/// by `RocksDB`.
+const TEMP_DIR_PREFIX: &str = "exonum-temporarydb-";
+
+/// This database is only used for testing and experimenting; is not designed to
 /// operate under load in production.
+///
+/// The database created with [`new`] is stored in memory. The database created with
+/// [`with_options`] is a `RocksDB` instance in a temporary directory, which allows to test
+/// the code against the specific database options. In both cases, all data is removed
+/// when the database is dropped.
+///
+/// [`new`]: #method.new
+/// [`with_options`]: #method.with_options
 #[derive(Debug)]
 pub struct TemporaryDB {
-    inner: Arc<ShardedLock<MemoryDB>>,
+    inner: Inner,
+}
+
+#[derive(Debug)]
+enum Inner {
+    Memory(Arc<ShardedLock<MemoryDB>>),
+    Disk {
+        // The database must be dropped before the directory it is stored in,
+        // so the order of the fields is important.
+        db: RocksDB,
+        dir: TempDir,
+    },
 }
 
 struct TemporarySnapshot {
@@ -52,40 +82,113 @@ impl TemporaryDB {
     pub fn new() -> Self {
         let mut db = HashMap::new();
 
-        db.insert(ResolvedAddress::system("default"), BTreeMap::new());
-        let inner = Arc::new(ShardedLock::new(db));
+        db.insert(
+            ResolvedAddress::system(DEFAULT_COLUMN_FAMILY),
+            BTreeMap::new(),
+        );
+        let inner = Inner::Memory(Arc::new(ShardedLock::new(db)));
         let mut db = Self { inner };
         check_database(&mut db).unwrap();
         db
     }
 
-    /// Clears the contents of the database.
-    pub fn clear(&self) -> crate::Result<()> {
-        let mut rw_lock = self.inner.write().expect("Couldn't get read-write lock");
+    /// Creates a new, empty `RocksDB` database with the specified options in a temporary
+    /// directory. The directory is removed when the database is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the options are invalid, or if the database cannot be opened
+    /// with them (e.g., direct I/O is not supported by the filesystem of the temporary
+    /// directory). The temporary directory is removed in this case as well.
+    pub fn with_options(options: &DbOptions) -> Result<Self> {
+        Self::with_options_in(&env::temp_dir(), options)
+    }
 
-        for collection in rw_lock.values_mut() {
-            collection.clear();
-        }
+    fn with_options_in(parent: &Path, options: &DbOptions) -> Result<Self> {
+        options.validate()?;
+        let dir = tempfile::Builder::new()
+            .prefix(TEMP_DIR_PREFIX)
+            .tempdir_in(parent)
+            .map_err(|e| {
+                crate::Error::new(format!(
+                    "Cannot create temporary directory for the database: {}",
+                    e
+                ))
+            })?;
+        let options = DbOptions {
+            create_if_missing: true,
+            ..options.clone()
+        };
+        // If opening fails, `dir` is dropped here, which removes the directory.
+        let db = RocksDB::open(dir.path(), &options)?;
+        Ok(Self {
+            inner: Inner::Disk { db, dir },
+        })
+    }
 
+    /// Calls `test` for databases opened with the options of each of the named presets
+    /// (see [`DbPreset`]) in turn. The database is dropped after the call, so that
+    /// its temporary directory is removed before the next preset is tested.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a database cannot be opened with the options of a preset;
+    /// the remaining presets are not tested in this case.
+    ///
+    /// [`DbPreset`]: enum.DbPreset.html
+    pub fn for_each_preset<F>(mut test: F) -> Result<()>
+    where
+        F: FnMut(DbPreset, Self),
+    {
+        for &preset in DbPreset::ALL {
+            let db = Self::with_options(&preset.options()).map_err(|e| {
+                crate::Error::with_kind(
+                    e.kind(),
+                    format!("Cannot open database with `{:?}` preset: {}", preset, e),
+                )
+            })?;
+            test(preset, db);
+        }
         Ok(())
     }
 
-    fn temporary_snapshot(&self) -> TemporarySnapshot {
-        TemporarySnapshot {
-            snapshot: self.inner.read().expect("Couldn't get read lock").clone(),
+    /// Returns the path to the temporary directory of the database, or `None` if the database
+    /// is stored in memory.
+    pub fn path(&self) -> Option<&Path> {
+        match self.inner {
+            Inner::Memory(_) => None,
+            Inner::Disk { ref dir, .. } => Some(dir.path()),
         }
     }
-}
 
-impl Database for TemporaryDB {
-    fn snapshot(&self) -> Box<dyn Snapshot> {
-        Box::new(self.temporary_snapshot())
+    /// Clears the contents of the database.
+    pub fn clear(&self) -> crate::Result<()> {
+        match self.inner {
+            Inner::Memory(ref inner) => {
+                let mut rw_lock = inner.write().expect("Couldn't get read-write lock");
+                for collection in rw_lock.values_mut() {
+                    collection.clear();
+                }
+                Ok(())
+            }
+            Inner::Disk { ref db, .. } => db.clear(),
+        }
     }
 
-    fn merge(&self, patch: Patch) -> Result<()> {
-        let mut inner = self.inner.write().expect("Couldn't get write lock");
+    fn temporary_snapshot(inner: &ShardedLock<MemoryDB>) -> TemporarySnapshot {
+        TemporarySnapshot {
+            snapshot: inner.read().expect("Couldn't get read lock").clone(),
+        }
+    }
+
+    fn merge_into_memory(inner: &ShardedLock<MemoryDB>, patch: Patch) {
+        let mut inner = inner.write().expect("Couldn't get write lock");
         for (resolved, changes) in patch.into_changes() {
             if !inner.contains_key(&resolved) {
+                // Collections are created lazily on the first write to them.
+                if changes.is_empty() {
+                    continue;
+                }
                 inner.insert(resolved.clone(), BTreeMap::new());
             }
 
@@ -130,11 +233,176 @@ impl Database for TemporaryDB {
                 }
             }
         }
-        Ok(())
+    }
+}
+
+impl Database for TemporaryDB {
+    fn snapshot(&self) -> Box<dyn Snapshot> {
+        match self.inner {
+            Inner::Memory(ref inner) => Box::new(Self::temporary_snapshot(inner)),
+            Inner::Disk { ref db, .. } => db.snapshot(),
+        }
+    }
+
+    fn merge(&self, patch: Patch) -> Result<()> {
+        match self.inner {
+            Inner::Memory(ref inner) => {
+                Self::merge_into_memory(inner, patch);
+                Ok(())
+            }
+            Inner::Disk { ref db, .. } => db.merge(patch),
+        }
     }
 
     fn merge_sync(&self, patch: Patch) -> Result<()> {
-        self.merge(patch)
+        match self.inner {
+            Inner::Memory(_) => self.merge(patch),
+            Inner::Disk { ref db, .. } => db.merge_sync(patch),
+        }
+    }
+
+    fn merge_async(&self, patch: Patch) -> MergeHandle {
+        match self.inner {
+            Inner::Memory(_) => MergeHandle::ready(self.merge(patch)),
+            Inner::Disk { ref db, .. } => db.merge_async(patch),
+        }
+    }
+
+    fn merge_in_batches(&self, patch: Patch) -> Result<()> {
+        match self.inner {
+            Inner::Memory(_) => self.merge_sync(patch),
+            Inner::Disk { ref db, .. } => db.merge_in_batches(patch),
+        }
+    }
+
+    fn stats(&self) -> DbStats {
+        match self.inner {
+            Inner::Memory(_) => DbStats::default(),
+            Inner::Disk { ref db, .. } => db.stats(),
+        }
+    }
+
+    fn create_checkpoint(&self, path: &Path) -> Result<()> {
+        match self.inner {
+            Inner::Memory(_) => Err(crate::Error::new(format!(
+                "Cannot create checkpoint at {}: checkpoints are not supported \
+                 by the in-memory database",
+                path.display()
+            ))),
+            Inner::Disk { ref db, .. } => Database::create_checkpoint(db, path),
+        }
+    }
+
+    fn backup(&self, backup_dir: &Path) -> Result<BackupInfo> {
+        match self.inner {
+            Inner::Memory(_) => Err(crate::Error::new(format!(
+                "Cannot create backup in {}: backups are not supported \
+                 by the in-memory database",
+                backup_dir.display()
+            ))),
+            Inner::Disk { ref db, .. } => Database::backup(db, backup_dir),
+        }
+    }
+
+    fn export_sst(&self, dir: &Path) -> Result<SstStats> {
+        match self.inner {
+            Inner::Memory(_) => Err(crate::Error::new(format!(
+                "Cannot export SST files to {}: SST files are not supported \
+                 by the in-memory database",
+                dir.display()
+            ))),
+            Inner::Disk { ref db, .. } => Database::export_sst(db, dir),
+        }
+    }
+
+    fn ingest_sst(&self, dir: &Path) -> Result<SstStats> {
+        match self.inner {
+            Inner::Memory(_) => Err(crate::Error::new(format!(
+                "Cannot ingest SST files from {}: SST files are not supported \
+                 by the in-memory database",
+                dir.display()
+            ))),
+            Inner::Disk { ref db, .. } => Database::ingest_sst(db, dir),
+        }
+    }
+
+    fn compact(&self) -> Result<()> {
+        match self.inner {
+            Inner::Memory(_) => Ok(()),
+            Inner::Disk { ref db, .. } => Database::compact(db),
+        }
+    }
+
+    fn flush(&self) -> Result<()> {
+        match self.inner {
+            Inner::Memory(_) => Ok(()),
+            Inner::Disk { ref db, .. } => db.flush(),
+        }
+    }
+
+    fn approximate_size(&self, name_prefix: &str) -> u64 {
+        match self.inner {
+            Inner::Memory(_) => 0,
+            Inner::Disk { ref db, .. } => Database::approximate_size(db, name_prefix),
+        }
+    }
+
+    fn verify_integrity(&self) -> Result<IntegrityReport> {
+        match self.inner {
+            Inner::Memory(ref inner) => {
+                let inner = inner.read().expect("Couldn't get read lock");
+                let mut column_families: BTreeMap<_, ColumnFamilyIntegrity> = BTreeMap::new();
+                for (resolved, collection) in inner.iter() {
+                    let cf_report =
+                        column_families
+                            .entry(resolved.name.clone())
+                            .or_insert_with(|| ColumnFamilyIntegrity {
+                                name: resolved.name.clone(),
+                                ..ColumnFamilyIntegrity::default()
+                            });
+                    cf_report.keys += collection.len() as u64;
+                    cf_report.bytes += collection
+                        .iter()
+                        .map(|(key, value)| (key.len() + value.len()) as u64)
+                        .sum::<u64>();
+                }
+                Ok(IntegrityReport {
+                    column_families: column_families.into_iter().map(|(_, cf)| cf).collect(),
+                    errors: vec![],
+                })
+            }
+            Inner::Disk { ref db, .. } => db.verify_integrity(),
+        }
+    }
+
+    fn drop_column_family(&self, name: &str) -> Result<()> {
+        match self.inner {
+            Inner::Memory(ref inner) => {
+                let mut inner = inner.write().expect("Couldn't get write lock");
+                let exists = inner.keys().any(|resolved| resolved.name == name);
+                check_droppable_column_family(name, exists)?;
+                inner.retain(|resolved, _| resolved.name != name);
+                Ok(())
+            }
+            Inner::Disk { ref db, .. } => db.drop_column_family(name),
+        }
+    }
+
+    fn drop_unused_column_families(&self, keep: &[&str]) -> Result<Vec<String>> {
+        match self.inner {
+            Inner::Memory(ref inner) => {
+                let snapshot = Self::temporary_snapshot(inner);
+                let names = snapshot
+                    .snapshot
+                    .keys()
+                    .map(|resolved| resolved.name.clone());
+                let unused = unused_column_families(&snapshot, names, keep)?;
+                let mut inner = inner.write().expect("Couldn't get write lock");
+                inner.retain(|resolved, _| !unused.contains(&resolved.name));
+                Ok(unused)
+            }
+            Inner::Disk { ref db, .. } => db.drop_unused_column_families(keep),
+        }
     }
 }
 
@@ -192,10 +460,20 @@ impl Snapshot for TemporarySnapshot {
         let collection = self
             .snapshot
             .get(name)
-            .or_else(|| self.snapshot.get(&ResolvedAddress::system("default")))
+            .or_else(|| {
+                self.snapshot
+                    .get(&ResolvedAddress::system(DEFAULT_COLUMN_FAMILY))
+            })
             .unwrap();
         let from = name.keyed(from).into_owned();
-        let iter = collection.range::<Vec<u8>, _>(&from..);
+        let iter = match name.id_to_bytes() {
+            // Keys of the index are bounded by the ID of the next index in the collection.
+            Some(id_bytes) => {
+                let upper_bound = next_id_bytes(id_bytes).to_vec();
+                collection.range::<Vec<u8>, _>(&from..&upper_bound)
+            }
+            None => collection.range::<Vec<u8>, _>(&from..),
+        };
 
         Box::new(TemporaryDBIterator {
             iter: iter.peekable(),
@@ -245,3 +523,25 @@ fn clearing_database() {
     assert_eq!(list.len(), 3);
     assert_eq!(list.iter().collect::<Vec<_>>(), vec![4, 5, 6]);
 }
+
+#[test]
+fn temporary_directory_is_removed_on_failed_open() {
+    let parent = TempDir::new().unwrap();
+    // Opening fails after the temporary directory is created, since the WAL directory
+    // cannot be created inside a regular file.
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let options = DbOptions {
+        wal_dir: Some(file.path().join("wal")),
+        ..DbOptions::default()
+    };
+    TemporaryDB::with_options_in(parent.path(), &options).unwrap_err();
+    let mut entries = std::fs::read_dir(parent.path()).unwrap();
+    assert!(entries.next().is_none());
+
+    let db = TemporaryDB::with_options_in(parent.path(), &DbOptions::default()).unwrap();
+    let path = db.path().unwrap().to_owned();
+    assert!(path.starts_with(parent.path()));
+    assert!(path.join("CURRENT").is_file());
+    drop(db);
+    assert!(!path.exists());
+}
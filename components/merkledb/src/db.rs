@@ -16,20 +16,24 @@ use std::{
     cell::RefCell,
     collections::{BTreeMap, HashMap, HashSet},
     fmt,
+    io::{Read, Write},
     iter::{Iterator as StdIterator, Peekable},
     marker::PhantomData,
     mem,
     ops::{Bound, Deref, DerefMut},
+    path::Path,
     rc::Rc,
     result::Result as StdResult,
+    sync::atomic::{AtomicU64, Ordering},
 };
 
 use crate::{
+    export::{self, ExportStats},
     validation::assert_valid_name_component,
     views::{
         get_object_hash, AsReadonly, ChangesIter, IndexesPool, RawAccess, ResolvedAddress, View,
     },
-    Error, Result, SystemSchema,
+    BackupInfo, Error, IntegrityReport, MergeHandle, Result, SstStats, SystemSchema,
 };
 
 /// Changes related to a specific `View`.
@@ -68,6 +72,11 @@ impl ViewChanges {
         self.namespace = namespace;
     }
 
+    /// Returns `true` if there are no changes to data within the view.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
     pub(crate) fn into_data(self) -> BTreeMap<Vec<u8>, Change> {
         self.data
     }
@@ -338,6 +347,22 @@ pub enum Change {
 /// # assert_eq!(list.iter().collect::<Vec<_>>(), vec![1, 2]);
 /// ```
 ///
+/// For finer-grained control, [`savepoint`] and [`rollback_to`] allow to discard changes
+/// made after a certain point, while keeping earlier changes:
+///
+/// ```
+/// # use exonum_merkledb::{access::CopyAccessExt, Database, TemporaryDB};
+/// let db = TemporaryDB::new();
+/// let mut fork = db.fork();
+/// fork.get_list("list").push(1_u32);
+/// let savepoint = fork.savepoint();
+/// fork.get_list("list").push(2_u32);
+/// fork.get_map("map").put(&1_u8, 2_u8);
+/// fork.rollback_to(savepoint);
+/// assert_eq!(fork.get_list::<_, u32>("list").len(), 1);
+/// assert!(fork.get_map::<_, u8, u8>("map").is_empty());
+/// ```
+///
 /// In order to convert a fork into `&dyn Snapshot` presentation, convert it into a `Patch`
 /// and use a reference to it (`Patch` implements `Snapshot`). Using `<Fork as RawAccess>::snapshot`
 /// for this purpose is logically incorrect and may lead to hard-to-debug errors.
@@ -388,12 +413,40 @@ pub enum Change {
 /// [`commit`]: #method.commit
 /// [`flush`]: #method.flush
 /// [`rollback`]: #method.rollback
+/// [`savepoint`]: #method.savepoint
+/// [`rollback_to`]: #method.rollback_to
 /// [`readonly`]: #method.readonly
 /// [`RefCell::borrow_mut()`]: https://doc.rust-lang.org/std/cell/struct.RefCell.html#method.borrow_mut
 #[derive(Debug)]
 pub struct Fork {
     patch: Patch,
     working_patch: WorkingPatch,
+    /// Active savepoints, from the outermost to the innermost one.
+    savepoints: Vec<Savepoint>,
+}
+
+/// Identifier of a savepoint within a `Fork`. See [`Fork::savepoint`] for details.
+///
+/// [`Fork::savepoint`]: struct.Fork.html#method.savepoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SavepointId(u64);
+
+impl SavepointId {
+    fn next() -> Self {
+        // Identifiers are unique across all forks, so that a savepoint from another fork
+        // is never mistaken for a local one.
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// State of the fork changes at the moment of a savepoint creation.
+#[derive(Debug)]
+struct Savepoint {
+    id: SavepointId,
+    changes: HashMap<ResolvedAddress, ViewChanges>,
+    changed_aggregated_addrs: HashMap<ResolvedAddress, String>,
+    removed_aggregated_addrs: HashSet<String>,
 }
 
 /// A set of changes that can be atomically applied to a `Database`.
@@ -545,6 +598,7 @@ pub trait Database: Send + Sync + 'static {
                 removed_aggregated_addrs: HashSet::new(),
             },
             working_patch: WorkingPatch::new(),
+            savepoints: vec![],
         }
     }
 
@@ -583,6 +637,261 @@ pub trait Database: Send + Sync + 'static {
     /// will be returned. In case of an error, the method guarantees no changes are applied to
     /// the database.
     fn merge_sync(&self, patch: Patch) -> Result<()>;
+
+    /// Applies a patch to the database with fsync, possibly splitting it into several
+    /// sequentially written batches to limit memory usage. For `RocksDB`, the maximum batch
+    /// size is set with the `max_batch_bytes` option; if it is not set, this method is
+    /// equivalent to [`merge_sync`](#tymethod.merge_sync).
+    ///
+    /// # Atomicity
+    ///
+    /// Unlike `merge` and `merge_sync`, this method is **not** atomic: if an error occurs
+    /// or the process crashes during the merge, only a part of the patch may be applied.
+    /// Thus, the method should only be used in contexts where partial application is
+    /// recoverable, e.g., when restoring the blockchain state during initial synchronization,
+    /// which can be restarted from scratch. It must never be used for committing blocks.
+    ///
+    /// The default implementation calls `merge_sync`.
+    fn merge_in_batches(&self, patch: Patch) -> Result<()> {
+        self.merge_sync(patch)
+    }
+
+    /// Applies a patch to the database in the background, returning a handle which can be used
+    /// to check whether the merge is finished and to obtain its result. Patches submitted with
+    /// this method are applied strictly in the order of their submission, so that snapshots
+    /// always observe a prefix of the submitted patches.
+    ///
+    /// Changes of the patch are guaranteed to be visible in snapshots only after
+    /// [`MergeHandle::wait`] returns. If a background merge fails, the database is poisoned:
+    /// patches submitted after the failed one are not applied, and all subsequent merges
+    /// (including synchronous ones) return an error until the error is acknowledged
+    /// by calling `wait()` on the handle of the failed merge or by dropping the handle.
+    ///
+    /// Patches merged synchronously with `merge` or `merge_sync` are not ordered with respect
+    /// to pending background merges; wait for the pending merges first if the order matters.
+    ///
+    /// The default implementation merges the patch synchronously with `merge`.
+    ///
+    /// [`MergeHandle::wait`]: struct.MergeHandle.html#method.wait
+    fn merge_async(&self, patch: Patch) -> MergeHandle {
+        MergeHandle::ready(self.merge(patch))
+    }
+
+    /// Returns statistics of the database. Backends which do not collect statistics
+    /// (e.g., `TemporaryDB`) return empty statistics; this is the default implementation.
+    fn stats(&self) -> DbStats {
+        DbStats::default()
+    }
+
+    /// Creates a consistent checkpoint of the database in the specified directory, which
+    /// can be archived or opened as a separate database. The checkpoint may be created
+    /// while patches are being merged into the database.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend does not support checkpoints (this is the default
+    /// implementation; e.g., in-memory `TemporaryDB` does not support them), or if
+    /// the checkpoint cannot be created, e.g., because the directory is not empty.
+    fn create_checkpoint(&self, path: &Path) -> Result<()> {
+        Err(Error::new(format!(
+            "Cannot create checkpoint at {}: checkpoints are not supported by the database",
+            path.display()
+        )))
+    }
+
+    /// Creates a new incremental backup of the database in the specified backup directory.
+    /// Backups can be managed with [`list_backups`] and [`purge_old_backups`] and restored
+    /// with [`restore_from_backup`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend does not support backups (this is the default
+    /// implementation; e.g., in-memory `TemporaryDB` does not support them), or if
+    /// the backup cannot be created.
+    ///
+    /// [`list_backups`]: fn.list_backups.html
+    /// [`purge_old_backups`]: fn.purge_old_backups.html
+    /// [`restore_from_backup`]: fn.restore_from_backup.html
+    fn backup(&self, backup_dir: &Path) -> Result<BackupInfo> {
+        Err(Error::new(format!(
+            "Cannot create backup in {}: backups are not supported by the database",
+            backup_dir.display()
+        )))
+    }
+
+    /// Catches up with the changes made by the primary instance of the database, if this
+    /// database is a secondary instance following the primary one (see
+    /// [`RocksDB::open_secondary`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is not a secondary instance (this is the default
+    /// implementation), or if catching up fails.
+    ///
+    /// [`RocksDB::open_secondary`]: struct.RocksDB.html#method.open_secondary
+    fn try_catch_up_with_primary(&self) -> Result<()> {
+        Err(Error::new(
+            "Cannot catch up with primary: the database is not a secondary instance",
+        ))
+    }
+
+    /// Compacts the database, reclaiming disk space occupied by deleted and overwritten
+    /// entries. The call blocks until the compaction is finished.
+    ///
+    /// The default implementation does nothing, which is appropriate for in-memory
+    /// databases such as `TemporaryDB`.
+    fn compact(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Flushes all data buffered in memory (e.g., `RocksDB` memtables) to persistent storage,
+    /// waiting for the flush to complete. The flush may be performed concurrently with merging
+    /// patches.
+    ///
+    /// The default implementation does nothing, which is appropriate for in-memory
+    /// databases such as `TemporaryDB`.
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Returns the approximate on-disk size in bytes of indexes with names starting with
+    /// `name_prefix`. Returns 0 if there are no matching indexes.
+    ///
+    /// The default implementation always returns 0, which is appropriate for in-memory
+    /// databases such as `TemporaryDB`.
+    fn approximate_size(&self, name_prefix: &str) -> u64 {
+        let _ = name_prefix;
+        0
+    }
+
+    /// Returns the approximate on-disk size of the database in bytes.
+    fn total_size(&self) -> u64 {
+        self.approximate_size("")
+    }
+
+    /// Exports all data from a consistent snapshot of the database into `writer`.
+    ///
+    /// The data is streamed in a simple length-prefixed binary format with a versioned header,
+    /// a section per index and a trailing checksum. The format does not depend on the database
+    /// backend, so the export can be imported into any database with `import_snapshot`.
+    fn export_snapshot(&self, writer: &mut dyn Write) -> Result<ExportStats> {
+        export::export_snapshot(self.snapshot().as_ref(), writer)
+    }
+
+    /// Exports data of all column families from a consistent snapshot of the database
+    /// into sorted SST files in the specified directory. Unlike `export_snapshot`, the export
+    /// is specific to the backend; it can be loaded into a fresh database with `ingest_sst`
+    /// considerably faster than merging the data.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory exists and is not empty, if the files cannot
+    /// be written, or if the backend does not support SST files (this is the default
+    /// implementation).
+    fn export_sst(&self, dir: &Path) -> Result<SstStats> {
+        Err(Error::new(format!(
+            "Cannot export SST files to {}: SST files are not supported by the database",
+            dir.display()
+        )))
+    }
+
+    /// Ingests SST files created by `export_sst` from the specified directory. All column
+    /// families, into which the files are ingested, must be empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the target column families is not empty, if the files
+    /// cannot be ingested, or if the backend does not support SST files (this is the default
+    /// implementation).
+    fn ingest_sst(&self, dir: &Path) -> Result<SstStats> {
+        Err(Error::new(format!(
+            "Cannot ingest SST files from {}: SST files are not supported by the database",
+            dir.display()
+        )))
+    }
+
+    /// Reads all data in the database, verifying its physical integrity (e.g., checksums
+    /// of data blocks). Errors are collected into the returned report rather than aborting
+    /// the verification.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the verification cannot be started, or if the backend does not
+    /// support it (this is the default implementation).
+    fn verify_integrity(&self) -> Result<IntegrityReport> {
+        Err(Error::new(
+            "Cannot verify integrity: verification is not supported by the database",
+        ))
+    }
+
+    /// Drops the column family with the specified name, removing all data stored in it.
+    /// Index metadata is not affected, so indexes stored in the column family are seen
+    /// as empty afterwards. The column family is created anew on the first write to it.
+    ///
+    /// This is a maintenance operation; it must not be called concurrently with merging
+    /// patches that affect the column family.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the column family does not exist, if it is used by the database
+    /// itself (e.g., to store index metadata), or if the backend does not support dropping
+    /// column families (this is the default implementation).
+    fn drop_column_family(&self, name: &str) -> Result<()> {
+        Err(Error::new(format!(
+            "Cannot drop column family `{}`: dropping column families is not supported \
+             by the database",
+            name
+        )))
+    }
+
+    /// Drops all column families which are not used by the database itself and are not
+    /// listed in `keep`, e.g., ones left over from obsolete data layouts. Returns names
+    /// of the dropped column families.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error without dropping anything if any of the column families to be dropped
+    /// contains live indexes, i.e., ones present in the index metadata. Also returns an error
+    /// if the backend does not support dropping column families (this is the default
+    /// implementation).
+    fn drop_unused_column_families(&self, keep: &[&str]) -> Result<Vec<String>> {
+        let _ = keep;
+        Err(Error::new(
+            "Cannot drop unused column families: dropping column families is not supported \
+             by the database",
+        ))
+    }
+
+    /// Imports data exported with `export_snapshot` from `reader`. The data is streamed
+    /// into the database in batches.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is not empty, or if the imported data is malformed
+    /// or its checksum does not match. In the latter case, the partially imported data
+    /// is removed from the database.
+    fn import_snapshot(&self, reader: &mut dyn Read) -> Result<ExportStats> {
+        export::import_snapshot(self, reader)
+    }
+}
+
+/// Statistics of a database, aggregated across all column families.
+///
+/// Each statistic is `None` if it is not supported by the database backend.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DbStats {
+    /// Estimated number of keys in the database.
+    pub estimated_num_keys: Option<u64>,
+    /// Approximate size of active and unflushed immutable memtables in bytes.
+    pub memtables_size: Option<u64>,
+    /// Estimated size of the live data in bytes.
+    pub estimated_live_data_size: Option<u64>,
+    /// Number of currently running compactions.
+    pub running_compactions: Option<u64>,
+    /// Estimated number of bytes which compactions need to rewrite to bring all levels
+    /// of the LSM tree under their target sizes (i.e., the compaction backlog).
+    pub pending_compaction_bytes: Option<u64>,
 }
 
 /// Extension trait for `Database`.
@@ -697,6 +1006,15 @@ pub trait Snapshot: Send + Sync + 'static {
         self.get(name, key).is_some()
     }
 
+    /// Returns values corresponding to the specified address and keys. The values are returned
+    /// in the same order as `keys`; a `None` is returned for each missing key.
+    ///
+    /// The default implementation calls [`get`](#tymethod.get) for each key. Backends may
+    /// override it to look up all keys in a single batch.
+    fn multi_get(&self, name: &ResolvedAddress, keys: &[&[u8]]) -> Vec<Option<Vec<u8>>> {
+        keys.iter().map(|key| self.get(name, key)).collect()
+    }
+
     /// Returns an iterator over the entries of the snapshot in ascending order starting from
     /// the specified key. The iterator element type is `(&[u8], &[u8])`.
     fn iter(&self, name: &ResolvedAddress, from: &[u8]) -> Iter<'_>;
@@ -714,6 +1032,20 @@ pub trait Iterator {
 }
 
 impl Patch {
+    /// Creates a patch with raw changes to the views. Unlike patches obtained from a `Fork`,
+    /// such a patch does not update index metadata or the state aggregator.
+    pub(crate) fn from_raw_changes(
+        snapshot: Box<dyn Snapshot>,
+        changes: HashMap<ResolvedAddress, ViewChanges>,
+    ) -> Self {
+        Self {
+            snapshot,
+            changes,
+            changed_aggregated_addrs: HashMap::new(),
+            removed_aggregated_addrs: HashSet::new(),
+        }
+    }
+
     /// Iterates over changes in this patch.
     pub(crate) fn into_changes(self) -> HashMap<ResolvedAddress, ViewChanges> {
         self.changes
@@ -737,6 +1069,37 @@ impl Snapshot for Patch {
             .unwrap_or_else(|()| self.snapshot.contains(name, key))
     }
 
+    fn multi_get(&self, name: &ResolvedAddress, keys: &[&[u8]]) -> Vec<Option<Vec<u8>>> {
+        let changes = match self.changes.get(name) {
+            Some(changes) => changes,
+            None => return self.snapshot.multi_get(name, keys),
+        };
+
+        // Values determined by the changes are filled in immediately; the remaining keys
+        // are looked up in the snapshot in a single batch.
+        let mut values = Vec::with_capacity(keys.len());
+        let mut snapshot_keys = vec![];
+        let mut snapshot_positions = vec![];
+        for (i, &key) in keys.iter().enumerate() {
+            match changes.get(key) {
+                Ok(value) => values.push(value),
+                Err(()) => {
+                    values.push(None);
+                    snapshot_keys.push(key);
+                    snapshot_positions.push(i);
+                }
+            }
+        }
+
+        if !snapshot_keys.is_empty() {
+            let snapshot_values = self.snapshot.multi_get(name, &snapshot_keys);
+            for (pos, value) in snapshot_positions.into_iter().zip(snapshot_values) {
+                values[pos] = value;
+            }
+        }
+        values
+    }
+
     fn iter(&self, name: &ResolvedAddress, from: &[u8]) -> Iter<'_> {
         let maybe_changes = self.changes.get(name);
         let changes_iter = maybe_changes.map(|changes| {
@@ -816,6 +1179,51 @@ impl Fork {
         self.working_patch = WorkingPatch::new();
     }
 
+    /// Creates a savepoint capturing the current state of changes in the fork. All changes made
+    /// after the savepoint (including puts, removals, clearing and creation of indexes) can be
+    /// discarded with [`rollback_to`], while keeping earlier changes.
+    ///
+    /// Savepoints can be nested. Creating a savepoint flushes the fork, so changes made before
+    /// the savepoint cannot be discarded with [`rollback`]. The savepoint copies all changes
+    /// in the fork, so its cost is proportional to the size of these changes.
+    ///
+    /// [`rollback_to`]: #method.rollback_to
+    /// [`rollback`]: #method.rollback
+    pub fn savepoint(&mut self) -> SavepointId {
+        self.flush();
+        let id = SavepointId::next();
+        self.savepoints.push(Savepoint {
+            id,
+            changes: self.patch.changes.clone(),
+            changed_aggregated_addrs: self.patch.changed_aggregated_addrs.clone(),
+            removed_aggregated_addrs: self.patch.removed_aggregated_addrs.clone(),
+        });
+        id
+    }
+
+    /// Discards all changes made after the specified savepoint, including the changes which
+    /// were not flushed. The savepoint remains valid, so it is possible to roll back to it
+    /// again; savepoints created after it are invalidated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the savepoint was created for another fork or was invalidated by rolling back
+    /// to an outer savepoint.
+    pub fn rollback_to(&mut self, savepoint: SavepointId) {
+        let pos = self
+            .savepoints
+            .iter()
+            .position(|sp| sp.id == savepoint)
+            .unwrap_or_else(|| panic!("Savepoint {:?} is invalid", savepoint));
+        self.savepoints.truncate(pos + 1);
+        self.working_patch = WorkingPatch::new();
+
+        let savepoint = &self.savepoints[pos];
+        self.patch.changes = savepoint.changes.clone();
+        self.patch.changed_aggregated_addrs = savepoint.changed_aggregated_addrs.clone();
+        self.patch.removed_aggregated_addrs = savepoint.removed_aggregated_addrs.clone();
+    }
+
     /// Rolls back the migration with the specified name. This will remove all indexes
     /// within the migration.
     pub(crate) fn rollback_migration(&mut self, prefix: &str) {
@@ -872,6 +1280,7 @@ impl From<Patch> for Fork {
         Self {
             patch,
             working_patch: WorkingPatch::new(),
+            savepoints: vec![],
         }
     }
 }
@@ -1055,6 +1464,10 @@ impl Snapshot for Box<dyn Snapshot> {
         self.as_ref().contains(name, key)
     }
 
+    fn multi_get(&self, name: &ResolvedAddress, keys: &[&[u8]]) -> Vec<Option<Vec<u8>>> {
+        self.as_ref().multi_get(name, keys)
+    }
+
     fn iter(&self, name: &ResolvedAddress, from: &[u8]) -> Iter<'_> {
         self.as_ref().iter(name, from)
     }
@@ -1204,6 +1617,73 @@ pub const DB_VERSION: u8 = 0;
 pub const DB_METADATA: &str = "__DB_METADATA__";
 /// Version attribute name.
 pub const VERSION_NAME: &str = "version";
+/// Attribute name for the length of the fixed key prefix used by prefix bloom filters.
+pub const PREFIX_EXTRACTOR_LEN_NAME: &str = "prefix_extractor_len";
+
+/// Name of the column family which always exists in the database.
+pub const DEFAULT_COLUMN_FAMILY: &str = "default";
+
+/// Checks whether the column family is used by the database itself rather than by indexes.
+pub(crate) fn is_system_column_family(name: &str) -> bool {
+    name == DEFAULT_COLUMN_FAMILY
+        || name == DB_METADATA
+        || name == IndexesPool::<&dyn Snapshot>::address().name
+}
+
+/// Checks that the column family can be dropped with `Database::drop_column_family`.
+pub(crate) fn check_droppable_column_family(name: &str, exists: bool) -> Result<()> {
+    if is_system_column_family(name) {
+        Err(Error::new(format!(
+            "Cannot drop column family `{}`: it is used by the database itself",
+            name
+        )))
+    } else if !exists {
+        Err(Error::new(format!(
+            "Cannot drop column family `{}`: it does not exist",
+            name
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Selects column families from `names` to be dropped by
+/// `Database::drop_unused_column_families`, i.e., ones which are neither system column
+/// families nor listed in `keep`. Returns an error if any of the selected column families
+/// contains indexes from the metadata pool in the `snapshot`.
+pub(crate) fn unused_column_families(
+    snapshot: &dyn Snapshot,
+    names: impl IntoIterator<Item = String>,
+    keep: &[&str],
+) -> Result<Vec<String>> {
+    let live_names: HashSet<_> = IndexesPool::new(snapshot)
+        .resolved_addresses()
+        .into_iter()
+        .map(|addr| addr.name)
+        .collect();
+
+    let mut unused: Vec<_> = names
+        .into_iter()
+        .filter(|name| !is_system_column_family(name) && !keep.contains(&name.as_str()))
+        .collect();
+    unused.sort();
+    unused.dedup();
+
+    let live: Vec<_> = unused
+        .iter()
+        .filter(|name| live_names.contains(name.as_str()))
+        .map(String::as_str)
+        .collect();
+    if live.is_empty() {
+        Ok(unused)
+    } else {
+        Err(Error::new(format!(
+            "Cannot drop unused column families: column families {} contain live indexes; \
+             add them to the list of kept column families or remove the indexes first",
+            live.join(", ")
+        )))
+    }
+}
 
 /// This function checks that the given database is compatible with the current `MerkleDB` version.
 pub fn check_database(db: &mut dyn Database) -> Result<()> {
@@ -1226,6 +1706,37 @@ pub fn check_database(db: &mut dyn Database) -> Result<()> {
     db.merge(fork.into_patch())
 }
 
+/// Checks that the prefix length of the prefix extractor matches the one recorded in
+/// the database. If `record` is set, the length is recorded if it has not been recorded yet;
+/// otherwise, e.g., for read-only databases, a missing length is not checked.
+pub(crate) fn check_prefix_extractor_len(
+    db: &dyn Database,
+    len: Option<usize>,
+    record: bool,
+) -> Result<()> {
+    let fork = db.fork();
+    {
+        let addr = ResolvedAddress::system(DB_METADATA);
+        let mut view = View::new(&fork, addr);
+        let len = len.map(|len| len as u64);
+        match (view.get::<_, u64>(PREFIX_EXTRACTOR_LEN_NAME), len) {
+            (Some(saved_len), _) if Some(saved_len) == len => return Ok(()),
+            (Some(saved_len), _) => {
+                return Err(Error::new(format!(
+                    "Prefix extractor length doesn't match: recorded in the database {}, \
+                     specified in options {:?}. Changing the prefix length of an existing \
+                     database is unsafe",
+                    saved_len, len
+                )));
+            }
+            (None, None) => return Ok(()),
+            (None, Some(_)) if !record => return Ok(()),
+            (None, Some(len)) => view.put(PREFIX_EXTRACTOR_LEN_NAME, len),
+        }
+    }
+    db.merge(fork.into_patch())
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
@@ -16,6 +16,8 @@
 
 use thiserror::Error;
 
+use crate::OptionsError;
+
 /// The error type for I/O operations with the `Database`.
 ///
 /// Application code in most cases should consider these errors as fatal. At the same time,
@@ -24,16 +26,46 @@ use thiserror::Error;
 #[derive(Debug, Clone, Error)]
 #[error("{}", message)]
 pub struct Error {
+    kind: ErrorKind,
     message: String,
 }
 
+/// Kind of a database error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A modifying operation was attempted on a database opened in the read-only mode.
+    ReadOnly,
+    /// The database is locked by another process, or is already opened within this process.
+    Locked,
+    /// Any other error.
+    Other,
+}
+
 impl Error {
     /// Creates a new storage error with an information message about the reason.
     pub fn new<T: Into<String>>(message: T) -> Self {
+        Self::with_kind(ErrorKind::Other, message)
+    }
+
+    /// Creates a new storage error of the specified kind.
+    pub fn with_kind<T: Into<String>>(kind: ErrorKind, message: T) -> Self {
         Self {
+            kind,
             message: message.into(),
         }
     }
+
+    /// Returns the kind of this error.
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+}
+
+impl From<OptionsError> for Error {
+    fn from(err: OptionsError) -> Self {
+        Self::new(format!("Invalid database options: {}", err))
+    }
 }
 
 impl From<rocksdb::Error> for Error {
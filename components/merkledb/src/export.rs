@@ -0,0 +1,345 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Export and import of database snapshots in a portable format.
+//!
+//! The format is a stream of the following items (all integers are little-endian):
+//!
+//! ```text
+//! header:  magic `EXNMDBEX` (8 bytes) | format version (u8) | `DB_VERSION` (u8)
+//! section: 0x01 | name length (u32) | name | index ID (u64, 0 for system views)
+//!          | entry* | 0x00
+//! entry:   0x01 | key length (u32) | key | value length (u32) | value
+//! trailer: 0x00 | SHA-256 hash of all preceding bytes (32 bytes)
+//! ```
+//!
+//! Each section corresponds to a single view of the database, i.e., to a single resolved
+//! address. The indexes pool is always exported first. Since keys are exported without
+//! the index ID prefix, the format does not depend on the database backend.
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use exonum_crypto::{Hash, HashStream, HASH_SIZE};
+
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+    mem,
+    num::NonZeroU64,
+};
+
+use crate::{
+    db::{Change, ViewChanges, DB_VERSION},
+    views::{IndexesPool, ResolvedAddress},
+    Database, Error, Patch, Result, Snapshot,
+};
+
+/// Magic bytes at the start of an exported snapshot.
+const MAGIC: &[u8; 8] = b"EXNMDBEX";
+/// Current version of the export format.
+const FORMAT_VERSION: u8 = 1;
+/// Tag starting a section or an entry.
+const TAG_ITEM: u8 = 1;
+/// Tag ending a section or the list of sections.
+const TAG_END: u8 = 0;
+/// Approximate total size of keys and values buffered in memory during import before
+/// they are merged into the database.
+const IMPORT_BATCH_SIZE: usize = 16 << 20;
+
+/// Statistics of an exported or imported snapshot.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ExportStats {
+    /// Number of views (indexes and system views) in the snapshot.
+    pub views: usize,
+    /// Total number of key-value pairs in the snapshot.
+    pub entries: u64,
+    /// Size of the exported data in bytes, including the header and the checksum.
+    pub size: u64,
+}
+
+fn io_error(err: io::Error) -> Error {
+    Error::new(format!(
+        "I/O error during snapshot export / import: {}",
+        err
+    ))
+}
+
+fn format_error(message: impl AsRef<str>) -> Error {
+    Error::new(format!("Invalid snapshot export: {}", message.as_ref()))
+}
+
+/// Writer wrapper hashing and counting all written bytes.
+struct HashingWriter<'a> {
+    inner: &'a mut dyn Write,
+    hasher: HashStream,
+    size: u64,
+}
+
+impl Write for HashingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher = mem::replace(&mut self.hasher, HashStream::new()).update(&buf[..written]);
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reader wrapper hashing and counting all read bytes.
+struct HashingReader<'a> {
+    inner: &'a mut dyn Read,
+    hasher: HashStream,
+    size: u64,
+}
+
+impl Read for HashingReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher = mem::replace(&mut self.hasher, HashStream::new()).update(&buf[..read]);
+        self.size += read as u64;
+        Ok(read)
+    }
+}
+
+fn write_bytes(writer: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    writer.write_u32::<LittleEndian>(bytes.len() as u32)?;
+    writer.write_all(bytes)
+}
+
+fn read_bytes(reader: &mut impl Read) -> Result<Vec<u8>> {
+    let len = u64::from(reader.read_u32::<LittleEndian>().map_err(io_error)?);
+    // Do not preallocate the buffer, since the length may be corrupted.
+    let mut bytes = vec![];
+    reader
+        .by_ref()
+        .take(len)
+        .read_to_end(&mut bytes)
+        .map_err(io_error)?;
+    if bytes.len() as u64 != len {
+        return Err(format_error("unexpected end of data"));
+    }
+    Ok(bytes)
+}
+
+fn read_tag(reader: &mut impl Read) -> Result<bool> {
+    match reader.read_u8().map_err(io_error)? {
+        TAG_ITEM => Ok(true),
+        TAG_END => Ok(false),
+        tag => Err(format_error(format!("unknown tag {}", tag))),
+    }
+}
+
+/// Returns addresses of all views in the snapshot, starting from the indexes pool.
+fn view_addresses(snapshot: &dyn Snapshot) -> Vec<ResolvedAddress> {
+    let mut addresses = vec![IndexesPool::<&dyn Snapshot>::address()];
+    addresses.extend(IndexesPool::new(snapshot).resolved_addresses());
+    addresses
+}
+
+/// Checks whether none of the views in the snapshot contain any data.
+fn is_empty(snapshot: &dyn Snapshot) -> bool {
+    IndexesPool::new(snapshot)
+        .resolved_addresses()
+        .iter()
+        .all(|address| snapshot.iter(address, &[]).next().is_none())
+}
+
+pub(crate) fn export_snapshot(
+    snapshot: &dyn Snapshot,
+    writer: &mut dyn Write,
+) -> Result<ExportStats> {
+    let mut writer = HashingWriter {
+        inner: writer,
+        hasher: HashStream::new(),
+        size: 0,
+    };
+    let mut stats = ExportStats::default();
+
+    writer.write_all(MAGIC).map_err(io_error)?;
+    writer.write_u8(FORMAT_VERSION).map_err(io_error)?;
+    writer.write_u8(DB_VERSION).map_err(io_error)?;
+
+    for address in view_addresses(snapshot) {
+        writer.write_u8(TAG_ITEM).map_err(io_error)?;
+        write_bytes(&mut writer, address.name.as_bytes()).map_err(io_error)?;
+        let id = address.id.map_or(0, NonZeroU64::get);
+        writer.write_u64::<LittleEndian>(id).map_err(io_error)?;
+
+        let mut iter = snapshot.iter(&address, &[]);
+        while let Some((key, value)) = iter.next() {
+            writer.write_u8(TAG_ITEM).map_err(io_error)?;
+            write_bytes(&mut writer, key).map_err(io_error)?;
+            write_bytes(&mut writer, value).map_err(io_error)?;
+            stats.entries += 1;
+        }
+        writer.write_u8(TAG_END).map_err(io_error)?;
+        stats.views += 1;
+    }
+
+    writer.write_u8(TAG_END).map_err(io_error)?;
+    let HashingWriter {
+        inner,
+        hasher,
+        size,
+    } = writer;
+    inner.write_all(hasher.hash().as_ref()).map_err(io_error)?;
+    inner.flush().map_err(io_error)?;
+
+    stats.size = size + HASH_SIZE as u64;
+    Ok(stats)
+}
+
+/// Buffers imported entries and merges them into the database in batches.
+struct Importer<'a, D: ?Sized> {
+    db: &'a D,
+    changes: HashMap<ResolvedAddress, ViewChanges>,
+    buffered_size: usize,
+    imported: Vec<ResolvedAddress>,
+    has_merged: bool,
+}
+
+impl<D: Database + ?Sized> Importer<'_, D> {
+    fn start_view(&mut self, address: ResolvedAddress) {
+        // Clearing the view removes any stale data, e.g., the metadata of empty indexes.
+        let mut changes = ViewChanges::default();
+        changes.clear();
+        self.changes.insert(address.clone(), changes);
+        self.imported.push(address);
+    }
+
+    fn put(&mut self, address: &ResolvedAddress, key: Vec<u8>, value: Vec<u8>) -> Result<()> {
+        self.buffered_size += key.len() + value.len();
+        self.changes
+            .entry(address.clone())
+            .or_default()
+            .data
+            .insert(key, Change::Put(value));
+
+        if self.buffered_size >= IMPORT_BATCH_SIZE {
+            self.merge(false)?;
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, sync: bool) -> Result<()> {
+        let changes = mem::replace(&mut self.changes, HashMap::new());
+        self.buffered_size = 0;
+        let patch = Patch::from_raw_changes(self.db.snapshot(), changes);
+        if sync {
+            self.db.merge_sync(patch)?;
+        } else {
+            self.db.merge(patch)?;
+        }
+        self.has_merged = true;
+        Ok(())
+    }
+
+    /// Removes all imported data from the database.
+    fn rollback(&mut self) -> Result<()> {
+        self.changes = self
+            .imported
+            .drain(..)
+            .map(|address| {
+                let mut changes = ViewChanges::default();
+                changes.clear();
+                (address, changes)
+            })
+            .collect();
+        self.merge(true)
+    }
+
+    fn import(&mut self, reader: &mut HashingReader<'_>) -> Result<ExportStats> {
+        let mut magic = [0_u8; 8];
+        reader.read_exact(&mut magic).map_err(io_error)?;
+        if &magic != MAGIC {
+            return Err(format_error("invalid magic bytes"));
+        }
+        let format_version = reader.read_u8().map_err(io_error)?;
+        if format_version != FORMAT_VERSION {
+            return Err(format_error(format!(
+                "unsupported format version {} (expected {})",
+                format_version, FORMAT_VERSION
+            )));
+        }
+        let db_version = reader.read_u8().map_err(io_error)?;
+        if db_version != DB_VERSION {
+            return Err(format_error(format!(
+                "incompatible database version {} (expected {})",
+                db_version, DB_VERSION
+            )));
+        }
+
+        let mut stats = ExportStats::default();
+        while read_tag(reader)? {
+            let name = String::from_utf8(read_bytes(reader)?)
+                .map_err(|_| format_error("non-UTF-8 view name"))?;
+            let id = NonZeroU64::new(reader.read_u64::<LittleEndian>().map_err(io_error)?);
+            let address = ResolvedAddress::new(name, id);
+            self.start_view(address.clone());
+
+            while read_tag(reader)? {
+                let key = read_bytes(reader)?;
+                let value = read_bytes(reader)?;
+                self.put(&address, key, value)?;
+                stats.entries += 1;
+            }
+            stats.views += 1;
+        }
+
+        let expected_checksum = mem::replace(&mut reader.hasher, HashStream::new()).hash();
+        let mut checksum = [0_u8; HASH_SIZE];
+        reader.inner.read_exact(&mut checksum).map_err(io_error)?;
+        if Hash::from_slice(&checksum) != Some(expected_checksum) {
+            return Err(format_error("checksum mismatch"));
+        }
+        stats.size = reader.size + HASH_SIZE as u64;
+
+        self.merge(true)?;
+        Ok(stats)
+    }
+}
+
+pub(crate) fn import_snapshot<D: Database + ?Sized>(
+    db: &D,
+    reader: &mut dyn Read,
+) -> Result<ExportStats> {
+    if !is_empty(db.snapshot().as_ref()) {
+        return Err(Error::new(
+            "Cannot import a snapshot into a non-empty database",
+        ));
+    }
+
+    let mut reader = HashingReader {
+        inner: reader,
+        hasher: HashStream::new(),
+        size: 0,
+    };
+    let mut importer = Importer {
+        db,
+        changes: HashMap::new(),
+        buffered_size: 0,
+        imported: vec![],
+        has_merged: false,
+    };
+    let result = importer.import(&mut reader);
+    if result.is_err() && importer.has_merged {
+        if let Err(err) = importer.rollback() {
+            log::error!("Failed to remove partially imported snapshot: {}", err);
+        }
+    }
+    result
+}
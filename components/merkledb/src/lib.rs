@@ -173,18 +173,31 @@ pub mod _reexports {
 }
 
 pub use self::{
-    backends::{rocksdb::RocksDB, temporarydb::TemporaryDB},
+    backends::{
+        async_merge::MergeHandle,
+        backup::{list_backups, purge_old_backups, restore_from_backup, BackupInfo},
+        integrity::{ColumnFamilyIntegrity, IntegrityError, IntegrityReport},
+        recompress::{recompress, RecompressProgress, RecompressReport},
+        rocksdb::{repair, RepairReport, RocksDB},
+        sst::SstStats,
+        temporarydb::TemporaryDB,
+    },
     db::{
-        Database, DatabaseExt, Fork, Iter, Iterator, OwnedReadonlyFork, Patch, ReadonlyFork,
-        Snapshot,
+        Database, DatabaseExt, DbStats, Fork, Iter, Iterator, OwnedReadonlyFork, Patch,
+        ReadonlyFork, SavepointId, Snapshot,
     },
-    error::Error,
+    error::{Error, ErrorKind},
+    export::ExportStats,
     hash::{root_hash, HashTag, ObjectHash, ValidationError},
     keys::BinaryKey,
     lazy::Lazy,
-    options::{CompressionType, DbOptions, LogVerbosity},
+    options::{
+        AccessPattern, CfOptions, CompactionStyle, CompressionOpts, CompressionType, DbOptions,
+        DbOptionsBuilder, DbPreset, LogVerbosity, OpenRetries, OptionsError, ParseVariantError,
+        WalSyncMode,
+    },
     values::BinaryValue,
-    views::{AsReadonly, IndexAddress, IndexType, ResolvedAddress, SystemSchema},
+    views::{AsReadonly, IndexAddress, IndexInfo, IndexType, ResolvedAddress, SystemSchema},
 };
 // Workaround for 'Linked file at path {exonum_merkledb_path}/struct.ProofMapIndex.html
 // does not exist!'
@@ -201,6 +214,7 @@ pub mod access;
 mod backends;
 mod db;
 mod error;
+mod export;
 pub mod generic;
 mod hash;
 pub mod indexes;
@@ -14,13 +14,95 @@
 
 //! Abstract settings for databases.
 
-use rocksdb::{DBCompressionType, LogLevel};
+use rocksdb::{DBCompactionStyle, DBCompressionType, LogLevel};
+use serde::de::IgnoredAny;
 use serde_derive::{Deserialize, Serialize};
+use thiserror::Error;
+
+use std::{
+    collections::BTreeMap, convert::TryFrom, fmt, path::PathBuf, str::FromStr, time::Duration,
+};
+
+/// Minimum size of the MANIFEST file (1 MiB).
+pub(crate) const MIN_MANIFEST_FILE_SIZE: usize = 1 << 20;
+
+/// Maximum edit distance between an unknown option and a known one, at which the known
+/// option is suggested as a replacement.
+const MAX_SUGGESTION_DISTANCE: usize = 3;
+
+/// Names of the keys which may be used in the serialized `DbOptions`.
+const OPTION_NAMES: &[&str] = &[
+    "preset",
+    "max_open_files",
+    "create_if_missing",
+    "compression_type",
+    "max_total_wal_size",
+    "log_verbosity",
+    "max_log_file_size",
+    "keep_log_file_num",
+    "recycle_log_file_num",
+    "block_cache_size",
+    "write_buffer_size",
+    "max_write_buffer_number",
+    "min_write_buffer_number_to_merge",
+    "bloom_filter_bits_per_key",
+    "block_based_bloom",
+    "compaction_style",
+    "fifo_max_table_files_size",
+    "max_background_jobs",
+    "increase_parallelism",
+    "paranoid_checks",
+    "verify_checksums_on_read",
+    "use_direct_reads",
+    "use_direct_io_for_flush_and_compaction",
+    "column_family_overrides",
+    "rate_limiter_bytes_per_sec",
+    "wal_sync_mode",
+    "wal_dir",
+    "db_log_dir",
+    "ttl_seconds",
+    "bottommost_compression",
+    "compression_per_level",
+    "compression_options",
+    "access_pattern",
+    "auto_open_files",
+    "periodic_compaction_seconds",
+    "compaction_readahead_size",
+    "atomic_flush",
+    "enable_statistics",
+    "stats_dump_period_sec",
+    "prefix_extractor_len",
+    "memtable_prefix_bloom_ratio",
+    "delete_obsolete_files_period_micros",
+    "max_manifest_file_size",
+    "enable_pipelined_write",
+    "unordered_write",
+    "allow_mmap_reads",
+    "allow_mmap_writes",
+    "read_only",
+    "max_batch_bytes",
+    "open_retries",
+];
+
+/// Number of levels of the LSM tree in `RocksDB`.
+pub(crate) const NUM_LEVELS: usize = 7;
 
 /// Options for the database.
 ///
 /// These parameters apply to the underlying database of Exonum, currently `RocksDB`.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+///
+/// When deserializing options, a `preset` key may be used to start from one of
+/// the presets ([`DbPreset`]) instead of the default options. The fields specified
+/// explicitly override the values of the preset.
+///
+/// Missing keys take their default values, so that configs written by older releases
+/// can still be loaded. Unknown keys are rejected during deserialization; the error message
+/// suggests the closest known option, if any. Options equal to `None` are omitted
+/// when serializing.
+///
+/// [`DbPreset`]: enum.DbPreset.html
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(try_from = "DbOptionsRepr")]
 #[non_exhaustive]
 pub struct DbOptions {
     /// Number of open files that can be used by the database.
@@ -30,7 +112,11 @@ pub struct DbOptions {
     /// adjust this option to match the limit. Note, that limiting the number
     /// of simultaneously open files might slow down the speed of database operation.
     ///
+    /// `Some(-1)` means that the number of open files is unlimited. See also
+    /// `auto_open_files`.
+    ///
     /// Defaults to `None`, meaning that the number of open files is unlimited.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub max_open_files: Option<i32>,
     /// An option to indicate whether the system should create a database or not,
     /// if it's missing.
@@ -50,28 +136,322 @@ pub struct DbOptions {
     ///
     /// Defaults to `None`, meaning that the size of WAL journal will be adjusted
     /// by the rocksdb.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub max_total_wal_size: Option<u64>,
     /// Verbosity of the LOG.
     ///
     /// Defaults to `Info`.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub log_verbosity: Option<LogVerbosity>,
     /// Maximal size of the info log file. If the file is larger than this, a new info log file
     /// will be created.
     ///
     /// Defaults to `0`, all logs will be written to the same file.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub max_log_file_size: Option<usize>,
     /// Maximum number of info log files to be kept.
     ///
     /// Default: 1000
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub keep_log_file_num: Option<usize>,
     /// Recycle log files. If non-zero, previously written log files will be reused.
     ///
     /// Defaults to `0`, log files will not be reused.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub recycle_log_file_num: Option<usize>,
+    /// Size of the LRU cache for uncompressed data blocks in bytes.
+    ///
+    /// Increasing the cache size speeds up read-heavy workloads at the cost of memory.
+    /// `Some(0)` disables the block cache completely.
+    ///
+    /// Defaults to `None`, meaning that `RocksDB` uses its default cache of 8 MiB.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_cache_size: Option<u64>,
+    /// Amount of data in bytes to build up in a memtable before it is flushed to disk.
+    ///
+    /// Larger values increase performance of bulk loads at the cost of memory usage
+    /// and recovery time after a crash.
+    ///
+    /// Defaults to `None`, meaning that `RocksDB` uses its default value of 64 MiB.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub write_buffer_size: Option<usize>,
+    /// Maximum number of memtables, both active and immutable.
+    ///
+    /// Defaults to `None`, meaning that `RocksDB` uses its default value of 2.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_write_buffer_number: Option<i32>,
+    /// Minimum number of immutable memtables which are merged together before being
+    /// flushed to disk. Must not exceed `max_write_buffer_number`.
+    ///
+    /// Defaults to `None`, meaning that `RocksDB` uses its default value of 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_write_buffer_number_to_merge: Option<i32>,
+    /// Number of bits per key used by bloom filters in SST files.
+    ///
+    /// Bloom filters allow to skip reading SST files which do not contain the requested key,
+    /// which greatly speeds up point lookups. The memory cost is roughly `bits_per_key / 8`
+    /// bytes per stored key; 10 bits per key yield a false positive rate of about 1%.
+    /// The filter applies to all column families.
+    ///
+    /// Defaults to `None`, meaning that bloom filters are not used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bloom_filter_bits_per_key: Option<i32>,
+    /// Whether to use the legacy block-based bloom filter format instead of the full filter
+    /// format. Block-based filters use less memory at once, but are slower.
+    ///
+    /// Has no effect if `bloom_filter_bits_per_key` is not set. Defaults to `false`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub block_based_bloom: bool,
+    /// Compaction style used by the database.
+    ///
+    /// Defaults to `None`, meaning that `RocksDB` uses level compaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compaction_style: Option<CompactionStyle>,
+    /// Maximum total size of SST files in bytes for FIFO compaction. Once the limit is
+    /// exceeded, the oldest files are deleted.
+    ///
+    /// Only used with `CompactionStyle::Fifo`. Defaults to `None`, meaning that `RocksDB`
+    /// uses its default value of 1 GiB.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fifo_max_table_files_size: Option<u64>,
+    /// Maximum number of concurrent background jobs (compactions and flushes).
+    ///
+    /// Defaults to `None`, meaning that `RocksDB` uses its default value of 2.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_background_jobs: Option<i32>,
+    /// Total number of threads in the background thread pools used for compactions
+    /// and flushes. A good value is the number of CPU cores.
+    ///
+    /// Defaults to `None`, meaning that `RocksDB` uses a single thread per pool.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub increase_parallelism: Option<i32>,
+    /// Whether `RocksDB` should aggressively check data consistency and stop
+    /// operating on the first detected corruption.
+    ///
+    /// Defaults to `None`, meaning that `RocksDB` uses its default value (`true`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paranoid_checks: Option<bool>,
+    /// Whether block checksums should be verified on every read from snapshots and
+    /// iterators. This setting can be changed without reopening the database with
+    /// [`RocksDB::set_verify_checksums_on_read`].
+    ///
+    /// Defaults to `None`, meaning that checksums are verified.
+    ///
+    /// [`RocksDB::set_verify_checksums_on_read`]: struct.RocksDB.html#method.set_verify_checksums_on_read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verify_checksums_on_read: Option<bool>,
+    /// Whether to bypass the OS page cache for reads. Direct I/O is not supported
+    /// by some filesystems (e.g., `tmpfs`); opening the database fails on them.
+    ///
+    /// Defaults to `None`, meaning that direct I/O is not used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_direct_reads: Option<bool>,
+    /// Whether to bypass the OS page cache for writes during flushes and compactions.
+    ///
+    /// Defaults to `None`, meaning that direct I/O is not used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_direct_io_for_flush_and_compaction: Option<bool>,
+    /// Options overriding the ones above for separate column families, keyed by
+    /// the column family name. Overrides for column families which do not exist
+    /// in the database are applied once they are created.
+    ///
+    /// Defaults to an empty map.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub column_family_overrides: BTreeMap<String, CfOptions>,
+    /// Limit on the total write rate of flushes and compactions in bytes per second.
+    /// Zero or negative values disable the limiter.
+    ///
+    /// Defaults to `None`, meaning that writes are not rate-limited.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limiter_bytes_per_sec: Option<i64>,
+    /// Durability mode of the write-ahead log.
+    ///
+    /// Defaults to `None`, which is equivalent to `WalSyncMode::Async`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wal_sync_mode: Option<WalSyncMode>,
+    /// Directory for the write-ahead log files. A relative path is resolved against
+    /// the database directory.
+    ///
+    /// Defaults to `None`, meaning that the WAL is stored in the database directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wal_dir: Option<PathBuf>,
+    /// Directory for the info LOG files of `RocksDB`. A relative path is resolved
+    /// against the database directory.
+    ///
+    /// Defaults to `None`, meaning that the info LOG is stored in the database directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub db_log_dir: Option<PathBuf>,
+    /// Time-to-live of the database entries in seconds. If set, the database is opened
+    /// in the TTL mode, in which entries older than the TTL are removed.
+    ///
+    /// Expiry is approximate: outdated entries are dropped only during compactions, so
+    /// they may remain readable for an arbitrary time after the TTL has passed. Since this
+    /// breaks the integrity of the blockchain state, the TTL mode is only suitable
+    /// for disposable networks.
+    ///
+    /// Defaults to `None`, meaning that entries never expire.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl_seconds: Option<u64>,
+    /// Compression type for the bottommost level, which holds most of the data.
+    ///
+    /// Defaults to `None`, meaning that `compression_type` is used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bottommost_compression: Option<CompressionType>,
+    /// Compression types for the levels of the LSM tree, starting from level 0.
+    /// Levels not covered by the vector use `compression_type`. The vector cannot be
+    /// longer than the number of levels (7).
+    ///
+    /// Defaults to `None`, meaning that `compression_type` is used for all levels.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression_per_level: Option<Vec<CompressionType>>,
+    /// Parameters of the compression algorithm, such as the `zstd` compression level.
+    /// Ignored with a warning if no compression is used.
+    ///
+    /// Defaults to `None`, meaning that `RocksDB` uses default parameters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression_options: Option<CompressionOpts>,
+    /// Preset of options tuned for the prevailing access pattern to the database.
+    ///
+    /// The preset is applied before other options, so options specified explicitly take
+    /// precedence over it. In particular, specifying `block_cache_size` or
+    /// `bloom_filter_bits_per_key` replaces the block-based table settings of the preset.
+    ///
+    /// Defaults to `None`, which is equivalent to `AccessPattern::Mixed`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_pattern: Option<AccessPattern>,
+    /// Whether to derive the number of open files from the limit of the process
+    /// (`RLIMIT_NOFILE` on Unix), leaving a reserve for sockets and log files.
+    /// Ignored if `max_open_files` is specified.
+    ///
+    /// Defaults to `false`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub auto_open_files: bool,
+    /// Interval in seconds after which SST files are compacted even if nothing triggers
+    /// their compaction, which removes tombstones and stale entries from cold key ranges.
+    ///
+    /// Defaults to `None`, meaning that periodic compaction is disabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub periodic_compaction_seconds: Option<u64>,
+    /// Size of the readahead in bytes used by compactions. A non-zero value is
+    /// recommended for spinning disks.
+    ///
+    /// Defaults to `None`, meaning that `RocksDB` does not use readahead.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compaction_readahead_size: Option<usize>,
+    /// Whether memtables of all column families are flushed atomically, so that the
+    /// column families are always persisted at the same point.
+    ///
+    /// Defaults to `None`, meaning that column families are flushed independently.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub atomic_flush: Option<bool>,
+    /// Whether `RocksDB` collects internal statistics, which are dumped to the info LOG.
+    /// Collecting statistics has a measurable overhead (typically 5-10%), so it should
+    /// only be switched on when diagnosing performance issues.
+    ///
+    /// Defaults to `None`, meaning that statistics are not collected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enable_statistics: Option<bool>,
+    /// Period in seconds of dumping statistics to the info LOG.
+    ///
+    /// Defaults to `None`, meaning that `RocksDB` uses its default period of 10 minutes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats_dump_period_sec: Option<u32>,
+    /// Length of the fixed key prefix used by prefix bloom filters. Since keys of indexes
+    /// are prefixed with 8-byte index identifiers, a value of 8 makes bloom filters
+    /// efficient for scans within a single index.
+    ///
+    /// The length is recorded in the database on the first open. Changing it for
+    /// an existing database is unsafe, so the database refuses to open if the recorded
+    /// length differs from the specified one.
+    ///
+    /// Defaults to `None`, meaning that prefix bloom filters are not used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefix_extractor_len: Option<usize>,
+    /// Size of the prefix bloom filter in memtables as a ratio of `write_buffer_size`,
+    /// between 0 and 0.25. Only used with `prefix_extractor_len`.
+    ///
+    /// Defaults to `None`, meaning that memtables do not use prefix bloom filters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memtable_prefix_bloom_ratio: Option<f64>,
+    /// Period in microseconds of deleting obsolete files. Obsolete files are always deleted
+    /// after compactions; this option additionally deletes files which were not deleted
+    /// due to other reasons.
+    ///
+    /// Defaults to `None`, meaning that `RocksDB` uses its default period of 6 hours.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delete_obsolete_files_period_micros: Option<u64>,
+    /// Maximum size of the MANIFEST file in bytes, after which it is rolled over.
+    /// Cannot be less than 1 MiB.
+    ///
+    /// Defaults to `None`, meaning that `RocksDB` uses its default value of 1 GiB.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_manifest_file_size: Option<usize>,
+    /// Whether writes to the WAL and to memtables are pipelined, which improves write
+    /// throughput when many writes are performed concurrently. Cannot be combined with
+    /// `unordered_write`.
+    ///
+    /// Defaults to `None`, meaning that pipelined writes are disabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enable_pipelined_write: Option<bool>,
+    /// Whether `RocksDB` relaxes the ordering of concurrent writes to memtables, trading
+    /// snapshot immutability for write throughput. Cannot be combined with
+    /// `enable_pipelined_write`.
+    ///
+    /// Write batches stay atomic in the WAL, so after a restart the database contains either
+    /// all or none of the changes of each merged patch. However, a snapshot taken while
+    /// a patch is being merged (e.g., with `merge_async`) may observe a partially applied
+    /// patch. Thus, unordered writes should only be enabled while the node is ingesting
+    /// blocks and nothing reads the database concurrently, e.g., during the initial sync
+    /// with the HTTP API disabled.
+    ///
+    /// Defaults to `None`, meaning that unordered writes are disabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unordered_write: Option<bool>,
+    /// Whether SST files are read using memory-mapped I/O. This reduces read latency on
+    /// machines with enough RAM to keep the database in the OS page cache. Cannot be combined
+    /// with `use_direct_reads`.
+    ///
+    /// Defaults to `None`, meaning that memory-mapped reads are disabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_mmap_reads: Option<bool>,
+    /// Whether SST files are written using memory-mapped I/O.
+    ///
+    /// Defaults to `None`, meaning that memory-mapped writes are disabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_mmap_writes: Option<bool>,
+    /// Whether the database is opened in the read-only mode, in which snapshots can be read,
+    /// but merging patches fails with an error of [`ErrorKind::ReadOnly`] kind. The database
+    /// may be opened read-only while it is used by another process (e.g., a running node),
+    /// so this mode is intended for tooling such as analytics and explorers; a node cannot
+    /// run on a read-only database. Cannot be combined with `ttl_seconds`.
+    ///
+    /// Defaults to `None`, meaning that the database is opened for reading and writing.
+    ///
+    /// [`ErrorKind::ReadOnly`]: enum.ErrorKind.html#variant.ReadOnly
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_only: Option<bool>,
+    /// Maximum size in bytes of a single write batch used by [`Database::merge_in_batches`].
+    /// Larger patches are split into several sequentially written batches, which limits memory
+    /// usage and WAL stalls when applying huge patches. Splitting makes the merge non-atomic,
+    /// so it is never applied by `merge` and `merge_sync`. Must be positive.
+    ///
+    /// Defaults to `None`, meaning that patches are never split.
+    ///
+    /// [`Database::merge_in_batches`]: trait.Database.html#method.merge_in_batches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_batch_bytes: Option<usize>,
+    /// Retries of opening the database if it is locked by another process, e.g., by a node
+    /// which is being restarted. Attempts are separated by exponentially increasing delays.
+    ///
+    /// Defaults to `None`, meaning that opening a locked database fails immediately.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_retries: Option<OpenRetries>,
 }
 
 impl DbOptions {
     /// Creates a new `DbOptions` object.
+    #[deprecated(note = "Use `DbOptions::builder()` instead")]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         max_open_files: Option<i32>,
         create_if_missing: bool,
@@ -91,10 +471,818 @@ impl DbOptions {
             max_log_file_size,
             keep_log_file_num,
             recycle_log_file_num,
+            block_cache_size: None,
+            write_buffer_size: None,
+            max_write_buffer_number: None,
+            min_write_buffer_number_to_merge: None,
+            bloom_filter_bits_per_key: None,
+            block_based_bloom: false,
+            compaction_style: None,
+            fifo_max_table_files_size: None,
+            max_background_jobs: None,
+            increase_parallelism: None,
+            paranoid_checks: None,
+            verify_checksums_on_read: None,
+            use_direct_reads: None,
+            use_direct_io_for_flush_and_compaction: None,
+            column_family_overrides: BTreeMap::new(),
+            rate_limiter_bytes_per_sec: None,
+            wal_sync_mode: None,
+            wal_dir: None,
+            db_log_dir: None,
+            ttl_seconds: None,
+            bottommost_compression: None,
+            compression_per_level: None,
+            compression_options: None,
+            access_pattern: None,
+            auto_open_files: false,
+            periodic_compaction_seconds: None,
+            compaction_readahead_size: None,
+            atomic_flush: None,
+            enable_statistics: None,
+            stats_dump_period_sec: None,
+            prefix_extractor_len: None,
+            memtable_prefix_bloom_ratio: None,
+            delete_obsolete_files_period_micros: None,
+            max_manifest_file_size: None,
+            enable_pipelined_write: None,
+            unordered_write: None,
+            allow_mmap_reads: None,
+            allow_mmap_writes: None,
+            read_only: None,
+            max_batch_bytes: None,
+            open_retries: None,
+        }
+    }
+
+    /// Returns options for a node with little memory (about 2 GB of RAM), such as a small VPS.
+    ///
+    /// Differences from the default options:
+    ///
+    /// - `max_open_files`: 256
+    /// - `compression_type`: `Snappy`
+    /// - `max_total_wal_size`: 64 MiB
+    /// - `log_verbosity`: `Warn`
+    /// - `max_log_file_size`: 10 MiB
+    /// - `keep_log_file_num`: 5
+    /// - `block_cache_size`: 64 MiB
+    /// - `write_buffer_size`: 16 MiB
+    /// - `max_write_buffer_number`: 2
+    /// - `max_background_jobs`: 2
+    pub fn small_node() -> Self {
+        Self {
+            max_open_files: Some(256),
+            compression_type: CompressionType::Snappy,
+            max_total_wal_size: Some(64 << 20),
+            log_verbosity: Some(LogVerbosity::Warn),
+            max_log_file_size: Some(10 << 20),
+            keep_log_file_num: Some(5),
+            block_cache_size: Some(64 << 20),
+            write_buffer_size: Some(16 << 20),
+            max_write_buffer_number: Some(2),
+            max_background_jobs: Some(2),
+            ..Self::default()
+        }
+    }
+
+    /// Returns options for an archive node, which stores a lot of data, possibly
+    /// on a spinning disk.
+    ///
+    /// Differences from the default options:
+    ///
+    /// - `compression_type`: `Snappy`
+    /// - `bottommost_compression`: `Snappy`
+    /// - `max_total_wal_size`: 1 GiB
+    /// - `max_log_file_size`: 64 MiB
+    /// - `keep_log_file_num`: 10
+    /// - `block_cache_size`: 1 GiB
+    /// - `write_buffer_size`: 128 MiB
+    /// - `max_write_buffer_number`: 4
+    /// - `min_write_buffer_number_to_merge`: 2
+    /// - `bloom_filter_bits_per_key`: 10
+    /// - `max_background_jobs`: 4
+    /// - `compaction_readahead_size`: 2 MiB
+    /// - `auto_open_files`: `true`
+    pub fn archive() -> Self {
+        Self {
+            compression_type: CompressionType::Snappy,
+            bottommost_compression: Some(CompressionType::Snappy),
+            max_total_wal_size: Some(1 << 30),
+            max_log_file_size: Some(64 << 20),
+            keep_log_file_num: Some(10),
+            block_cache_size: Some(1 << 30),
+            write_buffer_size: Some(128 << 20),
+            max_write_buffer_number: Some(4),
+            min_write_buffer_number_to_merge: Some(2),
+            bloom_filter_bits_per_key: Some(10),
+            max_background_jobs: Some(4),
+            compaction_readahead_size: Some(2 << 20),
+            auto_open_files: true,
+            ..Self::default()
+        }
+    }
+
+    /// Returns options for disposable benchmark nodes, which trade durability for speed.
+    /// **Data is lost on a crash**, since the write-ahead log is disabled.
+    ///
+    /// Differences from the default options:
+    ///
+    /// - `compression_type`: `None`
+    /// - `log_verbosity`: `Error`
+    /// - `max_log_file_size`: 10 MiB
+    /// - `keep_log_file_num`: 1
+    /// - `block_cache_size`: 512 MiB
+    /// - `write_buffer_size`: 256 MiB
+    /// - `max_write_buffer_number`: 4
+    /// - `wal_sync_mode`: `Disabled`
+    pub fn benchmark() -> Self {
+        Self {
+            compression_type: CompressionType::None,
+            log_verbosity: Some(LogVerbosity::Error),
+            max_log_file_size: Some(10 << 20),
+            keep_log_file_num: Some(1),
+            block_cache_size: Some(512 << 20),
+            write_buffer_size: Some(256 << 20),
+            max_write_buffer_number: Some(4),
+            wal_sync_mode: Some(WalSyncMode::Disabled),
+            ..Self::default()
+        }
+    }
+
+    /// Returns a builder of `DbOptions` with all fields set to their default values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use exonum_merkledb::{CompressionType, DbOptions};
+    /// let options = DbOptions::builder()
+    ///     .max_open_files(256)
+    ///     .compression(CompressionType::Lz4)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(options.max_open_files, Some(256));
+    /// assert!(options.create_if_missing);
+    /// ```
+    pub fn builder() -> DbOptionsBuilder {
+        DbOptionsBuilder::default()
+    }
+
+    /// Returns options for the column family with the specified name, with the overrides
+    /// for this column family applied.
+    pub(crate) fn for_column_family(&self, name: &str) -> Self {
+        let mut options = self.clone();
+        if let Some(overrides) = self.column_family_overrides.get(name) {
+            if let Some(compression_type) = overrides.compression_type {
+                options.compression_type = compression_type;
+            }
+            if let Some(write_buffer_size) = overrides.write_buffer_size {
+                options.write_buffer_size = Some(write_buffer_size);
+            }
+            if let Some(bits_per_key) = overrides.bloom_filter_bits_per_key {
+                options.bloom_filter_bits_per_key = Some(bits_per_key);
+            }
+        }
+        options
+    }
+
+    /// Returns compression types for all levels of the LSM tree, or `None` if they
+    /// are not specified separately.
+    pub(crate) fn level_compression_types(&self) -> Option<Vec<CompressionType>> {
+        let mut levels = self.compression_per_level.clone()?;
+        levels.resize(NUM_LEVELS, self.compression_type);
+        Some(levels)
+    }
+
+    /// Returns the size of the block cache in bytes, taking into account the `access_pattern`
+    /// preset, or `None` if the default cache is used.
+    pub(crate) fn effective_block_cache_size(&self) -> Option<u64> {
+        match (self.block_cache_size, self.access_pattern) {
+            (Some(size), _) => Some(size),
+            (None, Some(AccessPattern::PointLookup { cache_mb })) => {
+                Some(cache_mb.saturating_mul(1 << 20))
+            }
+            (None, _) => None,
+        }
+    }
+
+    /// Returns a textual description of the compression settings, which is equal for two
+    /// options if and only if they compress data in the same way. Overrides for column
+    /// families are not taken into account; use `for_column_family` to apply them.
+    pub(crate) fn compression_fingerprint(&self) -> String {
+        format!(
+            "{:?}/{:?}/{:?}/{:?}",
+            self.compression_type,
+            self.bottommost_compression,
+            self.level_compression_types(),
+            self.compression_options.filter(|_| self.uses_compression()),
+        )
+    }
+
+    /// Returns `true` if any level of the LSM tree is compressed.
+    pub(crate) fn uses_compression(&self) -> bool {
+        let compresses = |compression: &CompressionType| *compression != CompressionType::None;
+        compresses(&self.compression_type)
+            || self
+                .bottommost_compression
+                .as_ref()
+                .map_or(false, compresses)
+            || self
+                .compression_per_level
+                .as_ref()
+                .map_or(false, |levels| levels.iter().any(compresses))
+    }
+
+    /// Returns `true` if any of the direct I/O options is switched on.
+    pub(crate) fn uses_direct_io(&self) -> bool {
+        self.use_direct_reads == Some(true)
+            || self.use_direct_io_for_flush_and_compaction == Some(true)
+    }
+
+    /// Checks that the options are consistent, so that the database can be opened with them.
+    ///
+    /// This method is called when building options with [`DbOptionsBuilder`] and when opening
+    /// the database, but may be useful to check options deserialized from a configuration file.
+    ///
+    /// [`DbOptionsBuilder`]: struct.DbOptionsBuilder.html
+    pub fn validate(&self) -> Result<(), OptionsError> {
+        if let Some(value) = self.max_open_files {
+            if value < -1 {
+                return Err(OptionsError::MaxOpenFiles { value });
+            }
+        }
+
+        if self.keep_log_file_num == Some(0) {
+            return Err(OptionsError::KeepLogFileNumZero);
+        }
+        if let Some(max_log_file_size) = self.max_log_file_size {
+            let recycles_logs = self.recycle_log_file_num.map_or(false, |num| num > 0);
+            if max_log_file_size > 0 && self.keep_log_file_num == Some(1) && recycles_logs {
+                return Err(OptionsError::SingleRecycledLogFile { max_log_file_size });
+            }
+        }
+
+        let positive_fields = [
+            ("max_background_jobs", self.max_background_jobs),
+            ("increase_parallelism", self.increase_parallelism),
+        ];
+        for &(field, value) in &positive_fields {
+            if let Some(value) = value {
+                if value <= 0 {
+                    return Err(OptionsError::NotPositive {
+                        field,
+                        value: value.into(),
+                    });
+                }
+            }
+        }
+
+        if let (Some(max_number), Some(min_to_merge)) = (
+            self.max_write_buffer_number,
+            self.min_write_buffer_number_to_merge,
+        ) {
+            if min_to_merge > max_number {
+                return Err(OptionsError::WriteBuffersToMerge {
+                    max_number,
+                    min_to_merge,
+                });
+            }
+        }
+        // Zero WAL size limit means that `RocksDB` selects the limit automatically.
+        if let (Some(max_total_wal_size), Some(write_buffer_size)) =
+            (self.max_total_wal_size, self.write_buffer_size)
+        {
+            if max_total_wal_size > 0 && max_total_wal_size < write_buffer_size as u64 {
+                return Err(OptionsError::WalSizeBelowWriteBuffer {
+                    max_total_wal_size,
+                    write_buffer_size,
+                });
+            }
+        }
+
+        if self.enable_pipelined_write == Some(true) && self.unordered_write == Some(true) {
+            return Err(OptionsError::Conflict {
+                first: "enable_pipelined_write",
+                second: "unordered_write",
+            });
+        }
+
+        if self.allow_mmap_reads == Some(true) && self.use_direct_reads == Some(true) {
+            return Err(OptionsError::Conflict {
+                first: "allow_mmap_reads",
+                second: "use_direct_reads",
+            });
+        }
+
+        if self.read_only == Some(true) && self.ttl_seconds.is_some() {
+            return Err(OptionsError::Conflict {
+                first: "read_only",
+                second: "ttl_seconds",
+            });
+        }
+
+        if self.atomic_flush == Some(true) && self.wal_sync_mode == Some(WalSyncMode::Disabled) {
+            return Err(OptionsError::AtomicFlushWithoutWal);
+        }
+
+        if let Some(retries) = self.open_retries {
+            if retries.attempts == 0 {
+                return Err(OptionsError::NotPositive {
+                    field: "open_retries.attempts",
+                    value: 0,
+                });
+            }
+        }
+
+        if self.max_batch_bytes == Some(0) {
+            return Err(OptionsError::NotPositive {
+                field: "max_batch_bytes",
+                value: 0,
+            });
+        }
+
+        if self.prefix_extractor_len == Some(0) {
+            return Err(OptionsError::NotPositive {
+                field: "prefix_extractor_len",
+                value: 0,
+            });
+        }
+        if let Some(value) = self.memtable_prefix_bloom_ratio {
+            let in_range = value > 0.0 && value <= 0.25;
+            if !in_range {
+                return Err(OptionsError::MemtablePrefixBloomRatio { value });
+            }
+        }
+
+        if let Some(value) = self.max_manifest_file_size {
+            if value < MIN_MANIFEST_FILE_SIZE {
+                return Err(OptionsError::ManifestFileSize { value });
+            }
+        }
+
+        if self.periodic_compaction_seconds == Some(0) {
+            return Err(OptionsError::NotPositive {
+                field: "periodic_compaction_seconds",
+                value: 0,
+            });
         }
+
+        if let Some(ref compression_per_level) = self.compression_per_level {
+            if compression_per_level.len() > NUM_LEVELS {
+                return Err(OptionsError::CompressionPerLevel {
+                    levels: compression_per_level.len(),
+                    num_levels: NUM_LEVELS,
+                });
+            }
+        }
+
+        if let Some(ttl_seconds) = self.ttl_seconds {
+            if ttl_seconds == 0 {
+                return Err(OptionsError::NotPositive {
+                    field: "ttl_seconds",
+                    value: 0,
+                });
+            }
+            // FIFO compaction does not run compaction filters, which remove expired entries.
+            if self.compaction_style == Some(CompactionStyle::Fifo) {
+                return Err(OptionsError::TtlWithFifoCompaction { ttl_seconds });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builder of [`DbOptions`].
+///
+/// Fields which are not set explicitly keep their values from the options the builder
+/// was created from; for [`DbOptions::builder()`] these are the default values.
+///
+/// [`DbOptions`]: struct.DbOptions.html
+/// [`DbOptions::builder()`]: struct.DbOptions.html#method.builder
+#[derive(Debug, Clone, Default)]
+pub struct DbOptionsBuilder {
+    options: DbOptions,
+}
+
+impl From<DbOptions> for DbOptionsBuilder {
+    fn from(options: DbOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl DbOptionsBuilder {
+    /// Sets the number of open files that can be used by the database.
+    pub fn max_open_files(mut self, value: i32) -> Self {
+        self.options.max_open_files = Some(value);
+        self
+    }
+
+    /// Sets whether the database should be created if it's missing.
+    pub fn create_if_missing(mut self, value: bool) -> Self {
+        self.options.create_if_missing = value;
+        self
+    }
+
+    /// Sets the compression algorithm.
+    pub fn compression(mut self, value: CompressionType) -> Self {
+        self.options.compression_type = value;
+        self
+    }
+
+    /// Sets the maximum total size of the WAL journal in bytes.
+    pub fn wal_size_limit(mut self, value: u64) -> Self {
+        self.options.max_total_wal_size = Some(value);
+        self
+    }
+
+    /// Sets the verbosity of the info LOG.
+    pub fn log_verbosity(mut self, value: LogVerbosity) -> Self {
+        self.options.log_verbosity = Some(value);
+        self
+    }
+
+    /// Sets the maximal size of an info LOG file in bytes.
+    pub fn max_log_file_size(mut self, value: usize) -> Self {
+        self.options.max_log_file_size = Some(value);
+        self
+    }
+
+    /// Sets the maximum number of info LOG files to be kept.
+    pub fn keep_log_file_num(mut self, value: usize) -> Self {
+        self.options.keep_log_file_num = Some(value);
+        self
+    }
+
+    /// Sets the number of info LOG files to be recycled.
+    pub fn recycle_log_file_num(mut self, value: usize) -> Self {
+        self.options.recycle_log_file_num = Some(value);
+        self
+    }
+
+    /// Sets the size of the block cache in bytes. `0` disables the cache.
+    pub fn block_cache_size(mut self, value: u64) -> Self {
+        self.options.block_cache_size = Some(value);
+        self
+    }
+
+    /// Sets the size of a single memtable in bytes.
+    pub fn write_buffer_size(mut self, value: usize) -> Self {
+        self.options.write_buffer_size = Some(value);
+        self
+    }
+
+    /// Sets the maximum number of memtables.
+    pub fn max_write_buffer_number(mut self, value: i32) -> Self {
+        self.options.max_write_buffer_number = Some(value);
+        self
+    }
+
+    /// Sets the minimum number of memtables merged together before flushing.
+    pub fn min_write_buffer_number_to_merge(mut self, value: i32) -> Self {
+        self.options.min_write_buffer_number_to_merge = Some(value);
+        self
+    }
+
+    /// Enables bloom filters with the specified number of bits per key.
+    pub fn bloom_filter_bits_per_key(mut self, value: i32) -> Self {
+        self.options.bloom_filter_bits_per_key = Some(value);
+        self
+    }
+
+    /// Sets whether to use block-based bloom filters.
+    pub fn block_based_bloom(mut self, value: bool) -> Self {
+        self.options.block_based_bloom = value;
+        self
+    }
+
+    /// Sets the compaction style.
+    pub fn compaction_style(mut self, value: CompactionStyle) -> Self {
+        self.options.compaction_style = Some(value);
+        self
+    }
+
+    /// Sets the maximum total size of SST files for FIFO compaction.
+    pub fn fifo_max_table_files_size(mut self, value: u64) -> Self {
+        self.options.fifo_max_table_files_size = Some(value);
+        self
+    }
+
+    /// Sets the maximum number of concurrent background jobs.
+    pub fn max_background_jobs(mut self, value: i32) -> Self {
+        self.options.max_background_jobs = Some(value);
+        self
+    }
+
+    /// Sets the total number of background threads.
+    pub fn increase_parallelism(mut self, value: i32) -> Self {
+        self.options.increase_parallelism = Some(value);
+        self
+    }
+
+    /// Sets whether `RocksDB` should perform paranoid consistency checks.
+    pub fn paranoid_checks(mut self, value: bool) -> Self {
+        self.options.paranoid_checks = Some(value);
+        self
+    }
+
+    /// Sets whether block checksums should be verified on reads.
+    pub fn verify_checksums_on_read(mut self, value: bool) -> Self {
+        self.options.verify_checksums_on_read = Some(value);
+        self
+    }
+
+    /// Sets whether direct I/O is used for reads.
+    pub fn use_direct_reads(mut self, value: bool) -> Self {
+        self.options.use_direct_reads = Some(value);
+        self
+    }
+
+    /// Sets whether direct I/O is used for flushes and compactions.
+    pub fn use_direct_io_for_flush_and_compaction(mut self, value: bool) -> Self {
+        self.options.use_direct_io_for_flush_and_compaction = Some(value);
+        self
+    }
+
+    /// Overrides options for the column family with the specified name.
+    pub fn column_family_override(mut self, name: impl Into<String>, options: CfOptions) -> Self {
+        self.options
+            .column_family_overrides
+            .insert(name.into(), options);
+        self
+    }
+
+    /// Sets the limit on the write rate of flushes and compactions in bytes per second.
+    pub fn rate_limiter_bytes_per_sec(mut self, value: i64) -> Self {
+        self.options.rate_limiter_bytes_per_sec = Some(value);
+        self
+    }
+
+    /// Sets the durability mode of the write-ahead log.
+    pub fn wal_sync_mode(mut self, value: WalSyncMode) -> Self {
+        self.options.wal_sync_mode = Some(value);
+        self
+    }
+
+    /// Sets the directory for the write-ahead log files.
+    pub fn wal_dir(mut self, value: impl Into<PathBuf>) -> Self {
+        self.options.wal_dir = Some(value.into());
+        self
+    }
+
+    /// Sets the directory for the info LOG files.
+    pub fn db_log_dir(mut self, value: impl Into<PathBuf>) -> Self {
+        self.options.db_log_dir = Some(value.into());
+        self
+    }
+
+    /// Sets the time-to-live of the database entries in seconds.
+    pub fn ttl_seconds(mut self, value: u64) -> Self {
+        self.options.ttl_seconds = Some(value);
+        self
+    }
+
+    /// Sets the compression type for the bottommost level.
+    pub fn bottommost_compression(mut self, value: CompressionType) -> Self {
+        self.options.bottommost_compression = Some(value);
+        self
+    }
+
+    /// Sets compression types for the levels, starting from level 0.
+    pub fn compression_per_level(mut self, value: impl Into<Vec<CompressionType>>) -> Self {
+        self.options.compression_per_level = Some(value.into());
+        self
+    }
+
+    /// Sets parameters of the compression algorithm.
+    pub fn compression_options(mut self, value: CompressionOpts) -> Self {
+        self.options.compression_options = Some(value);
+        self
+    }
+
+    /// Sets the preset for the prevailing access pattern.
+    pub fn access_pattern(mut self, value: AccessPattern) -> Self {
+        self.options.access_pattern = Some(value);
+        self
+    }
+
+    /// Sets whether to derive the number of open files from the limit of the process.
+    pub fn auto_open_files(mut self, value: bool) -> Self {
+        self.options.auto_open_files = value;
+        self
+    }
+
+    /// Sets the periodic compaction interval in seconds.
+    pub fn periodic_compaction_seconds(mut self, value: u64) -> Self {
+        self.options.periodic_compaction_seconds = Some(value);
+        self
+    }
+
+    /// Sets the readahead size for compactions in bytes.
+    pub fn compaction_readahead_size(mut self, value: usize) -> Self {
+        self.options.compaction_readahead_size = Some(value);
+        self
+    }
+
+    /// Sets whether column families are flushed atomically.
+    pub fn atomic_flush(mut self, value: bool) -> Self {
+        self.options.atomic_flush = Some(value);
+        self
+    }
+
+    /// Sets whether `RocksDB` collects internal statistics.
+    pub fn enable_statistics(mut self, value: bool) -> Self {
+        self.options.enable_statistics = Some(value);
+        self
+    }
+
+    /// Sets the period of dumping statistics to the info LOG in seconds.
+    pub fn stats_dump_period_sec(mut self, value: u32) -> Self {
+        self.options.stats_dump_period_sec = Some(value);
+        self
+    }
+
+    /// Sets the length of the fixed key prefix used by prefix bloom filters.
+    pub fn prefix_extractor_len(mut self, value: usize) -> Self {
+        self.options.prefix_extractor_len = Some(value);
+        self
+    }
+
+    /// Sets the size ratio of the prefix bloom filter in memtables.
+    pub fn memtable_prefix_bloom_ratio(mut self, value: f64) -> Self {
+        self.options.memtable_prefix_bloom_ratio = Some(value);
+        self
+    }
+
+    /// Sets the period of deleting obsolete files in microseconds.
+    pub fn delete_obsolete_files_period_micros(mut self, value: u64) -> Self {
+        self.options.delete_obsolete_files_period_micros = Some(value);
+        self
+    }
+
+    /// Sets the maximum size of the MANIFEST file in bytes.
+    pub fn max_manifest_file_size(mut self, value: usize) -> Self {
+        self.options.max_manifest_file_size = Some(value);
+        self
+    }
+
+    /// Sets whether writes to the WAL and to memtables are pipelined.
+    pub fn enable_pipelined_write(mut self, value: bool) -> Self {
+        self.options.enable_pipelined_write = Some(value);
+        self
+    }
+
+    /// Sets whether concurrent writes to memtables may be unordered.
+    pub fn unordered_write(mut self, value: bool) -> Self {
+        self.options.unordered_write = Some(value);
+        self
+    }
+
+    /// Sets whether SST files are read using memory-mapped I/O.
+    pub fn allow_mmap_reads(mut self, value: bool) -> Self {
+        self.options.allow_mmap_reads = Some(value);
+        self
+    }
+
+    /// Sets whether SST files are written using memory-mapped I/O.
+    pub fn allow_mmap_writes(mut self, value: bool) -> Self {
+        self.options.allow_mmap_writes = Some(value);
+        self
+    }
+
+    /// Sets whether the database is opened in the read-only mode.
+    pub fn read_only(mut self, value: bool) -> Self {
+        self.options.read_only = Some(value);
+        self
+    }
+
+    /// Sets the maximum size of a single write batch used by `Database::merge_in_batches`.
+    pub fn max_batch_bytes(mut self, value: usize) -> Self {
+        self.options.max_batch_bytes = Some(value);
+        self
+    }
+
+    /// Sets the number of retries and the initial delay between them when opening
+    /// a locked database.
+    pub fn open_retries(mut self, attempts: u32, backoff: Duration) -> Self {
+        self.options.open_retries = Some(OpenRetries::new(attempts, backoff));
+        self
+    }
+
+    /// Completes building the options.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the options are inconsistent.
+    pub fn build(self) -> Result<DbOptions, OptionsError> {
+        self.options.validate()?;
+        Ok(self.options)
     }
 }
 
+/// Error signalling that `DbOptions` are inconsistent.
+#[derive(Debug, Clone, PartialEq, Error)]
+#[non_exhaustive]
+pub enum OptionsError {
+    /// `keep_log_file_num` is zero, while at least one info LOG file is always kept.
+    #[error("`keep_log_file_num` must be positive, got 0")]
+    KeepLogFileNumZero,
+    /// More memtables are required to be merged before a flush than may exist at all.
+    #[error(
+        "`min_write_buffer_number_to_merge` ({}) exceeds `max_write_buffer_number` ({})",
+        min_to_merge,
+        max_number
+    )]
+    WriteBuffersToMerge {
+        /// Value of `max_write_buffer_number`.
+        max_number: i32,
+        /// Value of `min_write_buffer_number_to_merge`.
+        min_to_merge: i32,
+    },
+    /// The field must be positive.
+    #[error("`{}` must be positive, got {}", field, value)]
+    NotPositive {
+        /// Name of the field.
+        field: &'static str,
+        /// Value of the field.
+        value: i64,
+    },
+    /// `max_open_files` is less than -1.
+    #[error(
+        "`max_open_files` must be -1 (unlimited) or non-negative, got {}",
+        value
+    )]
+    MaxOpenFiles {
+        /// Value of `max_open_files`.
+        value: i32,
+    },
+    /// Info LOG is rotated by size, while the single kept LOG file is recycled.
+    #[error(
+        "`max_log_file_size` ({}) cannot be set when `keep_log_file_num` is 1 and \
+         `recycle_log_file_num` is positive",
+        max_log_file_size
+    )]
+    SingleRecycledLogFile {
+        /// Value of `max_log_file_size`.
+        max_log_file_size: usize,
+    },
+    /// WAL size limit is less than the size of a single memtable, so that the WAL
+    /// would force flushes on every write buffer switch.
+    #[error(
+        "`max_total_wal_size` ({}) is less than `write_buffer_size` ({})",
+        max_total_wal_size,
+        write_buffer_size
+    )]
+    WalSizeBelowWriteBuffer {
+        /// Value of `max_total_wal_size`.
+        max_total_wal_size: u64,
+        /// Value of `write_buffer_size`.
+        write_buffer_size: usize,
+    },
+    /// Size ratio of the memtable prefix bloom filter is out of range.
+    #[error("`memtable_prefix_bloom_ratio` must be in (0, 0.25], got {}", value)]
+    MemtablePrefixBloomRatio {
+        /// Value of `memtable_prefix_bloom_ratio`.
+        value: f64,
+    },
+    /// Maximum size of the MANIFEST file is too small.
+    #[error("`max_manifest_file_size` must be at least 1 MiB, got {}", value)]
+    ManifestFileSize {
+        /// Value of `max_manifest_file_size`.
+        value: usize,
+    },
+    /// Two options which cannot be enabled simultaneously are both enabled.
+    #[error("`{}` and `{}` cannot be enabled simultaneously", first, second)]
+    Conflict {
+        /// Name of the first conflicting option.
+        first: &'static str,
+        /// Name of the second conflicting option.
+        second: &'static str,
+    },
+    /// Atomic flush is enabled, while the WAL is disabled.
+    #[error("`atomic_flush` cannot be enabled when `wal_sync_mode` is `disabled`")]
+    AtomicFlushWithoutWal,
+    /// More compression types are specified than there are levels.
+    #[error(
+        "`compression_per_level` specifies {} levels, while there are only {}",
+        levels,
+        num_levels
+    )]
+    CompressionPerLevel {
+        /// Length of `compression_per_level`.
+        levels: usize,
+        /// Number of levels in the database.
+        num_levels: usize,
+    },
+    /// TTL is used together with FIFO compaction, which never removes expired entries.
+    #[error(
+        "`ttl_seconds` ({}) cannot be used with the `fifo` compaction style",
+        ttl_seconds
+    )]
+    TtlWithFifoCompaction {
+        /// Value of `ttl_seconds`.
+        ttl_seconds: u64,
+    },
+}
+
 /// Log levels.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -121,6 +1309,31 @@ impl From<LogVerbosity> for LogLevel {
     }
 }
 
+impl LogVerbosity {
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (Self::Debug, "debug"),
+        (Self::Info, "info"),
+        (Self::Warn, "warn"),
+        (Self::Error, "error"),
+        (Self::Fatal, "fatal"),
+        (Self::Header, "header"),
+    ];
+}
+
+impl fmt::Display for LogVerbosity {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(variant_name(Self::NAMES, *self))
+    }
+}
+
+impl FromStr for LogVerbosity {
+    type Err = ParseVariantError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_variant("log verbosity", Self::NAMES, s)
+    }
+}
+
 /// Algorithms of compression for the database.
 ///
 /// Database contents are stored in a set of blocks, each of which holds a
@@ -154,17 +1367,1608 @@ impl From<CompressionType> for DBCompressionType {
     }
 }
 
+impl CompressionType {
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (Self::Bz2, "bz2"),
+        (Self::Lz4, "lz4"),
+        (Self::Lz4hc, "lz4hc"),
+        (Self::Snappy, "snappy"),
+        (Self::Zlib, "zlib"),
+        (Self::Zstd, "zstd"),
+        (Self::None, "none"),
+    ];
+}
+
+impl fmt::Display for CompressionType {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(variant_name(Self::NAMES, *self))
+    }
+}
+
+impl FromStr for CompressionType {
+    type Err = ParseVariantError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_variant("compression type", Self::NAMES, s)
+    }
+}
+
+/// Error parsing an option enum from a string.
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("Unknown {} `{}`, expected one of: {}", kind, value, expected.join(", "))]
+pub struct ParseVariantError {
+    kind: &'static str,
+    value: String,
+    expected: Vec<&'static str>,
+}
+
+/// Returns the name of `variant`, which is the same as the one used by `serde`.
+fn variant_name<T: PartialEq>(names: &[(T, &'static str)], variant: T) -> &'static str {
+    names
+        .iter()
+        .find(|(value, _)| *value == variant)
+        .map(|&(_, name)| name)
+        .expect("All variants are named")
+}
+
+/// Parses a variant from its case-insensitive name.
+fn parse_variant<T: Copy>(
+    kind: &'static str,
+    names: &[(T, &'static str)],
+    s: &str,
+) -> Result<T, ParseVariantError> {
+    names
+        .iter()
+        .find(|(_, name)| name.eq_ignore_ascii_case(s))
+        .map(|&(value, _)| value)
+        .ok_or_else(|| ParseVariantError {
+            kind,
+            value: s.to_owned(),
+            expected: names.iter().map(|&(_, name)| name).collect(),
+        })
+}
+
+/// Styles of compaction of the database files.
+///
+/// See [`RocksDB` wiki] for the detailed description of the compaction styles.
+///
+/// [`RocksDB` wiki]: https://github.com/facebook/rocksdb/wiki/Compaction
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CompactionStyle {
+    /// Level compaction, which is the default one. Provides a good balance between read,
+    /// write and space amplification.
+    Level,
+    /// Universal compaction, which reduces write amplification at the cost of space
+    /// amplification. Suits mostly-append workloads such as archive nodes.
+    Universal,
+    /// FIFO compaction, which drops the oldest files once the total size exceeds a limit.
+    /// Suits only disposable databases, since it loses data.
+    Fifo,
+}
+
+impl From<CompactionStyle> for DBCompactionStyle {
+    fn from(compaction_style: CompactionStyle) -> Self {
+        match compaction_style {
+            CompactionStyle::Level => Self::Level,
+            CompactionStyle::Universal => Self::Universal,
+            CompactionStyle::Fifo => Self::Fifo,
+        }
+    }
+}
+
+/// Parameters of the compression algorithm.
+///
+/// # Examples
+///
+/// ```
+/// # use exonum_merkledb::{CompressionOpts, CompressionType, DbOptions};
+/// let options = DbOptions::builder()
+///     .compression(CompressionType::Zstd)
+///     .compression_options(
+///         CompressionOpts::default()
+///             .level(12)
+///             .max_dict_bytes(16 << 10)
+///             .zstd_max_train_bytes(1600 << 10),
+///     )
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct CompressionOpts {
+    /// Compression level. The meaning depends on the algorithm; for `zstd`, levels
+    /// range from 1 to 22. The default value lets the algorithm choose the level.
+    pub level: i32,
+    /// Base two logarithm of the window size for `zlib`. Defaults to -14.
+    pub window_bits: i32,
+    /// Maximum size of the dictionary in bytes; 0 (default) disables dictionary compression.
+    pub max_dict_bytes: i32,
+    /// Maximum size of the data in bytes sampled to train the `zstd` dictionary;
+    /// 0 (default) disables training.
+    pub zstd_max_train_bytes: i32,
+}
+
+impl CompressionOpts {
+    /// Sets the compression level.
+    pub fn level(mut self, value: i32) -> Self {
+        self.level = value;
+        self
+    }
+
+    /// Sets the window bits.
+    pub fn window_bits(mut self, value: i32) -> Self {
+        self.window_bits = value;
+        self
+    }
+
+    /// Sets the maximum size of the dictionary in bytes.
+    pub fn max_dict_bytes(mut self, value: i32) -> Self {
+        self.max_dict_bytes = value;
+        self
+    }
+
+    /// Sets the maximum size of the training data for the `zstd` dictionary in bytes.
+    pub fn zstd_max_train_bytes(mut self, value: i32) -> Self {
+        self.zstd_max_train_bytes = value;
+        self
+    }
+}
+
+impl Default for CompressionOpts {
+    fn default() -> Self {
+        // Same as the defaults in `RocksDB`.
+        Self {
+            level: 32_767,
+            window_bits: -14,
+            max_dict_bytes: 0,
+            zstd_max_train_bytes: 0,
+        }
+    }
+}
+
+/// Retries of opening a database locked by another process.
+///
+/// # Examples
+///
+/// ```
+/// # use exonum_merkledb::DbOptions;
+/// # use std::time::Duration;
+/// let options = DbOptions::builder()
+///     .open_retries(5, Duration::from_millis(200))
+///     .build()
+///     .unwrap();
+/// let retries = options.open_retries.unwrap();
+/// assert_eq!(retries.attempts, 5);
+/// assert_eq!(retries.backoff(), Duration::from_millis(200));
+/// ```
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct OpenRetries {
+    /// Maximum number of retries. Must be positive.
+    pub attempts: u32,
+    /// Delay before the first retry in milliseconds. Each subsequent delay is twice as long
+    /// as the previous one.
+    pub backoff_millis: u64,
+}
+
+impl OpenRetries {
+    /// Creates retry settings with the specified number of attempts and the initial delay.
+    pub fn new(attempts: u32, backoff: Duration) -> Self {
+        Self {
+            attempts,
+            backoff_millis: backoff.as_millis() as u64,
+        }
+    }
+
+    /// Returns the delay before the first retry.
+    pub fn backoff(&self) -> Duration {
+        Duration::from_millis(self.backoff_millis)
+    }
+}
+
+/// Prevailing access patterns to the database, which define presets of options.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AccessPattern {
+    /// Mostly point lookups by exact keys, which is typical for Merkelized indexes.
+    /// The preset uses a block cache of the specified size in MiB, bloom filters and
+    /// hash indexes within blocks.
+    PointLookup {
+        /// Size of the block cache in MiB.
+        cache_mb: u64,
+    },
+    /// Mixed point lookups and range scans. This is the default `RocksDB` tuning.
+    Mixed,
+}
+
+/// Durability modes of the write-ahead log (WAL).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WalSyncMode {
+    /// Every write is synced to disk before it is acknowledged. The slowest, but the most
+    /// durable mode, which suits validator nodes.
+    Sync,
+    /// Writes are written to the WAL, but are not synced unless explicitly requested with
+    /// `Database::merge_sync`. Recent writes may be lost on a machine crash.
+    Async,
+    /// The WAL is not written at all. Writes which have not been flushed to SST files
+    /// are lost on a process crash; only suitable for disposable databases.
+    Disabled,
+}
+
+/// Options for a single column family, overriding the corresponding values in `DbOptions`.
+///
+/// # Examples
+///
+/// ```
+/// # use exonum_merkledb::{CfOptions, CompressionType, DbOptions};
+/// let options = DbOptions::builder()
+///     .column_family_override(
+///         "blocks",
+///         CfOptions::default().compression(CompressionType::Snappy),
+///     )
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct CfOptions {
+    /// Compression type for the column family.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression_type: Option<CompressionType>,
+    /// Size of a single memtable for the column family in bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub write_buffer_size: Option<usize>,
+    /// Number of bits per key in the bloom filter for the column family.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bloom_filter_bits_per_key: Option<i32>,
+}
+
+impl CfOptions {
+    /// Sets the compression type.
+    pub fn compression(mut self, compression_type: CompressionType) -> Self {
+        self.compression_type = Some(compression_type);
+        self
+    }
+
+    /// Sets the size of a single memtable in bytes.
+    pub fn write_buffer_size(mut self, value: usize) -> Self {
+        self.write_buffer_size = Some(value);
+        self
+    }
+
+    /// Sets the number of bits per key in the bloom filter.
+    pub fn bloom_filter_bits_per_key(mut self, value: i32) -> Self {
+        self.bloom_filter_bits_per_key = Some(value);
+        self
+    }
+}
+
+/// Deserialized representation of `DbOptions`, in which all fields are optional. Missing
+/// fields are taken from the preset, so that configs written by older releases, which lack
+/// the recently added options, remain valid.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct DbOptionsRepr {
+    preset: Option<DbPreset>,
+    /// Keys not corresponding to any option, which are collected to produce
+    /// a helpful error message.
+    #[serde(flatten)]
+    unknown: BTreeMap<String, IgnoredAny>,
+    max_open_files: Option<i32>,
+    create_if_missing: Option<bool>,
+    compression_type: Option<CompressionType>,
+    max_total_wal_size: Option<u64>,
+    log_verbosity: Option<LogVerbosity>,
+    max_log_file_size: Option<usize>,
+    keep_log_file_num: Option<usize>,
+    recycle_log_file_num: Option<usize>,
+    block_cache_size: Option<u64>,
+    write_buffer_size: Option<usize>,
+    max_write_buffer_number: Option<i32>,
+    min_write_buffer_number_to_merge: Option<i32>,
+    bloom_filter_bits_per_key: Option<i32>,
+    block_based_bloom: Option<bool>,
+    compaction_style: Option<CompactionStyle>,
+    fifo_max_table_files_size: Option<u64>,
+    max_background_jobs: Option<i32>,
+    increase_parallelism: Option<i32>,
+    paranoid_checks: Option<bool>,
+    verify_checksums_on_read: Option<bool>,
+    use_direct_reads: Option<bool>,
+    use_direct_io_for_flush_and_compaction: Option<bool>,
+    column_family_overrides: Option<BTreeMap<String, CfOptions>>,
+    rate_limiter_bytes_per_sec: Option<i64>,
+    wal_sync_mode: Option<WalSyncMode>,
+    wal_dir: Option<PathBuf>,
+    db_log_dir: Option<PathBuf>,
+    ttl_seconds: Option<u64>,
+    bottommost_compression: Option<CompressionType>,
+    compression_per_level: Option<Vec<CompressionType>>,
+    compression_options: Option<CompressionOpts>,
+    access_pattern: Option<AccessPattern>,
+    auto_open_files: Option<bool>,
+    periodic_compaction_seconds: Option<u64>,
+    compaction_readahead_size: Option<usize>,
+    atomic_flush: Option<bool>,
+    enable_statistics: Option<bool>,
+    stats_dump_period_sec: Option<u32>,
+    prefix_extractor_len: Option<usize>,
+    memtable_prefix_bloom_ratio: Option<f64>,
+    delete_obsolete_files_period_micros: Option<u64>,
+    max_manifest_file_size: Option<usize>,
+    enable_pipelined_write: Option<bool>,
+    unordered_write: Option<bool>,
+    allow_mmap_reads: Option<bool>,
+    allow_mmap_writes: Option<bool>,
+    read_only: Option<bool>,
+    max_batch_bytes: Option<usize>,
+    open_retries: Option<OpenRetries>,
+}
+
+impl TryFrom<DbOptionsRepr> for DbOptions {
+    type Error = String;
+
+    fn try_from(repr: DbOptionsRepr) -> Result<Self, Self::Error> {
+        if !repr.unknown.is_empty() {
+            let unknown: Vec<_> = repr.unknown.keys().map(String::as_str).collect();
+            return Err(unknown_options_message(&unknown));
+        }
+
+        let base = repr.preset.unwrap_or(DbPreset::Default).options();
+        Ok(Self {
+            max_open_files: repr.max_open_files.or(base.max_open_files),
+            create_if_missing: repr.create_if_missing.unwrap_or(base.create_if_missing),
+            compression_type: repr.compression_type.unwrap_or(base.compression_type),
+            max_total_wal_size: repr.max_total_wal_size.or(base.max_total_wal_size),
+            log_verbosity: repr.log_verbosity.or(base.log_verbosity),
+            max_log_file_size: repr.max_log_file_size.or(base.max_log_file_size),
+            keep_log_file_num: repr.keep_log_file_num.or(base.keep_log_file_num),
+            recycle_log_file_num: repr.recycle_log_file_num.or(base.recycle_log_file_num),
+            block_cache_size: repr.block_cache_size.or(base.block_cache_size),
+            write_buffer_size: repr.write_buffer_size.or(base.write_buffer_size),
+            max_write_buffer_number: repr
+                .max_write_buffer_number
+                .or(base.max_write_buffer_number),
+            min_write_buffer_number_to_merge: repr
+                .min_write_buffer_number_to_merge
+                .or(base.min_write_buffer_number_to_merge),
+            bloom_filter_bits_per_key: repr
+                .bloom_filter_bits_per_key
+                .or(base.bloom_filter_bits_per_key),
+            block_based_bloom: repr.block_based_bloom.unwrap_or(base.block_based_bloom),
+            compaction_style: repr.compaction_style.or(base.compaction_style),
+            fifo_max_table_files_size: repr
+                .fifo_max_table_files_size
+                .or(base.fifo_max_table_files_size),
+            max_background_jobs: repr.max_background_jobs.or(base.max_background_jobs),
+            increase_parallelism: repr.increase_parallelism.or(base.increase_parallelism),
+            paranoid_checks: repr.paranoid_checks.or(base.paranoid_checks),
+            verify_checksums_on_read: repr
+                .verify_checksums_on_read
+                .or(base.verify_checksums_on_read),
+            use_direct_reads: repr.use_direct_reads.or(base.use_direct_reads),
+            use_direct_io_for_flush_and_compaction: repr
+                .use_direct_io_for_flush_and_compaction
+                .or(base.use_direct_io_for_flush_and_compaction),
+            column_family_overrides: repr
+                .column_family_overrides
+                .unwrap_or(base.column_family_overrides),
+            rate_limiter_bytes_per_sec: repr
+                .rate_limiter_bytes_per_sec
+                .or(base.rate_limiter_bytes_per_sec),
+            wal_sync_mode: repr.wal_sync_mode.or(base.wal_sync_mode),
+            wal_dir: repr.wal_dir.or(base.wal_dir),
+            db_log_dir: repr.db_log_dir.or(base.db_log_dir),
+            ttl_seconds: repr.ttl_seconds.or(base.ttl_seconds),
+            bottommost_compression: repr.bottommost_compression.or(base.bottommost_compression),
+            compression_per_level: repr.compression_per_level.or(base.compression_per_level),
+            compression_options: repr.compression_options.or(base.compression_options),
+            access_pattern: repr.access_pattern.or(base.access_pattern),
+            auto_open_files: repr.auto_open_files.unwrap_or(base.auto_open_files),
+            periodic_compaction_seconds: repr
+                .periodic_compaction_seconds
+                .or(base.periodic_compaction_seconds),
+            compaction_readahead_size: repr
+                .compaction_readahead_size
+                .or(base.compaction_readahead_size),
+            atomic_flush: repr.atomic_flush.or(base.atomic_flush),
+            enable_statistics: repr.enable_statistics.or(base.enable_statistics),
+            stats_dump_period_sec: repr.stats_dump_period_sec.or(base.stats_dump_period_sec),
+            prefix_extractor_len: repr.prefix_extractor_len.or(base.prefix_extractor_len),
+            memtable_prefix_bloom_ratio: repr
+                .memtable_prefix_bloom_ratio
+                .or(base.memtable_prefix_bloom_ratio),
+            delete_obsolete_files_period_micros: repr
+                .delete_obsolete_files_period_micros
+                .or(base.delete_obsolete_files_period_micros),
+            max_manifest_file_size: repr.max_manifest_file_size.or(base.max_manifest_file_size),
+            enable_pipelined_write: repr.enable_pipelined_write.or(base.enable_pipelined_write),
+            unordered_write: repr.unordered_write.or(base.unordered_write),
+            allow_mmap_reads: repr.allow_mmap_reads.or(base.allow_mmap_reads),
+            allow_mmap_writes: repr.allow_mmap_writes.or(base.allow_mmap_writes),
+            read_only: repr.read_only.or(base.read_only),
+            max_batch_bytes: repr.max_batch_bytes.or(base.max_batch_bytes),
+            open_retries: repr.open_retries.or(base.open_retries),
+        })
+    }
+}
+
+/// Builds an error message for unknown options, suggesting the closest known option
+/// for each of them.
+fn unknown_options_message(unknown: &[&str]) -> String {
+    if let [name] = unknown {
+        match suggest_option(name) {
+            Some(suggestion) => format!(
+                "unknown database option `{}`; did you mean `{}`?",
+                name, suggestion
+            ),
+            None => format!("unknown database option `{}`", name),
+        }
+    } else {
+        let descriptions: Vec<_> = unknown
+            .iter()
+            .map(|name| match suggest_option(name) {
+                Some(suggestion) => format!("`{}` (did you mean `{}`?)", name, suggestion),
+                None => format!("`{}`", name),
+            })
+            .collect();
+        format!("unknown database options: {}", descriptions.join(", "))
+    }
+}
+
+/// Returns the known option closest to `name`, provided that it is close enough.
+fn suggest_option(name: &str) -> Option<&'static str> {
+    OPTION_NAMES
+        .iter()
+        .map(|&option| (option, edit_distance(name, option)))
+        .filter(|&(_, distance)| distance <= MAX_SUGGESTION_DISTANCE && distance < name.len())
+        .min_by_key(|&(_, distance)| distance)
+        .map(|(option, _)| option)
+}
+
+/// Computes the Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut prev_diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution = prev_diagonal + usize::from(a_char != b_char);
+            prev_diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(prev_diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Named presets of `DbOptions`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DbPreset {
+    /// Default options, see `DbOptions::default()`.
+    Default,
+    /// Options for a node with little memory, see `DbOptions::small_node()`.
+    SmallNode,
+    /// Options for an archive node, see `DbOptions::archive()`.
+    Archive,
+    /// Options for disposable benchmark nodes, see `DbOptions::benchmark()`.
+    Benchmark,
+}
+
+impl DbPreset {
+    /// All named presets.
+    pub const ALL: &'static [Self] = &[
+        DbPreset::Default,
+        DbPreset::SmallNode,
+        DbPreset::Archive,
+        DbPreset::Benchmark,
+    ];
+
+    /// Returns options corresponding to the preset.
+    pub fn options(self) -> DbOptions {
+        match self {
+            DbPreset::Default => DbOptions::default(),
+            DbPreset::SmallNode => DbOptions::small_node(),
+            DbPreset::Archive => DbOptions::archive(),
+            DbPreset::Benchmark => DbOptions::benchmark(),
+        }
+    }
+}
+
 impl Default for DbOptions {
     fn default() -> Self {
-        Self::new(
-            None,
-            true,
-            CompressionType::None,
-            None,
-            None,
-            None,
-            None,
-            None,
-        )
+        Self {
+            max_open_files: None,
+            create_if_missing: true,
+            compression_type: CompressionType::None,
+            max_total_wal_size: None,
+            log_verbosity: None,
+            max_log_file_size: None,
+            keep_log_file_num: None,
+            recycle_log_file_num: None,
+            block_cache_size: None,
+            write_buffer_size: None,
+            max_write_buffer_number: None,
+            min_write_buffer_number_to_merge: None,
+            bloom_filter_bits_per_key: None,
+            block_based_bloom: false,
+            compaction_style: None,
+            fifo_max_table_files_size: None,
+            max_background_jobs: None,
+            increase_parallelism: None,
+            paranoid_checks: None,
+            verify_checksums_on_read: None,
+            use_direct_reads: None,
+            use_direct_io_for_flush_and_compaction: None,
+            column_family_overrides: BTreeMap::new(),
+            rate_limiter_bytes_per_sec: None,
+            wal_sync_mode: None,
+            wal_dir: None,
+            db_log_dir: None,
+            ttl_seconds: None,
+            bottommost_compression: None,
+            compression_per_level: None,
+            compression_options: None,
+            access_pattern: None,
+            auto_open_files: false,
+            periodic_compaction_seconds: None,
+            compaction_readahead_size: None,
+            atomic_flush: None,
+            enable_statistics: None,
+            stats_dump_period_sec: None,
+            prefix_extractor_len: None,
+            memtable_prefix_bloom_ratio: None,
+            delete_obsolete_files_period_micros: None,
+            max_manifest_file_size: None,
+            enable_pipelined_write: None,
+            unordered_write: None,
+            allow_mmap_reads: None,
+            allow_mmap_writes: None,
+            read_only: None,
+            max_batch_bytes: None,
+            open_retries: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_applies_defaults() {
+        let options = DbOptions::builder().build().unwrap();
+        assert_eq!(options, DbOptions::default());
+    }
+
+    #[test]
+    fn builder_sets_fields() {
+        let options = DbOptions::builder()
+            .max_open_files(256)
+            .create_if_missing(false)
+            .compression(CompressionType::Zstd)
+            .wal_size_limit(1 << 20)
+            .log_verbosity(LogVerbosity::Warn)
+            .max_log_file_size(10 << 20)
+            .keep_log_file_num(10)
+            .recycle_log_file_num(1)
+            .block_cache_size(64 << 20)
+            .write_buffer_size(128 << 20)
+            .max_write_buffer_number(4)
+            .min_write_buffer_number_to_merge(2)
+            .bloom_filter_bits_per_key(10)
+            .block_based_bloom(true)
+            .compaction_style(CompactionStyle::Universal)
+            .fifo_max_table_files_size(1 << 30)
+            .max_background_jobs(4)
+            .increase_parallelism(8)
+            .paranoid_checks(false)
+            .verify_checksums_on_read(false)
+            .use_direct_reads(true)
+            .use_direct_io_for_flush_and_compaction(true)
+            .rate_limiter_bytes_per_sec(64 << 20)
+            .wal_sync_mode(WalSyncMode::Sync)
+            .wal_dir("/mnt/fast/wal")
+            .db_log_dir("logs")
+            .ttl_seconds(3_600)
+            .bottommost_compression(CompressionType::Zstd)
+            .compression_per_level(vec![CompressionType::None, CompressionType::Lz4])
+            .compression_options(CompressionOpts::default().level(3))
+            .access_pattern(AccessPattern::Mixed)
+            .auto_open_files(true)
+            .periodic_compaction_seconds(86_400)
+            .compaction_readahead_size(2 << 20)
+            .atomic_flush(true)
+            .enable_statistics(true)
+            .stats_dump_period_sec(60)
+            .prefix_extractor_len(8)
+            .memtable_prefix_bloom_ratio(0.1)
+            .delete_obsolete_files_period_micros(3_600_000_000)
+            .max_manifest_file_size(64 << 20)
+            .enable_pipelined_write(true)
+            .unordered_write(false)
+            .allow_mmap_reads(true)
+            .allow_mmap_writes(false)
+            .read_only(false)
+            .max_batch_bytes(1 << 20)
+            .open_retries(3, Duration::from_secs(1))
+            .build()
+            .unwrap();
+
+        assert_eq!(options.max_open_files, Some(256));
+        assert!(!options.create_if_missing);
+        assert_eq!(options.compression_type, CompressionType::Zstd);
+        assert_eq!(options.max_total_wal_size, Some(1 << 20));
+        assert_eq!(options.log_verbosity, Some(LogVerbosity::Warn));
+        assert_eq!(options.max_log_file_size, Some(10 << 20));
+        assert_eq!(options.keep_log_file_num, Some(10));
+        assert_eq!(options.recycle_log_file_num, Some(1));
+        assert_eq!(options.block_cache_size, Some(64 << 20));
+        assert_eq!(options.write_buffer_size, Some(128 << 20));
+        assert_eq!(options.max_write_buffer_number, Some(4));
+        assert_eq!(options.min_write_buffer_number_to_merge, Some(2));
+        assert_eq!(options.bloom_filter_bits_per_key, Some(10));
+        assert!(options.block_based_bloom);
+        assert_eq!(options.compaction_style, Some(CompactionStyle::Universal));
+        assert_eq!(options.fifo_max_table_files_size, Some(1 << 30));
+        assert_eq!(options.max_background_jobs, Some(4));
+        assert_eq!(options.increase_parallelism, Some(8));
+        assert_eq!(options.paranoid_checks, Some(false));
+        assert_eq!(options.verify_checksums_on_read, Some(false));
+        assert_eq!(options.use_direct_reads, Some(true));
+        assert_eq!(options.use_direct_io_for_flush_and_compaction, Some(true));
+        assert_eq!(options.rate_limiter_bytes_per_sec, Some(64 << 20));
+        assert_eq!(options.wal_sync_mode, Some(WalSyncMode::Sync));
+        assert_eq!(options.wal_dir, Some(PathBuf::from("/mnt/fast/wal")));
+        assert_eq!(options.db_log_dir, Some(PathBuf::from("logs")));
+        assert_eq!(options.ttl_seconds, Some(3_600));
+        assert_eq!(options.bottommost_compression, Some(CompressionType::Zstd));
+        assert_eq!(
+            options.compression_per_level,
+            Some(vec![CompressionType::None, CompressionType::Lz4])
+        );
+        assert_eq!(
+            options.compression_options,
+            Some(CompressionOpts::default().level(3))
+        );
+        assert_eq!(options.access_pattern, Some(AccessPattern::Mixed));
+        assert!(options.auto_open_files);
+        assert_eq!(options.periodic_compaction_seconds, Some(86_400));
+        assert_eq!(options.compaction_readahead_size, Some(2 << 20));
+        assert_eq!(options.atomic_flush, Some(true));
+        assert_eq!(options.enable_statistics, Some(true));
+        assert_eq!(options.stats_dump_period_sec, Some(60));
+        assert_eq!(options.prefix_extractor_len, Some(8));
+        assert_eq!(options.memtable_prefix_bloom_ratio, Some(0.1));
+        assert_eq!(
+            options.delete_obsolete_files_period_micros,
+            Some(3_600_000_000)
+        );
+        assert_eq!(options.max_manifest_file_size, Some(64 << 20));
+        assert_eq!(options.enable_pipelined_write, Some(true));
+        assert_eq!(options.unordered_write, Some(false));
+        assert_eq!(options.allow_mmap_reads, Some(true));
+        assert_eq!(options.allow_mmap_writes, Some(false));
+        assert_eq!(options.read_only, Some(false));
+        assert_eq!(options.max_batch_bytes, Some(1 << 20));
+        assert_eq!(
+            options.open_retries,
+            Some(OpenRetries::new(3, Duration::from_secs(1)))
+        );
+    }
+
+    #[test]
+    fn options_serde_roundtrip() {
+        let options = DbOptions::builder()
+            .max_open_files(256)
+            .compression(CompressionType::Lz4)
+            .block_cache_size(0)
+            .write_buffer_size(16 << 20)
+            .max_write_buffer_number(3)
+            .bloom_filter_bits_per_key(10)
+            .block_based_bloom(true)
+            .compaction_style(CompactionStyle::Universal)
+            .max_background_jobs(4)
+            .increase_parallelism(4)
+            .paranoid_checks(true)
+            .verify_checksums_on_read(false)
+            .use_direct_reads(true)
+            .rate_limiter_bytes_per_sec(16 << 20)
+            .wal_sync_mode(WalSyncMode::Disabled)
+            .wal_dir("wal")
+            .db_log_dir("/var/log/exonum")
+            .ttl_seconds(86_400)
+            .bottommost_compression(CompressionType::Zstd)
+            .compression_per_level(vec![CompressionType::None, CompressionType::Lz4])
+            .compression_options(CompressionOpts::default().level(12).max_dict_bytes(1 << 14))
+            .access_pattern(AccessPattern::PointLookup { cache_mb: 256 })
+            .auto_open_files(true)
+            .periodic_compaction_seconds(7 * 86_400)
+            .compaction_readahead_size(1 << 20)
+            .atomic_flush(false)
+            .enable_statistics(true)
+            .stats_dump_period_sec(300)
+            .prefix_extractor_len(8)
+            .memtable_prefix_bloom_ratio(0.125)
+            .delete_obsolete_files_period_micros(60_000_000)
+            .max_manifest_file_size(128 << 20)
+            .enable_pipelined_write(false)
+            .unordered_write(true)
+            .allow_mmap_reads(false)
+            .allow_mmap_writes(true)
+            .read_only(false)
+            .max_batch_bytes(4 << 20)
+            .open_retries(10, Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let json = serde_json::to_string(&options).unwrap();
+        let restored: DbOptions = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, options);
+
+        let options = DbOptions::builder()
+            .block_cache_size(u64::MAX)
+            .build()
+            .unwrap();
+        let json = serde_json::to_string(&options).unwrap();
+        let restored: DbOptions = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, options);
+    }
+
+    #[test]
+    fn options_toml_roundtrip() {
+        let cases = vec![
+            (
+                DbOptions::builder().column_family_override(
+                    "blocks",
+                    CfOptions::default()
+                        .compression(CompressionType::Zstd)
+                        .write_buffer_size(1 << 20),
+                ),
+                vec![
+                    "[column_family_overrides.blocks]",
+                    "compression_type = \"zstd\"",
+                ],
+            ),
+            (
+                DbOptions::builder().wal_sync_mode(WalSyncMode::Sync),
+                vec!["wal_sync_mode = \"sync\""],
+            ),
+            (
+                DbOptions::builder().wal_sync_mode(WalSyncMode::Async),
+                vec!["wal_sync_mode = \"async\""],
+            ),
+            (
+                DbOptions::builder().wal_sync_mode(WalSyncMode::Disabled),
+                vec!["wal_sync_mode = \"disabled\""],
+            ),
+            (
+                DbOptions::builder().ttl_seconds(86_400),
+                vec!["ttl_seconds = 86400"],
+            ),
+            (
+                DbOptions::builder()
+                    .compression_per_level(vec![CompressionType::None, CompressionType::Lz4])
+                    .bottommost_compression(CompressionType::Zstd),
+                vec![
+                    "compression_per_level = [\"none\", \"lz4\"]",
+                    "bottommost_compression = \"zstd\"",
+                ],
+            ),
+            (
+                DbOptions::builder()
+                    .compression(CompressionType::Zstd)
+                    .compression_options(CompressionOpts::default().level(12)),
+                vec!["[compression_options]", "level = 12"],
+            ),
+            (
+                DbOptions::builder().access_pattern(AccessPattern::PointLookup { cache_mb: 256 }),
+                vec!["[access_pattern]", "cache_mb = 256"],
+            ),
+            (
+                DbOptions::builder().access_pattern(AccessPattern::Mixed),
+                vec!["[access_pattern]"],
+            ),
+            (
+                DbOptions::builder()
+                    .periodic_compaction_seconds(604_800)
+                    .compaction_readahead_size(2 << 20),
+                vec!["periodic_compaction_seconds = 604800"],
+            ),
+            (
+                DbOptions::builder().atomic_flush(true),
+                vec!["atomic_flush = true"],
+            ),
+            (
+                DbOptions::builder()
+                    .enable_statistics(true)
+                    .stats_dump_period_sec(600),
+                vec!["enable_statistics = true", "stats_dump_period_sec = 600"],
+            ),
+            (
+                DbOptions::builder()
+                    .delete_obsolete_files_period_micros(21_600_000_000)
+                    .max_manifest_file_size(64 << 20),
+                vec!["delete_obsolete_files_period_micros = 21600000000"],
+            ),
+            (
+                DbOptions::builder()
+                    .allow_mmap_reads(true)
+                    .allow_mmap_writes(false),
+                vec!["allow_mmap_reads = true", "allow_mmap_writes = false"],
+            ),
+            (
+                DbOptions::builder().read_only(true),
+                vec!["read_only = true"],
+            ),
+            (
+                DbOptions::builder().max_batch_bytes(16 << 20),
+                vec!["max_batch_bytes = 16777216"],
+            ),
+            (
+                DbOptions::builder().open_retries(5, Duration::from_millis(200)),
+                vec!["[open_retries]\nattempts = 5\nbackoff_millis = 200"],
+            ),
+        ];
+
+        for (builder, expected_lines) in cases {
+            let options = builder.build().unwrap();
+            let serialized = toml::Value::try_from(&options).unwrap().to_string();
+            for line in expected_lines {
+                assert!(serialized.contains(line), "{}", serialized);
+            }
+            let restored: DbOptions = toml::from_str(&serialized).unwrap();
+            assert_eq!(restored, options);
+        }
+
+        // Unset options are omitted.
+        let serialized = toml::Value::try_from(DbOptions::default())
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            serialized,
+            "compression_type = \"none\"\ncreate_if_missing = true\n"
+        );
+    }
+
+    #[test]
+    fn builder_keeps_existing_values() {
+        let base = DbOptions::builder().max_open_files(64).build().unwrap();
+        let options = DbOptionsBuilder::from(base)
+            .keep_log_file_num(5)
+            .build()
+            .unwrap();
+        assert_eq!(options.max_open_files, Some(64));
+        assert_eq!(options.keep_log_file_num, Some(5));
+    }
+
+    #[test]
+    fn builder_rejects_zero_log_files() {
+        let err = DbOptions::builder()
+            .keep_log_file_num(0)
+            .build()
+            .unwrap_err();
+        assert_eq!(err, OptionsError::KeepLogFileNumZero);
+    }
+
+    #[test]
+    fn builder_rejects_too_many_write_buffers_to_merge() {
+        let err = DbOptions::builder()
+            .max_write_buffer_number(1)
+            .min_write_buffer_number_to_merge(2)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            OptionsError::WriteBuffersToMerge {
+                max_number: 1,
+                min_to_merge: 2,
+            }
+        );
+
+        DbOptions::builder()
+            .max_write_buffer_number(2)
+            .min_write_buffer_number_to_merge(2)
+            .build()
+            .unwrap();
+    }
+
+    #[test]
+    fn builder_rejects_non_positive_parallelism() {
+        let err = DbOptions::builder()
+            .max_background_jobs(0)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            OptionsError::NotPositive {
+                field: "max_background_jobs",
+                value: 0,
+            }
+        );
+
+        let err = DbOptions::builder()
+            .increase_parallelism(-2)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`increase_parallelism` must be positive, got -2"
+        );
+    }
+
+    #[test]
+    fn compaction_style_serde_names() {
+        let styles = [
+            (CompactionStyle::Level, "\"level\""),
+            (CompactionStyle::Universal, "\"universal\""),
+            (CompactionStyle::Fifo, "\"fifo\""),
+        ];
+        for &(style, name) in &styles {
+            assert_eq!(serde_json::to_string(&style).unwrap(), name);
+            assert_eq!(
+                serde_json::from_str::<CompactionStyle>(name).unwrap(),
+                style
+            );
+        }
+        assert!(serde_json::from_str::<CompactionStyle>("\"FIFO\"").is_err());
+    }
+
+    #[test]
+    fn wal_sync_mode_serde_names() {
+        let modes = [
+            (WalSyncMode::Sync, "\"sync\""),
+            (WalSyncMode::Async, "\"async\""),
+            (WalSyncMode::Disabled, "\"disabled\""),
+        ];
+        for &(mode, name) in &modes {
+            assert_eq!(serde_json::to_string(&mode).unwrap(), name);
+            assert_eq!(serde_json::from_str::<WalSyncMode>(name).unwrap(), mode);
+        }
+    }
+
+    #[test]
+    fn options_deserialize_without_new_fields() {
+        let json = r#"{ "max_open_files": null, "create_if_missing": true, "compression_type": "none",
+            "max_total_wal_size": null, "log_verbosity": null, "max_log_file_size": null,
+            "keep_log_file_num": null, "recycle_log_file_num": null }"#;
+        let options: DbOptions = serde_json::from_str(json).unwrap();
+        assert_eq!(options, DbOptions::default());
+    }
+
+    #[test]
+    fn column_family_overrides() {
+        let options = DbOptions::builder()
+            .compression(CompressionType::Lz4)
+            .write_buffer_size(64 << 20)
+            .column_family_override(
+                "blocks",
+                CfOptions::default()
+                    .compression(CompressionType::None)
+                    .bloom_filter_bits_per_key(10),
+            )
+            .build()
+            .unwrap();
+
+        let blocks = options.for_column_family("blocks");
+        assert_eq!(blocks.compression_type, CompressionType::None);
+        assert_eq!(blocks.write_buffer_size, Some(64 << 20));
+        assert_eq!(blocks.bloom_filter_bits_per_key, Some(10));
+        let other = options.for_column_family("other");
+        assert_eq!(other.compression_type, CompressionType::Lz4);
+        assert_eq!(other.bloom_filter_bits_per_key, None);
+
+        let json = serde_json::to_string(&options).unwrap();
+        let restored: DbOptions = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, options);
+    }
+
+    #[test]
+    fn builder_rejects_zero_ttl() {
+        let err = DbOptions::builder().ttl_seconds(0).build().unwrap_err();
+        assert_eq!(
+            err,
+            OptionsError::NotPositive {
+                field: "ttl_seconds",
+                value: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn validate_accepts_default_options() {
+        DbOptions::default().validate().unwrap();
+    }
+
+    #[test]
+    fn validate_rejects_invalid_max_open_files() {
+        DbOptions::builder().max_open_files(-1).build().unwrap();
+        DbOptions::builder().max_open_files(0).build().unwrap();
+        let err = DbOptions::builder().max_open_files(-2).build().unwrap_err();
+        assert_eq!(err, OptionsError::MaxOpenFiles { value: -2 });
+        assert_eq!(
+            err.to_string(),
+            "`max_open_files` must be -1 (unlimited) or non-negative, got -2"
+        );
+    }
+
+    #[test]
+    fn validate_rejects_single_recycled_log_file() {
+        let builder = DbOptions::builder()
+            .max_log_file_size(1 << 20)
+            .keep_log_file_num(1);
+        builder.clone().build().unwrap();
+        builder.clone().recycle_log_file_num(0).build().unwrap();
+        let err = builder.recycle_log_file_num(1).build().unwrap_err();
+        assert_eq!(
+            err,
+            OptionsError::SingleRecycledLogFile {
+                max_log_file_size: 1 << 20,
+            }
+        );
+    }
+
+    #[test]
+    fn validate_rejects_wal_size_below_write_buffer() {
+        DbOptions::builder()
+            .wal_size_limit(0)
+            .write_buffer_size(64 << 20)
+            .build()
+            .unwrap();
+        DbOptions::builder()
+            .wal_size_limit(1 << 20)
+            .build()
+            .unwrap();
+        let err = DbOptions::builder()
+            .wal_size_limit(1 << 20)
+            .write_buffer_size(64 << 20)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            OptionsError::WalSizeBelowWriteBuffer {
+                max_total_wal_size: 1 << 20,
+                write_buffer_size: 64 << 20,
+            }
+        );
+    }
+
+    #[test]
+    fn validate_rejects_ttl_with_fifo_compaction() {
+        DbOptions::builder()
+            .ttl_seconds(60)
+            .compaction_style(CompactionStyle::Universal)
+            .build()
+            .unwrap();
+        let err = DbOptions::builder()
+            .ttl_seconds(60)
+            .compaction_style(CompactionStyle::Fifo)
+            .build()
+            .unwrap_err();
+        assert_eq!(err, OptionsError::TtlWithFifoCompaction { ttl_seconds: 60 });
+    }
+
+    #[test]
+    fn validate_checks_deserialized_options() {
+        let mut options = DbOptions::default();
+        options.keep_log_file_num = Some(0);
+        assert_eq!(options.validate(), Err(OptionsError::KeepLogFileNumZero));
+    }
+
+    #[test]
+    fn log_verbosity_string_roundtrip() {
+        for &(level, name) in LogVerbosity::NAMES {
+            assert_eq!(level.to_string(), name);
+            assert_eq!(name.parse::<LogVerbosity>().unwrap(), level);
+            assert_eq!(name.to_uppercase().parse::<LogVerbosity>().unwrap(), level);
+            let serialized = serde_json::to_string(&level).unwrap();
+            assert_eq!(serialized, format!("\"{}\"", name));
+        }
+        assert_eq!(LogVerbosity::NAMES.len(), 6);
+    }
+
+    #[test]
+    fn compression_type_string_roundtrip() {
+        for &(compression, name) in CompressionType::NAMES {
+            assert_eq!(compression.to_string(), name);
+            assert_eq!(name.parse::<CompressionType>().unwrap(), compression);
+            let mixed_case = name[..1].to_uppercase() + &name[1..];
+            assert_eq!(mixed_case.parse::<CompressionType>().unwrap(), compression);
+            let serialized = serde_json::to_string(&compression).unwrap();
+            assert_eq!(serialized, format!("\"{}\"", name));
+        }
+        assert_eq!(CompressionType::NAMES.len(), 7);
+    }
+
+    #[test]
+    fn invalid_variant_strings() {
+        let err = "verbose".parse::<LogVerbosity>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown log verbosity `verbose`, expected one of: \
+             debug, info, warn, error, fatal, header"
+        );
+        let err = "gzip".parse::<CompressionType>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown compression type `gzip`, expected one of: \
+             bz2, lz4, lz4hc, snappy, zlib, zstd, none"
+        );
+        assert!("".parse::<CompressionType>().is_err());
+        assert!(" lz4".parse::<CompressionType>().is_err());
+    }
+
+    #[test]
+    fn level_compression_types() {
+        let options = DbOptions::builder()
+            .compression(CompressionType::Lz4)
+            .build()
+            .unwrap();
+        assert_eq!(options.level_compression_types(), None);
+
+        let options = DbOptions::builder()
+            .compression(CompressionType::Lz4)
+            .compression_per_level(vec![CompressionType::None; 2])
+            .build()
+            .unwrap();
+        let levels = options.level_compression_types().unwrap();
+        assert_eq!(levels.len(), NUM_LEVELS);
+        assert_eq!(levels[..2], [CompressionType::None; 2]);
+        assert!(levels[2..].iter().all(|&c| c == CompressionType::Lz4));
+    }
+
+    #[test]
+    fn validate_rejects_too_long_compression_per_level() {
+        DbOptions::builder()
+            .compression_per_level(vec![CompressionType::Lz4; NUM_LEVELS])
+            .build()
+            .unwrap();
+        let err = DbOptions::builder()
+            .compression_per_level(vec![CompressionType::Lz4; NUM_LEVELS + 1])
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            OptionsError::CompressionPerLevel {
+                levels: 8,
+                num_levels: 7,
+            }
+        );
+    }
+
+    #[test]
+    fn compression_usage() {
+        let options = DbOptions::builder()
+            .compression(CompressionType::None)
+            .compression_options(CompressionOpts::default().level(12))
+            .build()
+            .unwrap();
+        assert!(!options.uses_compression());
+
+        let options = DbOptionsBuilder::from(options.clone())
+            .bottommost_compression(CompressionType::Zstd)
+            .build()
+            .unwrap();
+        assert!(options.uses_compression());
+
+        let options = DbOptions::builder()
+            .compression(CompressionType::None)
+            .compression_per_level(vec![CompressionType::None, CompressionType::Lz4])
+            .build()
+            .unwrap();
+        assert!(options.uses_compression());
+        assert!(DbOptions::default().uses_compression());
+    }
+
+    #[test]
+    fn compression_options_defaults_in_serde() {
+        let options: CompressionOpts = serde_json::from_str(r#"{ "level": 12 }"#).unwrap();
+        assert_eq!(options, CompressionOpts::default().level(12));
+    }
+
+    #[test]
+    fn access_pattern_block_cache_size() {
+        let point_lookup = AccessPattern::PointLookup { cache_mb: 256 };
+        assert_eq!(DbOptions::default().effective_block_cache_size(), None);
+
+        let options = DbOptions::builder()
+            .access_pattern(point_lookup)
+            .build()
+            .unwrap();
+        assert_eq!(options.effective_block_cache_size(), Some(256 << 20));
+
+        // Explicit value takes precedence over the preset.
+        let options = DbOptions::builder()
+            .access_pattern(point_lookup)
+            .block_cache_size(64 << 20)
+            .build()
+            .unwrap();
+        assert_eq!(options.effective_block_cache_size(), Some(64 << 20));
+
+        let options = DbOptions::builder()
+            .access_pattern(AccessPattern::Mixed)
+            .build()
+            .unwrap();
+        assert_eq!(options.effective_block_cache_size(), None);
+    }
+
+    #[test]
+    fn access_pattern_serde_format() {
+        let pattern = AccessPattern::PointLookup { cache_mb: 128 };
+        let json = serde_json::to_string(&pattern).unwrap();
+        assert_eq!(json, r#"{"kind":"point_lookup","cache_mb":128}"#);
+        let json = serde_json::to_string(&AccessPattern::Mixed).unwrap();
+        assert_eq!(json, r#"{"kind":"mixed"}"#);
+        let restored: AccessPattern = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, AccessPattern::Mixed);
+    }
+
+    #[test]
+    fn validate_rejects_zero_periodic_compaction() {
+        let err = DbOptions::builder()
+            .periodic_compaction_seconds(0)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            OptionsError::NotPositive {
+                field: "periodic_compaction_seconds",
+                value: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn validate_rejects_atomic_flush_without_wal() {
+        let builder = DbOptions::builder().atomic_flush(true);
+        builder
+            .clone()
+            .wal_sync_mode(WalSyncMode::Sync)
+            .build()
+            .unwrap();
+        DbOptions::builder()
+            .atomic_flush(false)
+            .wal_sync_mode(WalSyncMode::Disabled)
+            .build()
+            .unwrap();
+
+        let err = builder
+            .wal_sync_mode(WalSyncMode::Disabled)
+            .build()
+            .unwrap_err();
+        assert_eq!(err, OptionsError::AtomicFlushWithoutWal);
+        assert_eq!(
+            err.to_string(),
+            "`atomic_flush` cannot be enabled when `wal_sync_mode` is `disabled`"
+        );
+    }
+
+    #[test]
+    fn small_node_preset() {
+        let options = DbOptions::small_node();
+        options.validate().unwrap();
+        assert_eq!(options.max_open_files, Some(256));
+        assert!(options.create_if_missing);
+        assert_eq!(options.compression_type, CompressionType::Snappy);
+        assert_eq!(options.max_total_wal_size, Some(64 << 20));
+        assert_eq!(options.log_verbosity, Some(LogVerbosity::Warn));
+        assert_eq!(options.max_log_file_size, Some(10 << 20));
+        assert_eq!(options.keep_log_file_num, Some(5));
+        assert_eq!(options.block_cache_size, Some(64 << 20));
+        assert_eq!(options.write_buffer_size, Some(16 << 20));
+        assert_eq!(options.max_write_buffer_number, Some(2));
+        assert_eq!(options.max_background_jobs, Some(2));
+        assert_eq!(options.wal_sync_mode, None);
+    }
+
+    #[test]
+    fn archive_preset() {
+        let options = DbOptions::archive();
+        options.validate().unwrap();
+        assert_eq!(options.max_open_files, None);
+        assert!(options.auto_open_files);
+        assert_eq!(options.compression_type, CompressionType::Snappy);
+        assert_eq!(
+            options.bottommost_compression,
+            Some(CompressionType::Snappy)
+        );
+        assert_eq!(options.max_total_wal_size, Some(1 << 30));
+        assert_eq!(options.max_log_file_size, Some(64 << 20));
+        assert_eq!(options.keep_log_file_num, Some(10));
+        assert_eq!(options.block_cache_size, Some(1 << 30));
+        assert_eq!(options.write_buffer_size, Some(128 << 20));
+        assert_eq!(options.max_write_buffer_number, Some(4));
+        assert_eq!(options.min_write_buffer_number_to_merge, Some(2));
+        assert_eq!(options.bloom_filter_bits_per_key, Some(10));
+        assert_eq!(options.max_background_jobs, Some(4));
+        assert_eq!(options.compaction_readahead_size, Some(2 << 20));
+    }
+
+    #[test]
+    fn benchmark_preset() {
+        let options = DbOptions::benchmark();
+        options.validate().unwrap();
+        assert_eq!(options.compression_type, CompressionType::None);
+        assert_eq!(options.log_verbosity, Some(LogVerbosity::Error));
+        assert_eq!(options.max_log_file_size, Some(10 << 20));
+        assert_eq!(options.keep_log_file_num, Some(1));
+        assert_eq!(options.block_cache_size, Some(512 << 20));
+        assert_eq!(options.write_buffer_size, Some(256 << 20));
+        assert_eq!(options.max_write_buffer_number, Some(4));
+        assert_eq!(options.wal_sync_mode, Some(WalSyncMode::Disabled));
+        assert_eq!(options.atomic_flush, None);
+    }
+
+    #[test]
+    fn preset_in_deserialized_options() {
+        let presets = [
+            ("default", DbOptions::default()),
+            ("small-node", DbOptions::small_node()),
+            ("archive", DbOptions::archive()),
+            ("benchmark", DbOptions::benchmark()),
+        ];
+        for (name, expected) in &presets {
+            let json = format!(r#"{{ "preset": "{}" }}"#, name);
+            let options: DbOptions = serde_json::from_str(&json).unwrap();
+            assert_eq!(options, *expected);
+            assert_eq!(
+                serde_json::from_str::<DbPreset>(&format!("\"{}\"", name))
+                    .unwrap()
+                    .options(),
+                *expected
+            );
+        }
+    }
+
+    #[test]
+    fn explicit_fields_override_preset() {
+        let json = r#"{
+            "preset": "small-node",
+            "block_cache_size": 134217728,
+            "compression_type": "none",
+            "create_if_missing": false
+        }"#;
+        let options: DbOptions = serde_json::from_str(json).unwrap();
+        let expected = DbOptions {
+            block_cache_size: Some(128 << 20),
+            compression_type: CompressionType::None,
+            create_if_missing: false,
+            ..DbOptions::small_node()
+        };
+        assert_eq!(options, expected);
+
+        let err = serde_json::from_str::<DbOptions>(r#"{ "preset": "huge-node" }"#).unwrap_err();
+        assert!(
+            err.to_string().contains("unknown variant `huge-node`"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn validate_prefix_bloom_options() {
+        let err = DbOptions::builder()
+            .prefix_extractor_len(0)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            OptionsError::NotPositive {
+                field: "prefix_extractor_len",
+                value: 0,
+            }
+        );
+
+        DbOptions::builder()
+            .memtable_prefix_bloom_ratio(0.25)
+            .build()
+            .unwrap();
+        for &value in &[0.0, -0.1, 0.5, f64::NAN] {
+            let err = DbOptions::builder()
+                .memtable_prefix_bloom_ratio(value)
+                .build()
+                .unwrap_err();
+            assert!(
+                matches!(err, OptionsError::MemtablePrefixBloomRatio { .. }),
+                "{:?}",
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn validate_rejects_small_manifest_file_size() {
+        DbOptions::builder()
+            .max_manifest_file_size(1 << 20)
+            .build()
+            .unwrap();
+        let err = DbOptions::builder()
+            .max_manifest_file_size(1_000)
+            .build()
+            .unwrap_err();
+        assert_eq!(err, OptionsError::ManifestFileSize { value: 1_000 });
+    }
+
+    #[test]
+    fn validate_rejects_pipelined_and_unordered_writes() {
+        DbOptions::builder()
+            .enable_pipelined_write(true)
+            .unordered_write(false)
+            .build()
+            .unwrap();
+        DbOptions::builder().unordered_write(true).build().unwrap();
+
+        let err = DbOptions::builder()
+            .enable_pipelined_write(true)
+            .unordered_write(true)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            OptionsError::Conflict {
+                first: "enable_pipelined_write",
+                second: "unordered_write",
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "`enable_pipelined_write` and `unordered_write` cannot be enabled simultaneously"
+        );
+    }
+
+    #[test]
+    fn validate_rejects_mmap_reads_with_direct_reads() {
+        DbOptions::builder()
+            .allow_mmap_reads(true)
+            .use_direct_reads(false)
+            .use_direct_io_for_flush_and_compaction(true)
+            .build()
+            .unwrap();
+
+        let err = DbOptions::builder()
+            .allow_mmap_reads(true)
+            .use_direct_reads(true)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            OptionsError::Conflict {
+                first: "allow_mmap_reads",
+                second: "use_direct_reads",
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "`allow_mmap_reads` and `use_direct_reads` cannot be enabled simultaneously"
+        );
+    }
+
+    #[test]
+    fn unknown_option_is_rejected_with_suggestion() {
+        let err = serde_json::from_str::<DbOptions>(r#"{ "max_opne_files": 64 }"#).unwrap_err();
+        assert!(
+            err.to_string().starts_with(
+                "unknown database option `max_opne_files`; did you mean `max_open_files`?"
+            ),
+            "{}",
+            err
+        );
+
+        let err = serde_json::from_str::<DbOptions>(r#"{ "frobnicate": true }"#).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("unknown database option `frobnicate`"),
+            "{}",
+            err
+        );
+        assert!(!err.to_string().contains("did you mean"), "{}", err);
+    }
+
+    #[test]
+    fn multiple_unknown_options_are_reported() {
+        let json = r#"{
+            "max_open_files": 64,
+            "write_bufer_size": 1024,
+            "frobnicate": true,
+            "compresion_type": "lz4"
+        }"#;
+        let err = serde_json::from_str::<DbOptions>(json).unwrap_err();
+        assert!(
+            err.to_string().starts_with(
+                "unknown database options: `compresion_type` (did you mean `compression_type`?), \
+                 `frobnicate`, `write_bufer_size` (did you mean `write_buffer_size`?)"
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn known_options_are_accepted() {
+        let json = r#"{
+            "preset": "small-node",
+            "max_open_files": 64,
+            "compression_type": "lz4",
+            "column_family_overrides": { "cf": { "write_buffer_size": 1024 } }
+        }"#;
+        let options: DbOptions = serde_json::from_str(json).unwrap();
+        assert_eq!(options.max_open_files, Some(64));
+        assert_eq!(options.compression_type, CompressionType::Lz4);
+    }
+
+    #[test]
+    fn option_names_are_complete() {
+        let options = DbOptions::builder()
+            .column_family_override("cf", CfOptions::default())
+            .build()
+            .unwrap();
+        let value = serde_json::to_value(&options).unwrap();
+        for name in value.as_object().unwrap().keys() {
+            assert!(OPTION_NAMES.contains(&name.as_str()), "{}", name);
+        }
+    }
+
+    #[test]
+    fn edit_distance_is_levenshtein() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("max_opne_files", "max_open_files"), 2);
+        assert_eq!(suggest_option("ttl_secs"), Some("ttl_seconds"));
+        assert_eq!(suggest_option("x"), None);
+    }
+
+    #[test]
+    fn validate_rejects_read_only_with_ttl() {
+        DbOptions::builder().read_only(true).build().unwrap();
+        DbOptions::builder()
+            .read_only(false)
+            .ttl_seconds(60)
+            .build()
+            .unwrap();
+
+        let err = DbOptions::builder()
+            .read_only(true)
+            .ttl_seconds(60)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            OptionsError::Conflict {
+                first: "read_only",
+                second: "ttl_seconds",
+            }
+        );
+    }
+
+    #[test]
+    fn validate_rejects_zero_max_batch_bytes() {
+        let err = DbOptions::builder().max_batch_bytes(0).build().unwrap_err();
+        assert_eq!(
+            err,
+            OptionsError::NotPositive {
+                field: "max_batch_bytes",
+                value: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn validate_rejects_zero_open_retries() {
+        let err = DbOptions::builder()
+            .open_retries(0, Duration::from_secs(1))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            OptionsError::NotPositive {
+                field: "open_retries.attempts",
+                value: 0,
+            }
+        );
     }
 }
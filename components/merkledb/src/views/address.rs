@@ -21,7 +21,7 @@ pub fn key_bytes<K: BinaryKey + ?Sized>(key: &K) -> Vec<u8> {
 }
 
 const SEPARATOR_CHAR: u8 = 0;
-const MIGRATION_CHAR: u8 = b'^';
+pub(super) const MIGRATION_CHAR: u8 = b'^';
 
 /// Represents the address of an index in the database.
 ///
@@ -24,7 +24,9 @@ use std::{borrow::Cow, io::Error, mem, num::NonZeroU64, vec};
 use crate::{
     access::{AccessError, AccessErrorKind},
     validation::check_index_valid_full_name,
-    views::{IndexAddress, RawAccess, RawAccessMut, ResolvedAddress, View},
+    views::{
+        address::MIGRATION_CHAR, IndexAddress, RawAccess, RawAccessMut, ResolvedAddress, View,
+    },
     BinaryKey, BinaryValue,
 };
 
@@ -268,6 +270,26 @@ impl IndexMetadata {
     }
 }
 
+/// Information about an index stored in the database.
+///
+/// Returned by [`SystemSchema::indexes()`].
+///
+/// [`SystemSchema::indexes()`]: struct.SystemSchema.html#method.indexes
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct IndexInfo {
+    /// Name of the index. For indexes in a group, this is the name of the group.
+    pub name: String,
+    /// Is the index a part of an index group?
+    pub is_in_group: bool,
+    /// Is the index created within a migration?
+    pub is_in_migration: bool,
+    /// Type of the index.
+    pub index_type: IndexType,
+    /// Globally unique numeric index identifier.
+    pub identifier: NonZeroU64,
+}
+
 #[derive(Debug)]
 pub struct IndexState<T, V> {
     metadata: IndexMetadata<V>,
@@ -320,6 +342,43 @@ impl<T: RawAccess> IndexesPool<T> {
         Self(view)
     }
 
+    /// Returns the resolved address of the pool itself.
+    pub(crate) fn address() -> ResolvedAddress {
+        ResolvedAddress::system(INDEXES_POOL_NAME)
+    }
+
+    /// Returns resolved addresses of all indexes in the pool, ordered by their fully qualified
+    /// names. Tombstones are skipped since they never contain any data.
+    pub(crate) fn resolved_addresses(&self) -> Vec<ResolvedAddress> {
+        // The pool length is stored under the empty key, which precedes all index names.
+        self.0
+            .iter_from::<_, _, Vec<u8>, IndexMetadata>(&(), &[0_u8][..])
+            .filter(|(_, metadata)| metadata.index_type != IndexType::Tombstone)
+            .map(|(full_name, metadata)| {
+                let (name, _) = IndexAddress::parse_fully_qualified_name(&full_name, 0);
+                ResolvedAddress::new(name, Some(metadata.identifier))
+            })
+            .collect()
+    }
+
+    /// Returns information about all indexes in the pool, ordered by their fully qualified
+    /// names. Tombstones are included.
+    pub(crate) fn indexes(&self) -> Vec<IndexInfo> {
+        self.0
+            .iter_from::<_, _, Vec<u8>, IndexMetadata>(&(), &[0_u8][..])
+            .map(|(full_name, metadata)| {
+                let (name, is_in_group) = IndexAddress::parse_fully_qualified_name(&full_name, 0);
+                IndexInfo {
+                    name,
+                    is_in_group,
+                    is_in_migration: full_name[0] == MIGRATION_CHAR,
+                    index_type: metadata.index_type,
+                    identifier: metadata.identifier,
+                }
+            })
+            .collect()
+    }
+
     pub(super) fn len(&self) -> u64 {
         self.0.get(&()).unwrap_or_default()
     }
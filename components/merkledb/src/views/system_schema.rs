@@ -15,7 +15,11 @@
 use exonum_crypto::Hash;
 
 use crate::{
-    views::{AsReadonly, IndexType, RawAccess, ViewWithMetadata},
+    db::{DB_METADATA, PREFIX_EXTRACTOR_LEN_NAME, VERSION_NAME},
+    views::{
+        AsReadonly, IndexInfo, IndexType, IndexesPool, RawAccess, ResolvedAddress, View,
+        ViewWithMetadata,
+    },
     Fork, ObjectHash, ProofMapIndex,
 };
 
@@ -86,6 +90,26 @@ impl<T: RawAccess> SystemSchema<T> {
     pub fn state_hash(&self) -> Hash {
         get_state_aggregator(self.0.clone(), "").object_hash()
     }
+
+    /// Returns the data layout version recorded in the database, or `None` if the version
+    /// is not recorded, e.g., if the database was not initialized by `MerkleDB`.
+    pub fn db_version(&self) -> Option<u8> {
+        View::new(self.0.clone(), ResolvedAddress::system(DB_METADATA)).get(VERSION_NAME)
+    }
+
+    /// Returns the length of the fixed key prefix used by prefix bloom filters, or `None`
+    /// if the length is not recorded in the database.
+    pub fn prefix_extractor_len(&self) -> Option<u64> {
+        View::new(self.0.clone(), ResolvedAddress::system(DB_METADATA))
+            .get(PREFIX_EXTRACTOR_LEN_NAME)
+    }
+
+    /// Returns information about all indexes in the database, ordered by their fully
+    /// qualified names. Each index in a group is listed separately; tombstones
+    /// of the indexes removed in migrations are listed as well.
+    pub fn indexes(&self) -> Vec<IndexInfo> {
+        IndexesPool::new(self.0.clone()).indexes()
+    }
 }
 
 impl<T: RawAccess + AsReadonly> SystemSchema<T> {
@@ -141,7 +165,7 @@ impl SystemSchema<&Fork> {
 
 #[cfg(test)]
 mod tests {
-    use super::{Fork, ObjectHash, SystemSchema};
+    use super::{Fork, IndexType, ObjectHash, SystemSchema};
     use crate::{
         access::{AccessExt, CopyAccessExt},
         migration::Migration,
@@ -244,6 +268,38 @@ mod tests {
         assert_eq!(aggregator.object_hash(), system_schema.state_hash());
     }
 
+    #[test]
+    fn listing_indexes() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        initial_changes(&fork);
+        Migration::new("test", &fork).get_entry("entry").set(1_u8);
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        let system_schema = SystemSchema::new(&snapshot);
+        assert_eq!(system_schema.db_version(), Some(0));
+        let indexes = system_schema.indexes();
+        let find = |name: &str| {
+            indexes
+                .iter()
+                .find(|index| index.name == name)
+                .unwrap_or_else(|| panic!("Index `{}` is not listed", name))
+        };
+
+        assert_eq!(find("list").index_type, IndexType::ProofList);
+        assert_eq!(find("non_hashed_list").index_type, IndexType::List);
+        assert_eq!(find("map").index_type, IndexType::ProofMap);
+        assert!(!find("map").is_in_group);
+        let grouped_list = find("grouped_list");
+        assert_eq!(grouped_list.index_type, IndexType::ProofList);
+        assert!(grouped_list.is_in_group);
+        let migrated_entry = find("test.entry");
+        assert_eq!(migrated_entry.index_type, IndexType::Entry);
+        assert!(migrated_entry.is_in_migration);
+        assert!(!find("entry").is_in_migration);
+    }
+
     #[test]
     fn migrated_indexes_do_not_influence_state_hash() {
         let db = TemporaryDB::new();
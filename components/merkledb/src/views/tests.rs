@@ -22,7 +22,8 @@ use crate::{
     db,
     validation::is_valid_identifier,
     views::{IndexAddress, IndexType, RawAccess, View, ViewWithMetadata},
-    Database, DbOptions, Fork, ListIndex, MapIndex, ResolvedAddress, RocksDB, TemporaryDB,
+    Database, DbOptions, Fork, ListIndex, MapIndex, ResolvedAddress, RocksDB, Snapshot,
+    TemporaryDB,
 };
 
 const IDX_NAME: &str = "idx_name";
@@ -410,6 +411,92 @@ fn changelog_prefixed() {
     test_changelog(&TemporaryDB::new(), PREFIXED_IDX);
 }
 
+fn test_multi_get<T, I>(db: &T, address: I)
+where
+    T: Database,
+    I: Into<ResolvedAddress> + Copy,
+{
+    let keys: &[&[u8]] = &[&[1], &[2], &[3], &[4], &[1], &[], &[3]];
+    let check = |snapshot: &dyn Snapshot, expected: &[Option<Vec<u8>>]| {
+        let address = address.into();
+        let values = snapshot.multi_get(&address, keys);
+        let single_values: Vec<_> = keys.iter().map(|key| snapshot.get(&address, key)).collect();
+        assert_eq!(values, single_values);
+        assert_eq!(values, expected);
+    };
+
+    // Missing views return `None` for each key.
+    check(
+        db.snapshot().as_ref(),
+        &[None, None, None, None, None, None, None],
+    );
+
+    let fork = db.fork();
+    {
+        let mut view = View::new(&fork, address);
+        view.put(&vec![1], vec![10]);
+        view.put(&vec![3], vec![30]);
+        view.put(&vec![], vec![0]);
+    }
+    // Unmerged changes are visible when reading from a patch.
+    let patch = fork.into_patch();
+    let expected = [
+        Some(vec![10]),
+        None,
+        Some(vec![30]),
+        None,
+        Some(vec![10]),
+        Some(vec![0]),
+        Some(vec![30]),
+    ];
+    check(&patch, &expected);
+    db.merge(patch).unwrap();
+    check(db.snapshot().as_ref(), &expected);
+
+    // Mix changes with values from the snapshot.
+    let fork = db.fork();
+    {
+        let mut view = View::new(&fork, address);
+        view.put(&vec![2], vec![20]);
+        view.remove(&vec![3]);
+    }
+    let patch = fork.into_patch();
+    let expected = [
+        Some(vec![10]),
+        Some(vec![20]),
+        None,
+        None,
+        Some(vec![10]),
+        Some(vec![0]),
+        None,
+    ];
+    check(&patch, &expected);
+
+    // Clearing the view hides all values from the snapshot.
+    let fork = Fork::from(patch);
+    View::new(&fork, address).clear();
+    let patch = fork.into_patch();
+    check(&patch, &[None, None, None, None, None, None, None]);
+}
+
+#[test]
+fn multi_get() {
+    test_multi_get(&TemporaryDB::new(), IDX_NAME);
+}
+
+#[test]
+fn multi_get_prefixed() {
+    test_multi_get(&TemporaryDB::new(), PREFIXED_IDX);
+}
+
+#[test]
+fn multi_get_rocksdb() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+    test_multi_get(&db, IDX_NAME);
+    test_multi_get(&db, PREFIXED_IDX);
+}
+
 #[test]
 fn multiple_views() {
     let db = TemporaryDB::new();
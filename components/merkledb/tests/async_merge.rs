@@ -0,0 +1,133 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for background merging of patches.
+
+use exonum_merkledb::{access::CopyAccessExt, Database, DbOptions, Patch, RocksDB, TemporaryDB};
+use tempfile::TempDir;
+
+const NUM_PATCHES: u64 = 100;
+
+/// Creates the indexes used in tests, so that patches created from concurrent forks
+/// do not conflict with each other.
+fn create_indexes(db: &dyn Database) {
+    let fork = db.fork();
+    fork.get_map("map").put(&0_u64, 0_u64);
+    fork.get_entry("last").set(0_u64);
+    db.merge_sync(fork.into_patch()).unwrap();
+}
+
+fn patch(db: &dyn Database, i: u64) -> Patch {
+    let fork = db.fork();
+    fork.get_map("map").put(&i, i * 2);
+    fork.get_entry("last").set(i);
+    fork.into_patch()
+}
+
+fn last(db: &dyn Database) -> u64 {
+    db.snapshot().get_entry("last").get().unwrap()
+}
+
+#[test]
+fn async_merges_are_visible_after_wait() {
+    let dir = TempDir::new().unwrap();
+    let db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+    create_indexes(&db);
+
+    for i in 1..=NUM_PATCHES {
+        let handle = db.merge_async(patch(&db, i));
+        // Snapshots observe a prefix of the submitted patches.
+        let observed = last(&db);
+        assert!(observed == i - 1 || observed == i);
+
+        handle.wait().unwrap();
+        let snapshot = db.snapshot();
+        assert_eq!(snapshot.get_entry::<_, u64>("last").get(), Some(i));
+        assert_eq!(snapshot.get_map::<_, u64, u64>("map").get(&i), Some(i * 2));
+    }
+}
+
+#[test]
+fn async_merges_are_applied_in_submission_order() {
+    let dir = TempDir::new().unwrap();
+    let db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+    create_indexes(&db);
+
+    let patches: Vec<_> = (1..=NUM_PATCHES).map(|i| patch(&db, i)).collect();
+    let mut handles = vec![];
+    let mut observed = 0;
+    for patch in patches {
+        handles.push(db.merge_async(patch));
+        let snapshot = db.snapshot();
+        let last = snapshot.get_entry::<_, u64>("last").get().unwrap();
+        assert!(last >= observed, "{} < {}", last, observed);
+        // All patches up to `last` are applied.
+        let map = snapshot.get_map::<_, u64, u64>("map");
+        assert!((1..=last).all(|i| map.get(&i) == Some(i * 2)));
+        observed = last;
+    }
+
+    let mut handles = handles.into_iter();
+    let mut last_handle = handles.next_back().unwrap();
+    for handle in handles {
+        handle.wait().unwrap();
+    }
+    while !last_handle.is_done() {
+        std::thread::yield_now();
+    }
+    last_handle.wait().unwrap();
+    assert_eq!(last(&db), NUM_PATCHES);
+}
+
+#[test]
+fn close_waits_for_pending_merges() {
+    let dir = TempDir::new().unwrap();
+    let db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+    create_indexes(&db);
+    for i in 1..=NUM_PATCHES {
+        // Handles are dropped without waiting.
+        db.merge_async(patch(&db, i));
+    }
+    db.close().unwrap();
+
+    let db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+    assert_eq!(last(&db), NUM_PATCHES);
+}
+
+#[test]
+fn async_merge_into_read_only_database_fails() {
+    let dir = TempDir::new().unwrap();
+    let db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+    create_indexes(&db);
+    drop(db);
+
+    let db = RocksDB::open_read_only(&dir, &DbOptions::default(), false).unwrap();
+    let mut handle = db.merge_async(patch(&db, 1));
+    assert!(handle.is_done());
+    let err = handle.wait().unwrap_err();
+    assert!(err.to_string().contains("read-only"), "{}", err);
+}
+
+#[test]
+fn async_merge_in_temporary_db() {
+    let db = TemporaryDB::new();
+    create_indexes(&db);
+    db.merge_async(patch(&db, 1)).wait().unwrap();
+    assert_eq!(last(&db), 1);
+
+    let db = TemporaryDB::with_options(&DbOptions::default()).unwrap();
+    create_indexes(&db);
+    db.merge_async(patch(&db, 1)).wait().unwrap();
+    assert_eq!(last(&db), 1);
+}
@@ -0,0 +1,103 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for merging patches split into several write batches.
+
+use exonum_merkledb::{
+    access::CopyAccessExt, Database, DbOptions, Patch, RocksDB, SystemSchema, TemporaryDB,
+};
+use tempfile::TempDir;
+
+const NUM_KEYS: u64 = 100_000;
+
+fn prepare_db(db: &dyn Database) {
+    let fork = db.fork();
+    let mut map = fork.get_proof_map("map");
+    for i in (0..NUM_KEYS).step_by(10) {
+        map.put(&i, i.to_string());
+    }
+    fork.get_list("list").extend(0_u64..1_000);
+    db.merge_sync(fork.into_patch()).unwrap();
+}
+
+fn large_patch(db: &dyn Database) -> Patch {
+    let fork = db.fork();
+    let mut map = fork.get_proof_map("map");
+    for i in 0..NUM_KEYS {
+        if i % 7 == 0 {
+            map.remove(&i);
+        } else {
+            map.put(&i, format!("value #{}", i));
+        }
+    }
+    // Clearing an index must be applied before writes to it, even if they end up
+    // in different batches.
+    let mut list = fork.get_list("list");
+    list.clear();
+    list.extend(0_u64..10_000);
+    fork.get_key_set(("group", &1_u8)).insert(&1_u64);
+    fork.into_patch()
+}
+
+fn export(db: &dyn Database) -> Vec<u8> {
+    let mut buffer = vec![];
+    db.export_snapshot(&mut buffer).unwrap();
+    buffer
+}
+
+#[test]
+fn split_merge_yields_same_state() {
+    let reference_dir = TempDir::new().unwrap();
+    let reference_db = RocksDB::open(&reference_dir, &DbOptions::default()).unwrap();
+    prepare_db(&reference_db);
+    reference_db.merge_sync(large_patch(&reference_db)).unwrap();
+
+    let options = DbOptions::builder()
+        .max_batch_bytes(64 << 10)
+        .build()
+        .unwrap();
+    let dir = TempDir::new().unwrap();
+    let db = RocksDB::open(&dir, &options).unwrap();
+    prepare_db(&db);
+    db.merge_in_batches(large_patch(&db)).unwrap();
+
+    assert_eq!(export(&db), export(&reference_db));
+    let snapshot = db.snapshot();
+    assert_eq!(
+        SystemSchema::new(&snapshot).state_hash(),
+        SystemSchema::new(&reference_db.snapshot()).state_hash()
+    );
+    let list = snapshot.get_list::<_, u64>("list");
+    assert_eq!(list.len(), 10_000);
+    assert_eq!(list.get(0), Some(0));
+
+    // The database remains usable after a split merge.
+    drop(snapshot);
+    let fork = db.fork();
+    fork.get_proof_map("map").put(&0_u64, "zero".to_owned());
+    db.merge(fork.into_patch()).unwrap();
+}
+
+#[test]
+fn merge_in_batches_without_limit() {
+    let dir = TempDir::new().unwrap();
+    let db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+    prepare_db(&db);
+    db.merge_in_batches(large_patch(&db)).unwrap();
+
+    let temp_db = TemporaryDB::new();
+    prepare_db(&temp_db);
+    temp_db.merge_in_batches(large_patch(&temp_db)).unwrap();
+    assert_eq!(export(&db), export(&temp_db));
+}
@@ -12,9 +12,18 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use exonum_merkledb::{access::CopyAccessExt, Database, DbOptions, RocksDB};
+use exonum_merkledb::{access::CopyAccessExt, Database, DbOptions, RocksDB, Snapshot, TemporaryDB};
 use tempfile::TempDir;
 
+use std::{
+    fs,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
+
 #[test]
 fn checkpoints() {
     let src_temp_dir = TempDir::new().unwrap();
@@ -88,3 +97,117 @@ fn checkpoints() {
         checkpoint.merge_sync(fork.into_patch()).unwrap();
     }
 }
+
+#[test]
+fn checkpoint_into_existing_directory() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = RocksDB::open(temp_dir.path().join("db"), &DbOptions::default()).unwrap();
+    let fork = db.fork();
+    fork.get_entry("entry").set(1_u64);
+    db.merge_sync(fork.into_patch()).unwrap();
+
+    // An empty directory is replaced with the checkpoint.
+    let empty_dir = temp_dir.path().join("empty");
+    fs::create_dir(&empty_dir).unwrap();
+    db.create_checkpoint(&empty_dir).unwrap();
+    let checkpoint = RocksDB::open(&empty_dir, &DbOptions::default()).unwrap();
+    assert_eq!(checkpoint.snapshot().get_entry("entry").get(), Some(1_u64));
+
+    // A non-empty directory is left intact.
+    let non_empty_dir = temp_dir.path().join("non_empty");
+    fs::create_dir(&non_empty_dir).unwrap();
+    fs::write(non_empty_dir.join("file"), b"data").unwrap();
+    let err = db.create_checkpoint(&non_empty_dir).unwrap_err();
+    assert!(
+        err.to_string().contains("is not an empty directory"),
+        "{}",
+        err
+    );
+    assert_eq!(fs::read(non_empty_dir.join("file")).unwrap(), b"data");
+
+    // Same for a file.
+    let err = db
+        .create_checkpoint(non_empty_dir.join("file"))
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("is not an empty directory"),
+        "{}",
+        err
+    );
+}
+
+/// Checks that the list and its length recorded in the entry are consistent,
+/// and returns the list length.
+fn check_list_consistency(snapshot: &dyn Snapshot) -> u64 {
+    let list = snapshot.get_list::<_, u64>("list");
+    assert_eq!(
+        snapshot.get_entry::<_, u64>("len").get().unwrap_or(0),
+        list.len()
+    );
+    assert!(list.iter().eq(0..list.len()));
+    list.len()
+}
+
+#[test]
+fn checkpoint_during_concurrent_merges() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = Arc::new(RocksDB::open(temp_dir.path().join("db"), &DbOptions::default()).unwrap());
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let writer = {
+        let db = Arc::clone(&db);
+        let stop = Arc::clone(&stop);
+        thread::spawn(move || {
+            while !stop.load(Ordering::SeqCst) {
+                let fork = db.fork();
+                let mut list = fork.get_list("list");
+                list.push(list.len());
+                fork.get_entry("len").set(list.len());
+                db.merge(fork.into_patch()).unwrap();
+            }
+        })
+    };
+
+    while check_list_consistency(&db.snapshot()) < 100 {
+        thread::yield_now();
+    }
+    let len_before = check_list_consistency(&db.snapshot());
+    let checkpoint_path = temp_dir.path().join("checkpoint");
+    db.create_checkpoint(&checkpoint_path).unwrap();
+    let len_after = check_list_consistency(&db.snapshot());
+
+    stop.store(true, Ordering::SeqCst);
+    writer.join().unwrap();
+
+    // The checkpoint contains a state of the database between the snapshots.
+    {
+        let checkpoint = RocksDB::open(&checkpoint_path, &DbOptions::default()).unwrap();
+        let len = check_list_consistency(&checkpoint.snapshot());
+        assert!(len >= len_before && len <= len_after);
+    }
+
+    // With no concurrent writes, the checkpoint matches a snapshot exactly.
+    let snapshot = db.snapshot();
+    let quiescent_path = temp_dir.path().join("quiescent");
+    db.create_checkpoint(&quiescent_path).unwrap();
+    let checkpoint = RocksDB::open(&quiescent_path, &DbOptions::default()).unwrap();
+    let checkpoint_snapshot = checkpoint.snapshot();
+    assert_eq!(
+        check_list_consistency(&checkpoint_snapshot),
+        check_list_consistency(&snapshot)
+    );
+    assert!(checkpoint_snapshot
+        .get_list::<_, u64>("list")
+        .iter()
+        .eq(snapshot.get_list::<_, u64>("list").iter()));
+}
+
+#[test]
+fn checkpoints_are_not_supported_by_temporary_db() {
+    let temp_dir = TempDir::new().unwrap();
+    let db: Box<dyn Database> = Box::new(TemporaryDB::new());
+    let path = temp_dir.path().join("checkpoint");
+    let err = db.create_checkpoint(&path).unwrap_err();
+    assert!(err.to_string().contains("not supported"), "{}", err);
+    assert!(!path.exists());
+}
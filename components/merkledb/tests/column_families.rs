@@ -0,0 +1,162 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for lazy creation and dropping of column families.
+
+use exonum_merkledb::{access::CopyAccessExt, Database, DbOptions, RocksDB, TemporaryDB};
+use tempfile::TempDir;
+
+use std::path::Path;
+
+/// Creates a column family with some data in the database, bypassing `RocksDB` wrapper,
+/// as if it were left over from an obsolete data layout.
+fn create_obsolete_column_family(path: &Path, name: &str) {
+    let mut options = rocksdb::Options::default();
+    options.create_if_missing(true);
+    let names = rocksdb::DB::list_cf(&options, path).unwrap();
+    let mut db = rocksdb::DB::open_cf(&options, path, &names).unwrap();
+    db.create_cf(name, &options).unwrap();
+    let cf = db.cf_handle(name).unwrap();
+    db.put_cf(cf, b"key", b"value").unwrap();
+}
+
+fn has_column_family(db: &RocksDB, name: &str) -> bool {
+    db.column_families().unwrap().iter().any(|cf| cf == name)
+}
+
+#[test]
+fn column_families_are_created_on_first_write() {
+    let dir = TempDir::new().unwrap();
+    let db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+    assert!(!has_column_family(&db, "list"));
+
+    // Touching the index creates its metadata, but not its column family.
+    let fork = db.fork();
+    assert!(fork.get_list::<_, u32>("list").is_empty());
+    db.merge(fork.into_patch()).unwrap();
+    assert!(!has_column_family(&db, "list"));
+    assert!(db.snapshot().get_list::<_, u32>("list").is_empty());
+
+    // Clearing a non-existing column family does not create it either.
+    let fork = db.fork();
+    fork.get_list::<_, u32>("list").clear();
+    db.merge(fork.into_patch()).unwrap();
+    assert!(!has_column_family(&db, "list"));
+
+    let fork = db.fork();
+    fork.get_list("list").push(1_u32);
+    db.merge(fork.into_patch()).unwrap();
+    assert!(has_column_family(&db, "list"));
+    drop(db);
+
+    // The column family is opened after reopening the database.
+    let db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+    assert!(has_column_family(&db, "list"));
+    assert_eq!(db.snapshot().get_list::<_, u32>("list").len(), 1);
+}
+
+fn check_dropping_column_family(db: &dyn Database) {
+    let fork = db.fork();
+    fork.get_list("list").extend(vec![1_u32, 2, 3]);
+    fork.get_map(("map", &1_u8)).put(&1_u8, "!".to_owned());
+    fork.get_map(("map", &2_u8)).put(&2_u8, "?".to_owned());
+    db.merge(fork.into_patch()).unwrap();
+
+    db.drop_column_family("map").unwrap();
+    let snapshot = db.snapshot();
+    assert!(snapshot.get_map::<_, u8, String>(("map", &1_u8)).is_empty());
+    assert!(snapshot.get_map::<_, u8, String>(("map", &2_u8)).is_empty());
+    assert_eq!(snapshot.get_list::<_, u32>("list").len(), 3);
+    let err = db.drop_column_family("map").unwrap_err();
+    assert!(err.to_string().contains("does not exist"), "{}", err);
+
+    // The column family is recreated on the next write.
+    let fork = db.fork();
+    fork.get_map(("map", &1_u8)).put(&3_u8, "!!".to_owned());
+    db.merge(fork.into_patch()).unwrap();
+    let snapshot = db.snapshot();
+    let map = snapshot.get_map::<_, u8, String>(("map", &1_u8));
+    assert_eq!(map.iter().collect::<Vec<_>>(), vec![(3, "!!".to_owned())]);
+
+    // System column families cannot be dropped.
+    for &name in &["default", "__INDEXES_POOL__", "__DB_METADATA__"] {
+        let err = db.drop_column_family(name).unwrap_err();
+        assert!(err.to_string().contains("used by the database"), "{}", err);
+    }
+    // Index metadata is intact.
+    assert_eq!(db.snapshot().get_list::<_, u32>("list").len(), 3);
+}
+
+#[test]
+fn dropping_column_family() {
+    let dir = TempDir::new().unwrap();
+    let db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+    check_dropping_column_family(&db);
+    assert!(has_column_family(&db, "map"));
+}
+
+#[test]
+fn dropping_column_family_in_temporary_db() {
+    check_dropping_column_family(&TemporaryDB::new());
+}
+
+#[test]
+fn dropping_unused_column_families() {
+    let dir = TempDir::new().unwrap();
+    {
+        let db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+        let fork = db.fork();
+        fork.get_list("list").extend(vec![1_u32, 2, 3]);
+        db.merge(fork.into_patch()).unwrap();
+    }
+    create_obsolete_column_family(dir.path(), "obsolete");
+
+    // Databases with extra column families can be opened.
+    let db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+    assert!(has_column_family(&db, "obsolete"));
+
+    // The `list` column family contains a live index.
+    let err = db.drop_unused_column_families(&[]).unwrap_err();
+    assert!(err.to_string().contains("list"), "{}", err);
+    assert!(has_column_family(&db, "obsolete"));
+
+    let dropped = db.drop_unused_column_families(&["list"]).unwrap();
+    assert_eq!(dropped, vec!["obsolete".to_owned()]);
+    assert!(!has_column_family(&db, "obsolete"));
+    assert!(has_column_family(&db, "list"));
+    assert_eq!(db.snapshot().get_list::<_, u32>("list").len(), 3);
+
+    let dropped = db.drop_unused_column_families(&["list"]).unwrap();
+    assert!(dropped.is_empty());
+    drop(db);
+
+    let db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+    assert!(!has_column_family(&db, "obsolete"));
+}
+
+#[test]
+fn dropping_column_families_in_read_only_mode() {
+    let dir = TempDir::new().unwrap();
+    {
+        let db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+        let fork = db.fork();
+        fork.get_list("list").push(1_u32);
+        db.merge(fork.into_patch()).unwrap();
+    }
+
+    let db = RocksDB::open_read_only(&dir, &DbOptions::default(), false).unwrap();
+    db.drop_column_family("list").unwrap_err();
+    db.drop_unused_column_families(&["list"]).unwrap_err();
+    assert!(has_column_family(&db, "list"));
+}
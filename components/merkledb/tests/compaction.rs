@@ -0,0 +1,125 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for manual compaction of databases.
+
+use exonum_merkledb::{
+    access::CopyAccessExt, Database, DbOptions, ErrorKind, RocksDB, TemporaryDB,
+};
+use tempfile::TempDir;
+
+fn fill_map(db: &dyn Database) {
+    let fork = db.fork();
+    let mut map = fork.get_map("map");
+    for i in 0_u64..10_000 {
+        map.put(&i, vec![i as u8; 256]);
+    }
+    db.merge_sync(fork.into_patch()).unwrap();
+}
+
+fn clear_map(db: &dyn Database) {
+    let fork = db.fork();
+    fork.get_map::<_, u64, Vec<u8>>("map").clear();
+    db.merge_sync(fork.into_patch()).unwrap();
+}
+
+fn live_data_size(db: &RocksDB) -> u64 {
+    db.stats().estimated_live_data_size.unwrap()
+}
+
+#[test]
+fn compaction_reclaims_space_after_clearing_index() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = RocksDB::open(temp_dir.path(), &DbOptions::default()).unwrap();
+    fill_map(&db);
+    db.compact().unwrap();
+    let size_before = live_data_size(&db);
+    assert!(size_before > 1 << 20, "{}", size_before);
+
+    clear_map(&db);
+    db.compact().unwrap();
+    let size_after = live_data_size(&db);
+    assert!(
+        size_after < size_before / 10,
+        "{} vs {}",
+        size_after,
+        size_before
+    );
+}
+
+#[test]
+fn compaction_of_ranges() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = RocksDB::open(temp_dir.path(), &DbOptions::default()).unwrap();
+    fill_map(&db);
+    db.compact_range(Some("map"), None, None).unwrap();
+    db.compact_range(None, Some(&[0]), Some(&[0xff])).unwrap();
+    db.compact_range(Some("default"), Some(&[0]), None).unwrap();
+
+    let err = db
+        .compact_range(Some("no_such_cf"), None, None)
+        .unwrap_err();
+    assert!(err.to_string().contains("`no_such_cf`"), "{}", err);
+
+    // Data is not affected by compaction.
+    let snapshot = db.snapshot();
+    let map = snapshot.get_map::<_, u64, Vec<u8>>("map");
+    assert_eq!(map.get(&1_000), Some(vec![232; 256]));
+    assert_eq!(map.iter().count(), 10_000);
+}
+
+#[test]
+fn background_compaction() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = RocksDB::open(temp_dir.path(), &DbOptions::default()).unwrap();
+    fill_map(&db);
+    clear_map(&db);
+    let handle = db.spawn_compaction();
+    // The database can be used while the compaction is running.
+    fill_map(&db);
+    handle.join().unwrap().unwrap();
+    assert_eq!(
+        db.snapshot()
+            .get_map::<_, u64, Vec<u8>>("map")
+            .iter()
+            .count(),
+        10_000
+    );
+}
+
+#[test]
+fn compaction_of_read_only_database_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    {
+        let db = RocksDB::open(temp_dir.path(), &DbOptions::default()).unwrap();
+        fill_map(&db);
+    }
+    let db = RocksDB::open_read_only(temp_dir.path(), &DbOptions::default(), false).unwrap();
+    let err = db.compact().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ReadOnly);
+}
+
+#[test]
+fn compaction_of_temporary_db_is_noop() {
+    let db = TemporaryDB::new();
+    fill_map(&db);
+    db.compact().unwrap();
+    assert_eq!(
+        db.snapshot()
+            .get_map::<_, u64, Vec<u8>>("map")
+            .iter()
+            .count(),
+        10_000
+    );
+}
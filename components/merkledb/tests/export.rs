@@ -0,0 +1,254 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for export and import of database snapshots.
+
+use exonum_crypto::Hash;
+use exonum_merkledb::{
+    access::CopyAccessExt, Database, DbOptions, RocksDB, SystemSchema, TemporaryDB,
+};
+use tempfile::TempDir;
+
+fn fill_db(db: &dyn Database) {
+    let fork = db.fork();
+    let mut map = fork.get_map("map");
+    for i in 0_u64..1_000 {
+        map.put(&i, i.to_string());
+    }
+    let mut list = fork.get_list("list");
+    list.extend(0_u32..500);
+    let mut proof_map = fork.get_proof_map("proof.map");
+    for i in 0_u64..100 {
+        proof_map.put(&i, vec![i as u8; 64]);
+    }
+    fork.get_proof_list("proof.list").extend(vec![1_u8, 2, 3]);
+    fork.get_entry("entry").set("value".to_owned());
+    for i in 0_u32..5 {
+        fork.get_proof_map(("group", &i)).put(&i, i.to_string());
+    }
+    // An empty index should survive the round trip as well.
+    fork.get_key_set::<_, u64>("empty");
+    db.merge_sync(fork.into_patch()).unwrap();
+
+    // Remove some data to check that deletions are reflected in the export.
+    let fork = db.fork();
+    fork.get_map::<_, u64, String>("map").remove(&5);
+    fork.get_list::<_, u32>("list").truncate(400);
+    db.merge_sync(fork.into_patch()).unwrap();
+}
+
+fn check_db(db: &dyn Database, expected_state_hash: Hash) {
+    let snapshot = db.snapshot();
+    assert_eq!(
+        SystemSchema::new(&snapshot).state_hash(),
+        expected_state_hash
+    );
+
+    let map = snapshot.get_map::<_, u64, String>("map");
+    assert_eq!(map.iter().count(), 999);
+    assert_eq!(map.get(&5), None);
+    assert_eq!(map.get(&42), Some("42".to_owned()));
+    let list = snapshot.get_list::<_, u32>("list");
+    assert_eq!(
+        list.iter().collect::<Vec<_>>(),
+        (0..400).collect::<Vec<_>>()
+    );
+    let proof_map = snapshot.get_proof_map::<_, u64, Vec<u8>>("proof.map");
+    assert_eq!(proof_map.iter().count(), 100);
+    assert_eq!(proof_map.get(&99), Some(vec![99; 64]));
+    let proof_list = snapshot.get_proof_list::<_, u8>("proof.list");
+    assert_eq!(proof_list.len(), 3);
+    assert_eq!(
+        snapshot.get_entry::<_, String>("entry").get(),
+        Some("value".to_owned())
+    );
+    for i in 0_u32..5 {
+        let group_map = snapshot.get_proof_map::<_, u32, String>(("group", &i));
+        assert_eq!(
+            group_map.iter().collect::<Vec<_>>(),
+            vec![(i, i.to_string())]
+        );
+    }
+    assert_eq!(snapshot.get_key_set::<_, u64>("empty").iter().count(), 0);
+
+    // Check that the index metadata is intact, so that new indexes can be created.
+    drop(snapshot);
+    let fork = db.fork();
+    fork.get_map("new_map").put(&1_u8, 2_u8);
+    fork.get_map("map").put(&5_u64, "5".to_owned());
+    db.merge_sync(fork.into_patch()).unwrap();
+    let snapshot = db.snapshot();
+    assert_eq!(snapshot.get_map::<_, u8, u8>("new_map").get(&1), Some(2));
+    assert_eq!(
+        snapshot.get_map::<_, u64, String>("map").iter().count(),
+        1_000
+    );
+}
+
+fn export(db: &dyn Database) -> Vec<u8> {
+    let mut buffer = vec![];
+    let stats = db.export_snapshot(&mut buffer).unwrap();
+    assert_eq!(stats.size, buffer.len() as u64);
+    assert!(stats.entries > 0);
+    buffer
+}
+
+#[test]
+fn export_and_import_between_rocksdb_instances() {
+    let src_dir = TempDir::new().unwrap();
+    let dst_dir = TempDir::new().unwrap();
+    let src = RocksDB::open(&src_dir, &DbOptions::default()).unwrap();
+    fill_db(&src);
+    let state_hash = SystemSchema::new(&src.snapshot()).state_hash();
+    let buffer = export(&src);
+
+    let dst = RocksDB::open(&dst_dir, &DbOptions::default()).unwrap();
+    let stats = dst.import_snapshot(&mut buffer.as_slice()).unwrap();
+    assert_eq!(stats, src.export_snapshot(&mut vec![]).unwrap());
+    check_db(&dst, state_hash);
+}
+
+#[test]
+fn export_and_import_between_backends() {
+    let src_dir = TempDir::new().unwrap();
+    let src = RocksDB::open(&src_dir, &DbOptions::default()).unwrap();
+    fill_db(&src);
+    let state_hash = SystemSchema::new(&src.snapshot()).state_hash();
+    let buffer = export(&src);
+
+    let temp_db = TemporaryDB::new();
+    temp_db.import_snapshot(&mut buffer.as_slice()).unwrap();
+    // The export format does not depend on the backend.
+    assert_eq!(export(&temp_db), buffer);
+
+    let dst_dir = TempDir::new().unwrap();
+    let dst = RocksDB::open(&dst_dir, &DbOptions::default()).unwrap();
+    dst.import_snapshot(&mut export(&temp_db).as_slice())
+        .unwrap();
+    check_db(&dst, state_hash);
+    check_db(&temp_db, state_hash);
+}
+
+#[test]
+fn import_into_used_but_empty_database() {
+    let src = TemporaryDB::new();
+    fill_db(&src);
+    let state_hash = SystemSchema::new(&src.snapshot()).state_hash();
+    let buffer = export(&src);
+
+    let dst = TemporaryDB::new();
+    let fork = dst.fork();
+    fork.get_map::<_, u64, u64>("other_map");
+    dst.merge_sync(fork.into_patch()).unwrap();
+
+    dst.import_snapshot(&mut buffer.as_slice()).unwrap();
+    // Metadata of the empty index in the target database is overwritten.
+    assert_eq!(export(&dst), buffer);
+    check_db(&dst, state_hash);
+}
+
+#[test]
+fn import_into_non_empty_database_is_rejected() {
+    let src = TemporaryDB::new();
+    fill_db(&src);
+    let buffer = export(&src);
+
+    let dst = TemporaryDB::new();
+    let fork = dst.fork();
+    fork.get_entry("entry").set(1_u8);
+    dst.merge_sync(fork.into_patch()).unwrap();
+
+    let err = dst.import_snapshot(&mut buffer.as_slice()).unwrap_err();
+    assert!(err.to_string().contains("non-empty"), "{}", err);
+    assert_eq!(dst.snapshot().get_entry::<_, u8>("entry").get(), Some(1));
+}
+
+#[test]
+fn import_with_corrupted_checksum_is_rejected() {
+    let dir = TempDir::new().unwrap();
+    let src = TemporaryDB::new();
+    fill_db(&src);
+    let mut buffer = export(&src);
+    // Corrupt the value of the entry, so that the export remains well-formed.
+    let pos = buffer
+        .windows(5)
+        .position(|window| window == b"value")
+        .unwrap();
+    buffer[pos] ^= 1;
+
+    let dst = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+    let err = dst.import_snapshot(&mut buffer.as_slice()).unwrap_err();
+    assert!(err.to_string().contains("checksum mismatch"), "{}", err);
+
+    // Imported data should be removed, so that the import can be retried.
+    let snapshot = dst.snapshot();
+    assert!(snapshot
+        .get_map::<_, u64, String>("map")
+        .iter()
+        .next()
+        .is_none());
+    assert!(snapshot.get_list::<_, u32>("list").is_empty());
+    drop(snapshot);
+    let buffer = export(&src);
+    dst.import_snapshot(&mut buffer.as_slice()).unwrap();
+    assert_eq!(
+        SystemSchema::new(&dst.snapshot()).state_hash(),
+        SystemSchema::new(&src.snapshot()).state_hash()
+    );
+}
+
+#[test]
+fn partially_imported_data_is_removed_on_error() {
+    let src = TemporaryDB::new();
+    let fork = src.fork();
+    let mut map = fork.get_map("large_map");
+    // The data does not fit into a single import batch.
+    for i in 0_u8..40 {
+        map.put(&i, vec![i; 1 << 20]);
+    }
+    src.merge_sync(fork.into_patch()).unwrap();
+    let mut buffer = export(&src);
+    *buffer.last_mut().unwrap() ^= 1;
+
+    let dir = TempDir::new().unwrap();
+    let dst = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+    let err = dst.import_snapshot(&mut buffer.as_slice()).unwrap_err();
+    assert!(err.to_string().contains("checksum mismatch"), "{}", err);
+    let snapshot = dst.snapshot();
+    let map = snapshot.get_map::<_, u8, Vec<u8>>("large_map");
+    assert!(map.iter().next().is_none());
+}
+
+#[test]
+fn import_of_truncated_export_is_rejected() {
+    let src = TemporaryDB::new();
+    fill_db(&src);
+    let buffer = export(&src);
+
+    let dst = TemporaryDB::new();
+    let truncated = &buffer[..buffer.len() - 1];
+    dst.import_snapshot(&mut &truncated[..]).unwrap_err();
+    let truncated = &buffer[..buffer.len() / 3];
+    dst.import_snapshot(&mut &truncated[..]).unwrap_err();
+    assert!(dst
+        .snapshot()
+        .get_map::<_, u64, String>("map")
+        .get(&1)
+        .is_none());
+
+    let err = dst.import_snapshot(&mut &b"garbage"[..]).unwrap_err();
+    assert!(err.to_string().contains("I/O error"), "{}", err);
+    let err = dst.import_snapshot(&mut &b"NOTANEXPORT"[..]).unwrap_err();
+    assert!(err.to_string().contains("magic"), "{}", err);
+}
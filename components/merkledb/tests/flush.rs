@@ -0,0 +1,166 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for flushing and closing databases.
+
+use exonum_merkledb::{
+    access::CopyAccessExt, Database, DbOptions, RocksDB, TemporaryDB, WalSyncMode,
+};
+use tempfile::TempDir;
+
+use std::{fs, mem, path::Path, sync::Arc, thread};
+
+/// Options with the disabled write-ahead log, so that the data not flushed from memtables
+/// is lost if the database is not closed properly.
+fn options_without_wal() -> DbOptions {
+    DbOptions::builder()
+        .wal_sync_mode(WalSyncMode::Disabled)
+        .build()
+        .unwrap()
+}
+
+fn write_data(db: &dyn Database) {
+    let fork = db.fork();
+    fork.get_list("list").extend(0_u32..100);
+    fork.get_proof_map(("map", &1_u8))
+        .put(&1_u8, "!".to_owned());
+    db.merge(fork.into_patch()).unwrap();
+}
+
+fn check_data(db: &dyn Database) {
+    let snapshot = db.snapshot();
+    assert_eq!(snapshot.get_list::<_, u32>("list").len(), 100);
+    assert_eq!(
+        snapshot
+            .get_proof_map::<_, u8, String>(("map", &1_u8))
+            .get(&1),
+        Some("!".to_owned())
+    );
+}
+
+/// Copies the database directory as a filesystem-level backup would do.
+fn copy_dir(from: &Path, to: &Path) {
+    for entry in fs::read_dir(from).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_file() {
+            fs::copy(&path, to.join(path.file_name().unwrap())).unwrap();
+        }
+    }
+}
+
+#[test]
+fn flushed_data_survives_lost_handle() {
+    let dir = TempDir::new().unwrap();
+    let db = RocksDB::open(&dir, &options_without_wal()).unwrap();
+    write_data(&db);
+    db.flush().unwrap();
+    // The handle is never dropped, so the data is only persisted because of the flush.
+    mem::forget(db);
+
+    let backup_dir = TempDir::new().unwrap();
+    copy_dir(dir.path(), backup_dir.path());
+    let db = RocksDB::open(&backup_dir, &options_without_wal()).unwrap();
+    check_data(&db);
+}
+
+#[test]
+fn flush_via_database_trait() {
+    let dir = TempDir::new().unwrap();
+    let db: Arc<dyn Database> = RocksDB::open(&dir, &options_without_wal()).unwrap().into();
+    write_data(db.as_ref());
+    db.flush().unwrap();
+    mem::forget(db);
+
+    let db = RocksDB::open_read_only(&dir, &options_without_wal(), false).unwrap();
+    check_data(&db);
+    // Flushing a read-only database is a no-op.
+    db.flush().unwrap();
+}
+
+#[test]
+fn closed_database_persists_data() {
+    let dir = TempDir::new().unwrap();
+    let db = RocksDB::open(&dir, &options_without_wal()).unwrap();
+    write_data(&db);
+    db.close().unwrap();
+
+    let db = RocksDB::open(&dir, &options_without_wal()).unwrap();
+    check_data(&db);
+    db.close().unwrap();
+
+    let db = RocksDB::open_read_only(&dir, &DbOptions::default(), false).unwrap();
+    check_data(&db);
+    db.close().unwrap();
+}
+
+#[test]
+fn flush_is_safe_during_merges() {
+    const THREADS: u32 = 4;
+    const PATCHES: u32 = 50;
+
+    let dir = TempDir::new().unwrap();
+    let db = Arc::new(RocksDB::open(&dir, &DbOptions::default()).unwrap());
+    // Indexes are created beforehand, since concurrent patches must not create indexes.
+    let fork = db.fork();
+    for i in 0..THREADS {
+        fork.get_map::<_, u32, String>(("map", &i));
+    }
+    db.merge(fork.into_patch()).unwrap();
+
+    let writers: Vec<_> = (0..THREADS)
+        .map(|i| {
+            let db = Arc::clone(&db);
+            thread::spawn(move || {
+                for j in 0..PATCHES {
+                    let fork = db.fork();
+                    fork.get_map(("map", &i)).put(&j, j.to_string());
+                    db.merge(fork.into_patch()).unwrap();
+                }
+            })
+        })
+        .collect();
+    let flusher = {
+        let db = Arc::clone(&db);
+        thread::spawn(move || {
+            for _ in 0..20 {
+                db.flush().unwrap();
+            }
+        })
+    };
+
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    flusher.join().unwrap();
+    db.flush().unwrap();
+
+    let snapshot = db.snapshot();
+    for i in 0..THREADS {
+        let map = snapshot.get_map::<_, u32, String>(("map", &i));
+        assert_eq!(map.keys().count() as u32, PATCHES);
+    }
+}
+
+#[test]
+fn flush_is_noop_for_temporary_db() {
+    let db = TemporaryDB::new();
+    write_data(&db);
+    db.flush().unwrap();
+    check_data(&db);
+
+    let db = TemporaryDB::with_options(&DbOptions::default()).unwrap();
+    write_data(&db);
+    db.flush().unwrap();
+    check_data(&db);
+}
@@ -0,0 +1,114 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests incremental backups created with `Database::backup()`.
+
+use exonum_merkledb::{
+    access::CopyAccessExt, list_backups, purge_old_backups, restore_from_backup, Database,
+    DbOptions, RocksDB, TemporaryDB,
+};
+use tempfile::TempDir;
+
+use std::fs;
+
+fn write_entry(db: &RocksDB, name: &str, value: u64) {
+    let fork = db.fork();
+    fork.get_entry(name).set(value);
+    db.merge_sync(fork.into_patch()).unwrap();
+}
+
+#[test]
+fn incremental_backups_and_restore() {
+    let temp_dir = TempDir::new().unwrap();
+    let backup_dir = temp_dir.path().join("backups");
+    let db = RocksDB::open(temp_dir.path().join("db"), &DbOptions::default()).unwrap();
+
+    write_entry(&db, "first", 1);
+    let first = db.backup(&backup_dir).unwrap();
+    write_entry(&db, "second", 2);
+    let second = db.backup(&backup_dir).unwrap();
+
+    assert!(second.id > first.id);
+    assert!(second.timestamp >= first.timestamp);
+    assert!(second.sequence_number.unwrap() > first.sequence_number.unwrap());
+    assert!(first.size > 0);
+    assert_eq!(list_backups(&backup_dir).unwrap(), vec![first, second]);
+    drop(db);
+
+    let restored_dir = temp_dir.path().join("restored");
+    restore_from_backup(&backup_dir, &restored_dir, first.id).unwrap();
+    let restored = RocksDB::open(&restored_dir, &DbOptions::default()).unwrap();
+    let snapshot = restored.snapshot();
+    assert_eq!(snapshot.get_entry::<_, u64>("first").get(), Some(1));
+    assert_eq!(snapshot.get_entry::<_, u64>("second").get(), None);
+    drop(snapshot);
+    drop(restored);
+
+    restore_from_backup(&backup_dir, &restored_dir, second.id).unwrap();
+    let restored = RocksDB::open(&restored_dir, &DbOptions::default()).unwrap();
+    let snapshot = restored.snapshot();
+    assert_eq!(snapshot.get_entry::<_, u64>("first").get(), Some(1));
+    assert_eq!(snapshot.get_entry::<_, u64>("second").get(), Some(2));
+}
+
+#[test]
+fn purging_old_backups() {
+    let temp_dir = TempDir::new().unwrap();
+    let backup_dir = temp_dir.path().join("backups");
+    let db = RocksDB::open(temp_dir.path().join("db"), &DbOptions::default()).unwrap();
+
+    let mut backups = vec![];
+    for i in 0..3 {
+        write_entry(&db, "entry", i);
+        backups.push(db.backup(&backup_dir).unwrap());
+    }
+    purge_old_backups(&backup_dir, 1).unwrap();
+    assert_eq!(list_backups(&backup_dir).unwrap(), vec![backups[2]]);
+
+    let err = restore_from_backup(&backup_dir, temp_dir.path().join("restored"), backups[0].id)
+        .unwrap_err();
+    assert!(err.to_string().contains("No backup with ID"), "{}", err);
+}
+
+#[test]
+fn invalid_backup_directories() {
+    let temp_dir = TempDir::new().unwrap();
+    let missing_dir = temp_dir.path().join("missing");
+    let err = list_backups(&missing_dir).unwrap_err();
+    assert!(err.to_string().contains("does not exist"), "{}", err);
+    let err = restore_from_backup(&missing_dir, temp_dir.path().join("db"), 1).unwrap_err();
+    assert!(err.to_string().contains("does not exist"), "{}", err);
+
+    // Corrupt the metadata of a backup.
+    let backup_dir = temp_dir.path().join("backups");
+    let db = RocksDB::open(temp_dir.path().join("db"), &DbOptions::default()).unwrap();
+    write_entry(&db, "entry", 1);
+    let info = db.backup(&backup_dir).unwrap();
+    drop(db);
+    let meta_path = backup_dir.join("meta").join(info.id.to_string());
+    fs::write(&meta_path, b"garbage").unwrap();
+
+    let result = list_backups(&backup_dir)
+        .and_then(|_| restore_from_backup(&backup_dir, temp_dir.path().join("restored"), info.id));
+    let err = result.unwrap_err();
+    assert!(err.to_string().to_lowercase().contains("backup"), "{}", err);
+}
+
+#[test]
+fn backups_are_not_supported_by_temporary_db() {
+    let temp_dir = TempDir::new().unwrap();
+    let db: Box<dyn Database> = Box::new(TemporaryDB::new());
+    let err = db.backup(temp_dir.path()).unwrap_err();
+    assert!(err.to_string().contains("not supported"), "{}", err);
+}
@@ -0,0 +1,118 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for verification of the physical integrity of databases.
+
+use exonum_merkledb::{
+    access::CopyAccessExt, Database, DbOptions, IntegrityReport, RocksDB, TemporaryDB,
+};
+use tempfile::TempDir;
+
+use std::{fs, path::Path};
+
+const NUM_ENTRIES: u64 = 10_000;
+
+fn write_data(db: &dyn Database) {
+    let fork = db.fork();
+    let mut map = fork.get_map("map");
+    for i in 0..NUM_ENTRIES {
+        map.put(&i, format!("value #{}", i).repeat(10));
+    }
+    fork.get_list(("list", &1_u8)).extend(0..NUM_ENTRIES);
+    db.merge_sync(fork.into_patch()).unwrap();
+}
+
+fn cf_keys(report: &IntegrityReport, name: &str) -> u64 {
+    report
+        .column_families
+        .iter()
+        .find(|cf| cf.name == name)
+        .map_or(0, |cf| cf.keys)
+}
+
+/// Returns the largest SST file in the directory.
+fn largest_sst_file(path: &Path) -> std::path::PathBuf {
+    fs::read_dir(path)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "sst"))
+        .max_by_key(|path| fs::metadata(path).unwrap().len())
+        .expect("No SST files")
+}
+
+#[test]
+fn healthy_database_has_no_errors() {
+    let dir = TempDir::new().unwrap();
+    let db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+    write_data(&db);
+    // Check both data in memtables and in SST files.
+    let report = db.verify_integrity().unwrap();
+    assert!(report.is_healthy(), "{:?}", report);
+    assert_eq!(cf_keys(&report, "map"), NUM_ENTRIES);
+    assert_eq!(cf_keys(&report, "list"), NUM_ENTRIES);
+
+    db.compact().unwrap();
+    let compacted_report = db.verify_integrity().unwrap();
+    assert!(compacted_report.is_healthy(), "{:?}", compacted_report);
+    assert_eq!(compacted_report.total_keys(), report.total_keys());
+    assert_eq!(compacted_report.total_bytes(), report.total_bytes());
+    assert!(report.total_bytes() > NUM_ENTRIES * 100);
+}
+
+#[test]
+fn truncated_sst_file_is_reported() {
+    let dir = TempDir::new().unwrap();
+    let mut options = DbOptions::default();
+    // All table files are opened on start, so that their footers are read before truncation.
+    options.max_open_files = Some(-1);
+    {
+        let db = RocksDB::open(&dir, &options).unwrap();
+        write_data(&db);
+        db.compact().unwrap();
+    }
+
+    let db = RocksDB::open(&dir, &options).unwrap();
+    let sst_file = largest_sst_file(dir.path());
+    let len = fs::metadata(&sst_file).unwrap().len();
+    fs::OpenOptions::new()
+        .write(true)
+        .open(&sst_file)
+        .unwrap()
+        .set_len(len / 2)
+        .unwrap();
+
+    let report = db.verify_integrity().unwrap();
+    assert!(!report.is_healthy());
+    assert!(cf_keys(&report, "map") + cf_keys(&report, "list") < NUM_ENTRIES * 2);
+    let error = &report.errors[0];
+    assert!(error.column_family == "map" || error.column_family == "list");
+    assert!(!error.message.is_empty());
+    // Verification is not aborted on errors.
+    assert!(report.column_families.iter().any(|cf| cf.name == "list"));
+    assert!(report.column_families.iter().any(|cf| cf.name == "map"));
+}
+
+#[test]
+fn report_is_serializable() {
+    let db = TemporaryDB::new();
+    write_data(&db);
+    let report = db.verify_integrity().unwrap();
+    assert!(report.is_healthy());
+    assert_eq!(cf_keys(&report, "map"), NUM_ENTRIES);
+
+    let json = serde_json::to_string_pretty(&report).unwrap();
+    assert!(json.contains("\"column_families\""), "{}", json);
+    let restored: IntegrityReport = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, report);
+}
@@ -0,0 +1,88 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests that database options serialized by previous releases can still be loaded.
+
+use exonum_merkledb::{CompressionType, DbOptions, LogVerbosity};
+
+/// `[database]` section written by `generate-config` of Exonum 1.0.
+const RELEASE_1_0_DEFAULT: &str = r#"
+compression_type = "none"
+create_if_missing = true
+"#;
+
+/// `[database]` section of Exonum 1.0 tuned by an operator.
+const RELEASE_1_0_TUNED: &str = r#"
+compression_type = "lz4"
+create_if_missing = false
+max_open_files = 256
+max_total_wal_size = 1073741824
+log_verbosity = "warn"
+max_log_file_size = 10485760
+keep_log_file_num = 5
+recycle_log_file_num = 2
+"#;
+
+#[test]
+fn release_1_0_default_config() {
+    let options: DbOptions = toml::from_str(RELEASE_1_0_DEFAULT).unwrap();
+    assert_eq!(options, DbOptions::default());
+    options.validate().unwrap();
+}
+
+#[test]
+fn release_1_0_tuned_config() {
+    let options: DbOptions = toml::from_str(RELEASE_1_0_TUNED).unwrap();
+    assert_eq!(options.compression_type, CompressionType::Lz4);
+    assert!(!options.create_if_missing);
+    assert_eq!(options.max_open_files, Some(256));
+    assert_eq!(options.max_total_wal_size, Some(1 << 30));
+    assert_eq!(options.log_verbosity, Some(LogVerbosity::Warn));
+    assert_eq!(options.max_log_file_size, Some(10 << 20));
+    assert_eq!(options.keep_log_file_num, Some(5));
+    assert_eq!(options.recycle_log_file_num, Some(2));
+
+    // Options introduced after 1.0 take their documented defaults.
+    assert_eq!(options.block_cache_size, None);
+    assert_eq!(options.write_buffer_size, None);
+    assert!(!options.block_based_bloom);
+    assert!(!options.auto_open_files);
+    assert!(options.column_family_overrides.is_empty());
+    assert_eq!(options.wal_sync_mode, None);
+    assert_eq!(options.ttl_seconds, None);
+    assert_eq!(options.access_pattern, None);
+    assert_eq!(options.atomic_flush, None);
+    assert_eq!(options.prefix_extractor_len, None);
+    options.validate().unwrap();
+}
+
+#[test]
+fn empty_config() {
+    let options: DbOptions = toml::from_str("").unwrap();
+    assert_eq!(options, DbOptions::default());
+}
+
+#[test]
+fn none_options_are_not_serialized() {
+    let options = DbOptions::default();
+    let serialized = toml::to_string(&options).unwrap();
+    assert!(!serialized.contains("max_open_files"), "{}", serialized);
+    let json = serde_json::to_string(&options).unwrap();
+    assert!(!json.contains("null"), "{}", json);
+
+    let options = DbOptions::builder().max_open_files(64).build().unwrap();
+    let json = serde_json::to_string(&options).unwrap();
+    assert!(json.contains(r#""max_open_files":64"#), "{}", json);
+    assert!(!json.contains("null"), "{}", json);
+}
@@ -0,0 +1,98 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for opening databases locked by another instance.
+
+use exonum_merkledb::{access::CopyAccessExt, Database, DbOptions, ErrorKind, RocksDB};
+use tempfile::TempDir;
+
+use std::{
+    process, thread,
+    time::{Duration, Instant},
+};
+
+#[test]
+fn opening_locked_database_fails_with_typed_error() {
+    let dir = TempDir::new().unwrap();
+    let _db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+
+    let err = RocksDB::open(&dir, &DbOptions::default()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Locked);
+    let message = err.to_string();
+    let lock_path = dir.path().join("LOCK");
+    assert!(
+        message.contains(&lock_path.display().to_string()),
+        "{}",
+        message
+    );
+    assert!(
+        message.contains(&format!("PID {}", process::id())),
+        "{}",
+        message
+    );
+    assert!(message.contains("--db-path"), "{}", message);
+}
+
+#[test]
+fn opening_locked_database_fails_after_retries() {
+    let dir = TempDir::new().unwrap();
+    let _db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+
+    let options = DbOptions::builder()
+        .open_retries(2, Duration::from_millis(50))
+        .build()
+        .unwrap();
+    let start = Instant::now();
+    let err = RocksDB::open(&dir, &options).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Locked);
+    // Delays are 50 ms and 100 ms.
+    assert!(start.elapsed() >= Duration::from_millis(150));
+}
+
+#[test]
+fn retries_succeed_once_database_is_released() {
+    let dir = TempDir::new().unwrap();
+    let db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+    let fork = db.fork();
+    fork.get_entry("entry").set(42_u64);
+    db.merge_sync(fork.into_patch()).unwrap();
+
+    let handle = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(300));
+        drop(db);
+    });
+
+    let options = DbOptions::builder()
+        .open_retries(10, Duration::from_millis(50))
+        .build()
+        .unwrap();
+    let db = RocksDB::open(&dir, &options).unwrap();
+    handle.join().unwrap();
+    let snapshot = db.snapshot();
+    assert_eq!(snapshot.get_entry::<_, u64>("entry").get(), Some(42));
+}
+
+#[test]
+fn other_errors_are_not_retried() {
+    let dir = TempDir::new().unwrap();
+    let options = DbOptions::builder()
+        .create_if_missing(false)
+        .open_retries(5, Duration::from_secs(10))
+        .build()
+        .unwrap();
+    let start = Instant::now();
+    let err = RocksDB::open(dir.path().join("missing"), &options).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Other);
+    assert!(start.elapsed() < Duration::from_secs(10));
+}
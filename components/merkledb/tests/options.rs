@@ -0,0 +1,587 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests opening `RocksDB` with non-default `DbOptions`.
+
+use exonum_merkledb::{
+    access::CopyAccessExt, AccessPattern, CfOptions, CompactionStyle, CompressionOpts,
+    CompressionType, Database, DbOptions, RocksDB, SystemSchema, WalSyncMode,
+};
+use tempfile::TempDir;
+
+use std::{fs, path::Path};
+
+/// Opens a database with the provided options, writes some data to it, reopens it and
+/// checks that the data is readable.
+fn check_open_with_options(options: &DbOptions) {
+    let temp_dir = TempDir::new().unwrap();
+    {
+        let db = RocksDB::open(temp_dir.path(), options).unwrap();
+        let fork = db.fork();
+        for i in 0_u64..1_000 {
+            fork.get_map("map").put(&i, vec![i as u8; 64]);
+        }
+        fork.get_entry("entry").set(42_u64);
+        db.merge_sync(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        assert_eq!(
+            snapshot.get_map::<_, u64, Vec<u8>>("map").get(&5),
+            Some(vec![5; 64])
+        );
+    }
+
+    let db = RocksDB::open(temp_dir.path(), options).unwrap();
+    let snapshot = db.snapshot();
+    let map = snapshot.get_map::<_, u64, Vec<u8>>("map");
+    assert_eq!(map.iter().count(), 1_000);
+    assert_eq!(map.get(&999), Some(vec![231; 64]));
+    assert_eq!(snapshot.get_entry::<_, u64>("entry").get(), Some(42));
+}
+
+#[test]
+fn open_with_block_cache() {
+    let options = DbOptions::builder()
+        .block_cache_size(16 << 20)
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+fn open_with_disabled_block_cache() {
+    let options = DbOptions::builder().block_cache_size(0).build().unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+fn open_with_write_buffers() {
+    let options = DbOptions::builder()
+        .write_buffer_size(1 << 20)
+        .max_write_buffer_number(4)
+        .min_write_buffer_number_to_merge(2)
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+fn open_with_bloom_filters() {
+    let options = DbOptions::builder()
+        .bloom_filter_bits_per_key(10)
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+
+    let options = DbOptions::builder()
+        .bloom_filter_bits_per_key(10)
+        .block_based_bloom(true)
+        .block_cache_size(8 << 20)
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+fn open_with_compaction_styles() {
+    for &style in &[CompactionStyle::Level, CompactionStyle::Universal] {
+        let options = DbOptions::builder()
+            .compaction_style(style)
+            .build()
+            .unwrap();
+        check_open_with_options(&options);
+    }
+
+    let options = DbOptions::builder()
+        .compaction_style(CompactionStyle::Fifo)
+        .fifo_max_table_files_size(256 << 20)
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+fn open_with_background_jobs() {
+    let options = DbOptions::builder()
+        .increase_parallelism(4)
+        .max_background_jobs(4)
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+fn open_with_paranoid_checks_and_checksums() {
+    let options = DbOptions::builder()
+        .paranoid_checks(true)
+        .verify_checksums_on_read(true)
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+fn toggle_checksum_verification_at_runtime() {
+    let temp_dir = TempDir::new().unwrap();
+    let options = DbOptions::builder()
+        .verify_checksums_on_read(false)
+        .build()
+        .unwrap();
+    let db = RocksDB::open(temp_dir.path(), &options).unwrap();
+    assert!(!db.verify_checksums_on_read());
+
+    let fork = db.fork();
+    fork.get_entry("entry").set(42_u64);
+    db.merge_sync(fork.into_patch()).unwrap();
+
+    db.set_verify_checksums_on_read(true);
+    assert!(db.verify_checksums_on_read());
+    let snapshot = db.snapshot();
+    assert_eq!(snapshot.get_entry::<_, u64>("entry").get(), Some(42));
+}
+
+#[test]
+fn open_with_direct_io() {
+    let options = DbOptions::builder()
+        .use_direct_reads(true)
+        .use_direct_io_for_flush_and_compaction(true)
+        .build()
+        .unwrap();
+
+    // Direct I/O may be unsupported by the filesystem of the temporary directory.
+    let temp_dir = TempDir::new().unwrap();
+    match RocksDB::open(temp_dir.path(), &options) {
+        Ok(db) => {
+            drop(db);
+            check_open_with_options(&options);
+        }
+        Err(e) => {
+            let message = e.to_string();
+            assert!(message.contains("use_direct_reads"), "{}", message);
+            assert!(
+                message.contains("use_direct_io_for_flush_and_compaction"),
+                "{}",
+                message
+            );
+        }
+    }
+}
+
+#[test]
+fn open_with_column_family_overrides() {
+    let options = DbOptions::builder()
+        .column_family_override(
+            "first",
+            CfOptions::default()
+                .compression(CompressionType::None)
+                .write_buffer_size(1 << 20),
+        )
+        .column_family_override(
+            "second",
+            CfOptions::default()
+                .compression(CompressionType::Snappy)
+                .bloom_filter_bits_per_key(10),
+        )
+        .column_family_override("missing", CfOptions::default())
+        .build()
+        .unwrap();
+
+    let temp_dir = TempDir::new().unwrap();
+    {
+        let db = RocksDB::open(temp_dir.path(), &options).unwrap();
+        let fork = db.fork();
+        for i in 0_u64..100 {
+            fork.get_map("first").put(&i, vec![1; 64]);
+            fork.get_map("second").put(&i, vec![2; 64]);
+        }
+        db.merge_sync(fork.into_patch()).unwrap();
+    }
+
+    let db = RocksDB::open(temp_dir.path(), &options).unwrap();
+    let snapshot = db.snapshot();
+    let first = snapshot.get_map::<_, u64, Vec<u8>>("first");
+    let second = snapshot.get_map::<_, u64, Vec<u8>>("second");
+    assert_eq!(first.iter().count(), 100);
+    assert_eq!(first.get(&99), Some(vec![1; 64]));
+    assert_eq!(second.iter().count(), 100);
+    assert_eq!(second.get(&99), Some(vec![2; 64]));
+}
+
+#[test]
+fn open_with_rate_limiter() {
+    let options = DbOptions::builder()
+        .rate_limiter_bytes_per_sec(1 << 20)
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+
+    let options = DbOptions::builder()
+        .rate_limiter_bytes_per_sec(0)
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+fn open_with_wal_sync_modes() {
+    let modes = [WalSyncMode::Sync, WalSyncMode::Async, WalSyncMode::Disabled];
+    for &mode in &modes {
+        let options = DbOptions::builder().wal_sync_mode(mode).build().unwrap();
+        check_open_with_options(&options);
+
+        let temp_dir = TempDir::new().unwrap();
+        let db = RocksDB::open(temp_dir.path(), &options).unwrap();
+        let fork = db.fork();
+        fork.get_entry("entry").set(1_u64);
+        db.merge(fork.into_patch()).unwrap();
+        let snapshot = db.snapshot();
+        assert_eq!(snapshot.get_entry::<_, u64>("entry").get(), Some(1));
+    }
+}
+
+fn has_files_with_extension(dir: &Path, extension: &str) -> bool {
+    fs::read_dir(dir).unwrap().any(|entry| {
+        let path = entry.unwrap().path();
+        path.extension().map_or(false, |ext| ext == extension)
+    })
+}
+
+#[test]
+fn open_with_separate_wal_dir() {
+    let temp_dir = TempDir::new().unwrap();
+    let wal_dir = TempDir::new().unwrap();
+    let options = DbOptions::builder()
+        .wal_dir(wal_dir.path())
+        .build()
+        .unwrap();
+    let db = RocksDB::open(temp_dir.path(), &options).unwrap();
+    let fork = db.fork();
+    fork.get_entry("entry").set(1_u64);
+    db.merge_sync(fork.into_patch()).unwrap();
+
+    assert!(has_files_with_extension(wal_dir.path(), "log"));
+    assert!(!has_files_with_extension(temp_dir.path(), "log"));
+}
+
+#[test]
+fn open_with_relative_wal_and_log_dirs() {
+    let temp_dir = TempDir::new().unwrap();
+    let options = DbOptions::builder()
+        .wal_dir("wal")
+        .db_log_dir("logs")
+        .build()
+        .unwrap();
+    let db = RocksDB::open(temp_dir.path(), &options).unwrap();
+    let fork = db.fork();
+    fork.get_entry("entry").set(1_u64);
+    db.merge_sync(fork.into_patch()).unwrap();
+
+    assert!(has_files_with_extension(
+        &temp_dir.path().join("wal"),
+        "log"
+    ));
+    assert!(fs::read_dir(temp_dir.path().join("logs")).unwrap().count() > 0);
+}
+
+#[test]
+fn open_with_ttl() {
+    let options = DbOptions::builder().ttl_seconds(3_600).build().unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+fn open_with_invalid_options() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut options = DbOptions::default();
+    options.max_open_files = Some(-5);
+    let err = RocksDB::open(temp_dir.path(), &options).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid database options: `max_open_files` must be -1 (unlimited) or \
+         non-negative, got -5"
+    );
+}
+
+#[test]
+fn open_with_mixed_compression() {
+    let options = DbOptions::builder()
+        .compression(CompressionType::Snappy)
+        .compression_per_level(vec![CompressionType::None, CompressionType::None])
+        .bottommost_compression(CompressionType::Snappy)
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+fn open_with_compression_options() {
+    let options = DbOptions::builder()
+        .compression(CompressionType::Snappy)
+        .compression_options(CompressionOpts::default().level(12))
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+
+    // Options are ignored without compression.
+    let options = DbOptions::builder()
+        .compression(CompressionType::None)
+        .compression_options(CompressionOpts::default().level(12))
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+#[cfg(feature = "rocksdb_zstd")]
+fn open_with_zstd_level() {
+    let options = DbOptions::builder()
+        .compression(CompressionType::Zstd)
+        .compression_options(
+            CompressionOpts::default()
+                .level(12)
+                .max_dict_bytes(16 << 10)
+                .zstd_max_train_bytes(160 << 10),
+        )
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+fn open_with_point_lookup_preset() {
+    let point_lookup = AccessPattern::PointLookup { cache_mb: 16 };
+    let options = DbOptions::builder()
+        .access_pattern(point_lookup)
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+
+    let options = DbOptions::builder()
+        .access_pattern(point_lookup)
+        .block_cache_size(8 << 20)
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+fn open_with_auto_open_files() {
+    let options = DbOptions::builder().auto_open_files(true).build().unwrap();
+    check_open_with_options(&options);
+
+    // Explicit value takes precedence.
+    let options = DbOptions::builder()
+        .max_open_files(-1)
+        .auto_open_files(true)
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+fn open_with_periodic_compaction() {
+    let options = DbOptions::builder()
+        .periodic_compaction_seconds(86_400)
+        .compaction_readahead_size(2 << 20)
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+fn open_with_atomic_flush() {
+    let options = DbOptions::builder().atomic_flush(true).build().unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+fn open_with_statistics() {
+    let options = DbOptions::builder()
+        .enable_statistics(true)
+        .stats_dump_period_sec(1)
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+fn open_with_prefix_extractor() {
+    let options = DbOptions::builder()
+        .prefix_extractor_len(8)
+        .memtable_prefix_bloom_ratio(0.1)
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+fn iteration_with_prefix_extractor() {
+    for &len in &[4, 16] {
+        let temp_dir = TempDir::new().unwrap();
+        let options = DbOptions::builder()
+            .prefix_extractor_len(len)
+            .memtable_prefix_bloom_ratio(0.1)
+            .build()
+            .unwrap();
+        let mut names: Vec<_> = (0_u8..20)
+            .map(|i| format!("{}{}.map", (b'a' + i) as char, i))
+            .collect();
+        {
+            let db = RocksDB::open(temp_dir.path(), &options).unwrap();
+            let fork = db.fork();
+            for name in &names {
+                let mut map = fork.get_map(name.as_str());
+                for i in 0_u64..100 {
+                    map.put(&i, i);
+                }
+            }
+            for i in 0_u64..10 {
+                fork.get_list(("group", &i)).push(i);
+            }
+            db.merge_sync(fork.into_patch()).unwrap();
+        }
+
+        // Reopen the database to read data from SST files as well.
+        let db = RocksDB::open(temp_dir.path(), &options).unwrap();
+        let snapshot = db.snapshot();
+        let indexes = SystemSchema::new(&snapshot).indexes();
+        names.push("group".to_owned());
+        names.sort();
+        let mut listed_names: Vec<_> = indexes.iter().map(|index| index.name.clone()).collect();
+        listed_names.dedup();
+        let listed_names: Vec<_> = listed_names
+            .into_iter()
+            .filter(|name| !name.starts_with("__"))
+            .collect();
+        assert_eq!(listed_names, names, "prefix_extractor_len = {}", len);
+        assert_eq!(
+            indexes.iter().filter(|index| index.name == "group").count(),
+            10
+        );
+
+        for name in names.iter().filter(|name| name.ends_with(".map")) {
+            let map = snapshot.get_map::<_, u64, u64>(name.as_str());
+            assert_eq!(map.keys().collect::<Vec<_>>(), (0..100).collect::<Vec<_>>());
+        }
+    }
+}
+
+#[test]
+fn prefix_extractor_len_cannot_be_changed() {
+    let temp_dir = TempDir::new().unwrap();
+    let options = DbOptions::builder()
+        .prefix_extractor_len(8)
+        .build()
+        .unwrap();
+    drop(RocksDB::open(temp_dir.path(), &options).unwrap());
+    drop(RocksDB::open(temp_dir.path(), &options).unwrap());
+
+    let other_options = DbOptions::builder()
+        .prefix_extractor_len(4)
+        .build()
+        .unwrap();
+    let err = RocksDB::open(temp_dir.path(), &other_options).unwrap_err();
+    assert!(
+        err.to_string().starts_with(
+            "Prefix extractor length doesn't match: recorded in the database 8, \
+             specified in options Some(4)"
+        ),
+        "{}",
+        err
+    );
+    let err = RocksDB::open(temp_dir.path(), &DbOptions::default()).unwrap_err();
+    assert!(
+        err.to_string().contains("specified in options None"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn prefix_extractor_len_is_not_recorded_by_read_only_instances() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("db");
+    drop(RocksDB::open(&db_path, &DbOptions::default()).unwrap());
+    let options = DbOptions::builder()
+        .prefix_extractor_len(8)
+        .build()
+        .unwrap();
+
+    drop(RocksDB::open_read_only(&db_path, &options, false).unwrap());
+    let secondary_path = temp_dir.path().join("secondary");
+    drop(RocksDB::open_secondary(&db_path, &secondary_path, &options).unwrap());
+    // The length is not recorded, so the database can still be opened without it.
+    drop(RocksDB::open(&db_path, &DbOptions::default()).unwrap());
+
+    // The recorded length is still checked.
+    drop(RocksDB::open(&db_path, &options).unwrap());
+    let other_options = DbOptions::builder()
+        .prefix_extractor_len(4)
+        .build()
+        .unwrap();
+    let err = RocksDB::open_read_only(&db_path, &other_options, false).unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("Prefix extractor length doesn't match"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn prefix_extractor_can_be_enabled_for_existing_database() {
+    let temp_dir = TempDir::new().unwrap();
+    drop(RocksDB::open(temp_dir.path(), &DbOptions::default()).unwrap());
+    let options = DbOptions::builder()
+        .prefix_extractor_len(8)
+        .build()
+        .unwrap();
+    drop(RocksDB::open(temp_dir.path(), &options).unwrap());
+    assert!(RocksDB::open(temp_dir.path(), &DbOptions::default()).is_err());
+}
+
+#[test]
+fn open_with_housekeeping_options() {
+    let options = DbOptions::builder()
+        .delete_obsolete_files_period_micros(60_000_000)
+        .max_manifest_file_size(4 << 20)
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+fn open_with_pipelined_write() {
+    let options = DbOptions::builder()
+        .enable_pipelined_write(true)
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+fn open_with_unordered_write() {
+    let options = DbOptions::builder().unordered_write(true).build().unwrap();
+    check_open_with_options(&options);
+}
+
+#[test]
+fn open_with_mmap() {
+    let options = DbOptions::builder()
+        .allow_mmap_reads(true)
+        .allow_mmap_writes(true)
+        .build()
+        .unwrap();
+    check_open_with_options(&options);
+}
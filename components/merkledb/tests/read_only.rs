@@ -0,0 +1,99 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the read-only mode of `RocksDB`.
+
+use exonum_merkledb::{access::CopyAccessExt, Database, DbOptions, ErrorKind, RocksDB};
+use tempfile::TempDir;
+
+fn fill_db(db: &RocksDB) {
+    let fork = db.fork();
+    fork.get_list("list").extend(0_u64..100);
+    fork.get_entry("entry").set(42_u64);
+    db.merge_sync(fork.into_patch()).unwrap();
+}
+
+fn check_read_only(db: &RocksDB) {
+    let snapshot = db.snapshot();
+    assert_eq!(snapshot.get_entry::<_, u64>("entry").get(), Some(42));
+    let list = snapshot.get_list::<_, u64>("list");
+    assert_eq!(list.len(), 100);
+    assert!(list.iter().eq(0..100));
+
+    let fork = db.fork();
+    fork.get_entry("entry").set(0_u64);
+    let err = db.merge(fork.into_patch()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ReadOnly);
+    let fork = db.fork();
+    fork.get_entry("other").set(0_u64);
+    let err = db.merge_sync(fork.into_patch()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ReadOnly);
+
+    assert_eq!(db.snapshot().get_entry::<_, u64>("entry").get(), Some(42));
+}
+
+#[test]
+fn read_only_while_opened_for_writing() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = RocksDB::open(temp_dir.path(), &DbOptions::default()).unwrap();
+    fill_db(&db);
+
+    let read_only = RocksDB::open_read_only(temp_dir.path(), &DbOptions::default(), false).unwrap();
+    check_read_only(&read_only);
+
+    // The writable handle is not affected.
+    let fork = db.fork();
+    fork.get_entry("entry").set(43_u64);
+    db.merge_sync(fork.into_patch()).unwrap();
+    // ...and the read-only handle sees the state at the moment of opening.
+    assert_eq!(
+        read_only.snapshot().get_entry::<_, u64>("entry").get(),
+        Some(42)
+    );
+}
+
+#[test]
+fn read_only_from_options() {
+    let temp_dir = TempDir::new().unwrap();
+    {
+        let db = RocksDB::open(temp_dir.path(), &DbOptions::default()).unwrap();
+        fill_db(&db);
+    }
+
+    let options = DbOptions::builder().read_only(true).build().unwrap();
+    let db = RocksDB::open(temp_dir.path(), &options).unwrap();
+    check_read_only(&db);
+}
+
+#[test]
+fn read_only_with_ttl_is_rejected() {
+    let temp_dir = TempDir::new().unwrap();
+    {
+        let db = RocksDB::open(temp_dir.path(), &DbOptions::default()).unwrap();
+        fill_db(&db);
+    }
+
+    let options = DbOptions::builder().ttl_seconds(60).build().unwrap();
+    let err = RocksDB::open_read_only(temp_dir.path(), &options, false).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Other);
+    assert!(err.to_string().contains("`read_only`"), "{}", err);
+}
+
+#[test]
+fn read_only_requires_existing_database() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("missing");
+    RocksDB::open_read_only(&path, &DbOptions::default(), false).unwrap_err();
+    assert!(!path.exists());
+}
@@ -0,0 +1,182 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for recompression of existing databases.
+
+use exonum_crypto::Hash;
+use exonum_merkledb::{
+    access::CopyAccessExt, recompress, CompressionType, Database, DbOptions, RecompressProgress,
+    RocksDB, SystemSchema,
+};
+use tempfile::TempDir;
+
+use std::{
+    cell::RefCell,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+};
+
+const NUM_ENTRIES: u64 = 5_000;
+
+fn options(compression: CompressionType) -> DbOptions {
+    DbOptions::builder()
+        .compression(compression)
+        .build()
+        .unwrap()
+}
+
+/// Creates an uncompressed database with several column families of well-compressible data.
+/// Returns the state hash of the database.
+fn create_db(path: &Path) -> Hash {
+    let db = RocksDB::open(path, &options(CompressionType::None)).unwrap();
+    let fork = db.fork();
+    let mut map = fork.get_proof_map("map");
+    for i in 0..NUM_ENTRIES {
+        map.put(&i, format!("value #{} ", i).repeat(20));
+    }
+    let mut list = fork.get_list(("list", &1_u8));
+    list.extend((0..NUM_ENTRIES).map(|i| vec![(i % 7) as u8; 256]));
+    fork.get_entry("entry").set("Hello, world!".to_owned());
+    db.merge_sync(fork.into_patch()).unwrap();
+    db.compact().unwrap();
+    SystemSchema::new(&db.snapshot()).state_hash()
+}
+
+fn check_data(path: &Path, compression: CompressionType, state_hash: Hash) {
+    let db = RocksDB::open(path, &options(compression)).unwrap();
+    let snapshot = db.snapshot();
+    assert_eq!(SystemSchema::new(&snapshot).state_hash(), state_hash);
+
+    let map = snapshot.get_proof_map::<_, u64, String>("map");
+    assert_eq!(map.keys().count() as u64, NUM_ENTRIES);
+    assert_eq!(map.get(&42), Some("value #42 ".repeat(20)));
+    let list = snapshot.get_list::<_, Vec<u8>>(("list", &1_u8));
+    assert_eq!(list.len(), NUM_ENTRIES);
+    assert_eq!(list.get(10), Some(vec![3; 256]));
+    assert_eq!(
+        snapshot.get_entry::<_, String>("entry").get(),
+        Some("Hello, world!".to_owned())
+    );
+}
+
+fn check_recompression(compression: CompressionType) {
+    let dir = TempDir::new().unwrap();
+    let state_hash = create_db(dir.path());
+
+    let reported = RefCell::new(vec![]);
+    let report = recompress(
+        dir.path(),
+        &options(CompressionType::None),
+        &options(compression),
+        |progress| reported.borrow_mut().push(progress),
+    )
+    .unwrap();
+
+    assert!(report.recompressed.contains(&"map".to_owned()));
+    assert!(report.recompressed.contains(&"list".to_owned()));
+    assert!(report.resumed.is_empty());
+    assert!(report.size_after < report.size_before / 2, "{:?}", report);
+
+    // Each column family is reported twice: before and after recompression.
+    let reported = reported.into_inner();
+    assert_eq!(reported.len(), report.recompressed.len() * 2);
+    for (i, progress) in reported.iter().enumerate() {
+        assert_eq!(progress.index, i / 2);
+        assert_eq!(progress.total, report.recompressed.len());
+        assert_eq!(progress.column_family, report.recompressed[i / 2]);
+        assert_eq!(progress.size_after.is_some(), i % 2 == 1);
+    }
+
+    check_data(dir.path(), compression, state_hash);
+    assert!(!dir.path().join("RECOMPRESS_PROGRESS").exists());
+}
+
+#[test]
+fn recompression_with_snappy() {
+    check_recompression(CompressionType::Snappy);
+}
+
+#[cfg(feature = "rocksdb_zstd")]
+#[test]
+fn recompression_with_zstd() {
+    check_recompression(CompressionType::Zstd);
+}
+
+#[test]
+fn interrupted_recompression_is_resumed() {
+    let dir = TempDir::new().unwrap();
+    let state_hash = create_db(dir.path());
+
+    let first_cf = RefCell::new(None);
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        recompress(
+            dir.path(),
+            &options(CompressionType::None),
+            &options(CompressionType::Snappy),
+            |progress: RecompressProgress| {
+                if progress.index == 1 {
+                    panic!("Interrupted");
+                }
+                *first_cf.borrow_mut() = Some(progress.column_family);
+            },
+        )
+    }));
+    assert!(res.is_err());
+    let first_cf = first_cf.into_inner().unwrap();
+    assert!(dir.path().join("RECOMPRESS_PROGRESS").exists());
+
+    let report = recompress(
+        dir.path(),
+        &options(CompressionType::None),
+        &options(CompressionType::Snappy),
+        |_| {},
+    )
+    .unwrap();
+    assert_eq!(report.resumed, vec![first_cf.clone()]);
+    assert!(!report.recompressed.is_empty());
+    assert!(!report.recompressed.contains(&first_cf));
+    check_data(dir.path(), CompressionType::Snappy, state_hash);
+    assert!(!dir.path().join("RECOMPRESS_PROGRESS").exists());
+}
+
+#[test]
+fn recompression_with_unchanged_settings_is_noop() {
+    let dir = TempDir::new().unwrap();
+    let state_hash = create_db(dir.path());
+
+    let report = recompress(
+        dir.path(),
+        &options(CompressionType::None),
+        &options(CompressionType::None),
+        |_| panic!("Nothing should be recompressed"),
+    )
+    .unwrap();
+    assert!(report.recompressed.is_empty());
+    assert!(report.unchanged.contains(&"map".to_owned()));
+    check_data(dir.path(), CompressionType::None, state_hash);
+}
+
+#[test]
+fn recompressing_missing_database_fails() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("missing");
+    recompress(
+        &path,
+        &options(CompressionType::None),
+        &options(CompressionType::Snappy),
+        |_| {},
+    )
+    .unwrap_err();
+    assert!(!path.exists());
+}
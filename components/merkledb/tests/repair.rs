@@ -0,0 +1,97 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for repairing corrupted databases.
+
+use exonum_merkledb::{access::CopyAccessExt, repair, Database, DbOptions, ErrorKind, RocksDB};
+use tempfile::TempDir;
+
+use std::{fs, path::Path};
+
+fn create_db(path: &Path) {
+    let db = RocksDB::open(path, &DbOptions::default()).unwrap();
+    let fork = db.fork();
+    let mut map = fork.get_map("map");
+    for i in 0_u64..10_000 {
+        map.put(&i, vec![i as u8; 256]);
+    }
+    db.merge_sync(fork.into_patch()).unwrap();
+    db.compact().unwrap();
+}
+
+fn largest_sst_file(path: &Path) -> std::path::PathBuf {
+    fs::read_dir(path)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "sst"))
+        .max_by_key(|path| fs::metadata(path).unwrap().len())
+        .expect("no SST files in the database directory")
+}
+
+#[test]
+fn repair_intact_database() {
+    let dir = TempDir::new().unwrap();
+    create_db(dir.path());
+
+    let report = repair(dir.path(), &DbOptions::default()).unwrap();
+    assert!(report.is_complete());
+    assert!(report.column_families.iter().any(|name| name == "map"));
+    assert!(report.sst_files > 0);
+
+    let db = RocksDB::open(dir.path(), &DbOptions::default()).unwrap();
+    let snapshot = db.snapshot();
+    let map = snapshot.get_map::<_, u64, Vec<u8>>("map");
+    assert_eq!(map.get(&42), Some(vec![42; 256]));
+}
+
+#[test]
+fn repair_corrupted_database() {
+    let dir = TempDir::new().unwrap();
+    create_db(dir.path());
+
+    let sst_file = largest_sst_file(dir.path());
+    let len = fs::metadata(&sst_file).unwrap().len();
+    fs::OpenOptions::new()
+        .write(true)
+        .open(&sst_file)
+        .unwrap()
+        .set_len(len / 2)
+        .unwrap();
+
+    let report = repair(dir.path(), &DbOptions::default()).unwrap();
+    assert!(!report.is_complete());
+    assert!(report.lost_files.iter().all(|file| file.is_file()));
+    RocksDB::open(dir.path(), &DbOptions::default()).unwrap();
+}
+
+#[test]
+fn repair_fails_for_open_database() {
+    let dir = TempDir::new().unwrap();
+    let _db = RocksDB::open(dir.path(), &DbOptions::default()).unwrap();
+
+    let err = repair(dir.path(), &DbOptions::default()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Locked);
+    assert!(err.to_string().contains("is locked by"), "{}", err);
+}
+
+#[test]
+fn repair_fails_without_database() {
+    let dir = TempDir::new().unwrap();
+    let err = repair(dir.path().join("missing"), &DbOptions::default()).unwrap_err();
+    assert!(
+        err.to_string().contains("does not contain a database"),
+        "{}",
+        err
+    );
+}
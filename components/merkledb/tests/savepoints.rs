@@ -0,0 +1,212 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for savepoints in forks.
+
+use exonum_merkledb::{
+    access::CopyAccessExt, Database, IndexType, ObjectHash, SystemSchema, TemporaryDB,
+};
+
+fn list_items(fork: &exonum_merkledb::Fork, name: &str) -> Vec<u32> {
+    fork.get_list::<_, u32>(name).iter().collect()
+}
+
+fn map_items(fork: &exonum_merkledb::Fork, name: &str) -> Vec<(u8, String)> {
+    fork.get_map::<_, u8, String>(name).iter().collect()
+}
+
+#[test]
+fn rollback_discards_later_changes() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    fork.get_list("list").extend(vec![1_u32, 2]);
+    fork.get_map("map").put(&1_u8, "1".to_owned());
+    db.merge(fork.into_patch()).unwrap();
+
+    let mut fork = db.fork();
+    fork.get_list("list").push(3_u32);
+    fork.get_map("map").put(&2_u8, "2".to_owned());
+    let savepoint = fork.savepoint();
+
+    {
+        let mut list = fork.get_list("list");
+        list.set(0, 100_u32);
+        list.push(4);
+        let mut map = fork.get_map::<_, u8, String>("map");
+        map.remove(&1);
+        map.put(&2, "two".to_owned());
+        map.put(&3, "3".to_owned());
+    }
+    // Some changes are flushed, and some remain in the working patch.
+    fork.flush();
+    fork.get_list("list").push(5_u32);
+    assert_eq!(list_items(&fork, "list"), vec![100, 2, 3, 4, 5]);
+
+    fork.rollback_to(savepoint);
+    assert_eq!(list_items(&fork, "list"), vec![1, 2, 3]);
+    assert_eq!(
+        map_items(&fork, "map"),
+        vec![(1, "1".to_owned()), (2, "2".to_owned())]
+    );
+
+    db.merge(fork.into_patch()).unwrap();
+    let snapshot = db.snapshot();
+    let list = snapshot.get_list::<_, u32>("list");
+    assert_eq!(list.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+    let map = snapshot.get_map::<_, u8, String>("map");
+    assert_eq!(map.values().collect::<Vec<_>>(), vec!["1", "2"]);
+}
+
+#[test]
+fn rollback_restores_cleared_indexes() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    fork.get_list("list").extend(vec![1_u32, 2]);
+    db.merge(fork.into_patch()).unwrap();
+
+    let mut fork = db.fork();
+    fork.get_list("list").push(3_u32);
+    let savepoint = fork.savepoint();
+    fork.get_list::<_, u32>("list").clear();
+    fork.get_list("list").push(10_u32);
+    assert_eq!(list_items(&fork, "list"), vec![10]);
+
+    fork.rollback_to(savepoint);
+    assert_eq!(list_items(&fork, "list"), vec![1, 2, 3]);
+}
+
+#[test]
+fn rollback_removes_created_indexes() {
+    let db = TemporaryDB::new();
+    let mut fork = db.fork();
+    fork.get_list("list").push(1_u32);
+    let savepoint = fork.savepoint();
+
+    fork.get_map("new_map").put(&1_u8, "1".to_owned());
+    fork.get_proof_list(("group", &1_u8)).push(1_u32);
+    assert_eq!(fork.index_type("new_map"), Some(IndexType::Map));
+    assert_eq!(
+        fork.index_type(("group", &1_u8)),
+        Some(IndexType::ProofList)
+    );
+
+    fork.rollback_to(savepoint);
+    assert_eq!(fork.index_type("new_map"), None);
+    assert_eq!(fork.index_type(("group", &1_u8)), None);
+    assert_eq!(fork.index_type("list"), Some(IndexType::List));
+
+    // Indexes can be created again, possibly with another type.
+    fork.get_list("new_map").push(1_u32);
+    db.merge(fork.into_patch()).unwrap();
+    let snapshot = db.snapshot();
+    assert_eq!(snapshot.index_type("new_map"), Some(IndexType::List));
+    assert_eq!(snapshot.index_type(("group", &1_u8)), None);
+}
+
+#[test]
+fn rollback_restores_aggregated_state() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    fork.get_proof_list("list").extend(vec![1_u32, 2]);
+    let expected_patch = fork.into_patch();
+    let expected_hash = SystemSchema::new(&expected_patch).state_hash();
+
+    let mut fork = db.fork();
+    fork.get_proof_list("list").extend(vec![1_u32, 2]);
+    let savepoint = fork.savepoint();
+    fork.get_proof_list("list").push(3_u32);
+    fork.get_proof_map("map").put(&1_u8, 1_u8);
+    fork.rollback_to(savepoint);
+
+    let patch = fork.into_patch();
+    assert_eq!(SystemSchema::new(&patch).state_hash(), expected_hash);
+    let aggregator = SystemSchema::new(&patch).state_aggregator();
+    assert_eq!(aggregator.keys().collect::<Vec<_>>(), vec!["list"]);
+    assert_eq!(
+        aggregator.get("list").unwrap(),
+        patch.get_proof_list::<_, u32>("list").object_hash()
+    );
+}
+
+#[test]
+fn nested_savepoints() {
+    let db = TemporaryDB::new();
+    let mut fork = db.fork();
+    fork.get_list("list").push(1_u32);
+    let outer = fork.savepoint();
+    fork.get_list("list").push(2_u32);
+    let inner = fork.savepoint();
+    fork.get_list("list").push(3_u32);
+    let innermost = fork.savepoint();
+    fork.get_list("list").push(4_u32);
+
+    fork.rollback_to(innermost);
+    assert_eq!(list_items(&fork, "list"), vec![1, 2, 3]);
+    fork.rollback_to(inner);
+    assert_eq!(list_items(&fork, "list"), vec![1, 2]);
+
+    // Savepoints remain valid after rolling back to them.
+    fork.get_list("list").push(5_u32);
+    fork.rollback_to(inner);
+    assert_eq!(list_items(&fork, "list"), vec![1, 2]);
+
+    // New savepoints can be created after a rollback.
+    fork.get_list("list").push(6_u32);
+    let new_inner = fork.savepoint();
+    fork.get_list("list").push(7_u32);
+    fork.rollback_to(new_inner);
+    assert_eq!(list_items(&fork, "list"), vec![1, 2, 6]);
+
+    fork.rollback_to(outer);
+    assert_eq!(list_items(&fork, "list"), vec![1]);
+}
+
+#[test]
+#[should_panic(expected = "is invalid")]
+fn rollback_to_invalidated_savepoint() {
+    let db = TemporaryDB::new();
+    let mut fork = db.fork();
+    let outer = fork.savepoint();
+    fork.get_list("list").push(1_u32);
+    let inner = fork.savepoint();
+    fork.rollback_to(outer);
+    fork.rollback_to(inner);
+}
+
+#[test]
+#[should_panic(expected = "is invalid")]
+fn rollback_to_savepoint_of_another_fork() {
+    let db = TemporaryDB::new();
+    let mut fork = db.fork();
+    let mut other_fork = db.fork();
+    fork.savepoint();
+    let savepoint = other_fork.savepoint();
+    fork.rollback_to(savepoint);
+}
+
+#[test]
+fn savepoint_flushes_fork() {
+    let db = TemporaryDB::new();
+    let mut fork = db.fork();
+    fork.get_list("list").push(1_u32);
+    let savepoint = fork.savepoint();
+    fork.get_list("list").push(2_u32);
+
+    // `rollback` only discards changes after the savepoint.
+    fork.rollback();
+    assert_eq!(list_items(&fork, "list"), vec![1]);
+    fork.get_list("list").push(3_u32);
+    fork.rollback_to(savepoint);
+    assert_eq!(list_items(&fork, "list"), vec![1]);
+}
@@ -0,0 +1,117 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for secondary instances of `RocksDB`.
+
+use exonum_merkledb::{
+    access::CopyAccessExt, Database, DbOptions, ErrorKind, RocksDB, TemporaryDB,
+};
+use tempfile::TempDir;
+
+fn push_values(db: &RocksDB, values: impl IntoIterator<Item = u64>) {
+    let fork = db.fork();
+    fork.get_list("list").extend(values);
+    db.merge_sync(fork.into_patch()).unwrap();
+}
+
+fn list_values(db: &dyn Database) -> Vec<u64> {
+    db.snapshot().get_list("list").iter().collect()
+}
+
+#[test]
+fn secondary_catches_up_with_primary() {
+    let temp_dir = TempDir::new().unwrap();
+    let primary_path = temp_dir.path().join("primary");
+    let secondary_path = temp_dir.path().join("secondary");
+    let primary = RocksDB::open(&primary_path, &DbOptions::default()).unwrap();
+    push_values(&primary, 0..10);
+
+    let secondary =
+        RocksDB::open_secondary(&primary_path, &secondary_path, &DbOptions::default()).unwrap();
+    assert_eq!(list_values(&secondary), (0..10).collect::<Vec<_>>());
+
+    // New writes are not visible until the secondary catches up.
+    push_values(&primary, 10..20);
+    assert_eq!(list_values(&secondary).len(), 10);
+    secondary.try_catch_up_with_primary().unwrap();
+    assert_eq!(list_values(&secondary), (0..20).collect::<Vec<_>>());
+
+    // Catching up works repeatedly, including via the `Database` trait.
+    push_values(&primary, 20..30);
+    let secondary: Box<dyn Database> = Box::new(secondary);
+    secondary.try_catch_up_with_primary().unwrap();
+    assert_eq!(list_values(&*secondary), (0..30).collect::<Vec<_>>());
+    // Catching up without new writes is a no-op.
+    secondary.try_catch_up_with_primary().unwrap();
+    assert_eq!(list_values(&*secondary).len(), 30);
+}
+
+#[test]
+fn secondary_is_read_only() {
+    let temp_dir = TempDir::new().unwrap();
+    let primary_path = temp_dir.path().join("primary");
+    let primary = RocksDB::open(&primary_path, &DbOptions::default()).unwrap();
+    push_values(&primary, 0..10);
+
+    let secondary = RocksDB::open_secondary(
+        &primary_path,
+        temp_dir.path().join("secondary"),
+        &DbOptions::default(),
+    )
+    .unwrap();
+    let fork = secondary.fork();
+    fork.get_list("list").push(100_u64);
+    let err = secondary.merge(fork.into_patch()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ReadOnly);
+}
+
+#[test]
+fn catching_up_requires_secondary_instance() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = RocksDB::open(temp_dir.path(), &DbOptions::default()).unwrap();
+    let err = db.try_catch_up_with_primary().unwrap_err();
+    assert!(
+        err.to_string().contains("not a secondary instance"),
+        "{}",
+        err
+    );
+
+    let db: Box<dyn Database> = Box::new(TemporaryDB::new());
+    let err = db.try_catch_up_with_primary().unwrap_err();
+    assert!(
+        err.to_string().contains("not a secondary instance"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn catching_up_with_deleted_primary_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let primary_path = temp_dir.path().join("primary");
+    {
+        let primary = RocksDB::open(&primary_path, &DbOptions::default()).unwrap();
+        push_values(&primary, 0..10);
+    }
+    let secondary = RocksDB::open_secondary(
+        &primary_path,
+        temp_dir.path().join("secondary"),
+        &DbOptions::default(),
+    )
+    .unwrap();
+
+    std::fs::remove_dir_all(&primary_path).unwrap();
+    let err = secondary.try_catch_up_with_primary().unwrap_err();
+    assert!(err.to_string().contains("Cannot catch up"), "{}", err);
+}
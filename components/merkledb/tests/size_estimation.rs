@@ -0,0 +1,88 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the estimation of on-disk sizes of indexes.
+
+use exonum_merkledb::{access::CopyAccessExt, Database, DbOptions, RocksDB, TemporaryDB};
+use rand::{RngCore, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use tempfile::TempDir;
+
+/// Writes `count` random (i.e., incompressible) values of 1 KiB to the map with the given name.
+fn fill_map(db: &dyn Database, name: &str, count: u64) {
+    let mut rng = XorShiftRng::from_seed([1; 16]);
+    let fork = db.fork();
+    let mut map = fork.get_map(name);
+    for i in 0..count {
+        let mut value = vec![0_u8; 1_024];
+        rng.fill_bytes(&mut value);
+        map.put(&i, value);
+    }
+    db.merge_sync(fork.into_patch()).unwrap();
+}
+
+#[test]
+fn larger_index_has_larger_size() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = RocksDB::open(temp_dir.path(), &DbOptions::default()).unwrap();
+    fill_map(&db, "small.values", 1_000);
+    fill_map(&db, "large.values", 10_000);
+    // Flush the data to SST files.
+    db.compact().unwrap();
+
+    let small_size = db.approximate_size("small.values");
+    let large_size = db.approximate_size("large.values");
+    assert!(small_size >= 1_000 * 1_024, "{}", small_size);
+    assert!(
+        large_size > 5 * small_size,
+        "{} vs {}",
+        large_size,
+        small_size
+    );
+
+    // Prefixes cover several indexes.
+    assert_eq!(db.approximate_size("small."), small_size);
+    let total_size = db.total_size();
+    assert!(total_size >= small_size + large_size, "{}", total_size);
+    assert_eq!(total_size, db.approximate_size(""));
+}
+
+#[test]
+fn index_spread_across_column_families() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = RocksDB::open(temp_dir.path(), &DbOptions::default()).unwrap();
+    fill_map(&db, "service.first", 2_000);
+    fill_map(&db, "service.second", 2_000);
+    fill_map(&db, "other", 2_000);
+    db.compact().unwrap();
+
+    let first = db.approximate_size("service.first");
+    let second = db.approximate_size("service.second");
+    assert!(first > 0 && second > 0);
+    assert_eq!(db.approximate_size("service."), first + second);
+}
+
+#[test]
+fn unknown_prefix_has_zero_size() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = RocksDB::open(temp_dir.path(), &DbOptions::default()).unwrap();
+    fill_map(&db, "map", 1_000);
+    db.compact().unwrap();
+    assert_eq!(db.approximate_size("no_such_index"), 0);
+
+    let db: Box<dyn Database> = Box::new(TemporaryDB::new());
+    fill_map(&*db, "map", 100);
+    assert_eq!(db.approximate_size("map"), 0);
+    assert_eq!(db.total_size(), 0);
+}
@@ -0,0 +1,167 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for export of databases to SST files and their ingestion.
+
+use exonum_merkledb::{
+    access::CopyAccessExt, Database, DbOptions, ObjectHash, RocksDB, SystemSchema, TemporaryDB,
+};
+use rocksdb::IteratorMode;
+use tempfile::TempDir;
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+type Contents = BTreeMap<String, Vec<(Box<[u8]>, Box<[u8]>)>>;
+
+fn write_data(db: &dyn Database) {
+    let fork = db.fork();
+    let mut map = fork.get_proof_map("map");
+    for i in 0_u64..5_000 {
+        map.put(&i, format!("value #{}", i));
+    }
+    fork.get_list(("list", &1_u8)).extend(0_u64..1_000);
+    fork.get_entry("entry").set("!".to_owned());
+    db.merge_sync(fork.into_patch()).unwrap();
+}
+
+/// Reads all column families of a closed database, except for the database metadata.
+fn read_contents(path: &Path) -> Contents {
+    let options = rocksdb::Options::default();
+    let names = rocksdb::DB::list_cf(&options, path).unwrap();
+    let db = rocksdb::DB::open_cf_for_read_only(&options, path, &names, false).unwrap();
+    names
+        .into_iter()
+        .filter(|name| name != "__DB_METADATA__")
+        .map(|name| {
+            let cf = db.cf_handle(&name).unwrap();
+            let entries = db.iterator_cf(cf, IteratorMode::Start).collect();
+            (name, entries)
+        })
+        .filter(|(_, entries): &(String, Vec<_>)| !entries.is_empty())
+        .collect()
+}
+
+#[test]
+fn sst_round_trip() {
+    let source_dir = TempDir::new().unwrap();
+    let export_dir = TempDir::new().unwrap();
+    let export_dir = export_dir.path().join("export");
+    let target_dir = TempDir::new().unwrap();
+
+    let source = RocksDB::open(&source_dir, &DbOptions::default()).unwrap();
+    write_data(&source);
+    let export_stats = source.export_sst(&export_dir).unwrap();
+    assert!(export_stats.files > 0);
+    assert!(export_stats.column_families > 0);
+    assert!(export_stats.size > 0);
+    let source_hash = SystemSchema::new(&source.snapshot()).state_hash();
+
+    let target = RocksDB::open(&target_dir, &DbOptions::default()).unwrap();
+    let ingest_stats = target.ingest_sst(&export_dir).unwrap();
+    assert_eq!(ingest_stats, export_stats);
+    // Exported files are copied, so they remain in place.
+    assert_eq!(
+        fs::read_dir(&export_dir).unwrap().count(),
+        export_stats.files
+    );
+
+    let snapshot = target.snapshot();
+    assert_eq!(SystemSchema::new(&snapshot).state_hash(), source_hash);
+    let map = snapshot.get_proof_map::<_, u64, String>("map");
+    assert_eq!(map.get(&42).unwrap(), "value #42");
+    assert_eq!(
+        map.object_hash(),
+        source
+            .snapshot()
+            .get_proof_map::<_, u64, String>("map")
+            .object_hash()
+    );
+    assert_eq!(snapshot.get_list::<_, u64>(("list", &1_u8)).len(), 1_000);
+
+    // The ingested database is fully functional.
+    write_data(&target);
+
+    source.close().unwrap();
+    target.close().unwrap();
+    assert_eq!(
+        read_contents(source_dir.path()),
+        read_contents(target_dir.path())
+    );
+}
+
+#[test]
+fn file_names_encode_column_family_and_sequence() {
+    let db = TemporaryDB::with_options(&DbOptions::default()).unwrap();
+    write_data(&db);
+    let export_dir = TempDir::new().unwrap();
+    db.export_sst(export_dir.path()).unwrap();
+
+    let mut names: Vec<_> = fs::read_dir(&export_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    for (i, name) in names.iter().enumerate() {
+        assert!(name.starts_with(&format!("{:08}-", i)), "{}", name);
+        assert!(name.ends_with(".sst"), "{}", name);
+    }
+    assert!(names
+        .iter()
+        .any(|name| name.ends_with("-__INDEXES_POOL__.sst")));
+    assert!(!names.iter().any(|name| name.contains("__DB_METADATA__")));
+}
+
+#[test]
+fn ingest_into_non_empty_database_fails() {
+    let source = TemporaryDB::with_options(&DbOptions::default()).unwrap();
+    write_data(&source);
+    let export_dir = TempDir::new().unwrap();
+    let export_dir = export_dir.path().join("export");
+    source.export_sst(&export_dir).unwrap();
+
+    let target = TemporaryDB::with_options(&DbOptions::default()).unwrap();
+    let fork = target.fork();
+    fork.get_entry("other").set(1_u8);
+    target.merge_sync(fork.into_patch()).unwrap();
+
+    let err = target.ingest_sst(&export_dir).unwrap_err();
+    assert!(err.to_string().contains("is not empty"), "{}", err);
+    // Nothing is ingested.
+    assert!(!target
+        .snapshot()
+        .get_proof_map::<_, u64, String>("map")
+        .contains(&0));
+}
+
+#[test]
+fn export_into_non_empty_directory_fails() {
+    let db = TemporaryDB::with_options(&DbOptions::default()).unwrap();
+    write_data(&db);
+    let export_dir = TempDir::new().unwrap();
+    fs::write(export_dir.path().join("file"), b"").unwrap();
+    let err = db.export_sst(export_dir.path()).unwrap_err();
+    assert!(
+        err.to_string().contains("not an empty directory"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn sst_files_are_not_supported_by_in_memory_db() {
+    let db = TemporaryDB::new();
+    let export_dir = TempDir::new().unwrap();
+    assert!(db.export_sst(export_dir.path()).is_err());
+    assert!(db.ingest_sst(export_dir.path()).is_err());
+}
@@ -0,0 +1,88 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for database statistics and property queries.
+
+use exonum_merkledb::{access::CopyAccessExt, Database, DbOptions, DbStats, RocksDB, TemporaryDB};
+use tempfile::TempDir;
+
+const NUM_KEYS: u64 = 5_000;
+
+fn fill_db(db: &dyn Database) {
+    let fork = db.fork();
+    {
+        let mut map = fork.get_map("map");
+        for i in 0..NUM_KEYS {
+            map.put(&i, vec![i as u8; 32]);
+        }
+    }
+    fork.get_list("list").extend(0..NUM_KEYS);
+    db.merge_sync(fork.into_patch()).unwrap();
+}
+
+#[test]
+fn rocksdb_stats_after_writes() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = RocksDB::open(temp_dir.path(), &DbOptions::default()).unwrap();
+    fill_db(&db);
+
+    let stats = db.stats();
+    let num_keys = stats.estimated_num_keys.unwrap();
+    // Estimates may be imprecise, but should be of the correct order of magnitude.
+    assert!(
+        num_keys >= NUM_KEYS && num_keys <= 4 * NUM_KEYS,
+        "{}",
+        num_keys
+    );
+    let memtables_size = stats.memtables_size.unwrap();
+    assert!(memtables_size >= NUM_KEYS * 32, "{}", memtables_size);
+    assert!(stats.estimated_live_data_size.is_some());
+    assert!(stats.running_compactions.unwrap() <= 1);
+    assert!(stats.pending_compaction_bytes.is_some());
+}
+
+#[test]
+fn rocksdb_property_queries() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = RocksDB::open(temp_dir.path(), &DbOptions::default()).unwrap();
+    fill_db(&db);
+
+    let stats = db.property("rocksdb.stats").unwrap();
+    assert!(stats.contains("Compaction Stats"), "{}", stats);
+    assert!(db.int_property("rocksdb.cur-size-all-mem-tables").unwrap() > 0);
+    assert_eq!(db.property("rocksdb.no-such-property"), None);
+    assert_eq!(db.int_property("rocksdb.no-such-property"), None);
+    // `rocksdb.stats` is not an integer property.
+    assert_eq!(db.int_property("rocksdb.stats"), None);
+
+    let cf_name = "__INDEXES_POOL__";
+    assert!(db
+        .cf_int_property(cf_name, "rocksdb.estimate-num-keys")
+        .is_some());
+    assert_eq!(
+        db.cf_int_property("no_such_cf", "rocksdb.estimate-num-keys"),
+        None
+    );
+    assert_eq!(db.cf_int_property(cf_name, "rocksdb.stats"), None);
+}
+
+#[test]
+fn temporary_db_stats_are_empty() {
+    let db = TemporaryDB::new();
+    fill_db(&db);
+    assert_eq!(db.stats(), DbStats::default());
+
+    let db: Box<dyn Database> = Box::new(db);
+    assert_eq!(db.stats().estimated_num_keys, None);
+}
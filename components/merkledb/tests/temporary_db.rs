@@ -0,0 +1,132 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for `TemporaryDB` opened with custom database options.
+
+use exonum_merkledb::{
+    access::CopyAccessExt, CompressionType, Database, DbOptions, DbPreset, TemporaryDB,
+};
+
+const NUM_ENTRIES: u64 = 10_000;
+
+fn check_roundtrip(db: &TemporaryDB) {
+    let fork = db.fork();
+    let mut map = fork.get_proof_map("map");
+    for i in 0..NUM_ENTRIES {
+        map.put(&i, format!("value #{}", i));
+    }
+    fork.get_list(("list", &1_u8)).extend(0_u64..NUM_ENTRIES);
+    db.merge_sync(fork.into_patch()).unwrap();
+
+    let fork = db.fork();
+    let mut map = fork.get_proof_map::<_, u64, String>("map");
+    for i in (0..NUM_ENTRIES).step_by(3) {
+        map.remove(&i);
+    }
+    db.merge(fork.into_patch()).unwrap();
+
+    let snapshot = db.snapshot();
+    let map = snapshot.get_proof_map::<_, u64, String>("map");
+    assert_eq!(
+        map.keys().count() as u64,
+        NUM_ENTRIES - (NUM_ENTRIES + 2) / 3
+    );
+    assert_eq!(map.get(&1), Some("value #1".to_owned()));
+    assert_eq!(map.get(&3), None);
+    let list = snapshot.get_list::<_, u64>(("list", &1_u8));
+    assert_eq!(list.len(), NUM_ENTRIES);
+    assert_eq!(
+        list.iter().sum::<u64>(),
+        NUM_ENTRIES * (NUM_ENTRIES - 1) / 2
+    );
+}
+
+#[test]
+fn in_memory_database_has_no_path() {
+    let db = TemporaryDB::new();
+    assert!(db.path().is_none());
+    check_roundtrip(&db);
+}
+
+#[test]
+fn database_with_compression() {
+    let options = DbOptions::builder()
+        .compression(CompressionType::Snappy)
+        .bottommost_compression(CompressionType::Snappy)
+        .build()
+        .unwrap();
+    let db = TemporaryDB::with_options(&options).unwrap();
+    check_roundtrip(&db);
+    db.compact().unwrap();
+    check_roundtrip(&db);
+}
+
+#[test]
+fn database_with_tiny_write_buffer() {
+    // Memtables are flushed to SST files very often with these options.
+    let options = DbOptions::builder()
+        .write_buffer_size(64 << 10)
+        .max_write_buffer_number(2)
+        .build()
+        .unwrap();
+    let db = TemporaryDB::with_options(&options).unwrap();
+    check_roundtrip(&db);
+    // Compaction flushes memtables, so that the data is accounted in the size estimate.
+    db.compact().unwrap();
+    assert!(db.total_size() > 0);
+}
+
+#[test]
+fn clearing_database_with_options() {
+    let db = TemporaryDB::with_options(&DbOptions::default()).unwrap();
+    check_roundtrip(&db);
+    db.clear().unwrap();
+
+    let snapshot = db.snapshot();
+    assert!(snapshot.index_type("map").is_none());
+    assert!(snapshot.index_type(("list", &1_u8)).is_none());
+}
+
+#[test]
+fn directory_is_removed_on_drop() {
+    let db = TemporaryDB::with_options(&DbOptions::default()).unwrap();
+    check_roundtrip(&db);
+    let path = db.path().unwrap().to_owned();
+    assert!(path.is_dir());
+    drop(db);
+    assert!(!path.exists());
+}
+
+#[test]
+fn invalid_options_are_rejected() {
+    let mut options = DbOptions::default();
+    options.max_batch_bytes = Some(0);
+    let err = TemporaryDB::with_options(&options).unwrap_err();
+    assert!(
+        err.to_string().contains("Invalid database options"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn all_presets() {
+    let mut tested = vec![];
+    TemporaryDB::for_each_preset(|preset, db| {
+        check_roundtrip(&db);
+        tested.push(preset);
+    })
+    .unwrap();
+    assert_eq!(tested, DbPreset::ALL);
+}
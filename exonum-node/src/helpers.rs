@@ -18,7 +18,10 @@ use exonum::merkledb::Fork;
 
 use crate::schema::NodeSchema;
 
-/// Clears cached consensus messages.
-pub fn clear_consensus_messages_cache(fork: &Fork) {
-    NodeSchema::new(fork).consensus_messages_cache().clear();
+/// Clears cached consensus messages. Returns the number of removed messages.
+pub fn clear_consensus_messages_cache(fork: &Fork) -> u64 {
+    let mut cache = NodeSchema::new(fork).consensus_messages_cache();
+    let len = cache.len();
+    cache.clear();
+    len
 }
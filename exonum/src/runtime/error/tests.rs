@@ -74,6 +74,42 @@ fn execution_error_binary_value_round_trip() {
     }
 }
 
+#[test]
+fn execution_error_binary_value_round_trip_all_codes() {
+    let descriptions = [
+        "",
+        "Not enough funds",
+        "Недостаточно средств",
+        "残高不足 💸",
+    ];
+
+    for code in 0..=u8::MAX {
+        let kinds = [
+            ErrorKind::Core { code },
+            ErrorKind::Common { code },
+            ErrorKind::Runtime { code },
+            ErrorKind::Service { code },
+        ];
+        for (&kind, description) in kinds.iter().zip(descriptions.iter().cycle()) {
+            let err = ExecutionError::new(kind, *description);
+            let err2 = ExecutionError::from_bytes(err.to_bytes().into()).unwrap();
+            assert_eq!(err, err2);
+            assert_eq!(err2.description(), *description);
+        }
+    }
+}
+
+#[test]
+fn execution_error_binary_value_with_unknown_field() {
+    let err = ExecutionError::new(ErrorKind::Service { code: 3 }, "Unknown field follows");
+    let mut bytes = err.to_bytes();
+    // Varint field #100 with value 1, which may be added in the future versions of the message.
+    bytes.extend_from_slice(&[0xa0, 0x06, 0x01]);
+
+    let err2 = ExecutionError::from_bytes(bytes.into()).unwrap();
+    assert_eq!(err, err2);
+}
+
 #[test]
 fn execution_error_binary_value_unexpected_with_code() {
     let bytes = {
@@ -1002,10 +1002,12 @@ unsafe impl RawKey for CallerAddress {
 #[cfg(test)]
 mod tests {
     use exonum_crypto as crypto;
+    use exonum_proto::ProtobufConvert;
     use pretty_assertions::assert_eq;
     use serde_json::json;
 
     use super::{ArtifactId, Caller, InstanceSpec, Version};
+    use crate::proto::schema;
 
     #[test]
     fn parse_artifact_id_correct() {
@@ -1054,6 +1056,59 @@ mod tests {
         );
     }
 
+    #[test]
+    fn artifact_id_protobuf_roundtrip() {
+        let artifacts = [
+            "0:my-service:1.2.0",
+            "0:my-service:1.2.0-rc.1",
+            "0:my-service:1.2.0+build.55",
+            "0:my-service:1.2.0-rc.1+build.55",
+            "1:com.my.java.service:0.0.1-alpha.beta.7",
+        ];
+
+        for &artifact in &artifacts {
+            let artifact_id = artifact.parse::<ArtifactId>().unwrap();
+            let pb = artifact_id.to_pb();
+            assert_eq!(pb.get_version(), artifact_id.version.to_string());
+
+            let restored = ArtifactId::from_pb(pb).unwrap();
+            assert_eq!(restored, artifact_id);
+            // `Version` equality ignores build metadata, so compare string forms as well.
+            assert_eq!(restored.to_string(), artifact);
+        }
+
+        let release = "0:my-service:1.2.0".parse::<ArtifactId>().unwrap();
+        let pre_release = "0:my-service:1.2.0-rc.1".parse::<ArtifactId>().unwrap();
+        let restored = ArtifactId::from_pb(pre_release.to_pb()).unwrap();
+        assert_ne!(restored, release);
+        assert!(restored.version < release.version);
+    }
+
+    #[test]
+    fn artifact_id_protobuf_incorrect_version() {
+        let versions = [
+            ("1.2", "Expected dot"),
+            ("1.x.0", "Error parsing minor identifier"),
+            ("garbage", "Error parsing major identifier"),
+        ];
+
+        for (version, expected_err) in &versions {
+            let mut pb = schema::base::ArtifactId::new();
+            pb.set_runtime_id(0);
+            pb.set_name("my-service".to_owned());
+            pb.set_version((*version).to_owned());
+
+            let actual_err = ArtifactId::from_pb(pb).unwrap_err().to_string();
+            assert!(
+                actual_err.contains(expected_err),
+                "version: '{}' actual_err '{}', expected_err '{}'",
+                version,
+                actual_err,
+                expected_err
+            );
+        }
+    }
+
     #[test]
     fn parse_artifact_id_incorrect_layout() {
         let artifacts = [
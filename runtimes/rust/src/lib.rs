@@ -355,7 +355,7 @@ use exonum_api::{ApiBuilder, UpdateEndpoints};
 use futures::{channel::mpsc, executor, SinkExt};
 use log::trace;
 
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet};
 
 use self::api::ServiceApiBuilder;
 
@@ -411,17 +411,17 @@ impl<T> MigrateData for WithoutMigrations<T> {
 pub struct RustRuntime {
     blockchain: Option<Blockchain>,
     api_notifier: mpsc::Sender<UpdateEndpoints>,
-    available_artifacts: HashMap<ArtifactId, Box<dyn FactoryWithMigrations>>,
-    deployed_artifacts: HashSet<ArtifactId>,
+    available_artifacts: BTreeMap<ArtifactId, Box<dyn FactoryWithMigrations>>,
+    deployed_artifacts: BTreeSet<ArtifactId>,
     started_services: BTreeMap<InstanceId, Instance>,
-    started_services_by_name: HashMap<String, InstanceId>,
+    started_services_by_name: BTreeMap<String, InstanceId>,
     changed_services_since_last_block: bool,
 }
 
 /// Builder of the `RustRuntime`.
 #[derive(Debug, Default)]
 pub struct RustRuntimeBuilder {
-    available_artifacts: HashMap<ArtifactId, Box<dyn FactoryWithMigrations>>,
+    available_artifacts: BTreeMap<ArtifactId, Box<dyn FactoryWithMigrations>>,
 }
 
 #[derive(Debug)]
@@ -457,8 +457,14 @@ impl RustRuntimeBuilder {
     /// # Return value
     ///
     /// Returns a modified `RustRuntime` object for further chaining.
+    ///
+    /// # Panics
+    ///
+    /// - If a factory for the same artifact is already added to the builder. Use
+    ///   [`replace_factory`](#method.replace_factory) if overwriting is intended.
     pub fn with_factory<S: ServiceFactory>(mut self, service_factory: S) -> Self {
         let artifact = service_factory.artifact_id();
+        self.assert_not_added(&artifact);
         trace!(
             "Added available artifact {} without migration support",
             artifact
@@ -474,11 +480,18 @@ impl RustRuntimeBuilder {
     /// # Return value
     ///
     /// Returns a modified `RustRuntime` object for further chaining.
+    ///
+    /// # Panics
+    ///
+    /// - If a factory for the same artifact is already added to the builder. Use
+    ///   [`replace_migrating_factory`](#method.replace_migrating_factory) if overwriting
+    ///   is intended.
     pub fn with_migrating_factory<S>(mut self, service_factory: S) -> Self
     where
         S: ServiceFactory + MigrateData,
     {
         let artifact = service_factory.artifact_id();
+        self.assert_not_added(&artifact);
         trace!(
             "Added available artifact {} with migration support",
             artifact
@@ -488,15 +501,62 @@ impl RustRuntimeBuilder {
         self
     }
 
+    /// Adds a service factory without migration support to the runtime, replacing
+    /// the factory previously added for the same artifact (if any).
+    ///
+    /// # Return value
+    ///
+    /// Returns a modified `RustRuntime` object for further chaining.
+    pub fn replace_factory<S: ServiceFactory>(mut self, service_factory: S) -> Self {
+        let artifact = service_factory.artifact_id();
+        trace!(
+            "Replaced available artifact {} with a factory without migration support",
+            artifact
+        );
+        let service_factory = WithoutMigrations(service_factory);
+        self.available_artifacts
+            .insert(artifact, Box::new(service_factory));
+        self
+    }
+
+    /// Adds a service factory with migration support to the runtime, replacing
+    /// the factory previously added for the same artifact (if any).
+    ///
+    /// # Return value
+    ///
+    /// Returns a modified `RustRuntime` object for further chaining.
+    pub fn replace_migrating_factory<S>(mut self, service_factory: S) -> Self
+    where
+        S: ServiceFactory + MigrateData,
+    {
+        let artifact = service_factory.artifact_id();
+        trace!(
+            "Replaced available artifact {} with a factory with migration support",
+            artifact
+        );
+        self.available_artifacts
+            .insert(artifact, Box::new(service_factory));
+        self
+    }
+
+    fn assert_not_added(&self, artifact: &ArtifactId) {
+        assert!(
+            !self.available_artifacts.contains_key(artifact),
+            "Service factory for artifact {} is already added to the Rust runtime; \
+             use `replace_factory` / `replace_migrating_factory` if overwriting is intended",
+            artifact
+        );
+    }
+
     /// Completes the build process, converting the builder into a `RustRuntime`.
     pub fn build(self, api_notifier: mpsc::Sender<UpdateEndpoints>) -> RustRuntime {
         RustRuntime {
             blockchain: None,
             api_notifier,
             available_artifacts: self.available_artifacts,
-            deployed_artifacts: HashSet::new(),
+            deployed_artifacts: BTreeSet::new(),
             started_services: BTreeMap::new(),
-            started_services_by_name: HashMap::new(),
+            started_services_by_name: BTreeMap::new(),
             changed_services_since_last_block: true,
             // ^-- We set this flag to `true` to propagate initial changes to API (which always
             // include the runtime API) after the runtime is resumed or the genesis block
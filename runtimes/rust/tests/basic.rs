@@ -20,7 +20,7 @@ use exonum::{
     helpers::Height,
     merkledb::{access::AccessExt, BinaryValue, SystemSchema},
     runtime::{
-        Caller, CommonError, CoreError, ErrorMatch, ExecutionContext, ExecutionError,
+        ArtifactId, Caller, CommonError, CoreError, ErrorMatch, ExecutionContext, ExecutionError,
         InstanceStatus, SnapshotExt,
     },
 };
@@ -109,6 +109,30 @@ impl DefaultInstance for TestServiceImpl {
     }
 }
 
+/// Service with the same artifact as `TestServiceImpl`, but a different constructor.
+#[derive(Debug, ServiceFactory, ServiceDispatcher)]
+#[service_dispatcher(implements())]
+#[service_factory(artifact_name = "test_service", artifact_version = "0.1.0")]
+pub struct ReplacedTestServiceImpl;
+
+impl ReplacedTestServiceImpl {
+    const CONSTRUCTOR_MESSAGE: &'static str = "replaced_constructor_message";
+}
+
+impl Service for ReplacedTestServiceImpl {
+    fn initialize(
+        &self,
+        context: ExecutionContext<'_>,
+        _params: Vec<u8>,
+    ) -> Result<(), ExecutionError> {
+        context
+            .service_data()
+            .get_proof_entry("constructor_entry")
+            .set(Self::CONSTRUCTOR_MESSAGE.to_owned());
+        Ok(())
+    }
+}
+
 #[derive(Debug, ServiceFactory, ServiceDispatcher)]
 #[service_dispatcher(implements("Test"))]
 #[service_factory(artifact_name = "test_service", artifact_version = "0.2.0")]
@@ -425,6 +449,74 @@ fn create_runtime_non_empty_spec() {
     create_runtime(Blockchain::build_for_tests(), genesis_config);
 }
 
+/// Registering two factories for the same artifact is a programming error.
+#[test]
+#[should_panic(expected = "Service factory for artifact 0:test_service:0.1.0 is already added")]
+fn duplicate_service_factory() {
+    RustRuntimeBuilder::new()
+        .with_factory(TestServiceImpl)
+        .with_factory(TestServiceImpl);
+}
+
+/// Migrating and non-migrating factories share the same namespace of artifacts.
+#[test]
+#[should_panic(expected = "Service factory for artifact 0:test_service:0.1.0 is already added")]
+fn duplicate_service_factory_with_migrations() {
+    RustRuntimeBuilder::new()
+        .with_factory(TestServiceImpl)
+        .with_migrating_factory(TestServiceImpl);
+}
+
+/// An existing factory can be overwritten explicitly, after which the runtime
+/// uses the replacement to deploy the artifact and to start its instances.
+#[test]
+fn replaced_service_factory() {
+    let genesis_config = create_genesis_config_builder()
+        .with_artifact(TestServiceImpl.artifact_id())
+        .with_instance(TestServiceImpl.default_instance())
+        .build();
+    let inspected = Inspected::new(
+        RustRuntimeBuilder::new()
+            .with_factory(TestServiceImpl)
+            .replace_factory(ReplacedTestServiceImpl)
+            .with_factory(ToySupervisorService)
+            .build_for_tests(),
+    );
+    let events_handle = inspected.events.clone();
+    let blockchain = BlockchainBuilder::new(Blockchain::build_for_tests())
+        .with_genesis_config(genesis_config)
+        .with_runtime(inspected)
+        .build();
+
+    assert!(events_handle.take().contains(&RuntimeEvent::DeployArtifact(
+        TestServiceImpl.artifact_id(),
+        vec![]
+    )));
+    // The constructor of the replacement has been called instead of the original one.
+    assert_eq!(
+        blockchain
+            .snapshot()
+            .for_service(TestServiceImpl::INSTANCE_NAME)
+            .unwrap()
+            .get_proof_entry::<_, String>("constructor_entry")
+            .get(),
+        Some(ReplacedTestServiceImpl::CONSTRUCTOR_MESSAGE.to_owned())
+    );
+}
+
+/// Available artifacts are reported in the lexicographic order regardless of the order
+/// in which their factories were added to the runtime.
+#[test]
+#[should_panic(expected = "Available artifacts: 0:dependent_service:0.1.0, \
+                           0:test_service:0.1.0, 0:test_service:0.2.0, 0:toy_supervisor:0.1.0")]
+fn available_artifacts_order() {
+    let unknown_artifact = "0:unknown_service:1.0.0".parse::<ArtifactId>().unwrap();
+    let genesis_config = create_genesis_config_builder()
+        .with_artifact(unknown_artifact)
+        .build();
+    create_runtime(Blockchain::build_for_tests(), genesis_config);
+}
+
 /// In this test, we simulate blockchain restart and check events from inspector.
 #[test]
 fn runtime_restart() {
@@ -12,10 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use exonum_merkledb::{Database, DatabaseExt, Patch, Result as StorageResult, Snapshot};
+use exonum_merkledb::{
+    BackupInfo, Database, DatabaseExt, DbStats, Patch, Result as StorageResult, Snapshot,
+};
 
 use std::{
     fmt,
+    path::Path,
     sync::{Arc, RwLock},
 };
 
@@ -80,6 +83,54 @@ impl<T: Database> Database for CheckpointDb<T> {
     fn merge_sync(&self, patch: Patch) -> StorageResult<()> {
         self.merge(patch)
     }
+
+    fn create_checkpoint(&self, path: &Path) -> StorageResult<()> {
+        self.inner
+            .read()
+            .expect("Cannot lock CheckpointDb for checkpoint")
+            .db
+            .create_checkpoint(path)
+    }
+
+    fn backup(&self, backup_dir: &Path) -> StorageResult<BackupInfo> {
+        self.inner
+            .read()
+            .expect("Cannot lock CheckpointDb for backup")
+            .db
+            .backup(backup_dir)
+    }
+
+    fn try_catch_up_with_primary(&self) -> StorageResult<()> {
+        self.inner
+            .read()
+            .expect("Cannot lock CheckpointDb for catching up")
+            .db
+            .try_catch_up_with_primary()
+    }
+
+    fn compact(&self) -> StorageResult<()> {
+        self.inner
+            .read()
+            .expect("Cannot lock CheckpointDb for compaction")
+            .db
+            .compact()
+    }
+
+    fn approximate_size(&self, name_prefix: &str) -> u64 {
+        self.inner
+            .read()
+            .expect("Cannot lock CheckpointDb for size estimation")
+            .db
+            .approximate_size(name_prefix)
+    }
+
+    fn stats(&self) -> DbStats {
+        self.inner
+            .read()
+            .expect("Cannot lock CheckpointDb for stats")
+            .db
+            .stats()
+    }
 }
 
 #[allow(clippy::use_self)] // false positive